@@ -0,0 +1,104 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    path::Path,
+};
+
+use bruh::{
+    palette::{closest, median_cut},
+    BruhEncoder, ColorAdjustments, PixelFilter,
+};
+
+use crate::{input::read_rgb, output::Output};
+
+/// What [`convert`] reduces an image's colors to.
+///
+/// A .bruh file always stores 8-bit RGB, so a mode only limits which colors appear in it,
+/// which also makes runs longer and files smaller.
+#[derive(Clone, Copy)]
+pub enum ColorMode {
+    /// At most this many colors, chosen by median cut like the `palette` command does.
+    Palette(usize),
+    /// Shades of grey, using the same Rec. 709 weights as `compile --grayscale`.
+    Gray,
+}
+
+/// What a conversion lost.
+pub struct Conversion {
+    /// Distinct colors before and after.
+    pub colors_before: usize,
+    pub colors_after: usize,
+    /// Pixels whose color changed.
+    pub changed: u64,
+    pub pixels: u64,
+    /// The largest difference in any channel of any pixel.
+    pub max_error: u8,
+}
+
+/// Re-encodes the image at `input` in `mode` and saves it as a .bruh file at `output`,
+/// which may be the same file.
+pub fn convert(
+    input: &Path,
+    mode: ColorMode,
+    output: &Path,
+) -> Result<Conversion, Box<dyn Error>> {
+    let (width, height, rgb) = read_rgb(input)?;
+    let converted = match mode {
+        ColorMode::Palette(_) if rgb.is_empty() => Vec::new(),
+        ColorMode::Palette(colors) => {
+            let palette: Vec<[u8; 3]> = median_cut(&rgb, colors)
+                .into_iter()
+                .map(|entry| entry.color)
+                .collect();
+            // Images repeat colors a lot, so each one's nearest entry is only looked up once.
+            let mut nearest: HashMap<[u8; 3], [u8; 3]> = HashMap::new();
+            rgb.chunks_exact(3)
+                .flat_map(|pixel| {
+                    let color = [pixel[0], pixel[1], pixel[2]];
+                    *nearest
+                        .entry(color)
+                        .or_insert_with(|| closest(&palette, color))
+                })
+                .collect()
+        }
+        ColorMode::Gray => {
+            let mut gray = ColorAdjustments {
+                grayscale: true,
+                ..ColorAdjustments::default()
+            };
+            let mut rgba: Vec<u8> = rgb
+                .chunks_exact(3)
+                .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+                .collect();
+            gray.filter_row(0, &mut rgba);
+            rgba.chunks_exact(4)
+                .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+                .collect()
+        }
+    };
+
+    let mut conversion = Conversion {
+        colors_before: count_colors(&rgb),
+        colors_after: count_colors(&converted),
+        changed: 0,
+        pixels: (rgb.len() / 3) as u64,
+        max_error: 0,
+    };
+    for (before, after) in rgb.chunks_exact(3).zip(converted.chunks_exact(3)) {
+        if before != after {
+            conversion.changed += 1;
+        }
+        for (&a, &b) in before.iter().zip(after) {
+            conversion.max_error = conversion.max_error.max(a.abs_diff(b));
+        }
+    }
+
+    let mut encoder = BruhEncoder::new(Output::create(output)?, width, height)?;
+    encoder.push_pixels(&converted)?;
+    encoder.finish()?.commit()?;
+    Ok(conversion)
+}
+
+fn count_colors(rgb: &[u8]) -> usize {
+    rgb.chunks_exact(3).collect::<HashSet<_>>().len()
+}
@@ -0,0 +1,265 @@
+use std::{
+    error::Error,
+    io::Write,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use bruh::{decode_bruh, BruhEncoder};
+use image::{codecs, ColorType, ImageEncoder, ImageFormat};
+
+use crate::{corpus::find_files, input::read_rgb};
+
+/// How many times each codec encodes and decodes the image. The fastest round counts,
+/// which keeps a cold cache or a busy machine from skewing one codec.
+const ROUNDS: usize = 3;
+
+/// Turns packed RGB of the given size into a codec's bytes.
+type Encode = fn(&[u8], u32, u32) -> Result<Vec<u8>, Box<dyn Error>>;
+/// Decodes a codec's bytes, throwing away the pixels.
+type Decode = fn(&[u8]) -> Result<(), Box<dyn Error>>;
+
+/// A codec being compared, turning packed RGB into its own bytes and back.
+pub struct Codec {
+    /// How it's named on the command line, e.g. in `shootout --against`.
+    pub id: &'static str,
+    name: &'static str,
+    encode: Encode,
+    decode: Decode,
+}
+
+/// Every codec that can be compared, BRUH first.
+pub const CODECS: &[Codec] = &[
+    Codec {
+        id: "bruh",
+        name: "BRUH",
+        encode: |rgb, width, height| {
+            let mut encoder = BruhEncoder::new(Vec::new(), width, height)?;
+            encoder.push_pixels(rgb)?;
+            Ok(encoder.finish()?)
+        },
+        decode: |bytes| {
+            decode_bruh(bytes)?;
+            Ok(())
+        },
+    },
+    Codec {
+        id: "qoi",
+        name: "QOI",
+        encode: |rgb, width, height| {
+            let mut bytes = Vec::new();
+            codecs::qoi::QoiEncoder::new(&mut bytes).write_image(
+                rgb,
+                width,
+                height,
+                ColorType::Rgb8,
+            )?;
+            Ok(bytes)
+        },
+        decode: |bytes| {
+            image::load_from_memory_with_format(bytes, ImageFormat::Qoi)?;
+            Ok(())
+        },
+    },
+    Codec {
+        id: "png",
+        name: "PNG",
+        encode: |rgb, width, height| {
+            let mut bytes = Vec::new();
+            codecs::png::PngEncoder::new(&mut bytes).write_image(
+                rgb,
+                width,
+                height,
+                ColorType::Rgb8,
+            )?;
+            Ok(bytes)
+        },
+        decode: |bytes| {
+            image::load_from_memory_with_format(bytes, ImageFormat::Png)?;
+            Ok(())
+        },
+    },
+    Codec {
+        id: "webp-lossless",
+        name: "WebP lossless",
+        encode: |rgb, width, height| {
+            let mut bytes = Vec::new();
+            codecs::webp::WebPEncoder::new_lossless(&mut bytes).write_image(
+                rgb,
+                width,
+                height,
+                ColorType::Rgb8,
+            )?;
+            Ok(bytes)
+        },
+        decode: |bytes| {
+            image::load_from_memory_with_format(bytes, ImageFormat::WebP)?;
+            Ok(())
+        },
+    },
+];
+
+/// The codec named `id` on the command line.
+pub fn find_codec(id: &str) -> Option<&'static Codec> {
+    CODECS.iter().find(|codec| codec.id.eq_ignore_ascii_case(id))
+}
+
+/// Encodes and decodes the image at `path` with each codec and writes a table of the
+/// sizes and times to `out`.
+///
+/// `path` can be a .bruh file or any image [`read_rgb`] accepts, so every codec gets the
+/// same RGB pixels that a .bruh file would hold.
+pub fn compare_codecs(path: &Path, mut out: impl Write) -> Result<(), Box<dyn Error>> {
+    let (width, height, rgb) = read_rgb(path)?;
+    if width == 0 || height == 0 {
+        return Err("Can't compare codecs on an empty image.".into());
+    }
+
+    writeln!(out, "{}×{} pixels, {} bytes as raw RGB", width, height, rgb.len())?;
+    writeln!(
+        out,
+        "{:<13} {:>12} {:>8} {:>10} {:>10}",
+        "codec", "bytes", "ratio", "encode", "decode"
+    )?;
+    for codec in CODECS {
+        let mut encoded = Vec::new();
+        let mut encode_time = Duration::MAX;
+        let mut decode_time = Duration::MAX;
+        for _ in 0..ROUNDS {
+            let start = Instant::now();
+            encoded = (codec.encode)(&rgb, width, height)?;
+            encode_time = encode_time.min(start.elapsed());
+
+            let start = Instant::now();
+            (codec.decode)(&encoded)?;
+            decode_time = decode_time.min(start.elapsed());
+        }
+
+        writeln!(
+            out,
+            "{:<13} {:>12} {:>7.1}% {:>8.2}ms {:>8.2}ms",
+            codec.name,
+            encoded.len(),
+            encoded.len() as f64 / rgb.len() as f64 * 100.0,
+            encode_time.as_secs_f64() * 1000.0,
+            decode_time.as_secs_f64() * 1000.0
+        )?;
+    }
+    Ok(())
+}
+
+/// What one codec added up to over a corpus.
+#[derive(Default)]
+struct Totals {
+    bytes: u64,
+    encode_time: Duration,
+    decode_time: Duration,
+    /// Files it made the smallest of, ties going to every codec in them.
+    smallest: usize,
+}
+
+/// Encodes and decodes every image under `root` with BRUH and each of `against`, and
+/// writes a matrix of each file's size with every codec to `out`, then each codec's total
+/// size, times and how often it was smallest.
+///
+/// Each image is encoded once per codec, not the best of several rounds like
+/// [`compare_codecs`], so a large corpus doesn't take three times as long. An image that
+/// some codec can't handle, such as one too large for WebP, is left out for all of them so
+/// the totals stay comparable.
+pub fn shootout(
+    root: &Path,
+    against: &[&Codec],
+    mut out: impl Write,
+) -> Result<(), Box<dyn Error>> {
+    let mut codecs = vec![&CODECS[0]];
+    codecs.extend(against.iter().filter(|codec| codec.id != CODECS[0].id));
+    let mut totals: Vec<Totals> = codecs.iter().map(|_| Totals::default()).collect();
+    let (mut files, mut raw_bytes) = (0, 0);
+
+    write!(out, "{:<32} {:>12}", "file", "raw")?;
+    for codec in &codecs {
+        write!(out, " {:>13}", codec.name)?;
+    }
+    writeln!(out)?;
+
+    for path in find_files(root)? {
+        let name = path.strip_prefix(root).unwrap_or(&path).display().to_string();
+        let (width, height, rgb) = match read_rgb(&path) {
+            Ok(image) if image.0 > 0 && image.1 > 0 => image,
+            Ok(_) => continue,
+            Err(e) => {
+                eprintln!("Skipped {}: {}", name, e);
+                continue;
+            }
+        };
+
+        let mut results = Vec::with_capacity(codecs.len());
+        for codec in &codecs {
+            match round_trip(codec, &rgb, width, height) {
+                Ok(result) => results.push(result),
+                Err(e) => {
+                    eprintln!("Skipped {}: {} failed: {}", name, codec.name, e);
+                    break;
+                }
+            }
+        }
+        if results.len() < codecs.len() {
+            continue;
+        }
+
+        files += 1;
+        raw_bytes += rgb.len() as u64;
+        write!(out, "{:<32} {:>12}", name, rgb.len())?;
+        let smallest = results.iter().map(|&(bytes, ..)| bytes).min().unwrap_or(0);
+        for (total, (bytes, encode_time, decode_time)) in totals.iter_mut().zip(results) {
+            write!(out, " {:>13}", bytes)?;
+            total.bytes += bytes;
+            total.encode_time += encode_time;
+            total.decode_time += decode_time;
+            if bytes == smallest {
+                total.smallest += 1;
+            }
+        }
+        writeln!(out)?;
+    }
+
+    writeln!(out)?;
+    writeln!(out, "{} images, {} bytes as raw RGB", files, raw_bytes)?;
+    writeln!(
+        out,
+        "{:<13} {:>14} {:>8} {:>10} {:>12} {:>12} {:>9}",
+        "codec", "bytes", "ratio", "vs BRUH", "encode", "decode", "smallest"
+    )?;
+    let bruh_bytes = totals[0].bytes.max(1) as f64;
+    for (codec, total) in codecs.iter().zip(&totals) {
+        writeln!(
+            out,
+            "{:<13} {:>14} {:>7.1}% {:>9.1}% {:>10.1}ms {:>10.1}ms {:>9}",
+            codec.name,
+            total.bytes,
+            total.bytes as f64 / raw_bytes.max(1) as f64 * 100.0,
+            total.bytes as f64 / bruh_bytes * 100.0,
+            total.encode_time.as_secs_f64() * 1000.0,
+            total.decode_time.as_secs_f64() * 1000.0,
+            total.smallest
+        )?;
+    }
+    Ok(())
+}
+
+/// Encodes and decodes the image once with `codec`, returning the encoded size and how
+/// long each took.
+fn round_trip(
+    codec: &Codec,
+    rgb: &[u8],
+    width: u32,
+    height: u32,
+) -> Result<(u64, Duration, Duration), Box<dyn Error>> {
+    let start = Instant::now();
+    let encoded = (codec.encode)(rgb, width, height)?;
+    let encode_time = start.elapsed();
+
+    let start = Instant::now();
+    (codec.decode)(&encoded)?;
+    Ok((encoded.len() as u64, encode_time, start.elapsed()))
+}
@@ -0,0 +1,263 @@
+use std::{
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use bruh::{decode_bruh, BruhEncoder};
+
+use crate::{input::read_rgb, stats::RunStats};
+
+/// How one image of a corpus fared when encoded as BRUH.
+pub struct FileStats {
+    /// Relative to the corpus folder.
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    /// Size of the file as found, in whatever format it was.
+    pub file_size: u64,
+    /// Size of the encoded .bruh file.
+    pub bruh_size: u64,
+    pub runs: u64,
+    pub single_pixel_runs: u64,
+    pub encode_time: Duration,
+    pub decode_time: Duration,
+}
+
+impl FileStats {
+    fn pixels(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    /// Size of the pixels as raw RGB8 over the size as BRUH.
+    fn ratio(&self) -> f64 {
+        (self.pixels() * 3) as f64 / self.bruh_size as f64
+    }
+}
+
+/// Every image in a folder encoded with the current codec, for seeing how a change to the
+/// format does on real data.
+pub struct Corpus {
+    pub files: Vec<FileStats>,
+    /// Files that couldn't be read as images, and why.
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+impl Corpus {
+    /// Encodes and decodes every image under `root`, in subfolders too.
+    ///
+    /// .bruh files are decoded and encoded again, so their numbers are the current
+    /// encoder's rather than whatever wrote them. Other images go through [`read_rgb`]
+    /// first, like `compile` would.
+    pub fn measure(root: &Path) -> Result<Corpus, Box<dyn Error>> {
+        let mut corpus = Corpus {
+            files: Vec::new(),
+            skipped: Vec::new(),
+        };
+        for path in find_files(root)? {
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            match measure_file(&path) {
+                Ok(mut stats) => {
+                    stats.path = relative;
+                    corpus.files.push(stats);
+                }
+                Err(e) => corpus.skipped.push((relative, e.to_string())),
+            }
+        }
+        Ok(corpus)
+    }
+
+    pub fn to_json(&self) -> String {
+        let files: Vec<_> = self
+            .files
+            .iter()
+            .map(|file| {
+                serde_json::json!({
+                    "path": file.path.to_string_lossy(),
+                    "width": file.width,
+                    "height": file.height,
+                    "file_size": file.file_size,
+                    "bruh_size": file.bruh_size,
+                    "ratio": file.ratio(),
+                    "runs": file.runs,
+                    "single_pixel_runs": file.single_pixel_runs,
+                    "encode_ms": file.encode_time.as_secs_f64() * 1000.0,
+                    "decode_ms": file.decode_time.as_secs_f64() * 1000.0,
+                })
+            })
+            .collect();
+        let skipped: Vec<_> = self
+            .skipped
+            .iter()
+            .map(|(path, reason)| {
+                serde_json::json!({ "path": path.to_string_lossy(), "reason": reason })
+            })
+            .collect();
+        let summary = self.summary();
+        serde_json::json!({
+            "files": files,
+            "skipped": skipped,
+            "summary": {
+                "files": self.files.len(),
+                "pixels": summary.pixels,
+                "raw_size": summary.pixels * 3,
+                "bruh_size": summary.bruh_size,
+                "ratio": summary.ratio(),
+                "median_ratio": summary.median_ratio,
+                "runs": summary.runs,
+                "single_pixel_runs": summary.single_pixel_runs,
+                "encode_ms": summary.encode_time.as_secs_f64() * 1000.0,
+                "decode_ms": summary.decode_time.as_secs_f64() * 1000.0,
+            },
+        })
+        .to_string()
+    }
+
+    fn summary(&self) -> Summary {
+        let mut ratios: Vec<f64> = self.files.iter().map(FileStats::ratio).collect();
+        ratios.sort_by(f64::total_cmp);
+        let median_ratio = match ratios.len() {
+            0 => None,
+            n if n % 2 == 1 => Some(ratios[n / 2]),
+            n => Some((ratios[n / 2 - 1] + ratios[n / 2]) / 2.0),
+        };
+        Summary {
+            pixels: self.files.iter().map(FileStats::pixels).sum(),
+            bruh_size: self.files.iter().map(|file| file.bruh_size).sum(),
+            runs: self.files.iter().map(|file| file.runs).sum(),
+            single_pixel_runs: self.files.iter().map(|file| file.single_pixel_runs).sum(),
+            median_ratio,
+            encode_time: self.files.iter().map(|file| file.encode_time).sum(),
+            decode_time: self.files.iter().map(|file| file.decode_time).sum(),
+        }
+    }
+}
+
+/// The whole corpus added up.
+struct Summary {
+    pixels: u64,
+    bruh_size: u64,
+    runs: u64,
+    single_pixel_runs: u64,
+    median_ratio: Option<f64>,
+    encode_time: Duration,
+    decode_time: Duration,
+}
+
+impl Summary {
+    /// Like [`FileStats::ratio`], for every file together, so large files weigh more.
+    fn ratio(&self) -> f64 {
+        (self.pixels * 3) as f64 / self.bruh_size.max(1) as f64
+    }
+}
+
+impl fmt::Display for Corpus {
+    /// A table of the files, followed by the totals.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |time: Duration| time.as_secs_f64() * 1000.0;
+        writeln!(
+            f,
+            "{:<32} {:>11} {:>12} {:>12} {:>7} {:>10} {:>8} {:>10} {:>10}",
+            "file", "dimensions", "raw", "bruh", "ratio", "runs", "px/run", "encode", "decode"
+        )?;
+        for file in &self.files {
+            // Formatted first, since the columns are padded as strings.
+            let path = file.path.display().to_string();
+            let dimensions = format!("{}×{}", file.width, file.height);
+            writeln!(
+                f,
+                "{:<32} {:>11} {:>12} {:>12} {:>6.2}x {:>10} {:>8.1} {:>8.2}ms {:>8.2}ms",
+                path,
+                dimensions,
+                file.pixels() * 3,
+                file.bruh_size,
+                file.ratio(),
+                file.runs,
+                file.pixels() as f64 / file.runs.max(1) as f64,
+                ms(file.encode_time),
+                ms(file.decode_time)
+            )?;
+        }
+
+        let summary = self.summary();
+        writeln!(f)?;
+        writeln!(f, "Files         {} ({} skipped)", self.files.len(), self.skipped.len())?;
+        writeln!(
+            f,
+            "Size          {} bytes as raw RGB, {} as BRUH ({:.2}x overall)",
+            summary.pixels * 3,
+            summary.bruh_size,
+            summary.ratio()
+        )?;
+        if let Some(median) = summary.median_ratio {
+            writeln!(f, "Median ratio  {:.2}x", median)?;
+        }
+        writeln!(
+            f,
+            "Runs          {} ({:.1} pixels a run, {:.1}% single pixels)",
+            summary.runs,
+            summary.pixels as f64 / summary.runs.max(1) as f64,
+            summary.single_pixel_runs as f64 / summary.runs.max(1) as f64 * 100.0
+        )?;
+        let throughput = |time: Duration| summary.pixels as f64 / time.as_secs_f64() / 1e6;
+        writeln!(
+            f,
+            "Encoding      {:.1}ms ({:.1} Mpx/s)",
+            ms(summary.encode_time),
+            throughput(summary.encode_time)
+        )?;
+        write!(
+            f,
+            "Decoding      {:.1}ms ({:.1} Mpx/s)",
+            ms(summary.decode_time),
+            throughput(summary.decode_time)
+        )
+    }
+}
+
+fn measure_file(path: &Path) -> Result<FileStats, Box<dyn Error>> {
+    let file_size = fs::metadata(path)?.len();
+    let (width, height, rgb) = read_rgb(path)?;
+
+    let start = Instant::now();
+    let mut encoder = BruhEncoder::new(Vec::new(), width, height)?;
+    encoder.push_pixels(&rgb)?;
+    let encoded = encoder.finish()?;
+    let encode_time = start.elapsed();
+
+    let start = Instant::now();
+    decode_bruh(&encoded)?;
+    let decode_time = start.elapsed();
+
+    let runs = RunStats::of(&encoded)?;
+    Ok(FileStats {
+        path: path.to_path_buf(),
+        width,
+        height,
+        file_size,
+        bruh_size: encoded.len() as u64,
+        runs: runs.runs,
+        single_pixel_runs: runs.lengths.get(&1).copied().unwrap_or(0),
+        encode_time,
+        decode_time,
+    })
+}
+
+/// Every file under `root`, sorted.
+pub fn find_files(root: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    let mut folders = vec![root.to_path_buf()];
+    while let Some(folder) = folders.pop() {
+        for entry in fs::read_dir(&folder)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                folders.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
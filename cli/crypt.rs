@@ -0,0 +1,211 @@
+use std::{
+    env,
+    error::Error,
+    io::{self, BufRead, IsTerminal, Write},
+};
+
+use argon2::Argon2;
+use bruh::{ENCRYPTED_MAGIC, HEADER_SIZE};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    terminal::{disable_raw_mode, enable_raw_mode},
+};
+use rand::{rngs::OsRng, RngCore};
+
+pub const SALT_LEN: usize = 16;
+pub const NONCE_LEN: usize = 24;
+const TAG_LEN: usize = 16;
+/// Everything in an encrypted file before the ciphertext.
+const PREFIX_LEN: usize = HEADER_SIZE + ENCRYPTED_MAGIC.len() + SALT_LEN + NONCE_LEN;
+
+/// Where the passphrase comes from if it's set, instead of asking for it.
+pub const PASSPHRASE_VAR: &str = "BRUH_PASSPHRASE";
+
+/// Encrypts the runs of the .bruh file `contents`, keeping its header readable.
+///
+/// The key comes from `passphrase` through Argon2id with a random salt, and the runs are
+/// sealed with XChaCha20-Poly1305. The header is authenticated along with them, so
+/// changing the size it claims makes decryption fail.
+pub fn encrypt(contents: &[u8], passphrase: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    if contents.len() < HEADER_SIZE {
+        return Err("Not a .bruh file: it's too short for the header.".into());
+    }
+    let (header, runs) = contents.split_at(HEADER_SIZE);
+
+    let mut salt = [0; SALT_LEN];
+    let mut nonce = [0; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut encrypted = Vec::with_capacity(PREFIX_LEN + runs.len() + TAG_LEN);
+    encrypted.extend_from_slice(header);
+    encrypted.extend_from_slice(ENCRYPTED_MAGIC);
+    encrypted.extend_from_slice(&salt);
+    encrypted.extend_from_slice(&nonce);
+
+    let payload = Payload {
+        msg: runs,
+        aad: &encrypted[..HEADER_SIZE + ENCRYPTED_MAGIC.len() + SALT_LEN],
+    };
+    let ciphertext = cipher(passphrase, &salt)?
+        .encrypt(XNonce::from_slice(&nonce), payload)
+        .map_err(|_| "Encryption failed.")?;
+    encrypted.extend_from_slice(&ciphertext);
+    Ok(encrypted)
+}
+
+/// Turns what [`encrypt`] wrote back into a plain .bruh file.
+pub fn decrypt(contents: &[u8], passphrase: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    if !is_encrypted(contents) {
+        return Err("This .bruh file isn't encrypted.".into());
+    }
+    let (prefix, ciphertext) = contents.split_at(PREFIX_LEN);
+    let (authenticated, nonce) = prefix.split_at(PREFIX_LEN - NONCE_LEN);
+    let salt = &authenticated[HEADER_SIZE + ENCRYPTED_MAGIC.len()..];
+
+    let payload = Payload {
+        msg: ciphertext,
+        aad: authenticated,
+    };
+    let runs = cipher(passphrase, salt)?
+        .decrypt(XNonce::from_slice(nonce), payload)
+        .map_err(|_| "Wrong passphrase, or the file has been changed.")?;
+
+    let mut decrypted = Vec::with_capacity(HEADER_SIZE + runs.len());
+    decrypted.extend_from_slice(&contents[..HEADER_SIZE]);
+    decrypted.extend_from_slice(&runs);
+    Ok(decrypted)
+}
+
+/// Whether `contents` looks like what [`encrypt`] writes.
+///
+/// A plain .bruh file could start its runs with the same bytes, so check that it isn't
+/// a valid one first if that matters.
+pub fn is_encrypted(contents: &[u8]) -> bool {
+    contents.len() >= PREFIX_LEN + TAG_LEN
+        && &contents[HEADER_SIZE..HEADER_SIZE + ENCRYPTED_MAGIC.len()] == ENCRYPTED_MAGIC
+}
+
+/// The passphrase from [`PASSPHRASE_VAR`], or else typed on the terminal without being
+/// shown, unless `stdin_in_use` because an image is being read from there. With `confirm`,
+/// as when encrypting, it has to be typed twice, since a typo would lock the file for good.
+pub fn passphrase(stdin_in_use: bool, confirm: bool) -> Result<String, Box<dyn Error>> {
+    if let Ok(passphrase) = env::var(PASSPHRASE_VAR) {
+        return Ok(passphrase);
+    }
+    if stdin_in_use {
+        return Err(format!(
+            "The image comes from standard input, so set {} to the passphrase.",
+            PASSPHRASE_VAR
+        )
+        .into());
+    }
+
+    let passphrase = read_hidden("Passphrase: ")?;
+    if passphrase.is_empty() {
+        return Err("The passphrase can't be empty.".into());
+    }
+    if confirm && read_hidden("Passphrase again: ")? != passphrase {
+        return Err("The passphrases don't match.".into());
+    }
+    Ok(passphrase)
+}
+
+/// Reads a line after showing `prompt` on standard error. On a terminal, what's typed
+/// isn't echoed; piped input is read as it is.
+fn read_hidden(prompt: &str) -> Result<String, Box<dyn Error>> {
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+    if !io::stdin().is_terminal() {
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line)?;
+        return Ok(line.trim_end_matches(['\r', '\n']).to_owned());
+    }
+
+    enable_raw_mode()?;
+    let line = read_line_raw();
+    disable_raw_mode()?;
+    eprintln!();
+    line
+}
+
+/// Collects typed characters up to Enter while the terminal is in raw mode.
+fn read_line_raw() -> Result<String, Box<dyn Error>> {
+    let mut line = String::new();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(line),
+            KeyCode::Char('c' | 'd') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Err("No passphrase given.".into());
+            }
+            KeyCode::Esc => return Err("No passphrase given.".into()),
+            KeyCode::Backspace => {
+                line.pop();
+            }
+            KeyCode::Char(c) => line.push(c),
+            _ => {}
+        }
+    }
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<XChaCha20Poly1305, Box<dyn Error>> {
+    let mut key = [0; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Couldn't derive a key from the passphrase: {}", e))?;
+    Ok(XChaCha20Poly1305::new(&key.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bruh::{decode_bruh, encode_bruh};
+
+    fn image() -> Vec<u8> {
+        encode_bruh(3, 2, (0..6u8).map(|i| [i * 40, 255 - i, 7]))
+    }
+
+    #[test]
+    fn decrypts_what_it_encrypted() {
+        let plain = image();
+        let encrypted = encrypt(&plain, "hunter2").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert_eq!(encrypted[..HEADER_SIZE], plain[..HEADER_SIZE]);
+        assert!(decode_bruh(&encrypted).is_err());
+        assert_eq!(decrypt(&encrypted, "hunter2").unwrap(), plain);
+    }
+
+    #[test]
+    fn rejects_the_wrong_passphrase() {
+        let encrypted = encrypt(&image(), "hunter2").unwrap();
+        assert!(decrypt(&encrypted, "hunter3").is_err());
+    }
+
+    #[test]
+    fn rejects_changed_files() {
+        let encrypted = encrypt(&image(), "hunter2").unwrap();
+
+        let mut tag = encrypted.clone();
+        *tag.last_mut().unwrap() ^= 1;
+        assert!(decrypt(&tag, "hunter2").is_err());
+
+        let mut header = encrypted.clone();
+        header[0] ^= 1;
+        assert!(decrypt(&header, "hunter2").is_err());
+
+        let truncated = &encrypted[..encrypted.len() - 1];
+        assert!(decrypt(truncated, "hunter2").is_err());
+        assert!(decrypt(&encrypted[..PREFIX_LEN + TAG_LEN - 1], "hunter2").is_err());
+    }
+}
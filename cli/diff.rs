@@ -0,0 +1,230 @@
+use std::{error::Error, fmt, fmt::Write as _, io::Write, path::Path};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use bruh::DecodedImage;
+
+use crate::{
+    input::read_rgb,
+    output::{png_bytes, Output},
+    serve::html_escape,
+};
+
+/// Color that marks tiles that differ.
+const HIGHLIGHT: [u8; 3] = [230, 40, 40];
+/// How strongly a differing tile is tinted with [`HIGHLIGHT`], from 0 to 1.
+const TINT: f32 = 0.35;
+/// How much differences are brightened in the difference image, so small ones show up.
+const DIFFERENCE_GAIN: u16 = 4;
+
+/// A tile where the images differ.
+pub struct ChangedTile {
+    /// Position of the tile's top-left pixel.
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Pixels in the tile that aren't the same in both images.
+    pub pixels: u64,
+    /// The largest difference in any channel of any of those pixels.
+    pub max_delta: u8,
+}
+
+/// How two images of the same size differ, tile by tile.
+pub struct Diff {
+    pub width: u32,
+    pub height: u32,
+    pub tile_size: u32,
+    pub tiles: u64,
+    pub changed: Vec<ChangedTile>,
+    before: Vec<u8>,
+    after: Vec<u8>,
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pixels: u64 = self.changed.iter().map(|tile| tile.pixels).sum();
+        write!(
+            f,
+            "{} of {} tiles ({}×{} pixels) differ, {} pixels in all.",
+            self.changed.len(),
+            self.tiles,
+            self.tile_size,
+            self.tile_size,
+            pixels
+        )
+    }
+}
+
+/// Compares the images at `before` and `after`, which can be .bruh files or any image
+/// [`read_rgb`] reads, in square tiles of `tile_size` pixels.
+pub fn diff(before: &Path, after: &Path, tile_size: u32) -> Result<Diff, Box<dyn Error>> {
+    let (width, height, before_rgb) = read_rgb(before)?;
+    let (after_width, after_height, after_rgb) = read_rgb(after)?;
+    if (width, height) != (after_width, after_height) {
+        return Err(format!(
+            "The images are different sizes: {}×{} and {}×{} pixels.",
+            width, height, after_width, after_height
+        )
+        .into());
+    }
+
+    let tiles_across = (width as u64).div_ceil(tile_size as u64);
+    let tiles_down = (height as u64).div_ceil(tile_size as u64);
+    let mut changed = Vec::new();
+    for tile_y in 0..tiles_down as u32 {
+        for tile_x in 0..tiles_across as u32 {
+            let (x, y) = (tile_x * tile_size, tile_y * tile_size);
+            let mut tile = ChangedTile {
+                x,
+                y,
+                width: tile_size.min(width - x),
+                height: tile_size.min(height - y),
+                pixels: 0,
+                max_delta: 0,
+            };
+            for row in y..y + tile.height {
+                let start = (row as usize * width as usize + x as usize) * 3;
+                let end = start + tile.width as usize * 3;
+                let pairs = before_rgb[start..end]
+                    .chunks_exact(3)
+                    .zip(after_rgb[start..end].chunks_exact(3));
+                for (a, b) in pairs.filter(|(a, b)| a != b) {
+                    tile.pixels += 1;
+                    let delta = a.iter().zip(b).map(|(a, b)| a.abs_diff(*b)).max();
+                    tile.max_delta = tile.max_delta.max(delta.unwrap_or(0));
+                }
+            }
+            if tile.pixels > 0 {
+                changed.push(tile);
+            }
+        }
+    }
+
+    Ok(Diff {
+        width,
+        height,
+        tile_size,
+        tiles: tiles_across * tiles_down,
+        changed,
+        before: before_rgb,
+        after: after_rgb,
+    })
+}
+
+impl Diff {
+    /// The second image with the tiles that differ tinted and outlined.
+    fn highlighted(&self) -> DecodedImage {
+        let mut pixels = rgba(&self.after);
+        for tile in &self.changed {
+            for y in tile.y..tile.y + tile.height {
+                for x in tile.x..tile.x + tile.width {
+                    let i = (y as usize * self.width as usize + x as usize) * 4;
+                    let edge = x == tile.x
+                        || y == tile.y
+                        || x == tile.x + tile.width - 1
+                        || y == tile.y + tile.height - 1;
+                    for (channel, &highlight) in pixels[i..i + 3].iter_mut().zip(&HIGHLIGHT) {
+                        *channel = if edge {
+                            highlight
+                        } else {
+                            (*channel as f32 * (1.0 - TINT) + highlight as f32 * TINT) as u8
+                        };
+                    }
+                }
+            }
+        }
+        self.image(pixels)
+    }
+
+    /// The difference of every pixel, brightened by [`DIFFERENCE_GAIN`], on black.
+    fn difference(&self) -> DecodedImage {
+        let difference: Vec<u8> = self
+            .before
+            .iter()
+            .zip(&self.after)
+            .map(|(a, b)| (a.abs_diff(*b) as u16 * DIFFERENCE_GAIN).min(255) as u8)
+            .collect();
+        self.image(rgba(&difference))
+    }
+
+    fn image(&self, pixels: Vec<u8>) -> DecodedImage {
+        DecodedImage {
+            width: self.width,
+            height: self.height,
+            pixels,
+        }
+    }
+
+    /// Writes the report to `output`: the highlighted image alone if it ends in `.png`,
+    /// or else a self-contained HTML page showing both images, the highlighted one and
+    /// the difference, followed by a table of the tiles that differ.
+    pub fn write_report(&self, output: &Path, names: (&str, &str)) -> Result<(), Box<dyn Error>> {
+        let is_png = output
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+        let report = if is_png {
+            png_bytes(&self.highlighted())?
+        } else {
+            self.html(names)?.into_bytes()
+        };
+
+        let mut file = Output::create(output)?;
+        file.write_all(&report)?;
+        file.commit()?;
+        Ok(())
+    }
+
+    fn html(&self, (before, after): (&str, &str)) -> Result<String, Box<dyn Error>> {
+        let data_uri = |image: &DecodedImage| -> Result<String, Box<dyn Error>> {
+            Ok(format!("data:image/png;base64,{}", BASE64.encode(png_bytes(image)?)))
+        };
+        let (before, after) = (html_escape(before), html_escape(after));
+
+        let mut page = String::new();
+        write!(
+            page,
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{0} → {1}</title>\
+             <style>body{{font-family:sans-serif}}figure{{display:inline-block;margin:8px}}\
+             img{{max-width:480px;image-rendering:pixelated}}td,th{{padding:2px 12px;\
+             text-align:right}}</style></head><body><h1>{0} → {1}</h1><p>{2}</p>\n",
+            before, after, self
+        )?;
+        let figures = [
+            (before.as_str(), self.image(rgba(&self.before))),
+            (after.as_str(), self.image(rgba(&self.after))),
+            ("Changed tiles", self.highlighted()),
+            ("Difference", self.difference()),
+        ];
+        for (caption, image) in &figures {
+            writeln!(
+                page,
+                "<figure><img src=\"{}\" alt=\"{1}\"><figcaption>{1}</figcaption></figure>",
+                data_uri(image)?,
+                caption
+            )?;
+        }
+
+        if !self.changed.is_empty() {
+            page.push_str(
+                "<table><tr><th>x</th><th>y</th><th>size</th><th>pixels changed</th>\
+                 <th>largest change</th></tr>\n",
+            );
+            for tile in &self.changed {
+                writeln!(
+                    page,
+                    "<tr><td>{}</td><td>{}</td><td>{}×{}</td><td>{}</td><td>{}</td></tr>",
+                    tile.x, tile.y, tile.width, tile.height, tile.pixels, tile.max_delta
+                )?;
+            }
+            page.push_str("</table>\n");
+        }
+        page.push_str("</body></html>\n");
+        Ok(page)
+    }
+}
+
+fn rgba(rgb: &[u8]) -> Vec<u8> {
+    rgb.chunks_exact(3)
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+        .collect()
+}
@@ -0,0 +1,221 @@
+use std::{error::Error, io::Write, path::Path};
+
+use bruh::{
+    check_bruh, decode_bruh, BruhEncoder, DecodedImage, Runs, DATA_MAGIC, FILL_RUNS_MAGIC,
+    HEADER_SIZE,
+};
+
+use crate::{input::read_source, output::Output};
+
+/// Marks an image that has something hidden in its colors.
+const MAGIC: &[u8; 4] = b"BEMB";
+
+/// The magic, the payload's length and its CRC-32, each stored ahead of the payload.
+const HEADER_LEN: usize = 12;
+
+/// Stores the contents of `payload` in a [`DATA_MAGIC`] chunk of the .bruh image `input`,
+/// writing the result to `output`. Returns the payload's size.
+///
+/// The runs are copied as they are, so the image doesn't change at all, and decoders skip
+/// the chunk. Data embedded before is replaced, and a signature is dropped, since it
+/// wouldn't match any more.
+pub fn embed(input: &Path, payload: &Path, output: &Path) -> Result<usize, Box<dyn Error>> {
+    let mut contents = Vec::new();
+    read_source(input, &mut contents)?;
+    check_bruh(&contents)?;
+    let mut payload_bytes = Vec::new();
+    read_source(payload, &mut payload_bytes)?;
+    let len = u32::try_from(payload_bytes.len()).map_err(|_| {
+        format!(
+            "{} is {} bytes, but a file can only hold {}.",
+            payload.display(),
+            payload_bytes.len(),
+            u32::MAX
+        )
+    })?;
+
+    let runs = Runs::of(&contents);
+    let mut file = Output::create(output)?;
+    file.write_all(&contents[..HEADER_SIZE])?;
+    file.write_all(DATA_MAGIC)?;
+    file.write_all(&len.to_ne_bytes())?;
+    file.write_all(&payload_bytes)?;
+    if runs.fills {
+        file.write_all(FILL_RUNS_MAGIC)?;
+    }
+    file.write_all(runs.bytes)?;
+    file.commit()?;
+    Ok(payload_bytes.len())
+}
+
+/// Hides the contents of `payload` in the lowest bit of each color channel of the .bruh
+/// image `input` instead, writing the result to `output`.
+///
+/// Unlike [`embed`], this is lossy: colors change by at most 1, and as that breaks up
+/// runs, the file usually gets bigger. What it hides survives tools that drop chunks they
+/// don't know, though. Each pixel holds 3 bits, and the payload is stored with its length
+/// and CRC-32 so [`extract`] can tell a damaged or missing payload from a real one.
+///
+/// Returns how many bytes the image could have held.
+pub fn embed_lsb(input: &Path, payload: &Path, output: &Path) -> Result<usize, Box<dyn Error>> {
+    let mut contents = Vec::new();
+    read_source(input, &mut contents)?;
+    let mut image = decode_bruh(&contents)?;
+    let mut payload_bytes = Vec::new();
+    read_source(payload, &mut payload_bytes)?;
+
+    let capacity = capacity(&image);
+    let len = u32::try_from(payload_bytes.len()).ok().filter(|_| payload_bytes.len() <= capacity);
+    let Some(len) = len else {
+        return Err(format!(
+            "{} is {} bytes, but this image can only hold {}.",
+            payload.display(),
+            payload_bytes.len(),
+            capacity
+        )
+        .into());
+    };
+
+    let mut message = Vec::with_capacity(HEADER_LEN + payload_bytes.len());
+    message.extend_from_slice(MAGIC);
+    message.extend_from_slice(&len.to_le_bytes());
+    message.extend_from_slice(&crc32(&payload_bytes).to_le_bytes());
+    message.extend_from_slice(&payload_bytes);
+
+    let bits = message
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |bit| (byte >> bit) & 1));
+    for (channel, bit) in channels(&mut image.pixels).zip(bits) {
+        *channel = (*channel & !1) | bit;
+    }
+
+    let mut encoder = BruhEncoder::new(Output::create(output)?, image.width, image.height)?;
+    for pixel in image.pixels.chunks_exact(4) {
+        encoder.push([pixel[0], pixel[1], pixel[2]])?;
+    }
+    encoder.finish()?.commit()?;
+    Ok(capacity)
+}
+
+/// Recovers what [`embed`] or [`embed_lsb`] stored in `input` and writes it to `output`,
+/// returning its size.
+pub fn extract(input: &Path, output: &Path) -> Result<usize, Box<dyn Error>> {
+    let mut contents = Vec::new();
+    read_source(input, &mut contents)?;
+    check_bruh(&contents)?;
+    let payload = match Runs::of(&contents).data {
+        Some(data) => data.to_vec(),
+        None => extract_lsb(&contents)?,
+    };
+
+    let mut file = Output::create(output)?;
+    file.write_all(&payload)?;
+    file.commit()?;
+    Ok(payload.len())
+}
+
+/// What [`embed_lsb`] hid in the colors of the .bruh file `contents`.
+fn extract_lsb(contents: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut image = decode_bruh(contents)?;
+    let mut bits = channels(&mut image.pixels).map(|channel| *channel & 1);
+    let mut next_byte = || -> Option<u8> {
+        (0..8).try_fold(0, |byte, _| Some((byte << 1) | bits.next()?))
+    };
+    let mut read = |len: usize| -> Option<Vec<u8>> { (0..len).map(|_| next_byte()).collect() };
+
+    let not_found = "Nothing is embedded in this image.";
+    let header = read(HEADER_LEN).ok_or(not_found)?;
+    if &header[..4] != MAGIC {
+        return Err(not_found.into());
+    }
+    let len = u32::from_le_bytes(header[4..8].try_into().expect("4 bytes")) as usize;
+    let crc = u32::from_le_bytes(header[8..12].try_into().expect("4 bytes"));
+    let payload = read(len).ok_or("The embedded data is longer than the image can hold.")?;
+    if crc32(&payload) != crc {
+        return Err("The embedded data is damaged: its checksum doesn't match.".into());
+    }
+    Ok(payload)
+}
+
+/// Bytes of payload the image can hold, after the header.
+fn capacity(image: &DecodedImage) -> usize {
+    (image.pixels.len() / 4 * 3 / 8).saturating_sub(HEADER_LEN)
+}
+
+/// The red, green and blue channels of RGBA pixels, in order.
+fn channels(pixels: &mut [u8]) -> impl Iterator<Item = &mut u8> {
+    pixels.chunks_exact_mut(4).flat_map(|pixel| pixel[..3].iter_mut())
+}
+
+/// The CRC-32 used by zip and PNG.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use bruh::encode_bruh;
+
+    fn pixels() -> Vec<[u8; 3]> {
+        (0..400u32).map(|i| [(i / 40) as u8, 7, 200]).collect()
+    }
+
+    #[test]
+    fn chunks_keep_the_image_as_it_was() {
+        let dir = tempfile::tempdir().unwrap();
+        let (image, payload) = (dir.path().join("a.bruh"), dir.path().join("secret.txt"));
+        let original = encode_bruh(20, 20, pixels());
+        fs::write(&image, &original).unwrap();
+        fs::write(&payload, "a secret").unwrap();
+
+        let embedded = dir.path().join("a.embedded.bruh");
+        assert_eq!(embed(&image, &payload, &embedded).unwrap(), 8);
+        let contents = fs::read(&embedded).unwrap();
+        assert_eq!(Runs::of(&contents).bytes, Runs::of(&original).bytes);
+        assert_eq!(decode_bruh(&contents).unwrap().pixels, decode_bruh(&original).unwrap().pixels);
+
+        // Embedding again replaces what was there.
+        fs::write(&payload, "another").unwrap();
+        embed(&embedded, &payload, &embedded).unwrap();
+        let extracted = dir.path().join("out.txt");
+        assert_eq!(extract(&embedded, &extracted).unwrap(), 7);
+        assert_eq!(fs::read(&extracted).unwrap(), b"another");
+    }
+
+    #[test]
+    fn lsb_round_trips_within_capacity() {
+        let dir = tempfile::tempdir().unwrap();
+        let (image, payload) = (dir.path().join("a.bruh"), dir.path().join("secret.txt"));
+        fs::write(&image, encode_bruh(20, 20, pixels())).unwrap();
+
+        let embedded = dir.path().join("a.embedded.bruh");
+        let capacity = 400 * 3 / 8 - HEADER_LEN;
+        fs::write(&payload, vec![0xa5; capacity + 1]).unwrap();
+        assert!(embed_lsb(&image, &payload, &embedded).is_err());
+
+        fs::write(&payload, vec![0xa5; capacity]).unwrap();
+        assert_eq!(embed_lsb(&image, &payload, &embedded).unwrap(), capacity);
+        let contents = fs::read(&embedded).unwrap();
+        assert_eq!(Runs::of(&contents).data, None);
+        let extracted = dir.path().join("out.txt");
+        assert_eq!(extract(&embedded, &extracted).unwrap(), capacity);
+        assert_eq!(fs::read(&extracted).unwrap(), vec![0xa5; capacity]);
+
+        assert!(extract(&image, &extracted).is_err());
+    }
+}
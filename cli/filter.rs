@@ -0,0 +1,290 @@
+use std::{
+    env::{self, consts},
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bruh::{decode_bruh, file::config_dir, FilteredEncoder, PixelFilter};
+use image::RgbaImage;
+use libloading::{Library, Symbol};
+
+use crate::{
+    input::{image_error, read_rgb, read_source, sniff_format},
+    output::Output,
+};
+
+/// Where filter plugins are looked for instead of the default folder, if it's set.
+pub const PLUGINS_VAR: &str = "BRUH_PLUGINS";
+
+/// Gap between a watermark and the edges it's placed against, in pixels.
+const WATERMARK_MARGIN: u32 = 16;
+
+/// Where a watermark goes, horizontally and vertically.
+#[derive(Clone, Copy)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+}
+
+/// Names `--position` takes, with the horizontal and vertical alignment of each.
+pub const POSITIONS: &[(&str, Align, Align)] = &[
+    ("top-left", Align::Start, Align::Start),
+    ("top", Align::Center, Align::Start),
+    ("top-right", Align::End, Align::Start),
+    ("left", Align::Start, Align::Center),
+    ("center", Align::Center, Align::Center),
+    ("right", Align::End, Align::Center),
+    ("bottom-left", Align::Start, Align::End),
+    ("bottom", Align::Center, Align::End),
+    ("bottom-right", Align::End, Align::End),
+];
+
+/// Where a watermark goes: against the edges or the center, or at exact coordinates.
+#[derive(Clone, Copy)]
+pub enum Placement {
+    /// Aligned horizontally and vertically, a small margin away from the edges.
+    Aligned(Align, Align),
+    /// With its top-left corner at these coordinates, which may be negative.
+    At(i64, i64),
+}
+
+/// How a watermark's colors are combined with the image's before its alpha is applied.
+#[derive(Clone, Copy)]
+pub enum Blend {
+    /// The watermark's color replaces the image's.
+    Normal,
+    /// The colors are multiplied, so the result is never lighter than either.
+    Multiply,
+    /// The colors are added, clamped to white.
+    Add,
+}
+
+/// Names `--blend` takes.
+pub const BLENDS: &[(&str, Blend)] = &[
+    ("normal", Blend::Normal),
+    ("multiply", Blend::Multiply),
+    ("add", Blend::Add),
+];
+
+/// Composites an image such as a logo over the one being encoded.
+///
+/// The watermark keeps its own size, and any part that doesn't fit is cut off.
+pub struct Watermark {
+    image: RgbaImage,
+    opacity: f32,
+    placement: Placement,
+    blend: Blend,
+    /// Where the watermark's top-left corner lands, worked out once the size is known.
+    origin: (i64, i64),
+}
+
+impl Watermark {
+    /// Loads the watermark from `path`, which can be a .bruh file or any image the
+    /// `image` crate reads. Its alpha channel is kept and scaled by `opacity`; .bruh files
+    /// have none, so they're opaque.
+    pub fn load(
+        path: &Path,
+        placement: Placement,
+        opacity: f32,
+        blend: Blend,
+    ) -> Result<Watermark, Box<dyn Error>> {
+        let mut contents = Vec::new();
+        read_source(path, &mut contents)?;
+        let image = match sniff_format(&contents) {
+            Some(format) => image::load_from_memory_with_format(&contents, format)
+                .map_err(|e| image_error(path, e))?
+                .into_rgba8(),
+            // .bruh files have no signature, so anything unrecognized is tried as one.
+            None => {
+                let decoded = decode_bruh(&contents)?;
+                RgbaImage::from_raw(decoded.width, decoded.height, decoded.pixels)
+                    .expect("decoded into RGBA")
+            }
+        };
+        Ok(Watermark {
+            image,
+            opacity,
+            placement,
+            blend,
+            origin: (0, 0),
+        })
+    }
+}
+
+impl PixelFilter for Watermark {
+    fn begin(&mut self, width: u32, height: u32) {
+        let place = |align, outer: u32, inner: u32| -> i64 {
+            let margin = WATERMARK_MARGIN.min(outer.saturating_sub(inner) / 2) as i64;
+            match align {
+                Align::Start => margin,
+                Align::Center => (outer as i64 - inner as i64) / 2,
+                Align::End => outer as i64 - inner as i64 - margin,
+            }
+        };
+        self.origin = match self.placement {
+            Placement::Aligned(horizontal, vertical) => (
+                place(horizontal, width, self.image.width()),
+                place(vertical, height, self.image.height()),
+            ),
+            Placement::At(x, y) => (x, y),
+        };
+    }
+
+    fn filter_row(&mut self, y: u32, row: &mut [u8]) {
+        let (left, top) = self.origin;
+        let Ok(mark_y) = u32::try_from(y as i64 - top) else {
+            return;
+        };
+        if mark_y >= self.image.height() {
+            return;
+        }
+
+        for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+            let Ok(mark_x) = u32::try_from(x as i64 - left) else {
+                continue;
+            };
+            if mark_x >= self.image.width() {
+                break;
+            }
+            let mark = self.image.get_pixel(mark_x, mark_y).0;
+            let alpha = mark[3] as f32 / 255.0 * self.opacity;
+            for (channel, &over) in pixel[..3].iter_mut().zip(&mark[..3]) {
+                let over = match self.blend {
+                    Blend::Normal => over as f32,
+                    Blend::Multiply => *channel as f32 * over as f32 / 255.0,
+                    Blend::Add => (*channel as f32 + over as f32).min(255.0),
+                };
+                let blended = *channel as f32 * (1.0 - alpha) + over * alpha;
+                *channel = blended.round() as u8;
+            }
+        }
+    }
+}
+
+/// Draws `overlay` onto the image at `base` and saves the result as a .bruh file at
+/// `output`, returning its size. The base can be anything [`read_rgb`] reads.
+pub fn composite(
+    base: &Path,
+    overlay: Watermark,
+    output: &Path,
+) -> Result<(u32, u32), Box<dyn Error>> {
+    let (width, height, rgb) = read_rgb(base)?;
+    let filters: Vec<Box<dyn PixelFilter>> = vec![Box::new(overlay)];
+    let file = Output::create(output)?;
+    let mut encoder = FilteredEncoder::with_filters(file, width, height, filters)?;
+    encoder.push_pixels(&rgb)?;
+    encoder.finish()?.commit()?;
+    Ok((width, height))
+}
+
+/// Called with the image's size before its first row, if the plugin exports it.
+type BeginFn = unsafe extern "C" fn(width: u32, height: u32);
+/// Changes a row of `width` RGBA8 pixels in place.
+type FilterRowFn = unsafe extern "C" fn(y: u32, width: u32, row: *mut u8);
+
+/// A [`PixelFilter`] in a dynamic library from the plugins folder.
+///
+/// The library has to export `bruh_filter_row` with the signature of [`FilterRowFn`], and
+/// may export `bruh_filter_begin` like [`BeginFn`]. Loading one runs its code with this
+/// program's permissions, so only install plugins you trust.
+pub struct Plugin {
+    begin: Option<BeginFn>,
+    filter_row: FilterRowFn,
+    // Declared last so it outlives the function pointers into it.
+    _library: Library,
+}
+
+impl Plugin {
+    /// Loads the plugin called `name` from [`plugins_dir`], or from `name` itself if it's a
+    /// path.
+    pub fn load(name: &str) -> Result<Plugin, Box<dyn Error>> {
+        let path = if name.contains(['/', '\\']) {
+            PathBuf::from(name)
+        } else {
+            let dir = plugins_dir().ok_or("There's no plugins folder on this system.")?;
+            let path = dir.join(library_file_name(name));
+            if !path.is_file() {
+                return Err(format!(
+                    "There's no filter called '{}' in {}.",
+                    name,
+                    dir.display()
+                )
+                .into());
+            }
+            path
+        };
+
+        // SAFETY: loading a library runs its initializers, and the functions are trusted
+        // to match the signatures documented above; that's what installing a plugin means.
+        unsafe {
+            let library = Library::new(&path)
+                .map_err(|e| format!("Couldn't load {}: {}", path.display(), e))?;
+            let filter_row: Symbol<FilterRowFn> = library
+                .get(b"bruh_filter_row\0")
+                .map_err(|_| format!("{} doesn't export bruh_filter_row.", path.display()))?;
+            let filter_row = *filter_row;
+            let begin = library
+                .get::<BeginFn>(b"bruh_filter_begin\0")
+                .ok()
+                .map(|begin| *begin);
+            Ok(Plugin {
+                begin,
+                filter_row,
+                _library: library,
+            })
+        }
+    }
+}
+
+impl PixelFilter for Plugin {
+    fn begin(&mut self, width: u32, height: u32) {
+        if let Some(begin) = self.begin {
+            // SAFETY: see `Plugin::load`.
+            unsafe { begin(width, height) }
+        }
+    }
+
+    fn filter_row(&mut self, y: u32, row: &mut [u8]) {
+        // SAFETY: see `Plugin::load`. The row holds exactly `width` RGBA pixels.
+        unsafe { (self.filter_row)(y, (row.len() / 4) as u32, row.as_mut_ptr()) }
+    }
+}
+
+/// Where plugins are installed: [`PLUGINS_VAR`] if it's set, or else a `plugins` folder
+/// in this program's config folder.
+pub fn plugins_dir() -> Option<PathBuf> {
+    env::var_os(PLUGINS_VAR)
+        .map(PathBuf::from)
+        .or_else(|| config_dir().map(|dir| dir.join("bruh").join("plugins")))
+}
+
+/// Names of the plugins in [`plugins_dir`], sorted.
+pub fn installed_plugins() -> Result<Vec<String>, Box<dyn Error>> {
+    let Some(dir) = plugins_dir().filter(|dir| dir.is_dir()) else {
+        return Ok(Vec::new());
+    };
+    let mut names = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if let Some(name) = plugin_name(&path) {
+            names.push(name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// The file a plugin called `name` is in on this platform, e.g. `libsepia.so`.
+fn library_file_name(name: &str) -> String {
+    format!("{}{}{}", consts::DLL_PREFIX, name, consts::DLL_SUFFIX)
+}
+
+fn plugin_name(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    let name = file_name
+        .strip_prefix(consts::DLL_PREFIX)?
+        .strip_suffix(consts::DLL_SUFFIX)?;
+    (!name.is_empty()).then(|| name.to_owned())
+}
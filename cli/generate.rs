@@ -0,0 +1,84 @@
+use std::error::Error;
+
+use bruh::BruhEncoder;
+
+use crate::output::Output;
+
+/// Width and height of the squares of [`Pattern::Checker`].
+const CHECKER_SIZE: u32 = 32;
+
+/// A synthetic image `generate` can make.
+#[derive(Clone, Copy)]
+pub enum Pattern {
+    /// Red increases to the right and green downwards, so every pixel differs from its
+    /// neighbours and runs stay short.
+    Gradient,
+    /// Random colors from `--seed`, the worst case for run-length encoding.
+    Noise,
+    /// Squares of the color and black, with long runs that break at every square.
+    Checker,
+    /// The color everywhere, the best case: a single run.
+    Solid,
+}
+
+/// Names `--pattern` takes.
+pub const PATTERNS: &[(&str, Pattern)] = &[
+    ("gradient", Pattern::Gradient),
+    ("noise", Pattern::Noise),
+    ("checker", Pattern::Checker),
+    ("solid", Pattern::Solid),
+];
+
+/// Writes a `width`×`height` image of `pattern` to `output`.
+///
+/// The same arguments always make the same image, whatever the platform or version, so
+/// an image can be described in a bug report by the command that made it.
+pub fn generate(
+    output: Output,
+    pattern: Pattern,
+    (width, height): (u32, u32),
+    color: [u8; 3],
+    seed: u64,
+) -> Result<(), Box<dyn Error>> {
+    let mut encoder = BruhEncoder::new(output, width, height)?;
+    let mut random = SplitMix64(seed);
+    let scale = |value: u32, size: u32| (value as u64 * 255 / (size.max(2) - 1) as u64) as u8;
+
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = match pattern {
+                Pattern::Gradient => {
+                    let (r, g) = (scale(x, width), scale(y, height));
+                    [r, g, 255 - ((r as u16 + g as u16) / 2) as u8]
+                }
+                Pattern::Noise => {
+                    let [r, g, b, ..] = random.next().to_le_bytes();
+                    [r, g, b]
+                }
+                Pattern::Checker if (x / CHECKER_SIZE + y / CHECKER_SIZE).is_multiple_of(2) => {
+                    color
+                }
+                Pattern::Checker => [0, 0, 0],
+                Pattern::Solid => color,
+            };
+            encoder.push(pixel)?;
+        }
+    }
+
+    encoder.finish()?.commit()?;
+    Ok(())
+}
+
+/// A tiny random number generator whose output is fixed by its algorithm, unlike `rand`'s
+/// generators, which may change between versions.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
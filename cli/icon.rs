@@ -0,0 +1,98 @@
+use std::{error::Error, io::Write, path::Path};
+
+use bruh::decode_bruh;
+use image::{
+    codecs::{
+        ico::{IcoEncoder, IcoFrame},
+        png::PngEncoder,
+    },
+    imageops::{self, FilterType},
+    ColorType, ImageEncoder, RgbaImage,
+};
+
+use crate::{input::read_source, output::Output};
+
+/// Sizes in a Windows icon, covering Explorer's views and the taskbar at common scales.
+const ICO_SIZES: &[u32] = &[16, 24, 32, 48, 64, 128, 256];
+
+/// Sizes in a macOS icon and the type code of each, all stored as PNG.
+const ICNS_SIZES: &[(u32, &[u8; 4])] = &[
+    (16, b"icp4"),
+    (32, b"icp5"),
+    (64, b"icp6"),
+    (128, b"ic07"),
+    (256, b"ic08"),
+    (512, b"ic09"),
+];
+
+/// Which kind of icon file [`make_icon`] writes.
+#[derive(Clone, Copy)]
+pub enum IconFormat {
+    Ico,
+    Icns,
+}
+
+/// Writes a .bruh image as an icon with one image per size.
+///
+/// Icons are square, so an image that isn't is scaled to fit and centered on a
+/// transparent background.
+pub fn make_icon(input: &Path, output: &Path, format: IconFormat) -> Result<(), Box<dyn Error>> {
+    let mut contents = Vec::new();
+    read_source(input, &mut contents)?;
+    let decoded = decode_bruh(&contents)?;
+    if decoded.is_empty() {
+        return Err("An icon can't be made from an empty image.".into());
+    }
+    let image = RgbaImage::from_raw(decoded.width, decoded.height, decoded.pixels)
+        .expect("decoded into RGBA");
+
+    let mut file = Output::create(output)?;
+    match format {
+        IconFormat::Ico => {
+            let frames = ICO_SIZES
+                .iter()
+                .map(|&size| {
+                    IcoFrame::as_png(&square(&image, size), size, size, ColorType::Rgba8)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            IcoEncoder::new(&mut file).encode_images(&frames)?;
+        }
+        IconFormat::Icns => {
+            let mut entries = Vec::new();
+            for &(size, code) in ICNS_SIZES {
+                let mut png = Vec::new();
+                PngEncoder::new(&mut png).write_image(
+                    &square(&image, size),
+                    size,
+                    size,
+                    ColorType::Rgba8,
+                )?;
+                entries.extend_from_slice(code);
+                entries.extend_from_slice(&(png.len() as u32 + 8).to_be_bytes());
+                entries.extend_from_slice(&png);
+            }
+            file.write_all(b"icns")?;
+            file.write_all(&(entries.len() as u32 + 8).to_be_bytes())?;
+            file.write_all(&entries)?;
+        }
+    }
+    file.commit()?;
+    Ok(())
+}
+
+/// Scales `image` to fit a `size`×`size` square, centered on transparency.
+fn square(image: &RgbaImage, size: u32) -> RgbaImage {
+    let scale = size as f64 / image.width().max(image.height()) as f64;
+    let width = ((image.width() as f64 * scale).round() as u32).clamp(1, size);
+    let height = ((image.height() as f64 * scale).round() as u32).clamp(1, size);
+    let scaled = imageops::resize(image, width, height, FilterType::Lanczos3);
+
+    let mut icon = RgbaImage::new(size, size);
+    imageops::overlay(
+        &mut icon,
+        &scaled,
+        ((size - width) / 2) as i64,
+        ((size - height) / 2) as i64,
+    );
+    icon
+}
@@ -0,0 +1,146 @@
+use std::{
+    error::Error,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use bruh::{check_bruh, decode_header, DecodeError, HEADER_SIZE};
+use image::ImageFormat;
+
+use crate::{
+    crypt,
+    input::{image_error, read_input, sniff_format, InputError},
+};
+
+/// What a file turned out to be, going by its contents.
+pub struct Identity {
+    pub path: PathBuf,
+    pub format: &'static str,
+    pub mime_type: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub bits_per_channel: u16,
+    pub has_color: bool,
+    pub has_alpha: bool,
+    /// Whether the pixels are encrypted, in which case only the size is known.
+    pub encrypted: bool,
+    /// Size of the file in bytes.
+    pub size: u64,
+}
+
+impl fmt::Display for Identity {
+    /// One line in the layout of ImageMagick's `identify`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}x{} {}x{}+0+0 {}-bit {}{} {}B{}",
+            self.path.display(),
+            self.format,
+            self.width,
+            self.height,
+            self.width,
+            self.height,
+            self.bits_per_channel,
+            if self.has_color { "sRGB" } else { "Gray" },
+            if self.has_alpha { "A" } else { "" },
+            self.size,
+            if self.encrypted { " encrypted" } else { "" }
+        )
+    }
+}
+
+/// Works out the format and properties of the file at `path` from its contents, whatever
+/// its extension.
+///
+/// .bruh files have no signature, so anything whose runs match its header counts as one,
+/// even if it starts like another format. Other formats are recognized by their signature.
+pub fn identify(path: &Path) -> Result<Identity, Box<dyn Error>> {
+    let mut contents = Vec::new();
+    read_input(path, &mut contents)?;
+    let size = contents.len() as u64;
+
+    if let Some(format) = sniff_format(&contents) {
+        let image = image::load_from_memory_with_format(&contents, format)
+            .map_err(|e| image_error(path, e))?;
+        let color = image.color();
+        return Ok(Identity {
+            path: path.to_path_buf(),
+            format: format_name(format),
+            mime_type: mime_type(format),
+            width: image.width(),
+            height: image.height(),
+            bits_per_channel: color.bits_per_pixel() / color.channel_count() as u16,
+            has_color: color.has_color(),
+            has_alpha: color.has_alpha(),
+            encrypted: false,
+            size,
+        });
+    }
+
+    let (width, height, encrypted) = match check_bruh(&contents) {
+        Ok((width, height)) => (width, height, false),
+        // Every frame of an animation is the size in its header.
+        Err(DecodeError::Animated) => {
+            let header = contents[..HEADER_SIZE].try_into().expect("checked by check_bruh");
+            let (width, height) = decode_header(header);
+            (width, height, false)
+        }
+        // The header of an encrypted file stays readable.
+        Err(_) if crypt::is_encrypted(&contents) => {
+            let header = contents[..HEADER_SIZE].try_into().expect("checked by is_encrypted");
+            let (width, height) = decode_header(header);
+            (width, height, true)
+        }
+        Err(e) => {
+            return Err(InputError::Unsupported {
+                path: path.to_path_buf(),
+                reason: format!("not a known image format, and not a valid .bruh file ({})", e),
+            }
+            .into())
+        }
+    };
+    Ok(Identity {
+        path: path.to_path_buf(),
+        format: "BRUH",
+        mime_type: "image/x-bruh",
+        width,
+        height,
+        bits_per_channel: 8,
+        has_color: true,
+        has_alpha: false,
+        encrypted,
+        size,
+    })
+}
+
+fn format_name(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Png => "PNG",
+        ImageFormat::Jpeg => "JPEG",
+        ImageFormat::WebP => "WEBP",
+        ImageFormat::Bmp => "BMP",
+        ImageFormat::Tiff => "TIFF",
+        ImageFormat::Gif => "GIF",
+        ImageFormat::Qoi => "QOI",
+        ImageFormat::Farbfeld => "FARBFELD",
+        ImageFormat::Pnm => "PNM",
+        ImageFormat::Avif => "AVIF",
+        _ => "UNKNOWN",
+    }
+}
+
+fn mime_type(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Png => "image/png",
+        ImageFormat::Jpeg => "image/jpeg",
+        ImageFormat::WebP => "image/webp",
+        ImageFormat::Bmp => "image/bmp",
+        ImageFormat::Tiff => "image/tiff",
+        ImageFormat::Gif => "image/gif",
+        ImageFormat::Qoi => "image/x-qoi",
+        ImageFormat::Farbfeld => "image/x-farbfeld",
+        ImageFormat::Pnm => "image/x-portable-anymap",
+        ImageFormat::Avif => "image/avif",
+        _ => "application/octet-stream",
+    }
+}
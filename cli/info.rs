@@ -0,0 +1,135 @@
+use std::{error::Error, fmt, path::Path};
+
+use bruh::{animation::Animation, check_bruh, decode_header, Runs, HEADER_SIZE};
+
+use crate::{crypt, input::read_source};
+
+/// What the header and chunks of a .bruh file say about it, read without decoding.
+pub struct Info {
+    pub width: u32,
+    pub height: u32,
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// Whether the runs are encrypted, in which case only the header can be read.
+    pub encrypted: bool,
+    pub signed: bool,
+    /// Bytes of data stored with `embed`, if there are any.
+    pub embedded: Option<usize>,
+    /// Whether the file has the marker for runs of length 0 filling the rest.
+    pub fills: bool,
+    /// How many runs there are, or why they don't match the header.
+    pub runs: Result<u64, String>,
+    /// The frames of an animation, which has no runs of its own.
+    pub animation: Option<Frames>,
+}
+
+/// What [`Info`] tells of an animation.
+pub struct Frames {
+    pub count: usize,
+    /// All the frames' delays added up.
+    pub duration_ms: u64,
+    /// Bytes of a last frame that was cut short.
+    pub partial: usize,
+}
+
+impl Info {
+    /// Reads the .bruh file at `path`. Its header has to be whole, but its runs needn't
+    /// match it, so a damaged or encrypted file can still be looked at.
+    pub fn read(path: &Path) -> Result<Info, Box<dyn Error>> {
+        let mut contents = Vec::new();
+        read_source(path, &mut contents)?;
+        Info::of(&contents)
+    }
+
+    /// Like [`Info::read`], for the contents of a .bruh file already in memory.
+    pub fn of(contents: &[u8]) -> Result<Info, Box<dyn Error>> {
+        let Some(header) = contents.get(..HEADER_SIZE) else {
+            return Err(format!(
+                "{} bytes is too short for a .bruh header of {} bytes",
+                contents.len(),
+                HEADER_SIZE
+            )
+            .into());
+        };
+        let (width, height) = decode_header(header.try_into().expect("HEADER_SIZE bytes"));
+        let size = contents.len() as u64;
+
+        // A plain file can start its runs like an encrypted one, but then it matches its header.
+        let checked = check_bruh(contents);
+        if checked.is_err() && crypt::is_encrypted(contents) {
+            return Ok(Info {
+                width,
+                height,
+                size,
+                encrypted: true,
+                signed: false,
+                embedded: None,
+                fills: false,
+                runs: Err("encrypted".to_owned()),
+                animation: None,
+            });
+        }
+        if let Some(animation) = Animation::of(contents)? {
+            return Ok(Info {
+                width,
+                height,
+                size,
+                encrypted: false,
+                signed: false,
+                embedded: None,
+                fills: false,
+                runs: Err("animated".to_owned()),
+                animation: Some(Frames {
+                    count: animation.frames.len(),
+                    duration_ms: animation.frames.iter().map(|f| f.delay_ms as u64).sum(),
+                    partial: animation.partial,
+                }),
+            });
+        }
+
+        let runs = Runs::of(contents);
+        Ok(Info {
+            width,
+            height,
+            size,
+            encrypted: false,
+            signed: runs.signature.is_some(),
+            embedded: runs.data.map(<[u8]>::len),
+            fills: runs.fills,
+            runs: match checked {
+                Ok(_) => Ok(runs.bytes.len() as u64 / 4),
+                Err(e) => Err(e.to_string()),
+            },
+            animation: None,
+        })
+    }
+}
+
+impl fmt::Display for Info {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let yes_no = |value| if value { "yes" } else { "no" };
+        let pixels = self.width as u64 * self.height as u64;
+        writeln!(f, "Size          {}×{} ({} pixels)", self.width, self.height, pixels)?;
+        writeln!(f, "File size     {} bytes", self.size)?;
+        if self.encrypted {
+            return write!(f, "Encrypted     yes, the runs need the passphrase");
+        }
+        writeln!(f, "Encrypted     no")?;
+        if let Some(frames) = &self.animation {
+            write!(f, "Frames        {} ({} ms)", frames.count, frames.duration_ms)?;
+            if frames.partial > 0 {
+                write!(f, "\nCut short     {} bytes of a last frame", frames.partial)?;
+            }
+            return Ok(());
+        }
+        writeln!(f, "Signed        {}", yes_no(self.signed))?;
+        if let Some(embedded) = self.embedded {
+            writeln!(f, "Embedded      {} bytes", embedded)?;
+        }
+        writeln!(f, "Fill runs     {}", yes_no(self.fills))?;
+        match &self.runs {
+            Ok(runs) => write!(f, "Runs          {}", runs),
+            Err(reason) => write!(f, "Runs          damaged: {}", reason),
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use std::{
+    error::Error,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+};
+
+pub use bruh::file::{open_input, read_input, InputError};
+use bruh::{
+    animation::FRAME_HEADER_SIZE, check_bruh, decode_bruh, decode_header, pixels_to_rgb,
+    DecodeError, PixelLayout, ANIMATION_MAGIC, DATA_MAGIC, FILL_RUNS_MAGIC, HEADER_SIZE,
+    SIGNATURE_LEN, SIGNATURE_MAGIC,
+};
+use image::ImageFormat;
+
+use crate::output::is_stdio;
+
+/// Like [`read_input`], but reads standard input when `path` is `-`.
+pub fn read_source(path: &Path, contents: &mut Vec<u8>) -> Result<(), InputError> {
+    if !is_stdio(path) {
+        return read_input(path, contents);
+    }
+    contents.clear();
+    io::stdin()
+        .lock()
+        .read_to_end(contents)
+        .map_err(|error| InputError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+    Ok(())
+}
+
+/// Like [`open_input`], but opens standard input when `path` is `-`.
+pub fn open_source(path: &Path) -> Result<Box<dyn Read>, InputError> {
+    Ok(if is_stdio(path) {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(open_input(path)?)
+    })
+}
+
+/// Reads a .bruh file or an image in a format `image` can sniff as packed RGB, returning
+/// its width, height and pixels. Alpha is flattened the way `compile` does it.
+pub fn read_rgb(path: &Path) -> Result<(u32, u32, Vec<u8>), Box<dyn Error>> {
+    let mut contents = Vec::new();
+    read_source(path, &mut contents)?;
+
+    Ok(match sniff_format(&contents) {
+        Some(format) => {
+            let image = image::load_from_memory_with_format(&contents, format)
+                .map_err(|e| image_error(path, e))?
+                .into_rgba8();
+            let mut rgb = Vec::new();
+            pixels_to_rgb(image.as_raw(), PixelLayout::Rgba, &mut rgb);
+            (image.width(), image.height(), rgb)
+        }
+        // .bruh files have no signature, so anything unrecognized is tried as one.
+        None => {
+            let decoded = decode_bruh(&contents)?;
+            let rgb = decoded
+                .pixels
+                .chunks_exact(4)
+                .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+                .collect();
+            (decoded.width, decoded.height, rgb)
+        }
+    })
+}
+
+/// The format of an image that isn't a .bruh file, by its signature.
+///
+/// .bruh files have none, and their header is the width in native byte order, so some
+/// widths look like another format's signature (19778 is "BM", a BMP). Contents whose runs
+/// match their header are taken to be BRUH before anything is sniffed.
+pub fn sniff_format(contents: &[u8]) -> Option<ImageFormat> {
+    if check_bruh(contents).is_ok() {
+        return None;
+    }
+    image::guess_format(contents).ok()
+}
+
+/// Like `read_exact`, but returns how much was read instead of failing at the end of the file.
+pub fn read_up_to(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Reads the runs of a .bruh file from `reader`, just past its header, passing each color
+/// and how many pixels it covers to `push`. Fails unless they cover exactly the `width` by
+/// `height` pixels in the header.
+///
+/// A signature, data chunk and fill runs marker at the start are skipped, unless `plain`
+/// says they only look like one, see [`plain_runs`]. Of an animation, only the first frame
+/// is read, and the result is `true`.
+pub fn read_runs(
+    reader: &mut impl Read,
+    (width, height): (u32, u32),
+    plain: bool,
+    mut push: impl FnMut(&[u8], u64) -> io::Result<()>,
+) -> Result<bool, Box<dyn Error>> {
+    let pixel_count = width as u64 * height as u64;
+    let mut remaining = pixel_count;
+    let mut run = [0; 4];
+    let mut fills = false;
+    let mut signed = false;
+    let mut has_data = false;
+    let mut animated = false;
+    let mut first = true;
+    while reader.read(&mut run[..1])? > 0 {
+        reader
+            .read_exact(&mut run[1..])
+            .map_err(|_| "Unexpected end of file while reading pixel data.")?;
+        // The first frame of an animation is a .bruh file of its own, read from its runs on.
+        if first && !signed && !animated && run == *ANIMATION_MAGIC {
+            let mut frame_header = [0; FRAME_HEADER_SIZE + HEADER_SIZE];
+            reader
+                .read_exact(&mut frame_header)
+                .map_err(|_| "The animation has no frames.")?;
+            let size = frame_header[FRAME_HEADER_SIZE..].try_into().expect("HEADER_SIZE bytes");
+            let (frame_width, frame_height) = decode_header(size);
+            if (frame_width, frame_height) != (width, height) {
+                return Err(DecodeError::FrameSize {
+                    frame: 0,
+                    width: frame_width,
+                    height: frame_height,
+                }
+                .into());
+            }
+            animated = true;
+            continue;
+        }
+        // A signature and a data chunk can come before the fill runs marker.
+        if !plain && first && !signed && !has_data && run == *SIGNATURE_MAGIC {
+            reader
+                .read_exact(&mut [0; SIGNATURE_LEN])
+                .map_err(|_| "Unexpected end of file while reading the signature.")?;
+            signed = true;
+            continue;
+        }
+        if !plain && first && !has_data && run == *DATA_MAGIC {
+            let mut len = [0; 4];
+            reader
+                .read_exact(&mut len)
+                .map_err(|_| "Unexpected end of file while reading the embedded data.")?;
+            let len = u32::from_ne_bytes(len) as u64;
+            if io::copy(&mut (&mut *reader).take(len), &mut io::sink())? < len {
+                return Err("Unexpected end of file while reading the embedded data.".into());
+            }
+            has_data = true;
+            continue;
+        }
+        if std::mem::take(&mut first) && !plain && run == *FILL_RUNS_MAGIC {
+            fills = true;
+            continue;
+        }
+
+        let run_length = match run[0] {
+            0 if fills => remaining,
+            length => length as u64,
+        };
+        if run_length > remaining {
+            return Err("Pixel data doesn't fit the image dimensions.".into());
+        }
+        remaining -= run_length;
+        push(&run[1..], run_length)?;
+        // The next frame starts after the first one's last pixel.
+        if animated && remaining == 0 {
+            break;
+        }
+    }
+
+    if remaining > 0 {
+        return Err(DecodeError::Truncated {
+            covered: pixel_count - remaining,
+            expected: pixel_count,
+        }
+        .into());
+    }
+    Ok(animated)
+}
+
+/// Whether the .bruh file that `open` opens is version 1 with a first run that only reads
+/// as a signature, data chunk or fill runs marker, which [`Runs::of`](bruh::Runs::of)
+/// tells by which reading of the runs covers the size in the header. [`read_runs`] can't
+/// go back once it's taken one, so this has to be known before it starts.
+///
+/// A file that starts with what reads as one is read through, keeping nothing, once or
+/// twice. A header cut short is left for the reader to report.
+pub fn plain_runs<R: Read>(
+    open: impl Fn() -> Result<R, InputError>,
+) -> Result<bool, Box<dyn Error>> {
+    let mut reader = BufReader::new(open()?);
+    let mut header = [0; HEADER_SIZE];
+    if read_up_to(&mut reader, &mut header)? < HEADER_SIZE {
+        return Ok(false);
+    }
+    let size = decode_header(&header);
+    let start = reader.fill_buf()?;
+    let marked = [SIGNATURE_MAGIC, DATA_MAGIC, FILL_RUNS_MAGIC]
+        .iter()
+        .any(|magic| start.starts_with(*magic));
+    if !marked || read_runs(&mut reader, size, false, |_, _| Ok(())).is_ok() {
+        return Ok(false);
+    }
+
+    let mut reader = BufReader::new(open()?);
+    reader.read_exact(&mut header)?;
+    Ok(read_runs(&mut reader, size, true, |_, _| Ok(())).is_ok())
+}
+
+/// Sorts an `image` decoding failure into a bad file or an I/O problem.
+pub fn image_error(path: &Path, error: image::ImageError) -> InputError {
+    match error {
+        image::ImageError::IoError(error) => InputError::Io {
+            path: path.to_path_buf(),
+            error,
+        },
+        error => InputError::Unsupported {
+            path: path.to_path_buf(),
+            reason: error.to_string(),
+        },
+    }
+}
+
+/// Sorts a PNG decoding failure into a bad file or an I/O problem.
+pub fn png_error(path: &Path, error: png::DecodingError) -> InputError {
+    match error {
+        png::DecodingError::IoError(error) => InputError::Io {
+            path: path.to_path_buf(),
+            error,
+        },
+        error => InputError::Unsupported {
+            path: path.to_path_buf(),
+            reason: error.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bruh::encode_bruh;
+
+    /// The colors `read_runs` gives for `contents`, a pixel at a time.
+    fn streamed(contents: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let plain = plain_runs(|| Ok(contents))?;
+        let mut reader = &contents[HEADER_SIZE..];
+        let size = decode_header(contents.first_chunk().expect("a header"));
+        let mut pixels = Vec::new();
+        read_runs(&mut reader, size, plain, |color, count| {
+            for _ in 0..count {
+                pixels.extend_from_slice(color);
+                pixels.push(u8::MAX);
+            }
+            Ok(())
+        })?;
+        Ok(pixels)
+    }
+
+    #[test]
+    fn streams_what_decodes_in_memory() {
+        let encoded = encode_bruh(20, 20, (0..400u32).map(|i| [(i / 30) as u8, 1, 2]));
+        assert!(!plain_runs(|| Ok(&encoded[..])).unwrap());
+        assert_eq!(streamed(&encoded).unwrap(), decode_bruh(&encoded).unwrap().pixels);
+
+        // Version 1 files whose first run reads as a marker.
+        for magic in [FILL_RUNS_MAGIC, SIGNATURE_MAGIC, DATA_MAGIC] {
+            let mut contents = [16u32.to_ne_bytes(), 16u32.to_ne_bytes()].concat();
+            contents.extend_from_slice(magic);
+            contents.extend([1, 0, 0, 0].repeat(256 - magic[0] as usize));
+            assert!(plain_runs(|| Ok(&contents[..])).unwrap());
+            assert_eq!(streamed(&contents).unwrap(), decode_bruh(&contents).unwrap().pixels);
+        }
+    }
+}
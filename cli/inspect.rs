@@ -0,0 +1,174 @@
+use std::{error::Error, fmt::Write as _, io::Write, path::Path};
+
+use bruh::{
+    animation::FRAME_HEADER_SIZE, check_bruh, decode_header, DecodeError, Runs, ANIMATION_MAGIC,
+    DATA_MAGIC, ENCRYPTED_MAGIC, HEADER_SIZE, SIGNATURE_LEN, SIGNATURE_MAGIC,
+};
+
+use crate::{
+    crypt::{is_encrypted, NONCE_LEN, SALT_LEN},
+    input::read_source,
+};
+
+/// A stretch of a .bruh file with one purpose.
+///
+/// The format has only a few tagged chunks and no checksums: a file is its header and
+/// runs, with a signature, embedded data and a marker between them if it's signed, carries
+/// data or uses fill runs, a header and ciphertext if it's encrypted, or a header and
+/// frames if it's animated. These are its parts as they'd be chunks, each checked the only
+/// way the format allows, against the header.
+pub struct Chunk<'a> {
+    pub offset: usize,
+    pub tag: &'static str,
+    pub bytes: &'a [u8],
+    /// `ok`, or what's wrong with the chunk.
+    pub status: String,
+}
+
+/// Splits `contents` into its [`Chunk`]s, in file order. Nothing is dropped: a damaged
+/// file's stray bytes get chunks of their own.
+pub fn chunks(contents: &[u8]) -> Vec<Chunk<'_>> {
+    let mut chunks = Vec::new();
+    let mut push = |offset: usize, tag, end: usize, status: String| {
+        chunks.push(Chunk {
+            offset,
+            tag,
+            bytes: &contents[offset..end],
+            status,
+        })
+    };
+
+    let Some(header) = contents.get(..HEADER_SIZE) else {
+        let status = format!("{} of {} bytes", contents.len(), HEADER_SIZE);
+        push(0, "header", contents.len(), status);
+        return chunks;
+    };
+    let (width, height) = decode_header(header.try_into().expect("HEADER_SIZE bytes"));
+    push(0, "header", HEADER_SIZE, "ok".to_owned());
+
+    if is_encrypted(contents) {
+        // The tag at the end of the ciphertext is the only check, and it needs the key.
+        let mut offset = HEADER_SIZE;
+        let parts = [("magic", ENCRYPTED_MAGIC.len()), ("salt", SALT_LEN), ("nonce", NONCE_LEN)];
+        for (tag, len) in parts {
+            let end = (offset + len).min(contents.len());
+            let status = match end - offset {
+                read if read < len => format!("{} of {} bytes", read, len),
+                _ => "ok".to_owned(),
+            };
+            push(offset, tag, end, status);
+            offset = end;
+        }
+        if offset < contents.len() {
+            push(offset, "ciphertext", contents.len(), "needs the passphrase".to_owned());
+        }
+        return chunks;
+    }
+
+    if check_bruh(contents) == Err(DecodeError::Animated) {
+        let mut offset = HEADER_SIZE + ANIMATION_MAGIC.len();
+        push(HEADER_SIZE, "magic", offset, "ok".to_owned());
+        // A frame cut short is as long as its header says, or as its header would be.
+        let mut expected = FRAME_HEADER_SIZE as u64;
+        while offset + FRAME_HEADER_SIZE <= contents.len() {
+            let frame_header = &contents[offset..offset + FRAME_HEADER_SIZE];
+            let delay_ms = u32::from_ne_bytes(frame_header[..4].try_into().expect("4 bytes"));
+            let len = u64::from_ne_bytes(frame_header[4..].try_into().expect("8 bytes"));
+            expected = FRAME_HEADER_SIZE as u64 + len;
+            let start = offset + FRAME_HEADER_SIZE;
+            let Some(end) = usize::try_from(len).ok().and_then(|len| start.checked_add(len)) else {
+                break;
+            };
+            if end > contents.len() {
+                break;
+            }
+            expected = FRAME_HEADER_SIZE as u64;
+            push(offset, "frame delay", start, format!("{} ms", delay_ms));
+            let status = match check_bruh(&contents[start..end]) {
+                Ok(size) if size == (width, height) => "ok".to_owned(),
+                Ok((w, h)) => format!("{}×{}, header says {}×{}", w, h, width, height),
+                Err(e) => e.to_string(),
+            };
+            push(start, "frame", end, status);
+            offset = end;
+        }
+        if offset < contents.len() {
+            let status = format!("{} of {} bytes", contents.len() - offset, expected);
+            push(offset, "cut frame", contents.len(), status);
+        }
+        return chunks;
+    }
+
+    let runs = Runs::of(contents);
+    let mut marker = HEADER_SIZE;
+    if runs.signature.is_some() {
+        let end = HEADER_SIZE + SIGNATURE_MAGIC.len() + SIGNATURE_LEN;
+        push(HEADER_SIZE, "signature", end, "needs the public key".to_owned());
+        marker = end;
+    }
+    if let Some(data) = runs.data {
+        let end = marker + DATA_MAGIC.len() + 4 + data.len();
+        push(marker, "data", end, format!("{} bytes", data.len()));
+        marker = end;
+    }
+    if runs.fills {
+        push(marker, "marker", runs.offset, "ok".to_owned());
+    }
+
+    let expected = width as u64 * height as u64;
+    let mut covered = 0u64;
+    let mut end = runs.offset;
+    for run in runs.bytes.chunks_exact(4) {
+        if covered >= expected {
+            break;
+        }
+        covered += runs.length(run[0], expected - covered);
+        end += run.len();
+    }
+    if end > runs.offset || expected > 0 {
+        let status = match covered {
+            covered if covered == expected => "ok".to_owned(),
+            covered if covered < expected => format!("only {} of {} pixels", covered, expected),
+            covered => format!("{} pixels, header says {}", covered, expected),
+        };
+        push(runs.offset, "runs", end, status);
+    }
+
+    let partial = (contents.len() - end) % 4;
+    let whole_end = contents.len() - partial;
+    if whole_end > end {
+        push(end, "extra runs", whole_end, "past the last pixel".to_owned());
+    }
+    if partial > 0 {
+        push(whole_end, "partial run", contents.len(), format!("{} of 4 bytes", partial));
+    }
+    chunks
+}
+
+/// Writes a line to `out` for every chunk of the file at `path`: its offset, tag, length,
+/// status and up to `dump_len` bytes of it in hex.
+pub fn inspect(path: &Path, dump_len: usize, mut out: impl Write) -> Result<(), Box<dyn Error>> {
+    let mut contents = Vec::new();
+    read_source(path, &mut contents)?;
+
+    writeln!(out, "{:<10}  {:<11}  {:>10}  {:<24}  bytes", "offset", "tag", "length", "status")?;
+    for chunk in chunks(&contents) {
+        let mut dump = String::new();
+        for byte in chunk.bytes.iter().take(dump_len) {
+            write!(dump, "{:02x} ", byte)?;
+        }
+        if chunk.bytes.len() > dump_len {
+            dump.push('…');
+        }
+        writeln!(
+            out,
+            "{:#010x}  {:<11}  {:>10}  {:<24}  {}",
+            chunk.offset,
+            chunk.tag,
+            chunk.bytes.len(),
+            chunk.status,
+            dump.trim_end()
+        )?;
+    }
+    Ok(())
+}
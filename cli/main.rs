@@ -0,0 +1,2556 @@
+#![cfg_attr(all(not(debug_assertions), feature = "gui"), windows_subsystem = "windows")] // hide console window on Windows in release
+
+mod color_mode;
+mod compare;
+mod corpus;
+mod crypt;
+mod diff;
+mod embed;
+mod filter;
+mod generate;
+mod icon;
+mod identify;
+mod info;
+mod input;
+mod inspect;
+mod metrics;
+mod output;
+mod pack;
+mod palette;
+mod register;
+mod repair;
+mod screenshot;
+mod selftest;
+mod serve;
+mod sheet;
+mod sign;
+mod split;
+mod stats;
+mod stream;
+mod terminal;
+mod trace;
+mod tui;
+mod vectors;
+mod video;
+mod websocket;
+
+use std::{
+    env,
+    ffi::OsString,
+    io::{self, BufRead, BufReader, Cursor, IsTerminal, Read, Seek, Write},
+    iter::Peekable,
+    net::TcpListener,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use bruh::{
+    animation::{Animation, AnimationWriter},
+    buffer_len, decode_bruh, decode_header, pixels_to_rgb,
+    sidecar::EncodeOptions,
+    ColorAdjustments, DecodeError, FilteredEncoder, PixelFilter, PixelLayout, HEADER_SIZE,
+};
+use css_color_parser::Color as CssColor;
+use image::{ColorType, ImageEncoder, ImageFormat};
+use input::{
+    image_error, open_input, open_source, plain_runs, png_error, read_rgb, read_runs,
+    read_source, read_up_to, InputError,
+};
+use output::{is_stdio, png_bytes, Output};
+
+/// Source formats besides PNG that `compile` accepts, converted by [`image_to_bruh`].
+const DECODED_IN_MEMORY: &[ImageFormat] = &[
+    ImageFormat::Jpeg,
+    ImageFormat::WebP,
+    ImageFormat::Bmp,
+    ImageFormat::Tiff,
+    ImageFormat::Gif,
+    ImageFormat::Qoi,
+    ImageFormat::Farbfeld,
+    ImageFormat::Pnm,
+];
+
+/// Converts a PNG, JPEG, WebP, BMP, TIFF, GIF, QOI, farbfeld, Netpbm or SVG image to
+/// BRUH, telling them apart by their contents rather than their extension. Only the first
+/// frame of an animated GIF is kept; [`gif_to_animation`] keeps them all.
+///
+/// SVGs are rasterized at `svg_size`, see [`svg_to_bruh`]; it's ignored for other images.
+/// Every image goes through the chain of `filters` before it's encoded.
+///
+/// An `input` of `-` reads standard input, which has to be read into memory first.
+fn compile(
+    input: &Path,
+    output: Output,
+    svg_size: (Option<u32>, Option<u32>),
+    filters: Vec<Box<dyn PixelFilter>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if is_stdio(input) {
+        let mut contents = Vec::new();
+        read_source(input, &mut contents)?;
+        compile_from(Cursor::new(contents), input, output, svg_size, filters)
+    } else {
+        let reader = BufReader::new(open_input(input)?);
+        compile_from(reader, input, output, svg_size, filters)
+    }
+}
+
+/// Converts an image whose `.bruhopts` sidecar sets encode options, applying them before
+/// `filters` as the viewer's batch conversion does. Resizing and picking a palette need the
+/// whole image, so it's decoded into memory first, and SVGs aren't supported.
+fn compile_with_options(
+    input: &Path,
+    output: Output,
+    options: EncodeOptions,
+    filters: Vec<Box<dyn PixelFilter>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (width, height, mut rgb) = read_rgb(input)?;
+    let (width, height) = options.apply(width, height, &mut rgb);
+    let mut encoder = FilteredEncoder::with_filters(output, width, height, filters)?;
+    encoder.push_pixels(&rgb)?;
+    encoder.finish()?.commit()?;
+    Ok(())
+}
+
+/// Converts every frame of a GIF into an animated .bruh file, keeping their delays. A frame
+/// is the whole image as the GIF shows it at that point, so frames that only update part
+/// of it come out whole. Like stills, frames go through `options` if a sidecar sets them,
+/// then through the chain of `filters`.
+fn gif_to_animation(
+    input: &Path,
+    output: Output,
+    options: Option<EncodeOptions>,
+    filters: Vec<Box<dyn PixelFilter>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use image::AnimationDecoder;
+
+    let mut contents = Vec::new();
+    read_source(input, &mut contents)?;
+    if image::guess_format(&contents).ok() != Some(ImageFormat::Gif) {
+        return Err(InputError::Unsupported {
+            path: input.to_path_buf(),
+            reason: "only GIFs can be compiled with --animate.".to_owned(),
+        }
+        .into());
+    }
+    let decoder = image::codecs::gif::GifDecoder::new(Cursor::new(contents))
+        .map_err(|e| image_error(input, e))?;
+
+    let frames = decoder.into_frames().map(|frame| {
+        let frame = frame.map_err(|e| image_error(input, e))?;
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        let image = frame.into_buffer();
+        let mut rgb = Vec::new();
+        pixels_to_rgb(image.as_raw(), PixelLayout::Rgba, &mut rgb);
+        Ok((image.width(), image.height(), rgb, numer / denom.max(1)))
+    });
+    frames_to_animation(input, frames, output, options, filters)
+}
+
+/// Converts a video into an animated .bruh file with `fps` frames a second, scaled to
+/// `size` if it's given, by running ffmpeg. An `input` of `-` reads raw `rgb24` video
+/// from standard input instead, see [`video::VideoFrames::open`]. Frames go through
+/// `options` and `filters` as a GIF's do.
+fn video_to_animation(
+    input: &Path,
+    output: Output,
+    fps: f64,
+    size: (Option<u32>, Option<u32>),
+    options: Option<EncodeOptions>,
+    filters: Vec<Box<dyn PixelFilter>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut video = video::VideoFrames::open(input, fps, size)?;
+    let (width, height) = (video.width, video.height);
+    let delay_ms = (1000.0 / fps).round() as u32;
+    let frames = std::iter::from_fn(|| {
+        let mut rgb = Vec::new();
+        match video.next_frame(&mut rgb) {
+            Ok(true) => Some(Ok((width, height, rgb, delay_ms))),
+            Ok(false) => None,
+            Err(e) => Some(Err(e)),
+        }
+    });
+    frames_to_animation(input, frames, output, options, filters)?;
+    video.finish()
+}
+
+/// Encodes `frames`, each its size, RGB8 pixels and delay in milliseconds, into an
+/// animated .bruh file. The animation's size is the first frame's, once `options` have
+/// resized it.
+fn frames_to_animation(
+    input: &Path,
+    frames: impl Iterator<Item = Result<(u32, u32, Vec<u8>, u32), Box<dyn std::error::Error>>>,
+    output: Output,
+    options: Option<EncodeOptions>,
+    mut filters: Vec<Box<dyn PixelFilter>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut output = Some(output);
+    let mut animation = None;
+    for frame in frames {
+        let (mut width, mut height, mut rgb, delay_ms) = frame?;
+        if let Some(options) = &options {
+            (width, height) = options.apply(width, height, &mut rgb);
+        }
+
+        let mut encoder = FilteredEncoder::with_filters(Vec::new(), width, height, filters)?;
+        encoder.push_pixels(&rgb)?;
+        let (encoded, rest) = encoder.finish_with_filters()?;
+        filters = rest;
+        let writer = match &mut animation {
+            Some(writer) => writer,
+            None => {
+                let output = output.take().expect("only taken for the first frame");
+                animation.insert(AnimationWriter::new(output, width, height)?)
+            }
+        };
+        writer.push_frame(&encoded, delay_ms)?;
+    }
+    match animation {
+        Some(writer) => writer.finish()?.commit()?,
+        None => return Err(format!("{} has no frames.", input.display()).into()),
+    }
+    Ok(())
+}
+
+fn compile_from(
+    mut reader: impl BufRead + Seek,
+    input: &Path,
+    output: Output,
+    svg_size: (Option<u32>, Option<u32>),
+    filters: Vec<Box<dyn PixelFilter>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut signature = [0; 16];
+    let read = read_up_to(&mut reader, &mut signature)?;
+    reader.rewind()?;
+
+    match image::guess_format(&signature[..read]) {
+        Ok(ImageFormat::Png) => png_to_bruh(reader, input, output, filters),
+        Ok(format) if DECODED_IN_MEMORY.contains(&format) => {
+            image_to_bruh(reader, input, output, format, filters)
+        }
+        _ if looks_like_svg(&signature[..read]) => {
+            svg_to_bruh(reader, input, output, svg_size, filters)
+        }
+        _ => Err(InputError::Unsupported {
+            path: input.to_path_buf(),
+            reason: "only PNG, JPEG, WebP, BMP, TIFF, GIF, QOI, farbfeld, Netpbm and SVG \
+                     images can be compiled."
+                .to_owned(),
+        }
+        .into()),
+    }
+}
+
+/// Whether a file starting with `signature` could be an SVG: XML or an `<svg>` element,
+/// possibly gzipped as in .svgz files. Whether it really is one is up to the parser.
+fn looks_like_svg(signature: &[u8]) -> bool {
+    let text = signature.strip_prefix("\u{feff}".as_bytes()).unwrap_or(signature);
+    let start = text.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(text.len());
+    let text = &text[start..];
+    text.starts_with(b"<?xml") || text.starts_with(b"<svg") || text.starts_with(&[0x1f, 0x8b])
+}
+
+/// Rasterizes an SVG and converts it to BRUH.
+///
+/// With neither dimension in `size` it's drawn at its own size. With one, the other
+/// follows the SVG's aspect ratio, and with both it's stretched to fit them. Transparent
+/// areas come out black, like alpha in any other source.
+fn svg_to_bruh(
+    mut reader: impl BufRead,
+    input: &Path,
+    output: Output,
+    size: (Option<u32>, Option<u32>),
+    filters: Vec<Box<dyn PixelFilter>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use resvg::{tiny_skia, usvg};
+    use usvg::{TreeParsing, TreeTextToPath};
+
+    let mut contents = Vec::new();
+    reader.read_to_end(&mut contents)?;
+    let unsupported = |reason: String| InputError::Unsupported {
+        path: input.to_path_buf(),
+        reason,
+    };
+
+    let mut tree = usvg::Tree::from_data(&contents, &usvg::Options::default())
+        .map_err(|e| unsupported(e.to_string()))?;
+    let mut fonts = usvg::fontdb::Database::new();
+    fonts.load_system_fonts();
+    tree.convert_text(&fonts);
+
+    let (svg_width, svg_height) = (tree.size.width() as f64, tree.size.height() as f64);
+    let (width, height) = match size {
+        (Some(width), Some(height)) => (width, height),
+        (Some(width), None) => (width, (width as f64 * svg_height / svg_width).round() as u32),
+        (None, Some(height)) => ((height as f64 * svg_width / svg_height).round() as u32, height),
+        (None, None) => (svg_width.ceil() as u32, svg_height.ceil() as u32),
+    };
+    let mut pixmap = tiny_skia::Pixmap::new(width.max(1), height.max(1))
+        .ok_or_else(|| unsupported(format!("can't rasterize at {}×{} pixels.", width, height)))?;
+    let transform = tiny_skia::Transform::from_scale(
+        pixmap.width() as f32 / svg_width as f32,
+        pixmap.height() as f32 / svg_height as f32,
+    );
+    resvg::Tree::from_usvg(&tree).render(transform, &mut pixmap.as_mut());
+
+    // The pixmap is premultiplied, which is the same as compositing over black.
+    let mut encoder =
+        FilteredEncoder::with_filters(output, pixmap.width(), pixmap.height(), filters)?;
+    for pixel in pixmap.data().chunks_exact(4) {
+        encoder.push([pixel[0], pixel[1], pixel[2]])?;
+    }
+    encoder.finish()?.commit()?;
+    Ok(())
+}
+
+/// Converts an image the `image` crate decodes to BRUH. Unlike PNGs, these are decoded
+/// into memory first, which also joins up TIFFs stored in several strips or tiles. The
+/// decoder converts grey, CMYK and 16-bit samples to 8-bit RGBA, and alpha is then
+/// flattened the same way as for PNGs.
+fn image_to_bruh(
+    reader: impl BufRead + Seek,
+    input: &Path,
+    output: Output,
+    format: ImageFormat,
+    filters: Vec<Box<dyn PixelFilter>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let decoded = image::io::Reader::with_format(reader, format)
+        .decode()
+        .map_err(|e| image_error(input, e))?
+        .into_rgba8();
+
+    let (width, height) = decoded.dimensions();
+    let mut encoder = FilteredEncoder::with_filters(output, width, height, filters)?;
+    let mut rgb = Vec::new();
+    encoder.push_pixels(pixels_to_rgb(decoded.as_raw(), PixelLayout::Rgba, &mut rgb))?;
+    encoder.finish()?.commit()?;
+    Ok(())
+}
+
+/// Converts a PNG to BRUH one row at a time, so only a single row of the source
+/// is ever held in memory.
+fn png_to_bruh(
+    reader: impl BufRead,
+    input: &Path,
+    output: Output,
+    filters: Vec<Box<dyn PixelFilter>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut decoder = png::Decoder::new(reader);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(|e| png_error(input, e))?;
+    let (width, height) = (reader.info().width, reader.info().height);
+    let (color_type, _) = reader.output_color_type();
+
+    let mut encoder = FilteredEncoder::with_filters(output, width, height, filters)?;
+    let mut rgb = Vec::new();
+
+    if reader.info().interlaced {
+        // Interlaced rows arrive pass by pass, so the whole image has to be assembled first.
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let frame = reader
+            .next_frame(&mut buffer)
+            .map_err(|e| png_error(input, e))?;
+        for row in buffer[..frame.buffer_size()].chunks_exact(frame.line_size) {
+            encoder.push_pixels(png_row_to_rgb(row, color_type, &mut rgb))?;
+        }
+    } else {
+        while let Some(row) = reader.next_row().map_err(|e| png_error(input, e))? {
+            encoder.push_pixels(png_row_to_rgb(row.data(), color_type, &mut rgb))?;
+        }
+    }
+
+    encoder.finish()?.commit()?;
+    Ok(())
+}
+
+/// Converts raw pixels with no header, such as a framebuffer dump, to BRUH. The size and
+/// layout aren't in the data, so they have to be given, and the data has to match them
+/// exactly. Rows are converted and filtered as they're read.
+fn raw_to_bruh(
+    input: &Path,
+    output: Output,
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    filters: Vec<Box<dyn PixelFilter>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut reader = BufReader::new(open_source(input)?);
+    let mut row = vec![0; buffer_len(width, 1, layout.channels())?];
+    let mut encoder = FilteredEncoder::with_filters(output, width, height, filters)?;
+    let mut rgb = Vec::new();
+
+    for y in 0..height {
+        let read = read_up_to(&mut reader, &mut row)?;
+        if read < row.len() {
+            return Err(format!(
+                "The raw data ends in row {} of {}; is the size right?",
+                y + 1,
+                height
+            )
+            .into());
+        }
+        encoder.push_pixels(pixels_to_rgb(&row, layout, &mut rgb))?;
+    }
+    if read_up_to(&mut reader, &mut [0])? > 0 {
+        return Err(format!(
+            "The raw data is longer than {}×{} pixels; is the size right?",
+            width, height
+        )
+        .into());
+    }
+
+    encoder.finish()?.commit()?;
+    Ok(())
+}
+
+/// Packs one row of 8-bit PNG samples as RGB, using `buffer` unless it already is.
+///
+/// Expects the `EXPAND` and `STRIP_16` transformations, which turn palettes, low bit
+/// depths and 16-bit samples into 8-bit grey or RGB, with or without alpha.
+fn png_row_to_rgb<'a>(
+    row: &'a [u8],
+    color_type: png::ColorType,
+    buffer: &'a mut Vec<u8>,
+) -> &'a [u8] {
+    let layout = match color_type {
+        png::ColorType::Grayscale => PixelLayout::Gray,
+        png::ColorType::GrayscaleAlpha => PixelLayout::GrayAlpha,
+        png::ColorType::Rgb | png::ColorType::Indexed => PixelLayout::Rgb,
+        png::ColorType::Rgba => PixelLayout::Rgba,
+    };
+    pixels_to_rgb(row, layout, buffer)
+}
+
+/// Formats `decompile` can write, by the name `--format` takes.
+const EXPORT_FORMATS: &[(&str, ImageFormat)] = &[
+    ("png", ImageFormat::Png),
+    ("jpeg", ImageFormat::Jpeg),
+    ("jpg", ImageFormat::Jpeg),
+    ("webp", ImageFormat::WebP),
+    #[cfg(feature = "avif")]
+    ("avif", ImageFormat::Avif),
+    ("qoi", ImageFormat::Qoi),
+    ("gif", ImageFormat::Gif),
+    ("farbfeld", ImageFormat::Farbfeld),
+    ("ff", ImageFormat::Farbfeld),
+    ("pnm", ImageFormat::Pnm),
+    ("ppm", ImageFormat::Pnm),
+];
+
+/// The contents of the .bruh file `input`, unless it's already been `decrypted`.
+fn load_bruh(input: &Path, decrypted: Option<Vec<u8>>) -> Result<Vec<u8>, InputError> {
+    if let Some(contents) = decrypted {
+        return Ok(contents);
+    }
+    let mut contents = Vec::new();
+    read_source(input, &mut contents)?;
+    Ok(contents)
+}
+
+/// The .bruh file to convert in `contents`: all of it, or the first frame if it's an
+/// animation, along with whether it was one.
+fn first_frame(contents: &[u8]) -> Result<(&[u8], bool), Box<dyn std::error::Error>> {
+    match Animation::of(contents)? {
+        Some(animation) => match animation.frames.first() {
+            Some(frame) => Ok((frame.contents, true)),
+            None => Err("The animation has no frames.".into()),
+        },
+        None => Ok((contents, false)),
+    }
+}
+
+/// Says that only the first frame of the animation `input` was converted.
+fn note_first_frame(input: &Path) {
+    eprintln!(
+        "{} is animated, so only its first frame was converted. Convert it to a GIF to keep \
+         every frame.",
+        input.display()
+    );
+}
+
+/// Converts a .bruh file to a format the `image` crate encodes, decoding it into memory
+/// first. Returns whether it was an animation of which only the first frame was converted,
+/// which is the case unless `format` is GIF.
+///
+/// `quality` goes from 1 to 100 and only applies to JPEG and AVIF; the other formats are
+/// always written losslessly, though GIFs only have 256 colors a frame. Netpbm images are
+/// written as binary PPMs.
+fn bruh_to_image(
+    contents: &[u8],
+    output: &Path,
+    format: ImageFormat,
+    quality: Option<u8>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if format == ImageFormat::Gif {
+        if let Some(animation) = Animation::of(contents)? {
+            animation_to_gif(&animation, output)?;
+            return Ok(false);
+        }
+    }
+    let (contents, animated) = first_frame(contents)?;
+    let decoded = decode_bruh(contents)?;
+    if decoded.is_empty() {
+        return Err(format!(
+            "{:?} can't store an empty image ({}×{} pixels).",
+            format, decoded.width, decoded.height
+        )
+        .into());
+    }
+
+    let mut rgb = Vec::with_capacity(decoded.pixels.len() / 4 * 3);
+    for pixel in decoded.pixels.chunks_exact(4) {
+        rgb.extend_from_slice(&pixel[..3]);
+    }
+
+    let (width, height) = (decoded.width, decoded.height);
+    let mut file = Output::create(output)?;
+    match format {
+        ImageFormat::Jpeg => {
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut file, quality.unwrap_or(90))
+                .write_image(&rgb, width, height, ColorType::Rgb8)?
+        }
+        ImageFormat::WebP => image::codecs::webp::WebPEncoder::new_lossless(&mut file)
+            .write_image(&rgb, width, height, ColorType::Rgb8)?,
+        #[cfg(feature = "avif")]
+        ImageFormat::Avif => image::codecs::avif::AvifEncoder::new_with_speed_quality(
+            &mut file,
+            4,
+            quality.unwrap_or(80),
+        )
+        .write_image(&rgb, width, height, ColorType::Rgb8)?,
+        ImageFormat::Qoi => image::codecs::qoi::QoiEncoder::new(&mut file)
+            .write_image(&rgb, width, height, ColorType::Rgb8)?,
+        ImageFormat::Gif => image::codecs::gif::GifEncoder::new(&mut file)
+            .encode(&rgb, width, height, ColorType::Rgb8)?,
+        ImageFormat::Farbfeld => {
+            // farbfeld is always 16-bit RGBA, which the encoder takes in native byte order.
+            let mut rgba16 = Vec::with_capacity(rgb.len() / 3 * 8);
+            for pixel in rgb.chunks_exact(3) {
+                for &sample in pixel {
+                    rgba16.extend_from_slice(&(sample as u16 * 257).to_ne_bytes());
+                }
+                rgba16.extend_from_slice(&u16::MAX.to_ne_bytes());
+            }
+            image::codecs::farbfeld::FarbfeldEncoder::new(&mut file)
+                .write_image(&rgba16, width, height, ColorType::Rgba16)?
+        }
+        ImageFormat::Pnm => {
+            use image::codecs::pnm::{PnmEncoder, PnmSubtype, SampleEncoding};
+            PnmEncoder::new(&mut file)
+                .with_subtype(PnmSubtype::Pixmap(SampleEncoding::Binary))
+                .write_image(&rgb, width, height, ColorType::Rgb8)?
+        }
+        #[cfg(not(feature = "avif"))]
+        ImageFormat::Avif => {
+            return Err("This bruh was built without the `avif` feature, so it can't write \
+                        AVIF images."
+                .into())
+        }
+        _ => return Err(format!("Can't write {:?} images.", format).into()),
+    }
+    file.commit()?;
+    Ok(animated)
+}
+
+/// Converts every frame of an animated .bruh file to an animated GIF that loops forever.
+fn animation_to_gif(
+    animation: &Animation,
+    output: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use image::{codecs::gif, Delay, Frame, RgbaImage};
+
+    if animation.frames.is_empty() {
+        return Err("The animation has no frames.".into());
+    }
+    let mut file = Output::create(output)?;
+    let mut encoder = gif::GifEncoder::new(&mut file);
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    for frame in &animation.frames {
+        let decoded = decode_bruh(frame.contents)?;
+        let image = RgbaImage::from_raw(decoded.width, decoded.height, decoded.pixels)
+            .expect("the frame's size");
+        let delay = Delay::from_numer_denom_ms(frame.delay_ms, 1);
+        encoder.encode_frame(Frame::from_parts(image, 0, 0, delay))?;
+    }
+    drop(encoder);
+    file.commit()?;
+    Ok(())
+}
+
+/// Writes the pixels of a .bruh file with no header, as RGB or RGBA with an opaque alpha.
+fn bruh_to_raw(
+    contents: &[u8],
+    output: &Path,
+    layout: PixelLayout,
+) -> Result<(u32, u32), Box<dyn std::error::Error>> {
+    let decoded = decode_bruh(contents)?;
+
+    let mut file = Output::create(output)?;
+    match layout {
+        PixelLayout::Rgba => file.write_all(&decoded.pixels)?,
+        PixelLayout::Rgb => {
+            for pixel in decoded.pixels.chunks_exact(4) {
+                file.write_all(&pixel[..3])?;
+            }
+        }
+        _ => return Err("Raw export only writes rgb8 or rgba8.".into()),
+    }
+    file.commit()?;
+    Ok((decoded.width, decoded.height))
+}
+
+/// Converts a .bruh file to a PNG `data:` URI, for pasting into HTML or CSS.
+fn bruh_to_data_uri(contents: &[u8]) -> Result<String, Box<dyn std::error::Error>> {
+    let png = png_bytes(&decode_bruh(contents)?)?;
+    Ok(format!("data:image/png;base64,{}", BASE64.encode(png)))
+}
+
+/// Converts a .bruh file to PNG while reading it, writing each scanline as soon as
+/// its runs have been read, so memory use is one row no matter how large the image is.
+/// `open` opens the file, which is read through first if its runs start with what reads
+/// as a marker, see [`plain_runs`].
+///
+/// Of an animation, only the first frame is converted, and the result is `true`.
+fn stream_bruh_to_png<R: Read>(
+    open: impl Fn() -> Result<R, InputError>,
+    output: &Path,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let plain = plain_runs(&open)?;
+    let mut reader = BufReader::new(open()?);
+
+    let mut header = [0; HEADER_SIZE];
+    if let Err(e) = reader.read_exact(&mut header) {
+        return Err(match e.kind() {
+            io::ErrorKind::UnexpectedEof => DecodeError::MissingHeader.into(),
+            _ => e.into(),
+        });
+    }
+    let (width, height) = decode_header(&header);
+    if width == 0 || height == 0 {
+        return Err(format!("PNG can't store an empty image ({}×{} pixels).", width, height).into());
+    }
+    let row_size = buffer_len(width, 1, 3)?;
+
+    let mut file = Output::create(output)?;
+    let mut encoder = png::Encoder::new(&mut file, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    let mut stream = writer.stream_writer()?;
+
+    let mut row = Vec::with_capacity(row_size);
+    let animated = read_runs(&mut reader, (width, height), plain, |color, count| {
+        for _ in 0..count {
+            row.extend_from_slice(color);
+            if row.len() == row_size {
+                stream.write_all(&row)?;
+                row.clear();
+            }
+        }
+        Ok(())
+    })?;
+
+    stream.finish()?;
+    drop(writer);
+    file.commit()?;
+    Ok(animated)
+}
+
+/// [`stream_bruh_to_png`] for the .bruh file at `input`. Standard input can only be read
+/// once, so it's held in memory, though its pixels still aren't.
+fn bruh_file_to_png(input: &Path, output: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    if !is_stdio(input) {
+        return stream_bruh_to_png(|| open_input(input), output);
+    }
+    let mut contents = Vec::new();
+    read_source(input, &mut contents)?;
+    stream_bruh_to_png(|| Ok(&contents[..]), output)
+}
+
+/// Parses a `--format` name from [`EXPORT_FORMATS`].
+fn parse_format(value: Option<&OsString>) -> Result<ImageFormat, String> {
+    let value = value
+        .ok_or("--format needs a format, e.g. `--format jpeg`")?
+        .to_string_lossy()
+        .to_ascii_lowercase();
+    EXPORT_FORMATS
+        .iter()
+        .find(|(name, _)| *name == value)
+        .map(|&(_, format)| format)
+        .ok_or_else(|| {
+            let names: Vec<_> = EXPORT_FORMATS.iter().map(|&(name, _)| name).collect();
+            format!("Unknown format '{}', expected one of {}.", value, names.join(", "))
+        })
+}
+
+/// Parses a `--quality` between 1 and 100.
+fn parse_quality(value: Option<&OsString>) -> Result<u8, String> {
+    let value = value
+        .ok_or("--quality needs a number, e.g. `--quality 85`")?
+        .to_string_lossy();
+    match value.parse::<u8>() {
+        Ok(quality) if (1..=100).contains(&quality) => Ok(quality),
+        _ => Err(format!("Invalid quality '{}', expected 1 to 100.", value)),
+    }
+}
+
+/// Parses a factor for a color adjustment such as `--brightness`, as a number or a
+/// percentage.
+fn parse_factor(option: &str, value: Option<&OsString>) -> Result<f32, String> {
+    let value = value
+        .ok_or_else(|| format!("{0} needs a factor, e.g. `{0} 1.2` or `{0} 120%`", option))?
+        .to_string_lossy();
+    let factor = match value.strip_suffix('%') {
+        Some(percentage) => percentage.parse::<f32>().map(|p| p / 100.0),
+        None => value.parse::<f32>(),
+    };
+    match factor {
+        Ok(factor) if factor.is_finite() && factor >= 0.0 => Ok(factor),
+        _ => Err(format!("Invalid {} '{}', expected a factor of 0 or more.", option, value)),
+    }
+}
+
+fn parse_colors(value: Option<&OsString>) -> Result<usize, String> {
+    let value = value
+        .ok_or("--colors needs a number of colors, e.g. `--colors 16`")?
+        .to_string_lossy();
+    match value.parse() {
+        Ok(colors) if (1..=256).contains(&colors) => Ok(colors),
+        _ => Err(format!("Invalid --colors '{}', expected 1 to 256.", value)),
+    }
+}
+
+fn parse_pattern(value: Option<&OsString>) -> Result<generate::Pattern, String> {
+    let value = value
+        .ok_or("--pattern needs a pattern, e.g. `--pattern gradient`")?
+        .to_string_lossy();
+    generate::PATTERNS
+        .iter()
+        .find(|(name, _)| value.eq_ignore_ascii_case(name))
+        .map(|&(_, pattern)| pattern)
+        .ok_or_else(|| {
+            let names: Vec<_> = generate::PATTERNS.iter().map(|(name, _)| *name).collect();
+            format!("Unknown --pattern '{}', expected one of {}.", value, names.join(", "))
+        })
+}
+
+/// Parses a `--size` such as `1024x768`.
+fn parse_size(value: Option<&OsString>) -> Result<(u32, u32), String> {
+    let value = value
+        .ok_or("--size needs a width and height, e.g. `--size 1024x768`")?
+        .to_string_lossy();
+    value
+        .split_once(['x', 'X', '×'])
+        .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+        .ok_or_else(|| format!("Invalid --size '{}', expected e.g. 1024x768.", value))
+}
+
+/// Parses a `--grid` of columns and rows such as `8x4`.
+fn parse_grid(value: Option<&OsString>) -> Result<(u32, u32), String> {
+    let value = value
+        .ok_or("--grid needs columns and rows, e.g. `--grid 8x4`")?
+        .to_string_lossy();
+    value
+        .split_once(['x', 'X', '×'])
+        .and_then(|(columns, rows)| Some((columns.parse().ok()?, rows.parse().ok()?)))
+        .filter(|&(columns, rows)| columns > 0 && rows > 0)
+        .ok_or_else(|| format!("Invalid --grid '{}', expected columns and rows, e.g. 8x4.", value))
+}
+
+fn parse_columns(value: Option<&OsString>) -> Result<u32, String> {
+    let value = value
+        .ok_or("--columns needs a number, e.g. `--columns 8`")?
+        .to_string_lossy();
+    match value.parse() {
+        Ok(columns) if columns > 0 => Ok(columns),
+        _ => Err(format!("Invalid --columns '{}', expected a number above 0.", value)),
+    }
+}
+
+fn parse_fps(value: Option<&OsString>) -> Result<f64, String> {
+    let value = value
+        .ok_or("--fps needs a frame rate, e.g. `--fps 10`")?
+        .to_string_lossy();
+    match value.parse::<f64>() {
+        Ok(fps) if fps > 0.0 && fps <= 1000.0 => Ok(fps),
+        _ => Err(format!("Invalid --fps '{}', expected a frame rate above 0.", value)),
+    }
+}
+
+fn parse_frames(value: Option<&OsString>) -> Result<u64, String> {
+    let value = value
+        .ok_or("--frames needs a number, e.g. `--frames 100`")?
+        .to_string_lossy();
+    value
+        .parse()
+        .map_err(|_| format!("Invalid --frames '{}', expected a whole number.", value))
+}
+
+fn parse_duration(value: Option<&OsString>) -> Result<f64, String> {
+    let value = value
+        .ok_or("--duration needs a number of seconds, e.g. `--duration 30`")?
+        .to_string_lossy();
+    match value.parse::<f64>() {
+        Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => Ok(seconds),
+        _ => Err(format!("Invalid --duration '{}', expected a number of seconds.", value)),
+    }
+}
+
+fn parse_address(option: &str, value: Option<&OsString>) -> Result<String, String> {
+    let value = value
+        .ok_or_else(|| format!("{} needs an address, e.g. `{} 127.0.0.1:7878`", option, option))?;
+    Ok(value.to_string_lossy().into_owned())
+}
+
+fn parse_seed(value: Option<&OsString>) -> Result<u64, String> {
+    let value = value
+        .ok_or("--seed needs a number, e.g. `--seed 42`")?
+        .to_string_lossy();
+    value
+        .parse()
+        .map_err(|_| format!("Invalid --seed '{}', expected a whole number.", value))
+}
+
+/// Parses a `--position` given by name, such as `bottom-right`, or as coordinates of the
+/// top-left corner, such as `10,10`.
+fn parse_position(value: Option<&OsString>) -> Result<filter::Placement, String> {
+    let value = value
+        .ok_or("--position needs a place, e.g. `--position bottom-right` or `--position 10,10`")?
+        .to_string_lossy();
+    if let Some((x, y)) = value.split_once(',') {
+        return match (x.trim().parse(), y.trim().parse()) {
+            (Ok(x), Ok(y)) => Ok(filter::Placement::At(x, y)),
+            _ => Err(format!("Invalid position '{}', expected x,y e.g. 10,10.", value)),
+        };
+    }
+    filter::POSITIONS
+        .iter()
+        .find(|(name, _, _)| name.eq_ignore_ascii_case(&value))
+        .map(|&(_, horizontal, vertical)| filter::Placement::Aligned(horizontal, vertical))
+        .ok_or_else(|| {
+            let names: Vec<&str> = filter::POSITIONS.iter().map(|(name, _, _)| *name).collect();
+            format!("Unknown position '{}', expected x,y or one of: {}", value, names.join(", "))
+        })
+}
+
+fn parse_blend(value: Option<&OsString>) -> Result<filter::Blend, String> {
+    let value = value
+        .ok_or("--blend needs a mode, e.g. `--blend multiply`")?
+        .to_string_lossy();
+    filter::BLENDS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(&value))
+        .map(|&(_, blend)| blend)
+        .ok_or_else(|| {
+            let names: Vec<&str> = filter::BLENDS.iter().map(|(name, _)| *name).collect();
+            format!("Unknown --blend '{}', expected one of: {}", value, names.join(", "))
+        })
+}
+
+/// Parses the comma-separated codecs `shootout --against` compares BRUH with.
+fn parse_codecs(value: Option<&OsString>) -> Result<Vec<&'static compare::Codec>, String> {
+    let value = value
+        .ok_or("--against needs codecs, e.g. `--against png,qoi,webp-lossless`")?
+        .to_string_lossy();
+    value
+        .split(',')
+        .map(|id| {
+            compare::find_codec(id.trim()).ok_or_else(|| {
+                let ids: Vec<&str> = compare::CODECS.iter().map(|codec| codec.id).collect();
+                format!("Unknown codec '{}', expected some of: {}", id, ids.join(", "))
+            })
+        })
+        .collect()
+}
+
+fn parse_opacity(value: Option<&OsString>) -> Result<f32, String> {
+    let value = value
+        .ok_or("--opacity needs a value from 0 to 1, e.g. `--opacity 0.5`")?
+        .to_string_lossy();
+    let opacity = match value.strip_suffix('%') {
+        Some(percentage) => percentage.parse::<f32>().map(|p| p / 100.0),
+        None => value.parse::<f32>(),
+    };
+    match opacity {
+        Ok(opacity) if (0.0..=1.0).contains(&opacity) => Ok(opacity),
+        _ => Err(format!("Invalid --opacity '{}', expected 0 to 1 or 0% to 100%.", value)),
+    }
+}
+
+/// Parses a `--width` or `--height` in pixels.
+fn parse_dimension(option: &str, value: Option<&OsString>) -> Result<u32, String> {
+    let value = value
+        .ok_or_else(|| format!("{0} needs a number of pixels, e.g. `{0} 640`", option))?
+        .to_string_lossy();
+    value
+        .parse()
+        .map_err(|_| format!("Invalid {} '{}', expected a number of pixels.", option, value))
+}
+
+/// Parses how many bytes of each chunk `inspect --bytes` dumps.
+fn parse_byte_count(value: Option<&OsString>) -> Result<usize, String> {
+    let value = value
+        .ok_or("--bytes needs a number of bytes, e.g. `--bytes 32`")?
+        .to_string_lossy();
+    value
+        .parse()
+        .map_err(|_| format!("Invalid --bytes '{}', expected a number of bytes.", value))
+}
+
+/// Parses a `--pixel-format` for raw pixels.
+fn parse_pixel_format(value: Option<&OsString>) -> Result<PixelLayout, String> {
+    let value = value
+        .ok_or("--pixel-format needs a format, e.g. `--pixel-format rgb8`")?
+        .to_string_lossy();
+    match value.as_ref() {
+        "gray8" => Ok(PixelLayout::Gray),
+        "graya8" => Ok(PixelLayout::GrayAlpha),
+        "rgb8" => Ok(PixelLayout::Rgb),
+        "rgba8" => Ok(PixelLayout::Rgba),
+        _ => Err(format!(
+            "Unknown pixel format '{}', expected gray8, graya8, rgb8 or rgba8.",
+            value
+        )),
+    }
+}
+
+/// Parses a `--port` number.
+fn parse_port(value: Option<&OsString>) -> Result<u16, String> {
+    let value = value
+        .ok_or("--port needs a number, e.g. `--port 8080`")?
+        .to_string_lossy();
+    value
+        .parse()
+        .map_err(|_| format!("Invalid port '{}', expected 0 to 65535.", value))
+}
+
+/// The next argument unless it's another option, for options whose value can be left out.
+fn optional_value<'a>(
+    args: &mut Peekable<impl Iterator<Item = &'a OsString>>,
+) -> Option<&'a OsString> {
+    args.next_if(|arg| !arg.to_string_lossy().starts_with('-'))
+}
+
+/// Parses a `--region` in the `WIDTHxHEIGHT+X+Y` form X11 and ImageMagick use. Without a
+/// value, the whole screen is captured, the same as without `--region`.
+fn parse_region(value: Option<&OsString>) -> Result<Option<screenshot::Region>, String> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let value = value.to_string_lossy();
+    let invalid = || format!("Invalid region '{}', expected e.g. 800x600+100+50.", value);
+
+    let sign = value.find(['+', '-']).ok_or_else(invalid)?;
+    let (size, position) = value.split_at(sign);
+    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+    // Coordinates keep their sign, as screens left of or above the primary one have
+    // negative ones.
+    let split = position[1..]
+        .find(['+', '-'])
+        .map(|i| i + 1)
+        .ok_or_else(invalid)?;
+    let (x, y) = position.split_at(split);
+    Ok(Some(screenshot::Region {
+        x: x.parse().map_err(|_| invalid())?,
+        y: y.parse().map_err(|_| invalid())?,
+        width: width.parse().map_err(|_| invalid())?,
+        height: height.parse().map_err(|_| invalid())?,
+    }))
+}
+
+/// Parses a CSS color such as `#202020` or `black`, ignoring its alpha.
+fn parse_color(option: &str, value: Option<&OsString>) -> Result<[u8; 3], String> {
+    let value = value
+        .ok_or_else(|| format!("{0} needs a color, e.g. `{0} #202020`", option))?
+        .to_string_lossy();
+    let color: CssColor = value
+        .parse()
+        .map_err(|_| format!("Invalid color '{}' for {}.", value, option))?;
+    Ok([color.r, color.g, color.b])
+}
+
+/// Why the program stopped early, which decides its exit code.
+enum Failure {
+    /// The command line didn't make sense; exits with 2.
+    Usage(String),
+    /// The command was understood but couldn't be carried out; exits with 1.
+    Failed(String),
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure::Usage(message)
+    }
+}
+
+/// Opens `path` for a .bruh file, to be encrypted if `encrypt` is set. See
+/// [`crypt::passphrase`] for `stdin_in_use`.
+fn bruh_output(path: &Path, encrypt: bool, stdin_in_use: bool) -> Result<Output, Failure> {
+    let file = if encrypt {
+        let passphrase =
+            crypt::passphrase(stdin_in_use, true).map_err(|e| Failure::Usage(e.to_string()))?;
+        Output::encrypted(path, passphrase)
+    } else {
+        Output::create(path)
+    };
+    file.map_err(|e| Failure::Failed(format!("Couldn't create {}: {}", path.display(), e)))
+}
+
+/// The output path given on the command line, or `input` with its extension replaced.
+fn output_path(
+    input: &Path,
+    output: Option<&OsString>,
+    extension: &str,
+) -> Result<PathBuf, Failure> {
+    let output = match output {
+        Some(output) => PathBuf::from(output),
+        // Whatever comes from standard input goes to standard output.
+        None if is_stdio(input) => input.to_path_buf(),
+        None => input.with_extension(extension),
+    };
+    if output == input && !is_stdio(input) {
+        return Err(Failure::Usage(format!(
+            "{} would be overwritten by its own conversion, pass an output path.",
+            input.display()
+        )));
+    }
+    Ok(output)
+}
+
+fn main() -> ExitCode {
+    match run(env::args_os().collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Usage(message)) => {
+            eprintln!("{}", message);
+            ExitCode::from(2)
+        }
+        Err(Failure::Failed(message)) => {
+            eprintln!("{}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Takes the arguments as `OsString`s, since paths don't have to be valid Unicode.
+fn run(args: Vec<OsString>) -> Result<(), Failure> {
+    let command = args.get(1).and_then(|arg| arg.to_str());
+    if command == Some("compile") {
+        let mut encrypt = false;
+        let mut raw = false;
+        let mut animate = false;
+        let mut from_video = false;
+        let mut fps = 10.0;
+        let mut width = None;
+        let mut height = None;
+        let mut layout = PixelLayout::Rgb;
+        let mut adjustments = ColorAdjustments::default();
+        let mut plugins = Vec::new();
+        let mut watermark = None;
+        let mut position = filter::Placement::Aligned(filter::Align::End, filter::Align::End);
+        let mut opacity = 1.0;
+        let mut paths = Vec::new();
+        let mut compile_args = args.iter().skip(2);
+        while let Some(arg) = compile_args.next() {
+            match arg.to_str() {
+                Some("--animate") => animate = true,
+                Some("--from-video") => from_video = true,
+                Some("--fps") => fps = parse_fps(compile_args.next())?,
+                Some("--encrypt") => encrypt = true,
+                Some("--raw") => raw = true,
+                Some("--width") => width = Some(parse_dimension("--width", compile_args.next())?),
+                Some("--height") => {
+                    height = Some(parse_dimension("--height", compile_args.next())?)
+                }
+                Some("--pixel-format") => layout = parse_pixel_format(compile_args.next())?,
+                Some("--brightness") => {
+                    adjustments.brightness = parse_factor("--brightness", compile_args.next())?
+                }
+                Some("--contrast") => {
+                    adjustments.contrast = parse_factor("--contrast", compile_args.next())?
+                }
+                Some("--saturate") => {
+                    adjustments.saturation = parse_factor("--saturate", compile_args.next())?
+                }
+                Some("--grayscale") => adjustments.grayscale = true,
+                Some("--filter") => match compile_args.next() {
+                    Some(name) => plugins.push(name.to_string_lossy().into_owned()),
+                    None => {
+                        return Err(Failure::Usage(
+                            "--filter needs the name of a plugin, e.g. `--filter sepia`".to_owned(),
+                        ))
+                    }
+                },
+                Some("--watermark") => match compile_args.next() {
+                    Some(path) => watermark = Some(PathBuf::from(path)),
+                    None => {
+                        return Err(Failure::Usage(
+                            "--watermark needs a file, e.g. `--watermark logo.png`".to_owned(),
+                        ))
+                    }
+                },
+                Some("--position") => position = parse_position(compile_args.next())?,
+                Some("--opacity") => opacity = parse_opacity(compile_args.next())?,
+                _ => paths.push(arg),
+            }
+        }
+        let Some(input) = paths.first().map(PathBuf::from) else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run compile ~/image.png`"
+                    .to_owned(),
+            ));
+        };
+        let output = output_path(&input, paths.get(1).copied(), "bruh")?;
+        if raw && (width.is_none() || height.is_none()) {
+            return Err(Failure::Usage(
+                "--raw needs the image size, e.g. `--raw --width 640 --height 480`".to_owned(),
+            ));
+        }
+        if animate && (raw || width.is_some() || height.is_some()) {
+            return Err(Failure::Usage(
+                "--animate compiles GIFs, so it can't be combined with --raw, --width or --height."
+                    .to_owned(),
+            ));
+        }
+        if from_video && (raw || animate) {
+            return Err(Failure::Usage(
+                "--from-video can't be combined with --raw or --animate.".to_owned(),
+            ));
+        }
+        // Color adjustments come first, then plugins in the order they were given, and the
+        // watermark goes on top of the result.
+        let mut filters: Vec<Box<dyn PixelFilter>> = Vec::new();
+        if !adjustments.is_identity() {
+            filters.push(Box::new(adjustments));
+        }
+        for name in &plugins {
+            let plugin = filter::Plugin::load(name).map_err(|e| Failure::Failed(e.to_string()))?;
+            filters.push(Box::new(plugin));
+        }
+        if let Some(path) = watermark {
+            let blend = filter::Blend::Normal;
+            let watermark = filter::Watermark::load(&path, position, opacity, blend).map_err(|e| {
+                Failure::Failed(format!("Couldn't load the watermark {}: {}", path.display(), e))
+            })?;
+            filters.push(Box::new(watermark));
+        }
+        // Standard input has no file next to it to hold options.
+        let options = match raw || is_stdio(&input) {
+            true => None,
+            false => EncodeOptions::load(&input).map_err(Failure::Failed)?,
+        };
+        let file = bruh_output(&output, encrypt, is_stdio(&input))?;
+
+        match ((width, height), options) {
+            ((Some(width), Some(height)), _) if raw => {
+                raw_to_bruh(&input, file, width, height, layout, filters)
+            }
+            (size, options) if from_video => {
+                video_to_animation(&input, file, fps, size, options, filters)
+            }
+            (_, options) if animate => gif_to_animation(&input, file, options, filters),
+            (_, Some(options)) => compile_with_options(&input, file, options, filters),
+            (size, None) => compile(&input, file, size, filters),
+        }
+        .map_err(|e| Failure::Failed(format!("Failed to convert to BRUH: {}", e)))?;
+        // Standard output might be piped into something that expects only the image.
+        if !is_stdio(&output) {
+            println!("Successfully converted to BRUH: {}", output.display());
+        }
+        Ok(())
+    } else if command == Some("composite") {
+        let mut paths = Vec::new();
+        let mut position = filter::Placement::At(0, 0);
+        let mut opacity = 1.0;
+        let mut blend = filter::Blend::Normal;
+        let mut output = None;
+        let mut composite_args = args.iter().skip(2);
+        while let Some(arg) = composite_args.next() {
+            match arg.to_str() {
+                Some("--position") => position = parse_position(composite_args.next())?,
+                Some("--opacity") => opacity = parse_opacity(composite_args.next())?,
+                Some("--blend") => blend = parse_blend(composite_args.next())?,
+                Some("-o" | "--output") => output = composite_args.next().map(PathBuf::from),
+                _ => paths.push(PathBuf::from(arg)),
+            }
+        }
+        let [base, overlay] = &paths[..] else {
+            return Err(Failure::Usage(
+                "Two images needed, the base and the overlay. Example: `cargo run composite base.bruh badge.png --position 10,10 -o out.bruh`"
+                    .to_owned(),
+            ));
+        };
+        let Some(output) = output else {
+            return Err(Failure::Usage("Pass a path for the result with `-o`.".to_owned()));
+        };
+
+        let overlay = filter::Watermark::load(overlay, position, opacity, blend).map_err(|e| {
+            Failure::Failed(format!("Couldn't load the overlay {}: {}", overlay.display(), e))
+        })?;
+        filter::composite(base, overlay, &output)
+            .map_err(|e| Failure::Failed(format!("Failed to composite: {}", e)))?;
+        println!("Successfully composited: {}", output.display());
+        Ok(())
+    } else if command == Some("filters") {
+        let plugins = filter::installed_plugins()
+            .map_err(|e| Failure::Failed(format!("Couldn't list the plugins: {}", e)))?;
+        match filter::plugins_dir() {
+            Some(dir) if plugins.is_empty() => println!("No plugins in {}", dir.display()),
+            Some(dir) => println!("Plugins in {}:", dir.display()),
+            None => println!("There's no plugins folder on this system."),
+        }
+        for name in plugins {
+            println!("  {}", name);
+        }
+        Ok(())
+    } else if command == Some("compare-codec") {
+        let Some(input) = args.get(2).map(PathBuf::from) else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run compare-codec ~/image.png`"
+                    .to_owned(),
+            ));
+        };
+        compare::compare_codecs(&input, io::stdout().lock()).map_err(|e| {
+            Failure::Failed(format!("Couldn't compare codecs on {}: {}", input.display(), e))
+        })?;
+        Ok(())
+    } else if command == Some("shootout") {
+        let mut folder = None;
+        let mut against: Vec<_> = compare::CODECS.iter().collect();
+        let mut shootout_args = args.iter().skip(2);
+        while let Some(arg) = shootout_args.next() {
+            match arg.to_str() {
+                Some("--against") => against = parse_codecs(shootout_args.next())?,
+                _ => folder = Some(PathBuf::from(arg)),
+            }
+        }
+        let Some(folder) = folder else {
+            return Err(Failure::Usage(
+                "Expected a folder of images. Example: `cargo run shootout images/ --against png,qoi,webp-lossless`"
+                    .to_owned(),
+            ));
+        };
+
+        compare::shootout(&folder, &against, io::stdout().lock()).map_err(|e| {
+            Failure::Failed(format!("Couldn't compare codecs on {}: {}", folder.display(), e))
+        })?;
+        Ok(())
+    } else if command == Some("metrics") {
+        let json = args[2..].iter().any(|arg| arg == "--json");
+        let paths: Vec<&OsString> = args[2..].iter().filter(|arg| *arg != "--json").collect();
+        let [original, converted] = paths[..] else {
+            return Err(Failure::Usage(
+                "Expected the original image and its conversion. Example: `cargo run metrics original.png roundtrip.bruh`"
+                    .to_owned(),
+            ));
+        };
+
+        let metrics = metrics::measure(Path::new(original), Path::new(converted))
+            .map_err(|e| Failure::Failed(format!("Couldn't compare the images: {}", e)))?;
+        if json {
+            println!("{}", metrics.to_json());
+        } else {
+            println!("{}", metrics);
+        }
+        Ok(())
+    } else if command == Some("diff") {
+        let mut paths = Vec::new();
+        let mut report = None;
+        let mut tile_size = 32;
+        let mut diff_args = args.iter().skip(2);
+        while let Some(arg) = diff_args.next() {
+            match arg.to_str() {
+                Some("--report") => match diff_args.next() {
+                    Some(path) => report = Some(PathBuf::from(path)),
+                    None => {
+                        return Err(Failure::Usage(
+                            "--report needs a path, e.g. `--report diff.html`".to_owned(),
+                        ))
+                    }
+                },
+                Some("--tile") => tile_size = parse_dimension("--tile", diff_args.next())?.max(1),
+                _ => paths.push(PathBuf::from(arg)),
+            }
+        }
+        let [before, after] = &paths[..] else {
+            return Err(Failure::Usage(
+                "Expected two images to compare. Example: `cargo run diff --report out.html a.bruh b.bruh`"
+                    .to_owned(),
+            ));
+        };
+
+        let diff = diff::diff(before, after, tile_size)
+            .map_err(|e| Failure::Failed(format!("Couldn't compare the images: {}", e)))?;
+        for tile in &diff.changed {
+            println!(
+                "{}×{} at {},{}: {} pixels changed, by up to {}",
+                tile.width, tile.height, tile.x, tile.y, tile.pixels, tile.max_delta
+            );
+        }
+        if let Some(report) = report {
+            let names = (before.display().to_string(), after.display().to_string());
+            diff.write_report(&report, (&names.0, &names.1)).map_err(|e| {
+                Failure::Failed(format!("Failed to write {}: {}", report.display(), e))
+            })?;
+            println!("Wrote the report to {}", report.display());
+        }
+        // Like diff(1), differences make the command fail, so scripts can check for them.
+        if !diff.changed.is_empty() {
+            return Err(Failure::Failed(diff.to_string()));
+        }
+        println!("The images are identical.");
+        Ok(())
+    } else if command == Some("palette") {
+        let mut input = None;
+        let mut colors = 8;
+        let mut swatch = None;
+        let mut palette_args = args.iter().skip(2);
+        while let Some(arg) = palette_args.next() {
+            match arg.to_str() {
+                Some("--colors") => colors = parse_colors(palette_args.next())?,
+                Some("--swatch") => match palette_args.next() {
+                    Some(path) => swatch = Some(PathBuf::from(path)),
+                    None => {
+                        return Err(Failure::Usage(
+                            "--swatch needs a path, e.g. `--swatch palette.png`".to_owned(),
+                        ))
+                    }
+                },
+                Some(option) if option.starts_with('-') && option != "-" => {
+                    return Err(Failure::Usage(format!(
+                        "Unknown option '{}'. Example: `cargo run palette image.bruh --colors 16`",
+                        option
+                    )))
+                }
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+        let Some(input) = input else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run palette image.bruh --colors 16`"
+                    .to_owned(),
+            ));
+        };
+
+        let palette = palette::palette(&input, colors).map_err(|e| {
+            Failure::Failed(format!("Couldn't find the palette of {}: {}", input.display(), e))
+        })?;
+        for entry in &palette {
+            let [r, g, b] = entry.color;
+            println!("#{:02x}{:02x}{:02x}  {:5.1}%", r, g, b, entry.share * 100.0);
+        }
+        if let Some(swatch) = swatch {
+            palette::write_swatch(&palette, &swatch).map_err(|e| {
+                Failure::Failed(format!("Failed to write {}: {}", swatch.display(), e))
+            })?;
+        }
+        Ok(())
+    } else if command == Some("convert-mode") {
+        let mut input = None;
+        let mut to = None;
+        let mut colors = 256;
+        let mut output = None;
+        let mut mode_args = args.iter().skip(2);
+        while let Some(arg) = mode_args.next() {
+            match arg.to_str() {
+                Some("--to") => match mode_args.next() {
+                    Some(mode) => to = Some(mode.to_string_lossy()),
+                    None => {
+                        return Err(Failure::Usage(
+                            "--to needs a mode, e.g. `--to palette` or `--to gray`".to_owned(),
+                        ))
+                    }
+                },
+                Some("--colors") => colors = parse_colors(mode_args.next())?,
+                Some("-o" | "--output") => match mode_args.next() {
+                    Some(path) => output = Some(PathBuf::from(path)),
+                    None => {
+                        return Err(Failure::Usage(
+                            "-o needs a path, e.g. `-o small.bruh`".to_owned(),
+                        ))
+                    }
+                },
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+        let Some(input) = input else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run convert-mode image.bruh --to palette`"
+                    .to_owned(),
+            ));
+        };
+        let mode = match to.as_deref() {
+            Some("palette") => color_mode::ColorMode::Palette(colors),
+            Some("gray" | "grey") => color_mode::ColorMode::Gray,
+            Some("rgba") => {
+                return Err(Failure::Usage(
+                    "A .bruh file has no alpha channel, so it can't be converted to RGBA."
+                        .to_owned(),
+                ))
+            }
+            Some(mode) => {
+                return Err(Failure::Usage(format!(
+                    "Unknown mode '{}', expected palette or gray.",
+                    mode
+                )))
+            }
+            None => {
+                return Err(Failure::Usage(
+                    "--to needs a mode, e.g. `--to palette` or `--to gray`".to_owned(),
+                ))
+            }
+        };
+        // The file is replaced atomically, so converting it in place is safe.
+        let output = output.unwrap_or_else(|| input.clone());
+
+        let conversion = color_mode::convert(&input, mode, &output).map_err(|e| {
+            Failure::Failed(format!("Failed to convert {}: {}", input.display(), e))
+        })?;
+        println!("Converted {} to {}", input.display(), output.display());
+        if conversion.changed == 0 {
+            println!("No information was lost.");
+        } else {
+            println!(
+                "{} colors became {}, changing {} of {} pixels by up to {} per channel.",
+                conversion.colors_before,
+                conversion.colors_after,
+                conversion.changed,
+                conversion.pixels,
+                conversion.max_error
+            );
+        }
+        Ok(())
+    } else if command == Some("cat") {
+        let mut input = None;
+        let mut mode = terminal::Mode::TrueColor;
+        // Terminals don't always export their width, but shells set this for themselves.
+        let mut columns = env::var("COLUMNS")
+            .ok()
+            .and_then(|columns| columns.parse().ok())
+            .unwrap_or(80);
+        let mut cat_args = args.iter().skip(2);
+        while let Some(arg) = cat_args.next() {
+            match arg.to_str() {
+                Some("--ascii") => mode = terminal::Mode::Ascii,
+                Some("--width") => columns = parse_dimension("--width", cat_args.next())?,
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+        let Some(input) = input else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run cat image.bruh --ascii --width 80`"
+                    .to_owned(),
+            ));
+        };
+
+        let text = terminal::render(&input, columns, mode)
+            .map_err(|e| Failure::Failed(format!("Couldn't show {}: {}", input.display(), e)))?;
+        print!("{}", text);
+        Ok(())
+    } else if command == Some("tui") {
+        let folder = args.get(2).map_or_else(|| PathBuf::from("."), PathBuf::from);
+        if !io::stdout().is_terminal() {
+            return Err(Failure::Usage("tui needs to run in a terminal.".to_owned()));
+        }
+
+        tui::browse(&folder)
+            .map_err(|e| Failure::Failed(format!("Couldn't browse {}: {}", folder.display(), e)))?;
+        Ok(())
+    } else if command == Some("render") {
+        let Some(input) = args.get(2).map(PathBuf::from) else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run render image.bruh | magick - small.jpg`"
+                    .to_owned(),
+            ));
+        };
+        if io::stdout().is_terminal() {
+            return Err(Failure::Usage(
+                "render writes PNG bytes, which would garble the terminal. Pipe it into another \
+                 program or redirect it to a file."
+                    .to_owned(),
+            ));
+        }
+
+        bruh_file_to_png(&input, Path::new("-"))
+            .map_err(|e| Failure::Failed(format!("Couldn't render {}: {}", input.display(), e)))?;
+        Ok(())
+    } else if command == Some("generate") {
+        let mut pattern = None;
+        let mut size = (512, 512);
+        let mut color = [255, 255, 255];
+        let mut seed = 0;
+        let mut output = None;
+        let mut generate_args = args.iter().skip(2);
+        while let Some(arg) = generate_args.next() {
+            match arg.to_str() {
+                Some("--pattern") => pattern = Some(parse_pattern(generate_args.next())?),
+                Some("--size") => size = parse_size(generate_args.next())?,
+                Some("--color") => color = parse_color("--color", generate_args.next())?,
+                Some("--seed") => seed = parse_seed(generate_args.next())?,
+                Some("-o" | "--output") => output = generate_args.next().map(PathBuf::from),
+                _ => {
+                    return Err(Failure::Usage(format!(
+                        "Unknown option '{}'. Example: `cargo run generate --pattern noise -o test.bruh`",
+                        arg.to_string_lossy()
+                    )))
+                }
+            }
+        }
+        let Some(pattern) = pattern else {
+            return Err(Failure::Usage(
+                "--pattern not provided. Example: `cargo run generate --pattern noise --size 1024x768 -o test.bruh`"
+                    .to_owned(),
+            ));
+        };
+        let Some(output) = output else {
+            return Err(Failure::Usage("Pass a path for the image with `-o`.".to_owned()));
+        };
+
+        let file = bruh_output(&output, false, false)?;
+        generate::generate(file, pattern, size, color, seed).map_err(|e| {
+            Failure::Failed(format!("Couldn't write {}: {}", output.display(), e))
+        })?;
+        if !is_stdio(&output) {
+            println!("Generated {}×{} image: {}", size.0, size.1, output.display());
+        }
+        Ok(())
+    } else if command == Some("split") {
+        let mut input = None;
+        let mut grid = None;
+        let mut format = split::CellFormat::Bruh;
+        let mut output = None;
+        let mut split_args = args.iter().skip(2);
+        while let Some(arg) = split_args.next() {
+            match arg.to_str() {
+                Some("--grid") => grid = Some(parse_grid(split_args.next())?),
+                Some("--png") => format = split::CellFormat::Png,
+                Some("-o" | "--output") => output = split_args.next().map(PathBuf::from),
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+        let Some(input) = input else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run split sheet.bruh --grid 8x4 -o sprites/`"
+                    .to_owned(),
+            ));
+        };
+        let Some(grid) = grid else {
+            return Err(Failure::Usage(
+                "--grid not provided. Example: `cargo run split sheet.bruh --grid 8x4 -o sprites/`"
+                    .to_owned(),
+            ));
+        };
+        let output = output.unwrap_or_else(|| input.with_extension(""));
+        if output == input {
+            return Err(Failure::Usage("Pass a folder to split into with `-o`.".to_owned()));
+        }
+
+        let count = split::split(&input, grid, &output, format)
+            .map_err(|e| Failure::Failed(format!("Failed to split {}: {}", input.display(), e)))?;
+        println!("Split {} into {} cells in {}", input.display(), count, output.display());
+        Ok(())
+    } else if command == Some("sheet") {
+        let mut inputs = Vec::new();
+        let mut columns = None;
+        let mut shelf = false;
+        let mut width = None;
+        let mut output = None;
+        let mut atlas = None;
+        let mut sheet_args = args.iter().skip(2);
+        while let Some(arg) = sheet_args.next() {
+            match arg.to_str() {
+                Some("--columns") => columns = Some(parse_columns(sheet_args.next())?),
+                Some("--shelf") => shelf = true,
+                Some("--width") => width = Some(parse_dimension("--width", sheet_args.next())?),
+                Some("-o" | "--output") => match sheet_args.next() {
+                    Some(path) => output = Some(PathBuf::from(path)),
+                    None => {
+                        return Err(Failure::Usage(
+                            "-o needs a path, e.g. `-o sheet.bruh`".to_owned(),
+                        ))
+                    }
+                },
+                Some("--atlas") => match sheet_args.next() {
+                    Some(path) => atlas = Some(PathBuf::from(path)),
+                    None => {
+                        return Err(Failure::Usage(
+                            "--atlas needs a path, e.g. `--atlas sheet.json`".to_owned(),
+                        ))
+                    }
+                },
+                _ => inputs.push(Path::new(arg)),
+            }
+        }
+        if inputs.is_empty() {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run sheet sprites/*.bruh --columns 8 -o sheet.bruh`"
+                    .to_owned(),
+            ));
+        }
+        let Some(output) = output else {
+            return Err(Failure::Usage("Pass a path for the sheet with `-o`.".to_owned()));
+        };
+        if shelf && columns.is_some() {
+            return Err(Failure::Usage("--columns and --shelf can't be combined.".to_owned()));
+        }
+        let layout = if shelf {
+            sheet::Layout::Shelf { width }
+        } else {
+            // Without --columns, the grid is kept roughly square.
+            let columns = columns.unwrap_or((inputs.len() as f64).sqrt().ceil() as u32);
+            sheet::Layout::Grid { columns }
+        };
+        let atlas = atlas.unwrap_or_else(|| output.with_extension("json"));
+
+        let (width, height) = sheet::sheet(&inputs, layout, &output, &atlas)
+            .map_err(|e| Failure::Failed(format!("Failed to build the sheet: {}", e)))?;
+        println!(
+            "Packed {} image(s) into a {}×{} sheet at {}, with its atlas at {}",
+            inputs.len(),
+            width,
+            height,
+            output.display(),
+            atlas.display()
+        );
+        Ok(())
+    } else if command == Some("identify") {
+        let mime = args[2..].iter().any(|arg| arg == "--mime");
+        let paths: Vec<&OsString> = args[2..].iter().filter(|arg| *arg != "--mime").collect();
+        if paths.is_empty() {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run identify *.bruh`"
+                    .to_owned(),
+            ));
+        }
+
+        // Like ImageMagick, one bad file doesn't stop the rest from being identified.
+        let mut failed = 0;
+        for path in paths {
+            match identify::identify(Path::new(path)) {
+                Ok(identity) if mime => {
+                    println!("{}: {}", identity.path.display(), identity.mime_type)
+                }
+                Ok(identity) => println!("{}", identity),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    failed += 1;
+                }
+            }
+        }
+        if failed > 0 {
+            return Err(Failure::Failed(format!("Couldn't identify {} file(s).", failed)));
+        }
+        Ok(())
+    } else if command == Some("info") {
+        let mut input = None;
+        for arg in args.iter().skip(2) {
+            match arg.to_str() {
+                Some(option) if option.starts_with('-') && option != "-" => {
+                    return Err(Failure::Usage(format!(
+                        "Unknown option '{}'. Example: `cargo run info image.bruh`",
+                        option
+                    )))
+                }
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+        let Some(input) = input else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run info image.bruh`"
+                    .to_owned(),
+            ));
+        };
+
+        let info = info::Info::read(&input).map_err(|e| {
+            Failure::Failed(format!("Couldn't read {}: {}", input.display(), e))
+        })?;
+        println!("{}", info);
+        Ok(())
+    } else if command == Some("inspect") {
+        let mut paths = Vec::new();
+        let mut dump_len = 16;
+        let mut inspect_args = args.iter().skip(2);
+        while let Some(arg) = inspect_args.next() {
+            match arg.to_str() {
+                Some("--bytes") => dump_len = parse_byte_count(inspect_args.next())?,
+                _ => paths.push(PathBuf::from(arg)),
+            }
+        }
+        if paths.is_empty() {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run inspect image.bruh`"
+                    .to_owned(),
+            ));
+        }
+
+        for (i, path) in paths.iter().enumerate() {
+            if paths.len() > 1 {
+                println!("{}{}:", if i > 0 { "\n" } else { "" }, path.display());
+            }
+            inspect::inspect(path, dump_len, io::stdout().lock()).map_err(|e| {
+                Failure::Failed(format!("Couldn't inspect {}: {}", path.display(), e))
+            })?;
+        }
+        Ok(())
+    } else if command == Some("stats") {
+        let mut input = None;
+        let mut runs_csv = None;
+        let mut stats_args = args.iter().skip(2);
+        while let Some(arg) = stats_args.next() {
+            match arg.to_str() {
+                Some("--runs-csv") => match stats_args.next() {
+                    Some(path) => runs_csv = Some(PathBuf::from(path)),
+                    None => {
+                        return Err(Failure::Usage(
+                            "--runs-csv needs a path, e.g. `--runs-csv runs.csv`".to_owned(),
+                        ))
+                    }
+                },
+                Some(option) if option.starts_with('-') && option != "-" => {
+                    return Err(Failure::Usage(format!(
+                        "Unknown option '{}'. Example: `cargo run stats image.bruh --runs-csv runs.csv`",
+                        option
+                    )))
+                }
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+        let Some(input) = input else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run stats image.bruh --runs-csv runs.csv`"
+                    .to_owned(),
+            ));
+        };
+
+        let stats = stats::RunStats::measure(&input).map_err(|e| {
+            Failure::Failed(format!("Couldn't read the runs of {}: {}", input.display(), e))
+        })?;
+        println!("{}", stats);
+        if let Some(path) = runs_csv {
+            stats.write_csv(&path).map_err(|e| {
+                Failure::Failed(format!("Failed to write {}: {}", path.display(), e))
+            })?;
+            println!("Wrote the run lengths and runs per row to {}", path.display());
+        }
+        Ok(())
+    } else if command == Some("corpus-stats") {
+        let json = args[2..].iter().any(|arg| arg == "--json");
+        let folders: Vec<&OsString> = args[2..].iter().filter(|arg| *arg != "--json").collect();
+        let [folder] = folders[..] else {
+            return Err(Failure::Usage(
+                "Expected a folder of images. Example: `cargo run corpus-stats images/ --json`"
+                    .to_owned(),
+            ));
+        };
+        let folder = Path::new(folder);
+
+        let corpus = corpus::Corpus::measure(folder).map_err(|e| {
+            Failure::Failed(format!("Couldn't read the images in {}: {}", folder.display(), e))
+        })?;
+        if json {
+            println!("{}", corpus.to_json());
+        } else {
+            for (path, reason) in &corpus.skipped {
+                eprintln!("Skipped {}: {}", path.display(), reason);
+            }
+            println!("{}", corpus);
+        }
+        Ok(())
+    } else if command == Some("serve") {
+        let mut folder = None;
+        let mut port = 8080;
+        let mut bind = "127.0.0.1".to_owned();
+        let mut serve_args = args.iter().skip(2);
+        while let Some(arg) = serve_args.next() {
+            match arg.to_str() {
+                Some("--port") => port = parse_port(serve_args.next())?,
+                Some("--bind") => match serve_args.next() {
+                    Some(address) => bind = address.to_string_lossy().into_owned(),
+                    None => {
+                        return Err(Failure::Usage(
+                            "--bind needs an address, e.g. `--bind 0.0.0.0`".to_owned(),
+                        ))
+                    }
+                },
+                _ => folder = Some(PathBuf::from(arg)),
+            }
+        }
+        let folder = folder.unwrap_or_else(|| PathBuf::from("."));
+
+        serve::serve(&folder, (bind.as_str(), port))
+            .map_err(|e| Failure::Failed(format!("Couldn't serve {}: {}", folder.display(), e)))?;
+        Ok(())
+    } else if command == Some("icon") {
+        let mut input = None;
+        let mut output = None;
+        let mut icon_args = args.iter().skip(2);
+        while let Some(arg) = icon_args.next() {
+            match arg.to_str() {
+                Some("-o" | "--output") => match icon_args.next() {
+                    Some(path) => output = Some(path),
+                    None => {
+                        return Err(Failure::Usage(
+                            "-o needs a path, e.g. `-o app.ico`".to_owned(),
+                        ))
+                    }
+                },
+                Some(option) if option.starts_with('-') && option != "-" => {
+                    return Err(Failure::Usage(format!(
+                        "Unknown option '{}'. Example: `cargo run icon app.bruh -o app.ico`",
+                        option
+                    )))
+                }
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+        let Some(input) = input else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run icon app.bruh -o app.ico`"
+                    .to_owned(),
+            ));
+        };
+        let output = output_path(&input, output, "ico")?;
+        let format = match output.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("icns") => icon::IconFormat::Icns,
+            Some(ext) if ext.eq_ignore_ascii_case("ico") => icon::IconFormat::Ico,
+            _ => {
+                return Err(Failure::Usage(
+                    "The icon's path has to end in .ico or .icns.".to_owned(),
+                ))
+            }
+        };
+
+        icon::make_icon(&input, &output, format)
+            .map_err(|e| Failure::Failed(format!("Failed to make an icon: {}", e)))?;
+        println!("Wrote {}", output.display());
+        Ok(())
+    } else if command == Some("screenshot") {
+        let mut output = None;
+        let mut region = None;
+        let mut encrypt = false;
+        let mut screenshot_args = args.iter().skip(2).peekable();
+        while let Some(arg) = screenshot_args.next() {
+            match arg.to_str() {
+                Some("-o" | "--output") => output = screenshot_args.next().map(PathBuf::from),
+                Some("--region") => region = parse_region(optional_value(&mut screenshot_args))?,
+                Some("--encrypt") => encrypt = true,
+                _ => {
+                    return Err(Failure::Usage(format!(
+                        "Unknown option '{}'. Example: `cargo run screenshot -o screen.bruh`",
+                        arg.to_string_lossy()
+                    )))
+                }
+            }
+        }
+        let output = output.unwrap_or_else(|| {
+            let seconds = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs());
+            PathBuf::from(format!("screenshot-{}.bruh", seconds))
+        });
+
+        let file = bruh_output(&output, encrypt, false)?;
+        screenshot::screenshot(region.as_ref(), file)
+            .map_err(|e| Failure::Failed(format!("Failed to take a screenshot: {}", e)))?;
+        if !is_stdio(&output) {
+            println!("Saved screenshot: {}", output.display());
+        }
+        Ok(())
+    } else if command == Some("record") {
+        let mut output = None;
+        let mut region = None;
+        let mut fps = 10.0;
+        let mut frames = None;
+        let mut duration = None;
+        let mut record_args = args.iter().skip(2).peekable();
+        while let Some(arg) = record_args.next() {
+            match arg.to_str() {
+                Some("-o" | "--output") => output = record_args.next().map(PathBuf::from),
+                Some("--region") => region = parse_region(optional_value(&mut record_args))?,
+                Some("--fps") => fps = parse_fps(record_args.next())?,
+                Some("--frames") => frames = Some(parse_frames(record_args.next())?),
+                Some("--duration") => duration = Some(parse_duration(record_args.next())?),
+                _ => {
+                    return Err(Failure::Usage(format!(
+                        "Unknown option '{}'. Example: `cargo run record --fps 10 -o capture.bruh`",
+                        arg.to_string_lossy()
+                    )))
+                }
+            }
+        }
+        let frames = frames.or(duration.map(|seconds| (seconds * fps).ceil() as u64));
+        let output = output.unwrap_or_else(|| {
+            let seconds = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs());
+            PathBuf::from(format!("recording-{}.bruh", seconds))
+        });
+
+        println!("Recording to {}, press Ctrl+C to stop", output.display());
+        let taken = screenshot::record(region.as_ref(), fps, frames, &output, |taken| {
+            print!("\r{} frame(s)", taken);
+            let _ = io::stdout().flush();
+        })
+        .map_err(|e| Failure::Failed(format!("\nFailed to record: {}", e)))?;
+        println!("\nSaved {} frame(s) to {}", taken, output.display());
+        Ok(())
+    } else if command == Some("stream") {
+        let mut listen = None;
+        let mut connect = None;
+        let mut output = None;
+        let mut region = None;
+        let mut fps = 10.0;
+        let mut frames = None;
+        let mut paths = Vec::new();
+        let mut stream_args = args.iter().skip(2).peekable();
+        while let Some(arg) = stream_args.next() {
+            match arg.to_str() {
+                Some("--listen") => listen = Some(parse_address("--listen", stream_args.next())?),
+                Some("--connect") => {
+                    connect = Some(parse_address("--connect", stream_args.next())?)
+                }
+                Some("-o" | "--output") => match stream_args.next() {
+                    Some(path) => output = Some(PathBuf::from(path)),
+                    None => {
+                        return Err(Failure::Usage(
+                            "-o needs a path, e.g. `-o live.bruh`".to_owned(),
+                        ))
+                    }
+                },
+                Some("--region") => region = parse_region(optional_value(&mut stream_args))?,
+                Some("--fps") => fps = parse_fps(stream_args.next())?,
+                Some("--frames") => frames = Some(parse_frames(stream_args.next())?),
+                _ => paths.push(PathBuf::from(arg)),
+            }
+        }
+
+        match (listen, connect) {
+            (Some(address), None) => {
+                let Some(output) = output else {
+                    return Err(Failure::Usage(
+                        "--listen needs -o for where to save the frames. Example: `cargo run stream --listen 0.0.0.0:7878 -o live.bruh`"
+                            .to_owned(),
+                    ));
+                };
+                let listener = TcpListener::bind(&address).map_err(|e| {
+                    Failure::Failed(format!("Couldn't listen on {}: {}", address, e))
+                })?;
+                println!(
+                    "Listening on {}, saving frames to {}",
+                    address,
+                    output.display()
+                );
+                watch_stream(listener, output)
+            }
+            (None, Some(address)) => {
+                let mut sender = stream::Sender::connect(&address).map_err(|e| {
+                    Failure::Failed(format!("Couldn't connect to {}: {}", address, e))
+                })?;
+                let sent = if paths.is_empty() {
+                    screenshot::capture_frames(region.as_ref(), fps, frames, |_, frame, _| {
+                        sender.send(&frame).map_err(Into::into)
+                    })
+                } else {
+                    stream::send_files(&mut sender, &paths, fps).map(|()| paths.len() as u64)
+                }
+                .and_then(|sent| sender.close().map(|()| sent))
+                .map_err(|e| Failure::Failed(format!("Stopped streaming: {}", e)))?;
+                println!("Sent {} frame(s) to {}", sent, address);
+                Ok(())
+            }
+            _ => Err(Failure::Usage(
+                "Pass either --listen or --connect. Example: `cargo run stream --listen 0.0.0.0:7878 -o live.bruh`, then `cargo run stream --connect host:7878`"
+                    .to_owned(),
+            )),
+        }
+    } else if command == Some("embed") {
+        let mut paths = Vec::new();
+        let mut output = None;
+        let mut lsb = false;
+        let mut embed_args = args.iter().skip(2);
+        while let Some(arg) = embed_args.next() {
+            match arg.to_str() {
+                Some("-o" | "--output") => match embed_args.next() {
+                    Some(path) => output = Some(PathBuf::from(path)),
+                    None => {
+                        return Err(Failure::Usage(
+                            "-o needs a path, e.g. `-o image.embedded.bruh`".to_owned(),
+                        ))
+                    }
+                },
+                Some("--lsb") => lsb = true,
+                Some(option) if option.starts_with('-') && option != "-" => {
+                    return Err(Failure::Usage(format!(
+                        "Unknown option '{}'. Example: `cargo run embed image.bruh secret.txt --lsb`",
+                        option
+                    )))
+                }
+                _ => paths.push(PathBuf::from(arg)),
+            }
+        }
+        let [input, payload] = &paths[..] else {
+            return Err(Failure::Usage(
+                "Expected an image and a file to embed. Example: `cargo run embed image.bruh secret.txt`"
+                    .to_owned(),
+            ));
+        };
+        let output = output.unwrap_or_else(|| input.with_extension("embedded.bruh"));
+        if output == *input {
+            return Err(Failure::Usage(format!(
+                "{} would be overwritten by its own conversion, pass an output path.",
+                input.display()
+            )));
+        }
+
+        let failed = |e: Box<dyn std::error::Error>| {
+            Failure::Failed(format!("Failed to embed {}: {}", payload.display(), e))
+        };
+        if lsb {
+            let capacity = embed::embed_lsb(input, payload, &output).map_err(failed)?;
+            println!("Wrote {} (room for {} bytes).", output.display(), capacity);
+        } else {
+            let size = embed::embed(input, payload, &output).map_err(failed)?;
+            println!("Wrote {} with {} bytes embedded.", output.display(), size);
+        }
+        Ok(())
+    } else if command == Some("extract") {
+        let mut input = None;
+        let mut output = PathBuf::from("-");
+        let mut extract_args = args.iter().skip(2);
+        while let Some(arg) = extract_args.next() {
+            match arg.to_str() {
+                Some("-o" | "--output") => {
+                    output = extract_args.next().map_or(output, PathBuf::from)
+                }
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+        let Some(input) = input else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run extract image.bruh -o secret.txt`"
+                    .to_owned(),
+            ));
+        };
+
+        let size = embed::extract(&input, &output).map_err(|e| {
+            Failure::Failed(format!("Failed to extract from {}: {}", input.display(), e))
+        })?;
+        if !is_stdio(&output) {
+            println!("Extracted {} bytes to {}", size, output.display());
+        }
+        Ok(())
+    } else if command == Some("keygen") {
+        let Some(key) = args.get(2).map(PathBuf::from) else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run keygen release.key`"
+                    .to_owned(),
+            ));
+        };
+
+        let public_key = sign::generate_key(&key)
+            .map_err(|e| Failure::Failed(format!("Couldn't make a key pair: {}", e)))?;
+        println!(
+            "Wrote the secret key to {} and the public key to {}",
+            key.display(),
+            public_key.display()
+        );
+        Ok(())
+    } else if command == Some("sign") {
+        let mut input = None;
+        let mut key = None;
+        let mut output = None;
+        let mut sign_args = args.iter().skip(2);
+        while let Some(arg) = sign_args.next() {
+            match arg.to_str() {
+                Some("--key") => key = sign_args.next().map(PathBuf::from),
+                Some("-o" | "--output") => output = sign_args.next().map(PathBuf::from),
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+        let (Some(input), Some(key)) = (input, key) else {
+            return Err(Failure::Usage(
+                "Expected a file and a secret key. Example: `cargo run sign image.bruh --key release.key`"
+                    .to_owned(),
+            ));
+        };
+        // Signs the file in place, or from standard input to standard output.
+        let output = output.unwrap_or_else(|| input.clone());
+
+        sign::sign(&input, &key, &output)
+            .map_err(|e| Failure::Failed(format!("Failed to sign {}: {}", input.display(), e)))?;
+        if !is_stdio(&output) {
+            println!("Signed {}", output.display());
+        }
+        Ok(())
+    } else if command == Some("verify-signature") {
+        let mut input = None;
+        let mut key = None;
+        let mut verify_args = args.iter().skip(2);
+        while let Some(arg) = verify_args.next() {
+            match arg.to_str() {
+                Some("--key") => key = verify_args.next().map(PathBuf::from),
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+        let (Some(input), Some(key)) = (input, key) else {
+            return Err(Failure::Usage(
+                "Expected a file and a public key. Example: `cargo run verify-signature image.bruh --key release.key.pub`"
+                    .to_owned(),
+            ));
+        };
+        sign::verify(&input, &key)
+            .map_err(|e| Failure::Failed(format!("Couldn't verify {}: {}", input.display(), e)))?;
+        println!("{}: signature OK", input.display());
+        Ok(())
+    } else if command == Some("pack") {
+        let mut list = false;
+        let mut dedup = false;
+        let mut input = None;
+        let mut output = None;
+        let mut pack_args = args.iter().skip(2);
+        while let Some(arg) = pack_args.next() {
+            match arg.to_str() {
+                Some("--list") => list = true,
+                Some("--dedup") => dedup = true,
+                Some("-o" | "--output") => match pack_args.next() {
+                    Some(path) => output = Some(PathBuf::from(path)),
+                    None => {
+                        return Err(Failure::Usage(
+                            "-o needs a path, e.g. `-o assets.bruhpack`".to_owned(),
+                        ))
+                    }
+                },
+                Some(option) if option.starts_with('-') && option != "-" => {
+                    return Err(Failure::Usage(format!(
+                        "Unknown option '{}'. Example: `cargo run pack assets/ -o assets.bruhpack`",
+                        option
+                    )))
+                }
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+        let Some(input) = input else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run pack assets/ -o assets.bruhpack`"
+                    .to_owned(),
+            ));
+        };
+
+        if list {
+            let entries = pack::list(&input).map_err(|e| {
+                Failure::Failed(format!("Couldn't read {}: {}", input.display(), e))
+            })?;
+            for entry in entries {
+                println!("{:>10}  {}", entry.len, entry.name);
+            }
+            return Ok(());
+        }
+        // `.` and `..` have no name to put the extension on.
+        let output = output.or_else(|| input.file_name().map(|_| input.with_extension("bruhpack")));
+        let Some(output) = output else {
+            return Err(Failure::Usage("Pass a path for the pack with `-o`.".to_owned()));
+        };
+        let packed = pack::pack(&input, &output, dedup)
+            .map_err(|e| Failure::Failed(format!("Failed to pack {}: {}", input.display(), e)))?;
+        println!("Packed {} file(s) into {}", packed.entries, output.display());
+        if dedup {
+            println!(
+                "{} were duplicates, saving {} bytes.",
+                packed.entries - packed.stored,
+                packed.saved
+            );
+        }
+        Ok(())
+    } else if command == Some("unpack") {
+        let mut input = None;
+        let mut output = None;
+        let mut unpack_args = args.iter().skip(2);
+        while let Some(arg) = unpack_args.next() {
+            match arg.to_str() {
+                Some("-o" | "--output") => match unpack_args.next() {
+                    Some(path) => output = Some(PathBuf::from(path)),
+                    None => {
+                        return Err(Failure::Usage(
+                            "-o needs a path, e.g. `-o assets/`".to_owned(),
+                        ))
+                    }
+                },
+                Some(option) if option.starts_with('-') && option != "-" => {
+                    return Err(Failure::Usage(format!(
+                        "Unknown option '{}'. Example: `cargo run unpack assets.bruhpack -o assets/`",
+                        option
+                    )))
+                }
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+        let Some(input) = input else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run unpack assets.bruhpack -o assets/`"
+                    .to_owned(),
+            ));
+        };
+        let output = output.unwrap_or_else(|| input.with_extension(""));
+        if output == input {
+            return Err(Failure::Usage("Pass a folder to unpack into with `-o`.".to_owned()));
+        }
+
+        let count = pack::unpack(&input, &output)
+            .map_err(|e| Failure::Failed(format!("Failed to unpack {}: {}", input.display(), e)))?;
+        println!("Unpacked {} file(s) into {}", count, output.display());
+        Ok(())
+    } else if command == Some("register") {
+        if !args[2..].iter().any(|arg| arg == "--windows") {
+            return Err(Failure::Usage(
+                "Pass the platform to register with. Example: `cargo run register --windows`"
+                    .to_owned(),
+            ));
+        }
+        register::register_windows().map_err(|e| {
+            Failure::Failed(format!("Couldn't register the .bruh file type: {}", e))
+        })?;
+        println!("Registered .bruh files with Explorer for the current user.");
+        Ok(())
+    } else if command == Some("selftest") {
+        if !selftest::run() {
+            return Err(Failure::Failed(
+                "This build doesn't produce the reference output.".to_owned(),
+            ));
+        }
+        Ok(())
+    } else if command == Some("gen-vectors") {
+        let Some(folder) = args.get(2).map(PathBuf::from) else {
+            return Err(Failure::Usage(
+                "Secondary argument ('folder') not provided. Example: `cargo run gen-vectors vectors/`"
+                    .to_owned(),
+            ));
+        };
+        let count = vectors::generate(&folder).map_err(|e| {
+            Failure::Failed(format!("Couldn't write test vectors to {}: {}", folder.display(), e))
+        })?;
+        println!(
+            "Wrote {} test vectors and manifest.json to {}",
+            count,
+            folder.display()
+        );
+        Ok(())
+    } else if command == Some("repair") {
+        let mut input = None;
+        let mut output = None;
+        let mut fill = [255, 0, 255];
+        let mut repair_args = args.iter().skip(2);
+        while let Some(arg) = repair_args.next() {
+            match arg.to_str() {
+                Some("-o" | "--output") => output = repair_args.next().map(PathBuf::from),
+                Some("--fill") => fill = parse_color("--fill", repair_args.next())?,
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+        let Some(input) = input else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run repair broken.bruh -o fixed.bruh`"
+                    .to_owned(),
+            ));
+        };
+        let output = output.unwrap_or_else(|| input.with_extension("repaired.bruh"));
+        if output == input {
+            return Err(Failure::Usage(
+                "Repairing a file in place would lose the original, pass a different output path."
+                    .to_owned(),
+            ));
+        }
+
+        let report = repair::repair(&input, &output, fill)
+            .map_err(|e| Failure::Failed(format!("Failed to repair {}: {}", input.display(), e)))?;
+        println!("{}", report);
+        println!("Wrote {}", output.display());
+        Ok(())
+    } else if command == Some("decompile") {
+        let mut trace = false;
+        let mut decrypt = false;
+        let mut data_uri = false;
+        let mut raw = false;
+        let mut layout = PixelLayout::Rgb;
+        let mut format = None;
+        let mut quality = None;
+        let mut paths = Vec::new();
+        let mut decompile_args = args.iter().skip(2);
+        while let Some(arg) = decompile_args.next() {
+            match arg.to_str() {
+                Some("--trace-decode") => trace = true,
+                Some("--decrypt") => decrypt = true,
+                Some("--data-uri") => data_uri = true,
+                Some("--raw") => raw = true,
+                Some("--pixel-format") => layout = parse_pixel_format(decompile_args.next())?,
+                Some("--format") => format = Some(parse_format(decompile_args.next())?),
+                Some("--quality") => quality = Some(parse_quality(decompile_args.next())?),
+                _ => paths.push(arg),
+            }
+        }
+        let Some(input) = paths.first().map(PathBuf::from) else {
+            return Err(Failure::Usage(
+                "Secondary argument ('path') not provided. Example: `cargo run decompile ~/image.bruh`"
+                    .to_owned(),
+            ));
+        };
+        if data_uri && (raw || format.is_some() || quality.is_some() || paths.len() > 1) {
+            return Err(Failure::Usage(
+                "--data-uri prints a PNG, so it can't be combined with an output path, --format, \
+                 --quality or --raw."
+                    .to_owned(),
+            ));
+        }
+        let extension = match format {
+            Some(ImageFormat::Pnm) => "ppm",
+            Some(format) => format.extensions_str()[0],
+            None if raw => "raw",
+            None => "png",
+        };
+        let output = output_path(&input, paths.get(1).copied(), extension)?;
+        if raw && (format.is_some() || quality.is_some()) {
+            return Err(Failure::Usage(
+                "--raw writes bare pixels, so it can't be combined with --format or --quality."
+                    .to_owned(),
+            ));
+        }
+        // Without --format, the output's extension picks it, PNG unless it's one of the others.
+        let format = format
+            .or_else(|| ImageFormat::from_path(&output).ok())
+            .unwrap_or(ImageFormat::Png);
+        if quality.is_some() && !matches!(format, ImageFormat::Jpeg | ImageFormat::Avif) {
+            return Err(Failure::Usage(format!(
+                "--quality only applies to JPEG and AVIF, not {:?}.",
+                format
+            )));
+        }
+
+        if trace && (is_stdio(&input) || decrypt) {
+            return Err(Failure::Usage(
+                "--trace-decode reads the file separately, so it can't be used with standard \
+                 input or --decrypt."
+                    .to_owned(),
+            ));
+        }
+        if trace {
+            trace::trace_decode(&input, io::stderr().lock()).map_err(|e| {
+                Failure::Failed(format!("Couldn't trace {}: {}", input.display(), e))
+            })?;
+        }
+        // A decrypted file is only ever held in memory.
+        let decrypted = if decrypt {
+            let passphrase = crypt::passphrase(is_stdio(&input), false)
+                .map_err(|e| Failure::Usage(e.to_string()))?;
+            let contents = load_bruh(&input, None).map_err(|e| Failure::Failed(e.to_string()))?;
+            Some(crypt::decrypt(&contents, &passphrase).map_err(|e| {
+                Failure::Failed(format!("Couldn't decrypt {}: {}", input.display(), e))
+            })?)
+        } else {
+            None
+        };
+        let failed_reading = |e: InputError| Failure::Failed(e.to_string());
+
+        if data_uri {
+            let contents = load_bruh(&input, decrypted).map_err(failed_reading)?;
+            let (uri, animated) = first_frame(&contents)
+                .and_then(|(frame, animated)| Ok((bruh_to_data_uri(frame)?, animated)))
+                .map_err(|e| {
+                    Failure::Failed(format!("Failed to convert BRUH to a data URI: {}", e))
+                })?;
+            if animated {
+                note_first_frame(&input);
+            }
+            println!("{}", uri);
+            return Ok(());
+        }
+        if raw {
+            let contents = load_bruh(&input, decrypted).map_err(failed_reading)?;
+            let ((width, height), animated) = first_frame(&contents)
+                .and_then(|(frame, animated)| Ok((bruh_to_raw(frame, &output, layout)?, animated)))
+                .map_err(|e| Failure::Failed(format!("Failed to export raw pixels: {}", e)))?;
+            if animated {
+                note_first_frame(&input);
+            }
+            if !is_stdio(&output) {
+                println!(
+                    "Successfully exported {}×{} raw pixels: {}",
+                    width,
+                    height,
+                    output.display()
+                );
+            }
+            return Ok(());
+        }
+        let animated = match (format, decrypted) {
+            (ImageFormat::Png, Some(contents)) => stream_bruh_to_png(|| Ok(&contents[..]), &output),
+            (ImageFormat::Png, None) => bruh_file_to_png(&input, &output),
+            (_, decrypted) => load_bruh(&input, decrypted)
+                .map_err(Into::into)
+                .and_then(|contents| bruh_to_image(&contents, &output, format, quality)),
+        }
+        .map_err(|e| Failure::Failed(format!("Failed to convert BRUH to {:?}: {}", format, e)))?;
+        if animated {
+            note_first_frame(&input);
+        }
+        if !is_stdio(&output) {
+            println!("Successfully converted BRUH to {:?}: {}", format, output.display());
+        }
+        Ok(())
+    } else {
+        view(&args, command)
+    }
+}
+
+/// Opens the viewer, for `bruh view` and for a command line that doesn't start with a
+/// command.
+#[cfg(feature = "gui")]
+fn view(args: &[OsString], command: Option<&str>) -> Result<(), Failure> {
+    // `bruh view <file>` and plain `bruh <file>` both open the viewer.
+    let skip = 1 + usize::from(command == Some("view"));
+    let (file_paths, options) = bruh_view::parse_args(args.iter().skip(skip))?;
+
+    bruh_view::run(file_paths, options)
+        .map_err(|e| Failure::Failed(format!("Couldn't start the viewer: {}", e)))
+}
+
+/// Receives frames on `listener` into `output` and shows them in the viewer as they
+/// arrive, once the first one is in.
+#[cfg(feature = "gui")]
+fn watch_stream(listener: TcpListener, output: PathBuf) -> Result<(), Failure> {
+    let (arrived, frames) = std::sync::mpsc::channel();
+    let path = output.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = stream::receive(&listener, &path, || {
+            let _ = arrived.send(());
+        }) {
+            eprintln!("Stopped receiving: {}", e);
+        }
+    });
+    frames.recv().map_err(|_| {
+        Failure::Failed("Stopped receiving before the first frame arrived.".to_owned())
+    })?;
+
+    let options = bruh_view::Options {
+        live: Some(frames),
+        ..Default::default()
+    };
+    bruh_view::run(vec![output], options)
+        .map_err(|e| Failure::Failed(format!("Couldn't start the viewer: {}", e)))
+}
+
+/// Receives frames on `listener` into `output`, without a viewer to show them in.
+#[cfg(not(feature = "gui"))]
+fn watch_stream(listener: TcpListener, output: PathBuf) -> Result<(), Failure> {
+    let mut received = 0;
+    stream::receive(&listener, &output, || {
+        received += 1;
+        print!("\r{} frame(s)", received);
+        let _ = io::stdout().flush();
+    })
+    .map_err(|e| Failure::Failed(format!("Stopped receiving: {}", e)))
+}
+
+#[cfg(not(feature = "gui"))]
+fn view(_args: &[OsString], _command: Option<&str>) -> Result<(), Failure> {
+    Err(Failure::Usage(
+        "This build of bruh has no viewer. Rebuild it with the `gui` feature, or run a \
+         command such as `bruh render image.bruh > image.png`."
+            .to_owned(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `parse` given `value` as the option's value.
+    fn parse<T>(
+        parse: fn(Option<&OsString>) -> Result<T, String>,
+        value: &str,
+    ) -> Result<T, String> {
+        parse(Some(&OsString::from(value)))
+    }
+
+    #[test]
+    fn regions_keep_the_sign_of_their_coordinates() {
+        let region = |value| {
+            parse(parse_region, value).map(|region| {
+                region.map(|region| (region.width, region.height, region.x, region.y))
+            })
+        };
+        assert_eq!(region("800x600+100+50"), Ok(Some((800, 600, 100, 50))));
+        assert_eq!(region("800x600-1920+0"), Ok(Some((800, 600, -1920, 0))));
+        assert_eq!(region("10x20+5-30"), Ok(Some((10, 20, 5, -30))));
+        assert_eq!(parse_region(None).map(|region| region.is_none()), Ok(true));
+        let invalid = ["800x600", "800x600+100", "800+100+50", "axb+1+2", "-5x5+0+0", "5x5+0+0+0"];
+        for invalid in invalid {
+            assert!(region(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn optional_values_stop_at_the_next_option() {
+        let args = ["800x600+0+0", "--fps", "10"].map(OsString::from);
+        let mut args = args.iter().peekable();
+        assert_eq!(optional_value(&mut args), Some(&"800x600+0+0".into()));
+        assert_eq!(optional_value(&mut args), None);
+        assert_eq!(args.next(), Some(&"--fps".into()));
+        assert_eq!(optional_value(&mut args), Some(&"10".into()));
+        assert_eq!(optional_value(&mut args), None);
+    }
+
+    #[test]
+    fn sizes_and_grids_need_both_numbers() {
+        assert_eq!(parse(parse_size, "1024x768"), Ok((1024, 768)));
+        assert_eq!(parse(parse_size, "1024×768"), Ok((1024, 768)));
+        assert!(parse(parse_size, "1024").is_err());
+        assert!(parse_size(None).is_err());
+        assert_eq!(parse(parse_grid, "8X4"), Ok((8, 4)));
+        assert!(parse(parse_grid, "0x4").is_err());
+        assert!(parse(parse_columns, "0").is_err());
+    }
+
+    #[test]
+    fn numbers_are_checked_against_their_range() {
+        assert_eq!(parse(parse_quality, "85"), Ok(85));
+        assert!(parse(parse_quality, "0").is_err());
+        assert!(parse(parse_quality, "101").is_err());
+        assert_eq!(parse(parse_colors, "256"), Ok(256));
+        assert!(parse(parse_colors, "257").is_err());
+        assert_eq!(parse(parse_fps, "29.97"), Ok(29.97));
+        assert!(parse(parse_fps, "0").is_err());
+        assert!(parse(parse_duration, "-1").is_err());
+        assert!(parse(parse_duration, "inf").is_err());
+        assert_eq!(parse(parse_port, "8080"), Ok(8080));
+        assert!(parse(parse_port, "65536").is_err());
+        assert!(parse(parse_frames, "1.5").is_err());
+        assert_eq!(parse(parse_byte_count, "32"), Ok(32));
+    }
+
+    #[test]
+    fn factors_and_opacities_take_percentages() {
+        assert_eq!(parse_factor("--brightness", Some(&"120%".into())), Ok(1.2));
+        assert_eq!(parse_factor("--brightness", Some(&"0.5".into())), Ok(0.5));
+        assert!(parse_factor("--brightness", Some(&"-1".into())).is_err());
+        assert!(parse_factor("--brightness", Some(&"NaN".into())).is_err());
+        assert_eq!(parse(parse_opacity, "50%"), Ok(0.5));
+        assert!(parse(parse_opacity, "1.5").is_err());
+    }
+
+    #[test]
+    fn names_are_looked_up() {
+        assert_eq!(parse(parse_format, "JPG"), Ok(ImageFormat::Jpeg));
+        assert!(parse(parse_format, "bmp").is_err());
+        assert_eq!(parse(parse_pixel_format, "graya8"), Ok(PixelLayout::GrayAlpha));
+        assert!(parse(parse_pixel_format, "rgb16").is_err());
+        let position = |value| parse(parse_position, value);
+        assert!(matches!(position("-10, 20"), Ok(filter::Placement::At(-10, 20))));
+        assert!(matches!(position("Bottom-Right"), Ok(filter::Placement::Aligned(..))));
+        assert!(position("middle").is_err());
+        assert!(parse(parse_blend, "multiply").is_ok());
+        assert!(parse(parse_blend, "screen").is_err());
+        assert_eq!(parse_color("--fill", Some(&"#202020".into())), Ok([32, 32, 32]));
+        assert_eq!(parse_color("--fill", Some(&"white".into())), Ok([255, 255, 255]));
+        assert!(parse_color("--fill", Some(&"nope".into())).is_err());
+        assert!(parse_color("--fill", None).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use std::{error::Error, fmt, path::Path};
+
+use crate::input::read_rgb;
+
+/// Standard deviation of the Gaussian window SSIM compares over, from the original paper.
+const SSIM_SIGMA: f64 = 1.5;
+/// How far the window reaches from its center, about 3.5 standard deviations.
+const SSIM_RADIUS: usize = 5;
+/// Constants that keep SSIM stable in flat areas, for 8-bit values.
+const SSIM_C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const SSIM_C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+/// How close a converted image is to its original.
+pub struct Metrics {
+    pub width: u32,
+    pub height: u32,
+    /// Peak signal-to-noise ratio over the red, green and blue channels, in decibels.
+    /// Infinite when the images are identical.
+    pub psnr: f64,
+    /// Structural similarity of the images' luma, from 1 for identical images down to 0
+    /// or below for unrelated ones.
+    pub ssim: f64,
+}
+
+impl Metrics {
+    /// The metrics as a JSON object. PSNR is `null` for identical images, since JSON has
+    /// no infinity.
+    pub fn to_json(&self) -> String {
+        serde_json::json!({
+            "width": self.width,
+            "height": self.height,
+            "psnr": self.psnr.is_finite().then_some(self.psnr),
+            "ssim": self.ssim,
+        })
+        .to_string()
+    }
+}
+
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}×{} pixels", self.width, self.height)?;
+        if self.psnr.is_finite() {
+            writeln!(f, "PSNR: {:.2} dB", self.psnr)?;
+        } else {
+            writeln!(f, "PSNR: infinite (identical)")?;
+        }
+        write!(f, "SSIM: {:.4}", self.ssim)
+    }
+}
+
+/// Compares the image at `converted` with the one at `original`. Each can be a .bruh file
+/// or any image [`read_rgb`] reads, and both must be the same size.
+pub fn measure(original: &Path, converted: &Path) -> Result<Metrics, Box<dyn Error>> {
+    let (width, height, original_rgb) = read_rgb(original)?;
+    let (converted_width, converted_height, converted_rgb) = read_rgb(converted)?;
+    if (width, height) != (converted_width, converted_height) {
+        return Err(format!(
+            "The images are different sizes: {}×{} and {}×{} pixels.",
+            width, height, converted_width, converted_height
+        )
+        .into());
+    }
+    if width == 0 || height == 0 {
+        return Err("Can't measure an empty image.".into());
+    }
+
+    Ok(Metrics {
+        width,
+        height,
+        psnr: psnr(&original_rgb, &converted_rgb),
+        ssim: ssim(
+            &luma(&original_rgb),
+            &luma(&converted_rgb),
+            width as usize,
+            height as usize,
+        ),
+    })
+}
+
+fn psnr(a: &[u8], b: &[u8]) -> f64 {
+    let squared_error: f64 = a
+        .iter()
+        .zip(b)
+        .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+        .sum();
+    let mse = squared_error / a.len() as f64;
+    10.0 * (255.0 * 255.0 / mse).log10()
+}
+
+/// Rec. 601 luma of packed RGB pixels, which is what SSIM is usually measured on.
+fn luma(rgb: &[u8]) -> Vec<f64> {
+    rgb.chunks_exact(3)
+        .map(|p| 0.299 * p[0] as f64 + 0.587 * p[1] as f64 + 0.114 * p[2] as f64)
+        .collect()
+}
+
+/// Mean SSIM over a Gaussian window centered on every pixel.
+///
+/// Near the edges the window is cut off and its weights scaled back up to 1, so small
+/// images still get a score.
+fn ssim(a: &[f64], b: &[f64], width: usize, height: usize) -> f64 {
+    let product = |x: &[f64], y: &[f64]| -> Vec<f64> {
+        x.iter().zip(y).map(|(x, y)| x * y).collect()
+    };
+    let mean_a = blur(a, width, height);
+    let mean_b = blur(b, width, height);
+    let mean_aa = blur(&product(a, a), width, height);
+    let mean_bb = blur(&product(b, b), width, height);
+    let mean_ab = blur(&product(a, b), width, height);
+
+    let total: f64 = (0..a.len())
+        .map(|i| {
+            let (mu_a, mu_b) = (mean_a[i], mean_b[i]);
+            let variance_a = mean_aa[i] - mu_a * mu_a;
+            let variance_b = mean_bb[i] - mu_b * mu_b;
+            let covariance = mean_ab[i] - mu_a * mu_b;
+            ((2.0 * mu_a * mu_b + SSIM_C1) * (2.0 * covariance + SSIM_C2))
+                / ((mu_a * mu_a + mu_b * mu_b + SSIM_C1) * (variance_a + variance_b + SSIM_C2))
+        })
+        .sum();
+    total / a.len() as f64
+}
+
+/// Gaussian blur of `values`, horizontally then vertically.
+fn blur(values: &[f64], width: usize, height: usize) -> Vec<f64> {
+    let weights: Vec<f64> = (0..=2 * SSIM_RADIUS)
+        .map(|i| {
+            let distance = i as f64 - SSIM_RADIUS as f64;
+            (-distance * distance / (2.0 * SSIM_SIGMA * SSIM_SIGMA)).exp()
+        })
+        .collect();
+    // Weighted mean of `len` values, `stride` apart from `start`, around `center`.
+    let blur_line = |source: &[f64], start: usize, stride: usize, len: usize, center: usize| {
+        let first = center.saturating_sub(SSIM_RADIUS);
+        let last = (center + SSIM_RADIUS).min(len - 1);
+        let (mut sum, mut weight_sum) = (0.0, 0.0);
+        for j in first..=last {
+            let weight = weights[j + SSIM_RADIUS - center];
+            sum += weight * source[start + j * stride];
+            weight_sum += weight;
+        }
+        sum / weight_sum
+    };
+
+    let mut horizontal = vec![0.0; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            horizontal[y * width + x] = blur_line(values, y * width, 1, width, x);
+        }
+    }
+    let mut blurred = vec![0.0; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            blurred[y * width + x] = blur_line(&horizontal, x, width, height, y);
+        }
+    }
+    blurred
+}
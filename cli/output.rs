@@ -0,0 +1,100 @@
+use std::{
+    error::Error,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use bruh::{file::AtomicFile, DecodedImage};
+
+use crate::crypt;
+
+/// Where a conversion writes: an [`AtomicFile`], or standard output when the path is `-`.
+pub enum Output {
+    File(AtomicFile),
+    Stdout(BufWriter<io::Stdout>),
+    /// Collected in memory and encrypted into `output` on commit, see [`crypt::encrypt`].
+    Encrypted {
+        buffer: Vec<u8>,
+        passphrase: String,
+        output: Box<Output>,
+    },
+}
+
+impl Output {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(if is_stdio(path) {
+            Output::Stdout(BufWriter::new(io::stdout()))
+        } else {
+            Output::File(AtomicFile::create(path)?)
+        })
+    }
+
+    /// Like [`Output::create`], but encrypts what's written with `passphrase`. It has to
+    /// be a .bruh file.
+    pub fn encrypted(path: &Path, passphrase: String) -> io::Result<Self> {
+        Ok(Output::Encrypted {
+            buffer: Vec::new(),
+            passphrase,
+            output: Box::new(Output::create(path)?),
+        })
+    }
+
+    /// Commits the file, or flushes standard output.
+    pub fn commit(self) -> io::Result<()> {
+        match self {
+            Output::File(file) => file.commit(),
+            Output::Stdout(mut stdout) => stdout.flush(),
+            Output::Encrypted {
+                buffer,
+                passphrase,
+                mut output,
+            } => {
+                let encrypted = crypt::encrypt(&buffer, &passphrase)
+                    .map_err(|e| io::Error::other(e.to_string()))?;
+                output.write_all(&encrypted)?;
+                output.commit()
+            }
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::File(file) => file.write(buf),
+            Output::Stdout(stdout) => stdout.write(buf),
+            Output::Encrypted { buffer, .. } => buffer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::File(file) => file.flush(),
+            Output::Stdout(stdout) => stdout.flush(),
+            Output::Encrypted { .. } => Ok(()),
+        }
+    }
+}
+
+/// Encodes a decoded .bruh image as a PNG in memory, for when it isn't going to a file.
+pub fn png_bytes(image: &DecodedImage) -> Result<Vec<u8>, Box<dyn Error>> {
+    if image.is_empty() {
+        return Err(format!(
+            "PNG can't store an empty image ({}×{} pixels).",
+            image.width, image.height
+        )
+        .into());
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, image.width, image.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&image.pixels)?;
+    Ok(png)
+}
+
+/// Whether `path` is `-`, which stands for standard input or output.
+pub fn is_stdio(path: &Path) -> bool {
+    path == Path::new("-")
+}
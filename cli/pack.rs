@@ -0,0 +1,286 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    io::Write,
+    path::{Component, Path},
+};
+
+use bruh::{check_bruh, decode_bruh};
+use sha2::{Digest, Sha256};
+
+use crate::{crypt, input::read_source, output::Output, serve::find_bruh_files};
+
+/// Starts every .bruhpack file.
+///
+/// After it comes the number of entries as a `u32`, then for each entry its name's length
+/// as a `u16`, the name as UTF-8 with `/` separators, and the offset and length of its
+/// .bruh file as `u64`s, all little-endian. The files follow the index, and offsets are
+/// from the start of the pack. Entries for the same image may share one file.
+const MAGIC: &[u8; 8] = b"BRUHPACK";
+
+/// A .bruh file in a pack.
+pub struct Entry {
+    pub name: String,
+    pub offset: u64,
+    pub len: u64,
+}
+
+/// What [`pack`] put in a pack.
+pub struct Packed {
+    /// Entries in the index.
+    pub entries: usize,
+    /// Files actually stored, fewer than the entries if some were duplicates.
+    pub stored: usize,
+    /// Bytes not written because of duplicates.
+    pub saved: u64,
+}
+
+/// Bundles every .bruh file under `folder` into a pack at `output`.
+///
+/// Each file is checked before it's added, so a pack only ever holds valid or encrypted
+/// .bruh files. With `dedup`, files that decode to the same pixels are stored once, even
+/// if their bytes differ, and their entries all point to it.
+pub fn pack(folder: &Path, output: &Path, dedup: bool) -> Result<Packed, Box<dyn Error>> {
+    let names = find_bruh_files(folder)?;
+    // Each name with the file its entry points to.
+    let mut entries = Vec::with_capacity(names.len());
+    let mut files: Vec<Vec<u8>> = Vec::new();
+    // The stored files by the digest of their images, so each file is only decoded once.
+    let mut stored_images: HashMap<[u8; 32], usize> = HashMap::new();
+    let mut saved = 0;
+    for name in names {
+        let mut contents = Vec::new();
+        read_source(&folder.join(&name), &mut contents)?;
+        if let Err(e) = check_bruh(&contents) {
+            if !crypt::is_encrypted(&contents) {
+                return Err(format!("{} isn't a valid .bruh file: {}", name, e).into());
+            }
+        }
+
+        if dedup {
+            let digest = image_digest(&contents);
+            if let Some(&i) = stored_images.get(&digest) {
+                saved += contents.len() as u64;
+                entries.push((name, i));
+                continue;
+            }
+            stored_images.insert(digest, files.len());
+        }
+        entries.push((name, files.len()));
+        files.push(contents);
+    }
+
+    let mut offsets = Vec::with_capacity(files.len());
+    let index_len: usize = entries.iter().map(|(name, _)| 2 + name.len() + 16).sum();
+    let mut offset = (MAGIC.len() + 4 + index_len) as u64;
+    for contents in &files {
+        offsets.push(offset);
+        offset += contents.len() as u64;
+    }
+
+    let mut index = Vec::with_capacity(MAGIC.len() + 4 + index_len);
+    index.extend_from_slice(MAGIC);
+    index.extend_from_slice(&u32::try_from(entries.len())?.to_le_bytes());
+    for (name, i) in &entries {
+        let name_len = u16::try_from(name.len())
+            .map_err(|_| format!("{} has too long a name to be packed.", name))?;
+        index.extend_from_slice(&name_len.to_le_bytes());
+        index.extend_from_slice(name.as_bytes());
+        index.extend_from_slice(&offsets[*i].to_le_bytes());
+        index.extend_from_slice(&(files[*i].len() as u64).to_le_bytes());
+    }
+
+    let mut file = Output::create(output)?;
+    file.write_all(&index)?;
+    for contents in &files {
+        file.write_all(contents)?;
+    }
+    file.commit()?;
+    Ok(Packed {
+        entries: entries.len(),
+        stored: files.len(),
+        saved,
+    })
+}
+
+/// What decides whether two packed files hold the same image: a SHA-256 digest of the
+/// size and pixels it decodes to, or of the bytes of an encrypted file, which can't be
+/// decoded. It's strong enough that equal digests are taken to mean equal images.
+fn image_digest(contents: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    match decode_bruh(contents) {
+        Ok(image) => {
+            hasher.update([0]);
+            hasher.update(image.width.to_le_bytes());
+            hasher.update(image.height.to_le_bytes());
+            hasher.update(&image.pixels);
+        }
+        Err(_) => {
+            hasher.update([1]);
+            hasher.update(contents);
+        }
+    }
+    hasher.finalize().into()
+}
+
+/// Reads the index of the pack `contents`, checking that every entry lies within it.
+pub fn entries(contents: &[u8]) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let truncated = "The pack's index is cut short.";
+    let mut rest = contents
+        .strip_prefix(MAGIC)
+        .ok_or("Not a .bruhpack file.")?;
+    let mut take = |len: usize| -> Result<&[u8], &str> {
+        if rest.len() < len {
+            return Err(truncated);
+        }
+        let (taken, remaining) = rest.split_at(len);
+        rest = remaining;
+        Ok(taken)
+    };
+
+    let count = u32::from_le_bytes(take(4)?.try_into().expect("4 bytes"));
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let name_len = u16::from_le_bytes(take(2)?.try_into().expect("2 bytes"));
+        let name = String::from_utf8(take(name_len as usize)?.to_vec())
+            .map_err(|_| "An entry's name isn't valid UTF-8.")?;
+        let offset = u64::from_le_bytes(take(8)?.try_into().expect("8 bytes"));
+        let len = u64::from_le_bytes(take(8)?.try_into().expect("8 bytes"));
+        if offset
+            .checked_add(len)
+            .is_none_or(|end| end > contents.len() as u64)
+        {
+            return Err(format!("{} lies past the end of the pack.", name).into());
+        }
+        entries.push(Entry { name, offset, len });
+    }
+    Ok(entries)
+}
+
+/// The entries in the pack at `input`.
+pub fn list(input: &Path) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let mut contents = Vec::new();
+    read_source(input, &mut contents)?;
+    entries(&contents)
+}
+
+/// Writes every file in the pack at `input` under `folder`, returning how many there were.
+pub fn unpack(input: &Path, folder: &Path) -> Result<usize, Box<dyn Error>> {
+    let mut contents = Vec::new();
+    read_source(input, &mut contents)?;
+    let entries = entries(&contents)?;
+
+    for entry in &entries {
+        // A pack from somewhere else mustn't be able to write outside `folder`.
+        let name = Path::new(&entry.name);
+        let is_safe = name.components().all(|c| matches!(c, Component::Normal(_)));
+        if entry.name.is_empty() || !is_safe {
+            return Err(format!("{} isn't a safe name to unpack to.", entry.name).into());
+        }
+
+        let path = folder.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let start = entry.offset as usize;
+        let mut file = Output::create(&path)?;
+        file.write_all(&contents[start..start + entry.len as usize])?;
+        file.commit()?;
+    }
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bruh::{encode_bruh, HEADER_SIZE};
+
+    fn image(seed: u8) -> Vec<u8> {
+        encode_bruh(3, 2, (0..6u8).map(|i| [seed, i, 255 - seed]))
+    }
+
+    /// A pack holding `contents` under the single entry `name`.
+    fn pack_of(name: &str, contents: &[u8]) -> Vec<u8> {
+        let mut pack = MAGIC.to_vec();
+        pack.extend_from_slice(&1u32.to_le_bytes());
+        pack.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        pack.extend_from_slice(name.as_bytes());
+        let offset = (pack.len() + 16) as u64;
+        pack.extend_from_slice(&offset.to_le_bytes());
+        pack.extend_from_slice(&(contents.len() as u64).to_le_bytes());
+        pack.extend_from_slice(contents);
+        pack
+    }
+
+    #[test]
+    fn unpacks_what_it_packed() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = dir.path().join("assets");
+        fs::create_dir_all(folder.join("icons")).unwrap();
+        fs::write(folder.join("a.bruh"), image(1)).unwrap();
+        fs::write(folder.join("icons/b.bruh"), image(2)).unwrap();
+
+        let pack_path = dir.path().join("assets.bruhpack");
+        let packed = pack(&folder, &pack_path, false).unwrap();
+        assert_eq!((packed.entries, packed.stored, packed.saved), (2, 2, 0));
+
+        let mut names: Vec<_> = list(&pack_path).unwrap().into_iter().map(|e| e.name).collect();
+        names.sort();
+        assert_eq!(names, ["a.bruh", "icons/b.bruh"]);
+
+        let unpacked = dir.path().join("unpacked");
+        assert_eq!(unpack(&pack_path, &unpacked).unwrap(), 2);
+        assert_eq!(fs::read(unpacked.join("a.bruh")).unwrap(), image(1));
+        assert_eq!(fs::read(unpacked.join("icons/b.bruh")).unwrap(), image(2));
+    }
+
+    #[test]
+    fn stores_the_same_image_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = dir.path().join("assets");
+        fs::create_dir_all(&folder).unwrap();
+        // The same pixels, as one run and as two.
+        let original = encode_bruh(3, 2, [[5, 6, 7]; 6]);
+        let copy = [&original[..HEADER_SIZE], &[2, 5, 6, 7, 4, 5, 6, 7]].concat();
+        assert_ne!(copy, original);
+        assert_eq!(decode_bruh(&copy).unwrap().pixels, decode_bruh(&original).unwrap().pixels);
+        fs::write(folder.join("a.bruh"), original).unwrap();
+        fs::write(folder.join("b.bruh"), &copy).unwrap();
+        fs::write(folder.join("c.bruh"), image(2)).unwrap();
+
+        let pack_path = dir.path().join("assets.bruhpack");
+        let packed = pack(&folder, &pack_path, true).unwrap();
+        assert_eq!((packed.entries, packed.stored), (3, 2));
+        assert_eq!(packed.saved, copy.len() as u64);
+
+        let entries = list(&pack_path).unwrap();
+        assert_eq!(entries[0].offset, entries[1].offset);
+        assert_ne!(entries[0].offset, entries[2].offset);
+    }
+
+    #[test]
+    fn refuses_names_outside_the_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let pack_path = dir.path().join("evil.bruhpack");
+        let folder = dir.path().join("out");
+        for name in ["../escaped.bruh", "a/../../escaped.bruh", "/tmp/escaped.bruh", ""] {
+            fs::write(&pack_path, pack_of(name, &image(1))).unwrap();
+            assert!(unpack(&pack_path, &folder).is_err(), "{:?}", name);
+        }
+        assert!(!dir.path().join("escaped.bruh").exists());
+    }
+
+    #[test]
+    fn refuses_broken_indexes() {
+        let pack = pack_of("a.bruh", &image(1));
+        assert_eq!(entries(&pack).unwrap().len(), 1);
+        let index_len = MAGIC.len() + 4 + 2 + "a.bruh".len() + 16;
+        for len in [0, MAGIC.len(), MAGIC.len() + 4, index_len - 1] {
+            assert!(entries(&pack[..len]).is_err(), "{}", len);
+        }
+        // The entry itself is cut short.
+        assert!(entries(&pack[..pack.len() - 1]).is_err());
+    }
+}
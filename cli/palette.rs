@@ -0,0 +1,48 @@
+use std::{error::Error, io::Write, path::Path};
+
+use bruh::{
+    palette::{median_cut, PaletteColor},
+    DecodedImage,
+};
+
+use crate::{
+    input::read_rgb,
+    output::{png_bytes, Output},
+};
+
+/// Width and height of each color's square in a swatch.
+const SWATCH_SIZE: u32 = 32;
+
+/// Finds the `colors` most representative colors of the image at `path` by median cut,
+/// most common first. An image with fewer distinct colors gets all of them.
+pub fn palette(path: &Path, colors: usize) -> Result<Vec<PaletteColor>, Box<dyn Error>> {
+    let (_, _, rgb) = read_rgb(path)?;
+    if rgb.is_empty() {
+        return Err("An empty image has no palette.".into());
+    }
+    Ok(median_cut(&rgb, colors))
+}
+
+/// Writes the palette as a PNG with a square of each color, side by side.
+pub fn write_swatch(palette: &[PaletteColor], output: &Path) -> Result<(), Box<dyn Error>> {
+    let width = SWATCH_SIZE * palette.len() as u32;
+    let mut pixels = Vec::with_capacity((width * SWATCH_SIZE * 4) as usize);
+    for _ in 0..SWATCH_SIZE {
+        for entry in palette {
+            let [r, g, b] = entry.color;
+            for _ in 0..SWATCH_SIZE {
+                pixels.extend_from_slice(&[r, g, b, 255]);
+            }
+        }
+    }
+
+    let swatch = DecodedImage {
+        width,
+        height: SWATCH_SIZE,
+        pixels,
+    };
+    let mut file = Output::create(output)?;
+    file.write_all(&png_bytes(&swatch)?)?;
+    file.commit()?;
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::error::Error;
+
+/// The ProgID .bruh files are associated with.
+#[cfg_attr(not(windows), allow(dead_code))]
+const PROG_ID: &str = "bruh.Image";
+
+/// Associates .bruh files with this executable for the current user: Explorer shows
+/// them with its icon, opens them in the viewer on a double-click, and offers a
+/// "Preview with bruh" verb that opens them without window decorations.
+///
+/// Everything goes under `HKEY_CURRENT_USER\Software\Classes`, so no administrator
+/// rights are needed. Explorer thumbnails need a shell extension DLL, which this
+/// doesn't install.
+pub fn register_windows() -> Result<(), Box<dyn Error>> {
+    platform::register()
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::{
+        env,
+        error::Error,
+        process::{Command, Stdio},
+        ptr,
+    };
+
+    use winapi::um::shlobj::{SHChangeNotify, SHCNE_ASSOCCHANGED, SHCNF_IDLIST};
+
+    use super::PROG_ID;
+
+    pub fn register() -> Result<(), Box<dyn Error>> {
+        let exe = env::current_exe()?;
+        let exe = exe.to_str().ok_or("The path to bruh.exe isn't valid Unicode.")?;
+        let classes = r"HKCU\Software\Classes";
+        let prog_id = format!(r"{}\{}", classes, PROG_ID);
+
+        let values = [
+            (format!(r"{}\.bruh", classes), None, PROG_ID.to_owned()),
+            (format!(r"{}\.bruh", classes), Some("Content Type"), "image/x-bruh".to_owned()),
+            (format!(r"{}\.bruh", classes), Some("PerceivedType"), "image".to_owned()),
+            (prog_id.clone(), None, "BRUH image".to_owned()),
+            (format!(r"{}\DefaultIcon", prog_id), None, format!("\"{}\",0", exe)),
+            (format!(r"{}\shell\open\command", prog_id), None, format!("\"{}\" \"%1\"", exe)),
+            (format!(r"{}\shell\preview", prog_id), None, "Preview with bruh".to_owned()),
+            (
+                format!(r"{}\shell\preview\command", prog_id),
+                None,
+                format!("\"{}\" view \"%1\" --no-decorations", exe),
+            ),
+        ];
+        for (key, name, data) in &values {
+            let mut command = Command::new("reg");
+            command.args(["add", key.as_str()]);
+            match name {
+                Some(name) => command.args(["/v", *name]),
+                None => command.arg("/ve"),
+            };
+            let status = command
+                .args(["/t", "REG_SZ", "/d", data.as_str(), "/f"])
+                .stdout(Stdio::null())
+                .status()?;
+            if !status.success() {
+                return Err(format!("`reg add {}` failed.", key).into());
+            }
+        }
+
+        // Explorer caches icons and verbs until it's told they changed.
+        unsafe {
+            SHChangeNotify(SHCNE_ASSOCCHANGED, SHCNF_IDLIST, ptr::null(), ptr::null());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use std::error::Error;
+
+    pub fn register() -> Result<(), Box<dyn Error>> {
+        Err("--windows only works on Windows.".into())
+    }
+}
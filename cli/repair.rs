@@ -0,0 +1,288 @@
+use std::{error::Error, fmt, io, path::Path};
+
+use bruh::{
+    check_bruh, decode_header, file::AtomicFile, BruhEncoder, DecodeError, Runs, ANIMATION_MAGIC,
+    DATA_MAGIC, HEADER_SIZE, SIGNATURE_MAGIC,
+};
+
+use crate::input::read_source;
+
+/// What [`repair`] kept, dropped and made up.
+pub struct RepairReport {
+    pub pixel_count: u64,
+    /// Pixels taken from the damaged file's runs.
+    pub salvaged: u64,
+    /// Pixels set to the fill color, past the end of the runs or where they were resynced.
+    pub filled: u64,
+    /// Pixels the runs described beyond the end of the image.
+    pub overflow: u64,
+    /// Bytes of a partial run at the end of the file.
+    pub partial_bytes: usize,
+    pub resync: Option<Resync>,
+}
+
+impl fmt::Display for RepairReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = match self.pixel_count {
+            0 => 100.0,
+            count => self.salvaged as f64 / count as f64 * 100.0,
+        };
+        write!(
+            f,
+            "Salvaged {} of {} pixels ({:.1}%)",
+            self.salvaged, self.pixel_count, percent
+        )?;
+        if let Some(resync) = &self.resync {
+            write!(
+                f,
+                ", cut {} stray bytes at offset {:#x} to get the runs back in step",
+                resync.bytes, resync.offset
+            )?;
+            if resync.filled > 0 {
+                write!(f, " ({} pixels lost there)", resync.filled)?;
+            }
+        }
+        if self.filled > 0 {
+            write!(f, ", filled {} missing pixels", self.filled)?;
+        }
+        if self.overflow > 0 {
+            write!(f, ", dropped {} pixels past the end of the image", self.overflow)?;
+        }
+        if self.partial_bytes > 0 {
+            write!(f, ", dropped a partial run of {} bytes", self.partial_bytes)?;
+        }
+        write!(f, ".")
+    }
+}
+
+/// Where [`repair`] cut bytes out of the runs to get them back in step.
+pub struct Resync {
+    /// Offset in the damaged file of the bytes that were cut.
+    pub offset: usize,
+    pub bytes: usize,
+    /// Pixels set to the fill color where the cut was, for the runs that were lost there.
+    pub filled: u64,
+}
+
+/// Runs after a cut that have to line up with the header for it to be trusted, so a file
+/// that's only cut short isn't taken for one with bytes missing near its end.
+const RESYNC_MIN_RUNS: usize = 16;
+
+/// How far from the header a cut may leave the number of pixels. A byte added or lost
+/// garbles the run it's in, and a lost one takes part of the next run with it.
+const RESYNC_TOLERANCE: u64 = 2 * u8::MAX as u64;
+
+/// Rewrites a damaged .bruh file so it matches its header again.
+///
+/// Runs are kept up to the last pixel of the image and anything after that is dropped.
+/// If the file ends early, the rest of the image is set to `fill`.
+///
+/// The format has no markers to resynchronize on, but a byte added or lost in the middle
+/// leaves the runs a few bytes off the end of the file, and every run after it misread.
+/// Then the few bytes that put the rest back in step are cut out, at the place where that
+/// makes the runs add up closest to the header, and the pixels of the runs lost there are
+/// set to `fill`. Damage that doesn't shift the runs, like a changed byte, can't be found
+/// this way and only costs its own run, or shifts the pixels after it.
+pub fn repair(
+    input: &Path,
+    output: &Path,
+    fill: [u8; 3],
+) -> Result<RepairReport, Box<dyn Error>> {
+    let mut contents = Vec::new();
+    read_source(input, &mut contents)?;
+    let header = contents.first_chunk::<HEADER_SIZE>().ok_or(DecodeError::MissingHeader)?;
+    let (width, height) = decode_header(header);
+    let pixel_count = width as u64 * height as u64;
+
+    // Readers of an animation already skip a last frame that was cut short.
+    if contents[HEADER_SIZE..].starts_with(ANIMATION_MAGIC) {
+        return Err(DecodeError::Animated.into());
+    }
+    // The repaired file is different, so a signature would no longer match and is dropped,
+    // and so is embedded data, which the encoder has no place for. A signature or data
+    // chunk that's cut short has nothing after it, unless the file is whole and its first
+    // run only reads as one.
+    let runs = Runs::of(&contents);
+    let signature_cut = runs.offset == HEADER_SIZE && runs.bytes.starts_with(SIGNATURE_MAGIC);
+    let data_cut = runs.data.is_none() && !runs.fills && runs.bytes.starts_with(DATA_MAGIC);
+    let bytes = match (signature_cut || data_cut) && check_bruh(&contents).is_err() {
+        true => &[][..],
+        false => runs.bytes,
+    };
+
+    let mut report = RepairReport {
+        pixel_count,
+        salvaged: 0,
+        filled: 0,
+        overflow: 0,
+        partial_bytes: 0,
+        resync: None,
+    };
+    let mut encoder = BruhEncoder::new(AtomicFile::create(output)?, width, height)?;
+
+    let mut rest = bytes;
+    if let Some((cut, gap)) = resync(&runs, bytes, pixel_count) {
+        push_runs(&mut encoder, &runs, &bytes[..cut], &mut report)?;
+        let filled = gap.min(pixel_count - report.salvaged);
+        encoder.push_run(fill, filled as usize)?;
+        report.filled += filled;
+        report.resync = Some(Resync {
+            offset: runs.offset + cut,
+            bytes: bytes.len() % 4,
+            filled,
+        });
+        rest = &bytes[cut + bytes.len() % 4..];
+    }
+    push_runs(&mut encoder, &runs, rest, &mut report)?;
+    report.partial_bytes = rest.len() % 4;
+
+    let missing = pixel_count - report.salvaged - report.filled;
+    encoder.push_run(fill, missing as usize)?;
+    report.filled += missing;
+    encoder.finish()?.commit()?;
+
+    Ok(report)
+}
+
+/// Pushes the whole runs in `bytes` to `encoder` as far as the image goes.
+fn push_runs(
+    encoder: &mut BruhEncoder<AtomicFile>,
+    runs: &Runs,
+    bytes: &[u8],
+    report: &mut RepairReport,
+) -> io::Result<()> {
+    for run in bytes.chunks_exact(4) {
+        let remaining = report.pixel_count - report.salvaged - report.filled;
+        let length = runs.length(run[0], remaining);
+        let kept = length.min(remaining);
+
+        encoder.push_run([run[1], run[2], run[3]], kept as usize)?;
+        report.salvaged += kept;
+        report.overflow += length - kept;
+    }
+    Ok(())
+}
+
+/// Where to cut `bytes.len() % 4` bytes out of the runs `bytes` to bring them back in step,
+/// and how many pixels short of the header they are then. `None` if they're in step, or
+/// cutting the bytes off the end does as well as anywhere.
+fn resync(runs: &Runs, bytes: &[u8], pixel_count: u64) -> Option<(usize, u64)> {
+    let skip = bytes.len() % 4;
+    if skip == 0 {
+        return None;
+    }
+    // A run filling the rest of the image could cover anything, so it counts for nothing.
+    let sum = |bytes: &[u8]| -> Vec<u64> {
+        let mut sums = vec![0];
+        for run in bytes.chunks_exact(4) {
+            sums.push(sums.last().unwrap() + runs.length(run[0], 0));
+        }
+        sums
+    };
+    // Pixels in the first `k` runs read from the start, and in the runs after a cut
+    // there, which are read from `skip` bytes later.
+    let before = sum(bytes);
+    let after = sum(&bytes[skip..]);
+    let total = *after.last().unwrap();
+    let runs_count = before.len() - 1;
+
+    let off_by = |k: usize| pixel_count.abs_diff(before[k] + total - after[k]);
+    let (best, _) = (0..runs_count.saturating_sub(RESYNC_MIN_RUNS - 1))
+        .map(|k| (k, off_by(k)))
+        .filter(|&(_, off)| off <= RESYNC_TOLERANCE)
+        .chain([(runs_count, off_by(runs_count))])
+        .min_by_key(|&(k, off)| (off, std::cmp::Reverse(k)))?;
+    if best == runs_count {
+        return None;
+    }
+    let covered = before[best] + total - after[best];
+    Some((best * 4, pixel_count.saturating_sub(covered)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use bruh::{decode_bruh, encode_bruh};
+
+    const FILL: [u8; 3] = [255, 0, 255];
+
+    /// 64 runs of 10 pixels, each a different color, in a 64×10 image.
+    fn image() -> Vec<u8> {
+        let colors = (0..64u8).map(|i| [i * 4 + 1, 200 - i, i ^ 0x55]);
+        encode_bruh(64, 10, colors.flat_map(|color| [color; 10]))
+    }
+
+    /// Repairs `contents`, returning the report and the repaired pixels.
+    fn repaired(contents: &[u8]) -> Result<(RepairReport, Vec<u8>), Box<dyn Error>> {
+        let dir = tempfile::tempdir().unwrap();
+        let (input, output) = (dir.path().join("broken.bruh"), dir.path().join("fixed.bruh"));
+        fs::write(&input, contents).unwrap();
+        let report = repair(&input, &output, FILL)?;
+        Ok((report, decode_bruh(&fs::read(&output).unwrap()).unwrap().pixels))
+    }
+
+    #[test]
+    fn leaves_whole_files_alone() {
+        let (report, pixels) = repaired(&image()).unwrap();
+        assert_eq!((report.salvaged, report.filled), (640, 0));
+        assert!(report.resync.is_none());
+        assert_eq!(pixels, decode_bruh(&image()).unwrap().pixels);
+    }
+
+    #[test]
+    fn cuts_out_an_added_byte() {
+        let mut contents = image();
+        let start = Runs::of(&contents).offset;
+        contents.insert(start + 20 * 4, 0x42);
+
+        let (report, pixels) = repaired(&contents).unwrap();
+        assert_eq!((report.salvaged, report.filled, report.overflow), (640, 0, 0));
+        let resync = report.resync.unwrap();
+        assert_eq!((resync.offset, resync.bytes, resync.filled), (start + 80, 1, 0));
+        assert_eq!(pixels, decode_bruh(&image()).unwrap().pixels);
+    }
+
+    #[test]
+    fn fills_in_for_a_lost_byte() {
+        let mut contents = image();
+        let start = Runs::of(&contents).offset;
+        // The green of the 21st run.
+        contents.remove(start + 20 * 4 + 2);
+
+        let (report, pixels) = repaired(&contents).unwrap();
+        assert_eq!((report.salvaged, report.filled, report.overflow), (630, 10, 0));
+        let resync = report.resync.unwrap();
+        // The run with the byte missing is read with the next one's length as its blue, and
+        // the rest of the next one is cut.
+        assert_eq!((resync.offset, resync.bytes, resync.filled), (start + 84, 3, 10));
+
+        let mut expected = decode_bruh(&image()).unwrap().pixels;
+        let garbled = [expected[200 * 4], expected[200 * 4 + 2], 10, 255];
+        let fill = [FILL[0], FILL[1], FILL[2], 255];
+        for (i, pixel) in expected[200 * 4..220 * 4].chunks_exact_mut(4).enumerate() {
+            pixel.copy_from_slice(if i < 10 { &garbled } else { &fill });
+        }
+        assert_eq!(pixels, expected);
+    }
+
+    #[test]
+    fn trusts_a_damaged_header() {
+        // A header claiming more rows than the runs cover leaves them to be filled.
+        let mut taller = image();
+        taller[4..8].copy_from_slice(&12u32.to_ne_bytes());
+        let (report, _) = repaired(&taller).unwrap();
+        assert_eq!((report.salvaged, report.filled, report.overflow), (640, 128, 0));
+        assert!(report.resync.is_none());
+
+        // And one claiming fewer drops the runs past its end.
+        let mut shorter = image();
+        shorter[4..8].copy_from_slice(&5u32.to_ne_bytes());
+        let (report, _) = repaired(&shorter).unwrap();
+        assert_eq!((report.salvaged, report.filled, report.overflow), (320, 0, 320));
+
+        assert!(repaired(&image()[..HEADER_SIZE - 1]).is_err());
+    }
+}
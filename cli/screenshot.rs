@@ -0,0 +1,132 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::BufWriter,
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use bruh::{animation::AnimationWriter, decode_header, HEADER_SIZE};
+
+use crate::output::Output;
+
+/// Part of the screen to capture, in screen coordinates.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub struct Region {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Captures the screen containing the top-left corner of `region`, or the primary screen
+/// without one, and writes it to `output` as BRUH.
+pub fn screenshot(region: Option<&Region>, output: Output) -> Result<(), Box<dyn Error>> {
+    capture::capture(region, output)?.commit()?;
+    Ok(())
+}
+
+/// Captures `region` `fps` times a second, until `frames` have been taken or forever
+/// without a limit, and hands each one to `frame` as a .bruh file with its index and the
+/// time until the next one is captured. Returns how many were taken.
+///
+/// If a frame takes longer than the interval to capture, the next one follows it
+/// immediately, and the time until it is that much longer.
+pub fn capture_frames(
+    region: Option<&Region>,
+    fps: f64,
+    frames: Option<u64>,
+    mut frame: impl FnMut(u64, Vec<u8>, Duration) -> Result<(), Box<dyn Error>>,
+) -> Result<u64, Box<dyn Error>> {
+    let interval = Duration::from_secs_f64(1.0 / fps);
+    let start = Instant::now();
+    let mut taken = 0;
+    while frames.is_none_or(|frames| taken < frames) {
+        let captured_at = Instant::now();
+        let contents = capture::capture(region, Vec::new())?;
+        taken += 1;
+
+        // Frames are timed from the start, so slow ones don't make the rest drift.
+        let next = (start + interval.mul_f64(taken as f64)).max(Instant::now());
+        frame(taken - 1, contents, next - captured_at)?;
+        if let Some(wait) = next.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+    }
+    Ok(taken)
+}
+
+/// Records `region` into the animated .bruh file at `path` with [`capture_frames`], each
+/// frame shown for as long as it was until the next one was captured, which is `1 / fps`
+/// seconds unless capturing is slower than that. `on_frame` is called with the number of
+/// frames taken so far.
+///
+/// Frames are appended and flushed as they're taken, and readers drop a last frame that's
+/// cut short, so stopping the recording at any point leaves every frame before it intact.
+pub fn record(
+    region: Option<&Region>,
+    fps: f64,
+    frames: Option<u64>,
+    path: &Path,
+    mut on_frame: impl FnMut(u64),
+) -> Result<u64, Box<dyn Error>> {
+    // The size of the animation is only known once the first frame is captured, so the file
+    // is created then.
+    let mut writer = None;
+    capture_frames(region, fps, frames, |index, contents, shown_for| {
+        let writer = match &mut writer {
+            Some(writer) => writer,
+            None => {
+                let header = contents.first_chunk::<HEADER_SIZE>().expect("a whole .bruh file");
+                let (width, height) = decode_header(header);
+                let file = BufWriter::new(File::create(path)?);
+                writer.insert(AnimationWriter::new(file, width, height)?)
+            }
+        };
+        let delay_ms = (shown_for.as_secs_f64() * 1000.0).round() as u32;
+        writer.push_frame(&contents, delay_ms)?;
+        on_frame(index + 1);
+        Ok(())
+    })
+}
+
+#[cfg(feature = "gui")]
+mod capture {
+    use std::{error::Error, io::Write};
+
+    use bruh::{pixels_to_rgb, BruhEncoder, PixelLayout};
+    use image::imageops;
+    use screenshots::Monitor;
+
+    use super::Region;
+
+    /// Writes the capture to `output` as BRUH and returns it.
+    pub fn capture<W: Write>(region: Option<&Region>, output: W) -> Result<W, Box<dyn Error>> {
+        let (x, y) = region.map_or((0, 0), |region| (region.x, region.y));
+        let monitor = Monitor::from_point(x, y)?;
+        let mut image = monitor.capture_image()?;
+        if let Some(region) = region {
+            // The monitor's capture starts at its own corner. Anything past its edges is
+            // cut off.
+            let (x, y) = ((region.x - monitor.x()) as u32, (region.y - monitor.y()) as u32);
+            image = imageops::crop_imm(&image, x, y, region.width, region.height).to_image();
+        }
+
+        let mut encoder = BruhEncoder::new(output, image.width(), image.height())?;
+        let mut rgb = Vec::new();
+        encoder.push_pixels(pixels_to_rgb(image.as_raw(), PixelLayout::Rgba, &mut rgb))?;
+        Ok(encoder.finish()?)
+    }
+}
+
+#[cfg(not(feature = "gui"))]
+mod capture {
+    use std::{error::Error, io::Write};
+
+    use super::Region;
+
+    pub fn capture<W: Write>(_region: Option<&Region>, _output: W) -> Result<W, Box<dyn Error>> {
+        Err("this build of bruh was made without the `gui` feature.".into())
+    }
+}
@@ -0,0 +1,141 @@
+use bruh::{decode_bruh, encode_bruh, pixels_to_rgb, PixelLayout, FILL_RUNS_MAGIC, HEADER_SIZE};
+
+/// A reference image and the runs every build must encode it to, after the fill runs
+/// marker for images large enough to have it.
+struct Vector {
+    name: &'static str,
+    width: u32,
+    height: u32,
+    pixels: fn() -> Vec<[u8; 3]>,
+    runs: &'static [[u8; 4]],
+}
+
+const RED: [u8; 3] = [255, 0, 0];
+const GREEN: [u8; 3] = [0, 255, 0];
+const BLUE: [u8; 3] = [0, 0, 255];
+const BLACK: [u8; 3] = [0, 0, 0];
+const WHITE: [u8; 3] = [255, 255, 255];
+
+const VECTORS: &[Vector] = &[
+    Vector {
+        name: "empty",
+        width: 0,
+        height: 0,
+        pixels: Vec::new,
+        runs: &[],
+    },
+    Vector {
+        name: "single black pixel",
+        width: 1,
+        height: 1,
+        pixels: || vec![BLACK],
+        runs: &[[1, 0, 0, 0]],
+    },
+    Vector {
+        name: "two runs",
+        width: 3,
+        height: 1,
+        pixels: || vec![RED, RED, BLUE],
+        runs: &[[2, 255, 0, 0], [1, 0, 0, 255]],
+    },
+    Vector {
+        name: "run across rows",
+        width: 2,
+        height: 2,
+        pixels: || vec![WHITE, BLACK, BLACK, WHITE],
+        runs: &[[1, 255, 255, 255], [2, 0, 0, 0], [1, 255, 255, 255]],
+    },
+    Vector {
+        name: "run split at 255",
+        width: 301,
+        height: 1,
+        pixels: || {
+            let mut pixels = vec![GREEN; 300];
+            pixels.push(RED);
+            pixels
+        },
+        runs: &[*FILL_RUNS_MAGIC, [255, 0, 255, 0], [45, 0, 255, 0], [1, 255, 0, 0]],
+    },
+    Vector {
+        name: "solid fill",
+        width: 20,
+        height: 15,
+        pixels: || vec![[128, 128, 128]; 300],
+        runs: &[*FILL_RUNS_MAGIC, [0, 128, 128, 128]],
+    },
+    Vector {
+        name: "flattened alpha",
+        width: 2,
+        height: 1,
+        pixels: || {
+            let rgba = [200, 100, 50, 128, 10, 20, 30, 0];
+            let mut buffer = Vec::new();
+            let rgb = pixels_to_rgb(&rgba, PixelLayout::Rgba, &mut buffer);
+            rgb.chunks_exact(3).map(|p| [p[0], p[1], p[2]]).collect()
+        },
+        runs: &[[1, 100, 50, 25], [1, 0, 0, 0]],
+    },
+];
+
+/// Encodes and decodes the reference images, printing a line per image, and returns
+/// whether all of them matched.
+///
+/// The expected bytes have little-endian headers. The header is in native byte order,
+/// so a big-endian build is expected to fail only on that, and says so.
+pub fn run() -> bool {
+    let mut passed = 0;
+    for vector in VECTORS {
+        match check(vector) {
+            Ok(()) => {
+                println!("ok    {}", vector.name);
+                passed += 1;
+            }
+            Err(reason) => println!("FAIL  {}: {}", vector.name, reason),
+        }
+    }
+
+    println!("{} of {} reference images passed.", passed, VECTORS.len());
+    passed == VECTORS.len()
+}
+
+fn check(vector: &Vector) -> Result<(), String> {
+    let pixels = (vector.pixels)();
+    let encoded = encode_bruh(vector.width, vector.height, pixels.iter().copied());
+    let (header, runs) = encoded.split_at(HEADER_SIZE.min(encoded.len()));
+
+    let expected_header = [vector.width.to_le_bytes(), vector.height.to_le_bytes()].concat();
+    if header != expected_header {
+        let mut reason = format!("header is {:?}, expected {:?}", header, expected_header);
+        if cfg!(target_endian = "big") {
+            reason.push_str(" (this is a big-endian build, and headers are native-endian)");
+        }
+        return Err(reason);
+    }
+    if runs != vector.runs.concat() {
+        return Err(format!("runs are {:?}, expected {:?}", runs, vector.runs.concat()));
+    }
+
+    let decoded = decode_bruh(&encoded).map_err(|e| format!("doesn't decode: {}", e))?;
+    let round_trip: Vec<[u8; 3]> = decoded
+        .pixels
+        .chunks_exact(4)
+        .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect();
+    if round_trip != pixels {
+        return Err("decoded pixels differ from the source".to_owned());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reference_images_encode_as_expected() {
+        for vector in VECTORS {
+            assert_eq!(check(vector), Ok(()), "{}", vector.name);
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    error::Error,
+    fmt::Write as _,
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    path::{Component, Path, PathBuf},
+    thread,
+};
+
+use bruh::decode_bruh;
+
+use crate::{input::read_input, output::png_bytes};
+
+/// Serves a page listing the .bruh files under `root`, each linked to a PNG transcoded
+/// on request, so they can be looked at in any browser.
+///
+/// Only GET requests for that page and for .bruh files under `root` are answered. Each
+/// connection gets its own thread and is closed after one response.
+pub fn serve(root: &Path, address: impl ToSocketAddrs) -> Result<(), Box<dyn Error>> {
+    let root = root.canonicalize()?;
+    let listener = TcpListener::bind(address)?;
+    println!(
+        "Serving .bruh files from {} at http://{}/",
+        root.display(),
+        listener.local_addr()?
+    );
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let root = root.clone();
+        thread::spawn(move || {
+            if let Err(e) = handle(stream, &root) {
+                eprintln!("Request failed: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream, root: &Path) -> Result<(), Box<dyn Error>> {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // The headers aren't needed, but closing with them unread can reset the connection
+    // before the browser has the response.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim_end().is_empty() {
+        header.clear();
+    }
+    drop(reader);
+
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return respond(&mut stream, "400 Bad Request", "text/plain", b"Bad request");
+    };
+    if method != "GET" {
+        return respond(&mut stream, "405 Method Not Allowed", "text/plain", b"Only GET");
+    }
+
+    let target = target.split('?').next().unwrap_or_default();
+    if target == "/" {
+        let page = index_page(root)?;
+        return respond(&mut stream, "200 OK", "text/html; charset=utf-8", page.as_bytes());
+    }
+
+    let Some(path) = target
+        .strip_prefix("/png/")
+        .and_then(percent_decode)
+        .and_then(|relative| resolve(root, &relative))
+    else {
+        return respond(&mut stream, "404 Not Found", "text/plain", b"Not found");
+    };
+    match transcode(&path) {
+        Ok(png) => respond(&mut stream, "200 OK", "image/png", &png),
+        Err(e) => respond(
+            &mut stream,
+            "500 Internal Server Error",
+            "text/plain; charset=utf-8",
+            e.to_string().as_bytes(),
+        ),
+    }
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> Result<(), Box<dyn Error>> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    Ok(())
+}
+
+/// Turns a path from a URL into a .bruh file under `root`, refusing anything that could
+/// lead outside it.
+fn resolve(root: &Path, relative: &str) -> Option<PathBuf> {
+    let relative = Path::new(relative);
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    let path = root.join(relative).canonicalize().ok()?;
+    let is_bruh = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("bruh"));
+    (is_bruh && path.starts_with(root) && path.is_file()).then_some(path)
+}
+
+fn transcode(path: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut contents = Vec::new();
+    read_input(path, &mut contents)?;
+    png_bytes(&decode_bruh(&contents)?)
+}
+
+/// The .bruh files under `root` as paths relative to it with `/` separators, sorted.
+pub fn find_bruh_files(root: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    let mut folders = vec![root.to_path_buf()];
+    while let Some(folder) = folders.pop() {
+        for entry in fs::read_dir(&folder)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                folders.push(path);
+            } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("bruh")) {
+                let relative = path.strip_prefix(root)?;
+                // Names that aren't valid Unicode can't be put in a URL or a pack reliably.
+                if let Some(relative) = relative.to_str() {
+                    files.push(relative.replace('\\', "/"));
+                }
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn index_page(root: &Path) -> Result<String, Box<dyn Error>> {
+    let files = find_bruh_files(root)?;
+    let title = html_escape(&root.display().to_string());
+
+    let mut page = String::new();
+    write!(
+        page,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{0}</title>\
+         <style>body{{font-family:sans-serif}}figure{{display:inline-block;margin:8px}}\
+         img{{max-width:256px;max-height:256px;image-rendering:pixelated}}</style>\
+         </head><body><h1>{0}</h1><p>{1} .bruh files</p>\n",
+        title,
+        files.len()
+    )?;
+    for file in &files {
+        let url = format!("/png/{}", percent_encode(file));
+        let name = html_escape(file);
+        writeln!(
+            page,
+            "<figure><a href=\"{0}\"><img src=\"{0}\" alt=\"{1}\" loading=\"lazy\"></a>\
+             <figcaption>{1}</figcaption></figure>",
+            url, name
+        )?;
+    }
+    page.push_str("</body></html>\n");
+    Ok(page)
+}
+
+pub fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Percent-encodes everything in `path` but unreserved characters and `/`.
+fn percent_encode(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+    }
+    encoded
+}
+
+fn percent_decode(encoded: &str) -> Option<String> {
+    let mut decoded = Vec::new();
+    let mut bytes = encoded.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = [bytes.next()?, bytes.next()?];
+            decoded.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            decoded.push(byte);
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A folder holding `root/inside.bruh` and `secret.bruh` next to `root`, returned
+    /// with `root`'s canonical path.
+    fn folders() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("inside.bruh"), b"").unwrap();
+        fs::write(root.join("sub/nested.bruh"), b"").unwrap();
+        fs::write(root.join("notes.txt"), b"").unwrap();
+        fs::write(dir.path().join("secret.bruh"), b"").unwrap();
+        let root = root.canonicalize().unwrap();
+        (dir, root)
+    }
+
+    /// What a request for `/png/{target}` would be served from.
+    fn served(root: &Path, target: &str) -> Option<PathBuf> {
+        percent_decode(target).and_then(|relative| resolve(root, &relative))
+    }
+
+    #[test]
+    fn serves_bruh_files_under_the_root() {
+        let (_dir, root) = folders();
+        assert_eq!(served(&root, "inside.bruh"), Some(root.join("inside.bruh")));
+        assert_eq!(served(&root, "sub%2Fnested.bruh"), Some(root.join("sub/nested.bruh")));
+        assert_eq!(served(&root, "notes.txt"), None);
+        assert_eq!(served(&root, "missing.bruh"), None);
+    }
+
+    #[test]
+    fn refuses_paths_outside_the_root() {
+        let (dir, root) = folders();
+        let secret = dir.path().join("secret.bruh").canonicalize().unwrap();
+        for target in [
+            "../secret.bruh",
+            "sub/../../secret.bruh",
+            "%2e%2e/secret.bruh",
+            "%2E%2E%2Fsecret.bruh",
+            "sub%2f%2e%2e%2f%2e%2e%2fsecret.bruh",
+            "",
+        ] {
+            assert_eq!(served(&root, target), None, "{}", target);
+        }
+        let absolute = secret.to_str().unwrap();
+        assert_eq!(served(&root, absolute), None);
+        assert_eq!(served(&root, &absolute.replace('/', "%2F")), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_links_outside_the_root() {
+        let (dir, root) = folders();
+        std::os::unix::fs::symlink(dir.path().join("secret.bruh"), root.join("link.bruh"))
+            .unwrap();
+        std::os::unix::fs::symlink(dir.path(), root.join("up")).unwrap();
+        assert_eq!(served(&root, "link.bruh"), None);
+        assert_eq!(served(&root, "up/secret.bruh"), None);
+
+        // A link that stays inside is fine.
+        std::os::unix::fs::symlink(root.join("inside.bruh"), root.join("alias.bruh")).unwrap();
+        assert_eq!(served(&root, "alias.bruh"), Some(root.join("inside.bruh")));
+    }
+}
@@ -0,0 +1,143 @@
+use std::{error::Error, io::Write, path::Path};
+
+use bruh::BruhEncoder;
+
+use crate::{input::read_rgb, output::Output};
+
+/// How [`sheet`] arranges the sprites.
+#[derive(Clone, Copy)]
+pub enum Layout {
+    /// Rows of this many cells, each as large as the largest sprite, in the order given.
+    Grid { columns: u32 },
+    /// Sprites sorted tallest first and placed left to right in rows no wider than
+    /// `width`, wasting less space when their sizes differ. Without a width, the sheet is
+    /// kept roughly square.
+    Shelf { width: Option<u32> },
+}
+
+/// A sprite's place on a sheet.
+struct Frame {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// Packs the images at `inputs` into one sheet saved at `output`, with a JSON atlas of
+/// where each one went saved at `atlas`. Unused space is black. Returns the size of the
+/// sheet.
+///
+/// Inputs can be .bruh files or anything `compile` reads, and appear in the atlas under
+/// their file stem, in the order given.
+pub fn sheet(
+    inputs: &[&Path],
+    layout: Layout,
+    output: &Path,
+    atlas: &Path,
+) -> Result<(u32, u32), Box<dyn Error>> {
+    let sprites = inputs
+        .iter()
+        .map(|path| read_rgb(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let sizes: Vec<(u32, u32)> = sprites.iter().map(|&(w, h, _)| (w, h)).collect();
+    let (frames, width, height) = match layout {
+        Layout::Grid { columns } => grid(&sizes, columns),
+        Layout::Shelf { width } => shelf(&sizes, width)?,
+    };
+    if u64::from(width) * u64::from(height) * 3 > isize::MAX as u64 {
+        return Err(format!("A {}×{} sheet is too large.", width, height).into());
+    }
+
+    let mut rgb = vec![0; width as usize * height as usize * 3];
+    for ((_, _, pixels), frame) in sprites.iter().zip(&frames) {
+        let row_len = frame.width as usize * 3;
+        for (row, source) in pixels.chunks_exact(row_len.max(1)).enumerate() {
+            let start = ((frame.y as usize + row) * width as usize + frame.x as usize) * 3;
+            rgb[start..start + row_len].copy_from_slice(source);
+        }
+    }
+    let mut encoder = BruhEncoder::new(Output::create(output)?, width, height)?;
+    encoder.push_pixels(&rgb)?;
+    encoder.finish()?.commit()?;
+
+    let image = output.file_name().map(|name| name.to_string_lossy());
+    let frames: Vec<_> = inputs
+        .iter()
+        .zip(&frames)
+        .map(|(path, frame)| {
+            serde_json::json!({
+                "name": path.file_stem().map(|stem| stem.to_string_lossy()),
+                "file": path.to_string_lossy(),
+                "x": frame.x,
+                "y": frame.y,
+                "width": frame.width,
+                "height": frame.height,
+            })
+        })
+        .collect();
+    let atlas_json = serde_json::json!({
+        "image": image,
+        "width": width,
+        "height": height,
+        "frames": frames,
+    });
+    let mut file = Output::create(atlas)?;
+    file.write_all(serde_json::to_string_pretty(&atlas_json)?.as_bytes())?;
+    file.commit()?;
+    Ok((width, height))
+}
+
+/// Places each sprite in the top-left corner of its cell.
+fn grid(sizes: &[(u32, u32)], columns: u32) -> (Vec<Frame>, u32, u32) {
+    let cell_width = sizes.iter().map(|&(w, _)| w).max().unwrap_or(0);
+    let cell_height = sizes.iter().map(|&(_, h)| h).max().unwrap_or(0);
+    let columns = columns.min(sizes.len() as u32).max(1);
+    let rows = (sizes.len() as u32).div_ceil(columns);
+    let frames = sizes
+        .iter()
+        .enumerate()
+        .map(|(i, &(width, height))| Frame {
+            x: i as u32 % columns * cell_width,
+            y: i as u32 / columns * cell_height,
+            width,
+            height,
+        })
+        .collect();
+    (frames, columns * cell_width, rows * cell_height)
+}
+
+/// Fills shelves left to right, starting a new one below when a sprite doesn't fit.
+fn shelf(sizes: &[(u32, u32)], width: Option<u32>) -> Result<(Vec<Frame>, u32, u32), String> {
+    let width = width.unwrap_or_else(|| {
+        let area: f64 = sizes.iter().map(|&(w, h)| f64::from(w) * f64::from(h)).sum();
+        let widest = sizes.iter().map(|&(w, _)| w).max().unwrap_or(0);
+        widest.max(area.sqrt().ceil() as u32)
+    });
+    if let Some(&(widest, _)) = sizes.iter().find(|&&(w, _)| w > width) {
+        return Err(format!(
+            "A sprite is {} pixels wide, wider than the {}-pixel sheet.",
+            widest, width
+        ));
+    }
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(sizes[i].1));
+
+    let mut frames: Vec<Option<Frame>> = sizes.iter().map(|_| None).collect();
+    let (mut x, mut y, mut shelf_height, mut used_width) = (0, 0, 0, 0);
+    for i in order {
+        let (w, h) = sizes[i];
+        if x + w > width {
+            (x, y, shelf_height) = (0, y + shelf_height, 0);
+        }
+        frames[i] = Some(Frame {
+            x,
+            y,
+            width: w,
+            height: h,
+        });
+        x += w;
+        used_width = used_width.max(x);
+        shelf_height = shelf_height.max(h);
+    }
+    Ok((frames.into_iter().flatten().collect(), used_width, y + shelf_height))
+}
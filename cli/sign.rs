@@ -0,0 +1,193 @@
+use std::{
+    borrow::Cow,
+    error::Error,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use bruh::{check_bruh, Runs, HEADER_SIZE, SIGNATURE_LEN, SIGNATURE_MAGIC};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use rand::rngs::OsRng;
+
+use crate::{crypt, input::read_source, output::Output};
+
+/// Makes a new Ed25519 key pair, writing the secret key to `path` and the public key
+/// next to it with `.pub` added, both as hex. Returns the public key's path.
+///
+/// The secret key is only readable by its owner, and a key already at `path` is never
+/// overwritten, since whatever it signed couldn't be verified any more.
+pub fn generate_key(path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let key = SigningKey::generate(&mut OsRng);
+    let mut public_path = path.as_os_str().to_owned();
+    public_path.push(".pub");
+    let public_path = PathBuf::from(public_path);
+
+    write_secret_hex(path, key.as_bytes())?;
+    write_hex(&public_path, key.verifying_key().as_bytes())?;
+    Ok(public_path)
+}
+
+/// Signs the .bruh file at `input` with the secret key at `key`, writing it to `output`
+/// with the signature in a chunk after its header. A signature it already had is replaced.
+pub fn sign(input: &Path, key: &Path, output: &Path) -> Result<(), Box<dyn Error>> {
+    let key = SigningKey::from_bytes(&read_hex(key)?);
+    let contents = read_bruh(input)?;
+    let unsigned = unsigned_bytes(&contents);
+    let signature = key.sign(&unsigned).to_bytes();
+
+    let mut file = Output::create(output)?;
+    file.write_all(&unsigned[..HEADER_SIZE])?;
+    file.write_all(SIGNATURE_MAGIC)?;
+    file.write_all(&signature)?;
+    file.write_all(&unsigned[HEADER_SIZE..])?;
+    file.commit()?;
+    Ok(())
+}
+
+/// Checks that the signature in the .bruh file at `input` was made from the rest of the
+/// file by the owner of the public key at `key`.
+pub fn verify(input: &Path, key: &Path) -> Result<(), Box<dyn Error>> {
+    let public_key = VerifyingKey::from_bytes(&read_hex(key)?)
+        .map_err(|_| format!("{} isn't a valid public key.", key.display()))?;
+    let contents = read_bruh(input)?;
+    let signature = Runs::of(&contents)
+        .signature
+        .map(Signature::from_bytes)
+        .ok_or("The file isn't signed.")?;
+
+    public_key
+        .verify_strict(&unsigned_bytes(&contents), &signature)
+        .map_err(|_| {
+            "The signature doesn't match: the file changed, or another key signed it.".into()
+        })
+}
+
+/// Reads a .bruh file that matches its header. Encrypted files can't be signed, since
+/// the signature would have to go inside the ciphertext.
+fn read_bruh(path: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut contents = Vec::new();
+    read_source(path, &mut contents)?;
+    if crypt::is_encrypted(&contents) {
+        return Err("The image is encrypted, so it can't be signed.".into());
+    }
+    check_bruh(&contents)?;
+    Ok(contents)
+}
+
+/// `contents` without its signature chunk, which is what the signature covers.
+fn unsigned_bytes(contents: &[u8]) -> Cow<'_, [u8]> {
+    match Runs::of(contents).signature {
+        Some(_) => {
+            let rest = &contents[HEADER_SIZE + SIGNATURE_MAGIC.len() + SIGNATURE_LEN..];
+            Cow::Owned([&contents[..HEADER_SIZE], rest].concat())
+        }
+        None => Cow::Borrowed(contents),
+    }
+}
+
+fn write_hex(path: &Path, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut file = Output::create(path)?;
+    for byte in bytes {
+        write!(file, "{:02x}", byte)?;
+    }
+    writeln!(file)?;
+    file.commit()?;
+    Ok(())
+}
+
+/// Like [`write_hex`], but creates a new file that only its owner can read or write.
+fn write_secret_hex(path: &Path, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path).map_err(|e| match e.kind() {
+        io::ErrorKind::AlreadyExists => format!(
+            "{} already exists. Delete it first if you really want a new key.",
+            path.display()
+        ),
+        _ => format!("Couldn't create {}: {}", path.display(), e),
+    })?;
+    for byte in bytes {
+        write!(file, "{:02x}", byte)?;
+    }
+    writeln!(file)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Reads a key or signature of exactly `N` bytes written as hex.
+fn read_hex<const N: usize>(path: &Path) -> Result<[u8; N], Box<dyn Error>> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    let text = text.trim();
+    let invalid = || format!("{} should hold {} bytes written as hex.", path.display(), N);
+    if text.len() != N * 2 || !text.is_ascii() {
+        return Err(invalid().into());
+    }
+
+    let mut bytes = [0; N];
+    for (byte, i) in bytes.iter_mut().zip((0..text.len()).step_by(2)) {
+        *byte = u8::from_str_radix(&text[i..i + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bruh::encode_bruh;
+
+    fn image(dir: &Path) -> PathBuf {
+        let path = dir.join("image.bruh");
+        let pixels = (0..12u8).map(|i| [i, i * 2, 255 - i]);
+        fs::write(&path, encode_bruh(4, 3, pixels)).unwrap();
+        path
+    }
+
+    #[test]
+    fn signed_files_verify_until_they_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = dir.path().join("release.key");
+        let public_key = generate_key(&key).unwrap();
+        let signed = dir.path().join("signed.bruh");
+        sign(&image(dir.path()), &key, &signed).unwrap();
+        verify(&signed, &public_key).unwrap();
+
+        let mut contents = fs::read(&signed).unwrap();
+        let last = contents.len() - 1;
+        contents[last] ^= 1;
+        fs::write(&signed, contents).unwrap();
+        assert!(verify(&signed, &public_key).is_err());
+    }
+
+    #[test]
+    fn another_key_doesnt_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = dir.path().join("a.key");
+        generate_key(&key).unwrap();
+        let other = generate_key(&dir.path().join("b.key")).unwrap();
+        let signed = dir.path().join("signed.bruh");
+        sign(&image(dir.path()), &key, &signed).unwrap();
+        assert!(verify(&signed, &other).is_err());
+    }
+
+    #[test]
+    fn secret_keys_are_private_and_never_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = dir.path().join("release.key");
+        generate_key(&key).unwrap();
+        let secret = fs::read(&key).unwrap();
+        assert!(generate_key(&key).is_err());
+        assert_eq!(fs::read(&key).unwrap(), secret);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&key).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::{error::Error, fs, io::Write, path::Path};
+
+use bruh::{
+    check_bruh, decode_bruh_region, pixels_to_rgb, BruhEncoder, DecodedImage, PixelLayout, Region,
+};
+
+use crate::{
+    input::read_source,
+    output::{png_bytes, Output},
+};
+
+/// What each cell of a split sheet is saved as.
+#[derive(Clone, Copy)]
+pub enum CellFormat {
+    Bruh,
+    Png,
+}
+
+impl CellFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            CellFormat::Bruh => "bruh",
+            CellFormat::Png => "png",
+        }
+    }
+}
+
+/// Slices the sprite sheet at `input` into a grid of `columns` × `rows` cells of equal
+/// size and saves each in `folder` as `<name>_<n>`, numbered row by row from 0 and padded
+/// so the files sort in order. Returns the number of cells.
+///
+/// One row of cells is decoded at a time, with [`decode_bruh_region`], so a large sheet
+/// is never expanded in memory as a whole.
+pub fn split(
+    input: &Path,
+    (columns, rows): (u32, u32),
+    folder: &Path,
+    format: CellFormat,
+) -> Result<usize, Box<dyn Error>> {
+    let mut contents = Vec::new();
+    read_source(input, &mut contents)?;
+    let (width, height) = check_bruh(&contents)?;
+    if width == 0 || height == 0 {
+        return Err(format!("The sheet is empty ({}×{} pixels).", width, height).into());
+    }
+    if columns == 0 || rows == 0 || width % columns != 0 || height % rows != 0 {
+        return Err(format!(
+            "A {}×{} sheet can't be split into {}×{} cells of equal size.",
+            width, height, columns, rows
+        )
+        .into());
+    }
+    let (cell_width, cell_height) = (width / columns, height / rows);
+
+    let name = input.file_stem().map_or("cell".into(), |stem| stem.to_string_lossy());
+    let count = columns as usize * rows as usize;
+    let digits = (count - 1).to_string().len();
+    fs::create_dir_all(folder)?;
+
+    for row in 0..rows {
+        let band = Region {
+            x: 0,
+            y: row * cell_height,
+            width,
+            height: cell_height,
+        };
+        let band = decode_bruh_region(&contents, band)?;
+        for column in 0..columns {
+            let index = (row * columns + column) as usize;
+            let path = folder.join(format!(
+                "{}_{:0digits$}.{}",
+                name,
+                index,
+                format.extension(),
+                digits = digits
+            ));
+            let cell = DecodedImage {
+                width: cell_width,
+                height: cell_height,
+                pixels: crop(&band.pixels, width, column * cell_width, cell_width),
+            };
+            save(&path, &cell, format)?;
+        }
+    }
+    Ok(count)
+}
+
+/// The columns `x..x + cell_width` of every row of `pixels`, RGBA8 rows `width` wide.
+fn crop(pixels: &[u8], width: u32, x: u32, cell_width: u32) -> Vec<u8> {
+    let (start, len) = (x as usize * 4, cell_width as usize * 4);
+    pixels
+        .chunks_exact(width as usize * 4)
+        .flat_map(|row| &row[start..start + len])
+        .copied()
+        .collect()
+}
+
+fn save(path: &Path, cell: &DecodedImage, format: CellFormat) -> Result<(), Box<dyn Error>> {
+    let mut file = Output::create(path)?;
+    match format {
+        CellFormat::Bruh => {
+            let mut encoder = BruhEncoder::new(file, cell.width, cell.height)?;
+            let mut rgb = Vec::new();
+            encoder.push_pixels(pixels_to_rgb(&cell.pixels, PixelLayout::Rgba, &mut rgb))?;
+            file = encoder.finish()?;
+        }
+        CellFormat::Png => file.write_all(&png_bytes(cell)?)?,
+    }
+    file.commit()?;
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    io::Write,
+    path::Path,
+};
+
+use bruh::{check_bruh, Runs};
+
+use crate::{crypt, input::read_source, output::Output};
+
+/// How the runs of a .bruh file are spread, to see why it compresses the way it does.
+pub struct RunStats {
+    pub width: u32,
+    pub height: u32,
+    pub runs: u64,
+    /// How many runs there are of each length in pixels, fill runs included.
+    pub lengths: BTreeMap<u64, u64>,
+    /// How many runs cover part of each row. A run reaching into the next row counts for
+    /// both.
+    pub row_runs: Vec<u64>,
+}
+
+impl RunStats {
+    /// Reads the runs of the .bruh file at `path`, which has to match its header.
+    pub fn measure(path: &Path) -> Result<RunStats, Box<dyn Error>> {
+        let mut contents = Vec::new();
+        read_source(path, &mut contents)?;
+        RunStats::of(&contents)
+    }
+
+    /// Like [`RunStats::measure`], for the contents of a .bruh file already in memory.
+    pub fn of(contents: &[u8]) -> Result<RunStats, Box<dyn Error>> {
+        if crypt::is_encrypted(contents) {
+            return Err("The image is encrypted, so its runs can't be read.".into());
+        }
+        let (width, height) = check_bruh(contents)?;
+
+        let pixel_count = width as u64 * height as u64;
+        let mut stats = RunStats {
+            width,
+            height,
+            runs: 0,
+            lengths: BTreeMap::new(),
+            row_runs: vec![0; height as usize],
+        };
+        let runs = Runs::of(contents);
+        let mut covered = 0;
+        for run in runs.bytes.chunks_exact(4) {
+            let length = runs.length(run[0], pixel_count - covered);
+            stats.runs += 1;
+            *stats.lengths.entry(length).or_default() += 1;
+            let first_row = (covered / width as u64) as usize;
+            let last_row = ((covered + length - 1) / width as u64) as usize;
+            for row in &mut stats.row_runs[first_row..=last_row] {
+                *row += 1;
+            }
+            covered += length;
+        }
+        Ok(stats)
+    }
+
+    /// Writes the histogram of run lengths, then the runs in each row, to `output` as CSV.
+    /// Each line is `run_length,<length>,<runs>` or `row,<row>,<runs>`, under a
+    /// `kind,value,count` header.
+    pub fn write_csv(&self, output: &Path) -> Result<(), Box<dyn Error>> {
+        let mut out = Output::create(output)?;
+        writeln!(out, "kind,value,count")?;
+        for (length, count) in &self.lengths {
+            writeln!(out, "run_length,{},{}", length, count)?;
+        }
+        for (row, count) in self.row_runs.iter().enumerate() {
+            writeln!(out, "row,{},{}", row, count)?;
+        }
+        out.commit()?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for RunStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pixels = self.width as u64 * self.height as u64;
+        writeln!(f, "Size          {}×{} ({} pixels)", self.width, self.height, pixels)?;
+        if self.runs == 0 {
+            return write!(f, "Runs          0");
+        }
+        writeln!(
+            f,
+            "Runs          {} ({:.1} pixels a run)",
+            self.runs,
+            pixels as f64 / self.runs as f64
+        )?;
+        let single = self.lengths.get(&1).copied().unwrap_or(0);
+        writeln!(
+            f,
+            "Single pixel  {} ({:.1}% of runs)",
+            single,
+            single as f64 / self.runs as f64 * 100.0
+        )?;
+        let longest = self.lengths.keys().next_back().copied().unwrap_or(0);
+        writeln!(f, "Longest       {} pixels", longest)?;
+        // The busiest row is where the image compresses worst.
+        let (row, runs) = (0..)
+            .zip(&self.row_runs)
+            .max_by_key(|&(row, runs)| (runs, std::cmp::Reverse(row)))
+            .expect("an image with runs has rows");
+        write!(f, "Busiest row   {} ({} runs)", row, runs)
+    }
+}
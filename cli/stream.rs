@@ -0,0 +1,274 @@
+use std::{
+    error::Error,
+    io::{self, BufReader, BufWriter, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use bruh::check_bruh;
+
+use crate::{input::read_source, output::Output, websocket};
+
+/// Starts every stream sent over plain TCP.
+///
+/// After it comes a frame at a time: its length as a little-endian `u32`, then that many
+/// bytes of a complete .bruh file. The stream ends when the sender closes the connection
+/// between two frames. Frames don't have to be the same size.
+///
+/// A stream can also be sent over a WebSocket instead, with a binary message per frame.
+/// The receiver tells them apart by whether the connection starts with an HTTP request.
+const MAGIC: &[u8; 8] = b"BRUHSTRM";
+
+/// Largest frame sent or received, 256 MiB, which holds a 4K screen many times over. A
+/// longer one is refused before it's read, so a sender can't make the receiver run out
+/// of memory.
+pub const MAX_FRAME: usize = 256 << 20;
+
+/// The sending end of a stream of frames.
+pub struct Sender {
+    stream: BufWriter<TcpStream>,
+    websocket: bool,
+}
+
+impl Sender {
+    /// Connects to a receiver at `address`, which is `host:port` for plain TCP or a
+    /// `ws://host:port/path` URL for a WebSocket.
+    pub fn connect(address: &str) -> Result<Sender, Box<dyn Error>> {
+        if address.starts_with("wss://") {
+            return Err("wss:// needs TLS, which isn't supported. Use ws:// instead.".into());
+        }
+        let url = address.strip_prefix("ws://");
+        let (host, path) = match url {
+            Some(url) => url.find('/').map_or((url, "/"), |slash| url.split_at(slash)),
+            None => (address, "/"),
+        };
+        let mut stream = match url {
+            Some(_) if !host.contains(':') => TcpStream::connect((host, 80))?,
+            _ => TcpStream::connect(host)?,
+        };
+        // Frames are sent whole and flushed, so waiting to fill packets only adds latency.
+        stream.set_nodelay(true)?;
+        if url.is_some() {
+            websocket::connect(&mut stream, host, path)?;
+        }
+
+        let mut stream = BufWriter::new(stream);
+        if url.is_none() {
+            stream.write_all(MAGIC)?;
+        }
+        Ok(Sender {
+            stream,
+            websocket: url.is_some(),
+        })
+    }
+
+    /// Sends one .bruh file.
+    pub fn send(&mut self, frame: &[u8]) -> io::Result<()> {
+        if frame.len() > MAX_FRAME {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "frame is too large"));
+        }
+        let len = frame.len() as u32;
+        if self.websocket {
+            websocket::write_message(&mut self.stream, websocket::BINARY, frame, true)?;
+        } else {
+            self.stream.write_all(&len.to_le_bytes())?;
+            self.stream.write_all(frame)?;
+        }
+        self.stream.flush()
+    }
+
+    /// Ends the stream. Over plain TCP closing the connection is enough, but a WebSocket
+    /// is closed with a message, and the receiver's answer to it waited for.
+    pub fn close(mut self) -> Result<(), Box<dyn Error>> {
+        if self.websocket {
+            websocket::write_message(&mut self.stream, websocket::CLOSE, &[], true)?;
+            self.stream.flush()?;
+            let ignore = |_, _: &[u8]| Ok(());
+            let mut stream = self.stream.get_ref();
+            while websocket::read_message(&mut stream, &mut Vec::new(), MAX_FRAME, ignore)? {}
+        }
+        Ok(self.stream.flush()?)
+    }
+}
+
+/// Sends the .bruh files at `paths` in order, `fps` a second.
+pub fn send_files(sender: &mut Sender, paths: &[PathBuf], fps: f64) -> Result<(), Box<dyn Error>> {
+    let interval = Duration::from_secs_f64(1.0 / fps);
+    let mut contents = Vec::new();
+    for (i, path) in paths.iter().enumerate() {
+        if i > 0 {
+            thread::sleep(interval);
+        }
+        read_source(path, &mut contents)?;
+        check_bruh(&contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+        sender.send(&contents)?;
+    }
+    Ok(())
+}
+
+/// The receiving end of a stream of frames.
+pub struct Receiver {
+    stream: BufReader<TcpStream>,
+    /// The same connection, to answer on, if it's a WebSocket.
+    websocket: Option<TcpStream>,
+}
+
+impl Receiver {
+    /// Waits for a sender to connect to `listener` and checks that it speaks the protocol,
+    /// over plain TCP or a WebSocket.
+    pub fn accept(listener: &TcpListener) -> Result<Receiver, Box<dyn Error>> {
+        let (stream, _) = listener.accept()?;
+        let mut stream = BufReader::new(stream);
+        let mut magic = [0; MAGIC.len()];
+        stream.read_exact(&mut magic)?;
+        if &magic == MAGIC {
+            return Ok(Receiver {
+                stream,
+                websocket: None,
+            });
+        }
+        if !magic.starts_with(b"GET ") {
+            return Err("the other end isn't sending a bruh stream.".into());
+        }
+        // The rest of the request line is read with the headers, and whatever the path is
+        // doesn't matter.
+        let mut writer = stream.get_ref().try_clone()?;
+        websocket::accept(&mut stream, &mut writer)?;
+        Ok(Receiver {
+            stream,
+            websocket: Some(writer),
+        })
+    }
+
+    /// Reads the next frame into `frame`, returning `false` once the sender has finished.
+    /// Frames that aren't valid .bruh files are refused.
+    pub fn receive(&mut self, frame: &mut Vec<u8>) -> Result<bool, Box<dyn Error>> {
+        if let Some(writer) = &mut self.websocket {
+            let reply = |opcode, payload: &[u8]| {
+                websocket::write_message(writer, opcode, payload, false)
+            };
+            if !websocket::read_message(&mut self.stream, frame, MAX_FRAME, reply)? {
+                return Ok(false);
+            }
+            check_bruh(frame).map_err(|e| format!("received a broken frame: {}", e))?;
+            return Ok(true);
+        }
+
+        let mut len = [0; 4];
+        match self.stream.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+            Err(e) => return Err(e.into()),
+        }
+        let len = u32::from_le_bytes(len) as u64;
+        if len > MAX_FRAME as u64 {
+            return Err(format!("received a frame of more than {} bytes.", MAX_FRAME).into());
+        }
+
+        // Even within the limit, only what's actually sent takes up memory.
+        frame.clear();
+        (&mut self.stream).take(len).read_to_end(frame)?;
+        if (frame.len() as u64) < len {
+            return Err("the connection closed in the middle of a frame.".into());
+        }
+        check_bruh(frame).map_err(|e| format!("received a broken frame: {}", e))?;
+        Ok(true)
+    }
+}
+
+/// Receives frames from one sender after another on `listener`, forever, and saves each
+/// one over `output`. `on_frame` is called after every frame is in place.
+///
+/// The file is replaced atomically, so anything reading it only ever sees whole frames. A
+/// sender that disconnects or sends something broken is dropped, and the next one waited
+/// for.
+pub fn receive(
+    listener: &TcpListener,
+    output: &Path,
+    mut on_frame: impl FnMut(),
+) -> Result<(), Box<dyn Error>> {
+    let mut frame = Vec::new();
+    loop {
+        let mut receiver = match Receiver::accept(listener) {
+            Ok(receiver) => receiver,
+            Err(e) => {
+                eprintln!("Refused a connection: {}", e);
+                continue;
+            }
+        };
+        loop {
+            match receiver.receive(&mut frame) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => {
+                    eprintln!("Dropped the sender: {}", e);
+                    break;
+                }
+            }
+            let mut file = Output::create(output)?;
+            file.write_all(&frame)?;
+            file.commit()?;
+            on_frame();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bruh::encode_bruh;
+
+    /// Sends `frames` to a receiver at `address` from another thread, and returns what it
+    /// received.
+    fn loopback(address: impl FnOnce(u16) -> String, frames: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = address(listener.local_addr().unwrap().port());
+        let sender = thread::spawn(move || {
+            let mut sender = Sender::connect(&address).unwrap();
+            for frame in &frames {
+                sender.send(frame).unwrap();
+            }
+            sender.close().unwrap();
+        });
+
+        let mut receiver = Receiver::accept(&listener).unwrap();
+        let mut received = Vec::new();
+        let mut frame = Vec::new();
+        while receiver.receive(&mut frame).unwrap() {
+            received.push(frame.clone());
+        }
+        sender.join().unwrap();
+        received
+    }
+
+    fn frames() -> Vec<Vec<u8>> {
+        vec![
+            encode_bruh(2, 2, [[1, 2, 3]; 4]),
+            encode_bruh(300, 200, (0..60_000u32).map(|i| [i as u8, (i >> 8) as u8, 0])),
+        ]
+    }
+
+    #[test]
+    fn sends_frames_over_tcp() {
+        assert_eq!(loopback(|port| format!("127.0.0.1:{}", port), frames()), frames());
+    }
+
+    #[test]
+    fn sends_frames_over_a_websocket() {
+        assert_eq!(loopback(|port| format!("ws://127.0.0.1:{}/live", port), frames()), frames());
+    }
+
+    #[test]
+    fn refuses_huge_frames() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        stream.write_all(MAGIC).unwrap();
+        stream.write_all(&u32::MAX.to_le_bytes()).unwrap();
+
+        let mut receiver = Receiver::accept(&listener).unwrap();
+        assert!(receiver.receive(&mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use std::{error::Error, fmt::Write as _, path::Path};
+
+use crate::input::read_rgb;
+
+/// Characters from darkest to brightest, for a light-on-dark terminal.
+const RAMP: &[u8] = b" .:-=+*#%@";
+
+/// How a terminal draws an image.
+#[derive(Clone, Copy)]
+pub enum Mode {
+    /// `▀` characters with 24-bit colors, two pixels to a character.
+    TrueColor,
+    /// Plain characters picked by luminance, for terminals without color.
+    Ascii,
+}
+
+/// Renders the image at `path` as text `columns` characters wide.
+///
+/// Characters are about twice as tall as they're wide, so each one covers two rows of
+/// pixels for every column, and the image keeps its proportions.
+pub fn render(path: &Path, columns: u32, mode: Mode) -> Result<String, Box<dyn Error>> {
+    let (width, height, rgb) = read_rgb(path)?;
+    if width == 0 || height == 0 {
+        return Err("Can't show an empty image.".into());
+    }
+
+    let columns = columns.clamp(1, width);
+    let rows = ((height as u64 * columns as u64 / width as u64 / 2) as u32).max(1);
+    let mut text = String::new();
+    match mode {
+        Mode::TrueColor => {
+            let cells = downscale(&rgb, width, height, columns, rows * 2);
+            for pair in cells.chunks_exact(columns as usize * 2) {
+                let (top, bottom) = pair.split_at(columns as usize);
+                for (&[r, g, b], &[br, bg, bb]) in top.iter().zip(bottom) {
+                    write!(text, "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m▀", r, g, b, br, bg, bb)?;
+                }
+                text.push_str("\x1b[0m\n");
+            }
+        }
+        Mode::Ascii => {
+            let cells = downscale(&rgb, width, height, columns, rows);
+            for row in cells.chunks_exact(columns as usize) {
+                for &[r, g, b] in row {
+                    let luma = 0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64;
+                    let index = (luma / 256.0 * RAMP.len() as f64) as usize;
+                    text.push(RAMP[index.min(RAMP.len() - 1)] as char);
+                }
+                text.push('\n');
+            }
+        }
+    }
+    Ok(text)
+}
+
+/// Averages packed RGB pixels into a `columns`×`rows` grid, row by row.
+pub fn downscale(rgb: &[u8], width: u32, height: u32, columns: u32, rows: u32) -> Vec<[u8; 3]> {
+    let (width, height) = (width as usize, height as usize);
+    let (columns, rows) = (columns as usize, rows as usize);
+    let mut cells = Vec::with_capacity(columns * rows);
+    for row in 0..rows {
+        // Stretching a short image to more rows repeats some of its rows.
+        let top = row * height / rows;
+        let bottom = ((row + 1) * height / rows).max(top + 1);
+        for column in 0..columns {
+            let left = column * width / columns;
+            let right = ((column + 1) * width / columns).max(left + 1);
+            let mut sums = [0u64; 3];
+            for y in top..bottom {
+                for pixel in rgb[(y * width + left) * 3..(y * width + right) * 3].chunks_exact(3) {
+                    for (sum, &channel) in sums.iter_mut().zip(pixel) {
+                        *sum += channel as u64;
+                    }
+                }
+            }
+            let count = ((bottom - top) * (right - left)) as u64;
+            cells.push(sums.map(|sum| (sum / count) as u8));
+        }
+    }
+    cells
+}
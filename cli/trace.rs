@@ -0,0 +1,144 @@
+use std::{
+    error::Error,
+    io::{self, BufReader, Read, Write},
+    path::Path,
+};
+
+use bruh::{
+    decode_header, ANIMATION_MAGIC, DATA_MAGIC, FILL_RUNS_MAGIC, HEADER_SIZE, SIGNATURE_LEN,
+    SIGNATURE_MAGIC,
+};
+
+use crate::input::{open_input, plain_runs, read_up_to};
+
+/// Writes every run of a .bruh file to `out`: its byte offset, where it starts in the
+/// image, its length and color. Then a summary of where the file stops matching its
+/// header, if it does.
+///
+/// Unlike decoding, this keeps going after a problem, so it shows everything that's
+/// in a damaged file.
+pub fn trace_decode(path: &Path, mut out: impl Write) -> Result<(), Box<dyn Error>> {
+    let plain = plain_runs(|| open_input(path))?;
+    let mut reader = BufReader::new(open_input(path)?);
+
+    let mut header = [0; HEADER_SIZE];
+    let header_read = read_up_to(&mut reader, &mut header)?;
+    if header_read < HEADER_SIZE {
+        writeln!(out, "File is {} bytes, too short for the header.", header_read)?;
+        return Ok(());
+    }
+    let (width, height) = decode_header(&header);
+    let expected = width as u64 * height as u64;
+    writeln!(out, "header: {}×{} ({} pixels)", width, height, expected)?;
+    if plain {
+        writeln!(out, "the first run reads as a marker, but only covers the image as a run")?;
+    }
+
+    let position = |pixel: u64| match width {
+        0 => (0, 0),
+        _ => (pixel / width as u64, pixel % width as u64),
+    };
+
+    let mut offset = HEADER_SIZE as u64;
+    let mut covered = 0u64;
+    let mut overflow: Option<(u64, u64)> = None;
+    let mut run = [0; 4];
+    let mut fills = false;
+    // Where the signature and data chunk end, or the header if there are none, which is
+    // where the next of them or the fill runs marker goes.
+    let mut chunks_end = HEADER_SIZE as u64;
+    let mut index = 0u64;
+    loop {
+        let read = read_up_to(&mut reader, &mut run)?;
+        if read == 0 {
+            break;
+        }
+        if read < run.len() {
+            writeln!(
+                out,
+                "offset {:#010x}: partial run of {} bytes at the end of the file",
+                offset, read
+            )?;
+            break;
+        }
+        // Each frame is a file of its own, and `inspect` lists them.
+        if offset == HEADER_SIZE as u64 && run == *ANIMATION_MAGIC {
+            writeln!(out, "offset {:#010x}: animation, inspect it to see its frames", offset)?;
+            return Ok(());
+        }
+        if !plain && offset == HEADER_SIZE as u64 && run == *SIGNATURE_MAGIC {
+            let read = read_up_to(&mut reader, &mut [0; SIGNATURE_LEN])?;
+            let length = format!("{} of {} bytes", read, SIGNATURE_LEN);
+            writeln!(out, "offset {:#010x}: signature, {}", offset, length)?;
+            offset += (run.len() + read) as u64;
+            chunks_end = offset;
+            continue;
+        }
+        if !plain && offset == chunks_end && run == *DATA_MAGIC {
+            let mut len = [0; 4];
+            let read = read_up_to(&mut reader, &mut len)?;
+            let len = u32::from_ne_bytes(len) as u64;
+            let data = io::copy(&mut (&mut reader).take(len), &mut io::sink())?;
+            let length = match read {
+                4 if data == len => format!("{} bytes", len),
+                4 => format!("{} of {} bytes", data, len),
+                _ => "cut short".to_owned(),
+            };
+            writeln!(out, "offset {:#010x}: embedded data, {}", offset, length)?;
+            offset += (run.len() + read) as u64 + data;
+            chunks_end = offset;
+            continue;
+        }
+        if !plain && offset == chunks_end && run == *FILL_RUNS_MAGIC {
+            writeln!(out, "offset {:#010x}: marker, runs of length 0 fill the image", offset)?;
+            fills = true;
+            offset += run.len() as u64;
+            continue;
+        }
+
+        let length = match run[0] {
+            0 if fills => expected.saturating_sub(covered),
+            length => length as u64,
+        };
+        let (row, column) = position(covered);
+        writeln!(
+            out,
+            "offset {:#010x}  run {:>8}  row {:>6} col {:>6}  length {:>3}{}  #{:02x}{:02x}{:02x}",
+            offset,
+            index,
+            row,
+            column,
+            length,
+            if run[0] == 0 && fills { " (fill)" } else { "" },
+            run[1],
+            run[2],
+            run[3]
+        )?;
+
+        if covered + length > expected && overflow.is_none() {
+            overflow = Some((index, offset));
+        }
+        covered += length;
+        offset += run.len() as u64;
+        index += 1;
+    }
+
+    writeln!(out, "runs cover {} of {} pixels", covered, expected)?;
+    if let Some((index, offset)) = overflow {
+        writeln!(
+            out,
+            "diverged at run {} (offset {:#010x}): runs go past the last pixel",
+            index, offset
+        )?;
+    } else if covered < expected {
+        let (row, column) = position(covered);
+        writeln!(
+            out,
+            "diverged at offset {:#010x}: the file ends at row {}, column {}",
+            offset, row, column
+        )?;
+    } else {
+        writeln!(out, "runs match the header")?;
+    }
+    Ok(())
+}
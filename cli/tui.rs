@@ -0,0 +1,314 @@
+use std::{
+    error::Error,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use bruh::decode_bruh;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame, Terminal,
+};
+
+use crate::{
+    identify::identify,
+    input::{read_input, read_rgb},
+    output::{png_bytes, Output},
+    serve::find_bruh_files,
+    terminal::downscale,
+};
+
+/// Browses the .bruh files under `folder` in the terminal, with a preview and details of
+/// the selected one, until the user quits.
+///
+/// Needs nothing but a terminal with 24-bit color, so it works over SSH on a server
+/// without a display.
+pub fn browse(folder: &Path) -> Result<(), Box<dyn Error>> {
+    let mut browser = Browser::new(folder)?;
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    let result = Terminal::new(CrosstermBackend::new(io::stdout()))
+        .map_err(Into::into)
+        .and_then(|mut terminal| browser.run(&mut terminal));
+    // The terminal is put back however the browser ended.
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen)?;
+    result
+}
+
+/// The selected file, decoded.
+struct Preview {
+    index: usize,
+    /// The pixels as packed RGB with their size, or why they couldn't be read.
+    image: Result<(u32, u32, Vec<u8>), String>,
+    details: Vec<String>,
+}
+
+struct Browser {
+    folder: PathBuf,
+    files: Vec<String>,
+    list: ListState,
+    preview: Option<Preview>,
+    /// What the last action did.
+    status: String,
+    /// Whether `y` deletes the selected file.
+    confirm_delete: bool,
+}
+
+impl Browser {
+    fn new(folder: &Path) -> Result<Browser, Box<dyn Error>> {
+        let mut browser = Browser {
+            folder: folder.to_path_buf(),
+            files: Vec::new(),
+            list: ListState::default(),
+            preview: None,
+            status: String::new(),
+            confirm_delete: false,
+        };
+        browser.refresh()?;
+        browser.status = format!(
+            "{} file(s). ↑↓ select, c convert to PNG, d delete, r refresh, q quit",
+            browser.files.len()
+        );
+        Ok(browser)
+    }
+
+    fn refresh(&mut self) -> Result<(), Box<dyn Error>> {
+        self.files = find_bruh_files(&self.folder)?;
+        let selected = self.list.selected().unwrap_or(0);
+        self.list
+            .select((!self.files.is_empty()).then(|| selected.min(self.files.len() - 1)));
+        self.preview = None;
+        Ok(())
+    }
+
+    fn selected_path(&self) -> Option<PathBuf> {
+        let name = self.files.get(self.list.selected()?)?;
+        Some(self.folder.join(name))
+    }
+
+    fn run(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    ) -> Result<(), Box<dyn Error>> {
+        loop {
+            self.load_preview();
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if self.confirm_delete {
+                self.confirm_delete = false;
+                self.status = match key.code {
+                    KeyCode::Char('y' | 'Y') => self.delete(),
+                    _ => "Kept the file.".to_owned(),
+                };
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Up | KeyCode::Char('k') => self.select_by(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.select_by(1),
+                KeyCode::PageUp => self.select_by(-10),
+                KeyCode::PageDown => self.select_by(10),
+                KeyCode::Home => self.select_by(isize::MIN / 2),
+                KeyCode::End => self.select_by(isize::MAX / 2),
+                KeyCode::Char('c') => self.status = self.convert(),
+                KeyCode::Char('d') if self.list.selected().is_some() => {
+                    self.confirm_delete = true;
+                    self.status = "Delete the file? y to confirm, any other key to keep it."
+                        .to_owned();
+                }
+                KeyCode::Char('r') => {
+                    self.status = match self.refresh() {
+                        Ok(()) => format!("{} file(s).", self.files.len()),
+                        Err(e) => format!("Couldn't list the files: {}", e),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn select_by(&mut self, offset: isize) {
+        if let Some(selected) = self.list.selected() {
+            let last = self.files.len() as isize - 1;
+            self.list
+                .select(Some((selected as isize).saturating_add(offset).clamp(0, last) as usize));
+        }
+    }
+
+    /// Decodes the selected file if it isn't already.
+    fn load_preview(&mut self) {
+        let Some(index) = self.list.selected() else {
+            self.preview = None;
+            return;
+        };
+        if self.preview.as_ref().map(|preview| preview.index) == Some(index) {
+            return;
+        }
+        let path = self.folder.join(&self.files[index]);
+        self.preview = Some(Preview {
+            index,
+            image: read_rgb(&path).map_err(|e| e.to_string()),
+            details: details(&path),
+        });
+    }
+
+    /// Saves the selected file as a PNG next to it.
+    fn convert(&self) -> String {
+        let Some(path) = self.selected_path() else {
+            return "No file selected.".to_owned();
+        };
+        let output = path.with_extension("png");
+        match save_png(&path, &output) {
+            Ok(()) => format!("Saved {}", output.display()),
+            Err(e) => format!("Couldn't convert {}: {}", path.display(), e),
+        }
+    }
+
+    fn delete(&mut self) -> String {
+        let Some(path) = self.selected_path() else {
+            return "No file selected.".to_owned();
+        };
+        if let Err(e) = fs::remove_file(&path) {
+            return format!("Couldn't delete {}: {}", path.display(), e);
+        }
+        match self.refresh() {
+            Ok(()) => format!("Deleted {}", path.display()),
+            Err(e) => format!("Deleted {}, but couldn't list the files: {}", path.display(), e),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] = split(frame.size(), Direction::Vertical, Constraint::Length(1));
+        let [files, right] = split(main, Direction::Horizontal, Constraint::Percentage(70));
+        let [preview, details] = split(right, Direction::Vertical, Constraint::Length(8));
+
+        let items: Vec<ListItem> =
+            self.files.iter().map(|name| ListItem::new(name.as_str())).collect();
+        let title = format!(" {} ", self.folder.display());
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, files, &mut self.list);
+
+        let block = Block::default().borders(Borders::ALL).title(" Preview ");
+        let inner = block.inner(preview);
+        frame.render_widget(block, preview);
+        let (image, lines) = match &self.preview {
+            Some(preview) => {
+                let image = match &preview.image {
+                    Ok((width, height, rgb)) => image_lines(rgb, *width, *height, inner),
+                    Err(error) => vec![Line::from(error.clone())],
+                };
+                let lines = preview.details.iter().map(|line| Line::from(line.clone()));
+                (image, lines.collect())
+            }
+            None if self.files.is_empty() => (vec![Line::from("No .bruh files here.")], Vec::new()),
+            None => (Vec::new(), Vec::new()),
+        };
+        frame.render_widget(Paragraph::new(image), inner);
+        let block = Block::default().borders(Borders::ALL).title(" Details ");
+        frame.render_widget(Paragraph::new(lines).block(block), details);
+
+        frame.render_widget(Paragraph::new(self.status.as_str()), status);
+    }
+}
+
+fn save_png(path: &Path, output: &Path) -> Result<(), Box<dyn Error>> {
+    let mut contents = Vec::new();
+    read_input(path, &mut contents)?;
+    let png = png_bytes(&decode_bruh(&contents)?)?;
+    let mut file = Output::create(output)?;
+    file.write_all(&png)?;
+    file.commit()?;
+    Ok(())
+}
+
+/// Splits `area` in two along `direction`, the second part taking `second` and the first
+/// the rest.
+fn split(area: Rect, direction: Direction, second: Constraint) -> [Rect; 2] {
+    let first = match second {
+        Constraint::Percentage(percent) => Constraint::Percentage(100 - percent),
+        _ => Constraint::Min(0),
+    };
+    let parts = Layout::default()
+        .direction(direction)
+        .constraints([first, second])
+        .split(area);
+    [parts[0], parts[1]]
+}
+
+/// The image shrunk to fit `area` in `▀` characters, two pixels to a character, the same
+/// way `cat` draws it.
+fn image_lines(rgb: &[u8], width: u32, height: u32, area: Rect) -> Vec<Line<'static>> {
+    if width == 0 || height == 0 || area.width == 0 || area.height == 0 {
+        return Vec::new();
+    }
+    // Fit the width first, then narrow it if the image is too tall for the area.
+    let mut columns = (area.width as u32).min(width);
+    let rows_for =
+        |columns: u32| ((height as u64 * columns as u64 / width as u64 / 2) as u32).max(1);
+    if rows_for(columns) > area.height as u32 {
+        let fitting = area.height as u64 * 2 * width as u64 / height as u64;
+        columns = (fitting as u32).clamp(1, columns);
+    }
+    let rows = rows_for(columns).min(area.height as u32);
+
+    let cells = downscale(rgb, width, height, columns, rows * 2);
+    cells
+        .chunks_exact(columns as usize * 2)
+        .map(|pair| {
+            let (top, bottom) = pair.split_at(columns as usize);
+            let spans: Vec<Span> = top
+                .iter()
+                .zip(bottom)
+                .map(|(&[r, g, b], &[br, bg, bb])| {
+                    let style = Style::default()
+                        .fg(Color::Rgb(r, g, b))
+                        .bg(Color::Rgb(br, bg, bb));
+                    Span::styled("▀", style)
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Lines for the details panel: what `identify` finds, and how well the file compresses.
+fn details(path: &Path) -> Vec<String> {
+    let identity = match identify(path) {
+        Ok(identity) => identity,
+        Err(e) => return vec![e.to_string()],
+    };
+    let mut lines = vec![
+        format!("Format      {}", identity.format),
+        format!("Size        {}×{}", identity.width, identity.height),
+        format!("File        {} bytes", identity.size),
+    ];
+    if identity.encrypted {
+        lines.push("Encrypted   yes".to_owned());
+    } else {
+        let raw = identity.width as f64 * identity.height as f64 * 3.0;
+        let runs = identity.size.saturating_sub(bruh::HEADER_SIZE as u64) / 4;
+        lines.push(format!("Runs        {}", runs));
+        lines.push(format!("Ratio       {:.1}:1", raw / identity.size.max(1) as f64));
+    }
+    lines
+}
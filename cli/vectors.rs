@@ -0,0 +1,421 @@
+use std::{error::Error, fs, io::Write, path::Path};
+
+use bruh::{
+    decode_bruh, encode_bruh, pixels_to_rgb, DecodeError, PixelLayout, Runs, HEADER_SIZE,
+};
+
+use crate::output::Output;
+
+/// A .bruh file every decoder should read, and the pixels it holds.
+struct Valid {
+    name: &'static str,
+    description: &'static str,
+    width: u32,
+    height: u32,
+    pixels: fn() -> Vec<[u8; 3]>,
+}
+
+/// A malformed .bruh file every decoder should reject, with a little-endian header.
+struct Invalid {
+    name: &'static str,
+    description: &'static str,
+    contents: &'static [u8],
+    error: DecodeError,
+}
+
+const VALID: &[Valid] = &[
+    Valid {
+        name: "empty",
+        description: "A 0×0 image: just the header.",
+        width: 0,
+        height: 0,
+        pixels: Vec::new,
+    },
+    Valid {
+        name: "zero-width",
+        description: "No columns, so no pixels, however many rows.",
+        width: 0,
+        height: 7,
+        pixels: Vec::new,
+    },
+    Valid {
+        name: "zero-height",
+        description: "No rows, so no pixels, however many columns.",
+        width: 7,
+        height: 0,
+        pixels: Vec::new,
+    },
+    Valid {
+        name: "single-pixel",
+        description: "One pixel in one run of length 1.",
+        width: 1,
+        height: 1,
+        pixels: || vec![[12, 34, 56]],
+    },
+    Valid {
+        name: "single-row",
+        description: "One row with a new color every pixel.",
+        width: 1000,
+        height: 1,
+        pixels: || (0..1000).map(|x| [(x % 256) as u8, (x / 4) as u8, 7]).collect(),
+    },
+    Valid {
+        name: "single-column",
+        description: "One column with a new color every pixel.",
+        width: 1,
+        height: 1000,
+        pixels: || (0..1000).map(|y| [7, (y / 4) as u8, (y % 256) as u8]).collect(),
+    },
+    Valid {
+        name: "odd-size",
+        description: "A prime width and height, with runs that wrap from row to row.",
+        width: 37,
+        height: 23,
+        pixels: || (0..37 * 23).map(|i| [(i / 10 % 256) as u8, 90, 180]).collect(),
+    },
+    Valid {
+        name: "every-color",
+        description: "All 65,536 red and green pairs, so every run is 1 pixel.",
+        width: 256,
+        height: 256,
+        pixels: || (0..65536).map(|i| [(i % 256) as u8, (i / 256) as u8, 128]).collect(),
+    },
+    Valid {
+        name: "run-255",
+        description: "The longest run a length byte holds, followed by another color.",
+        width: 256,
+        height: 1,
+        pixels: || run_then_pixel(255),
+    },
+    Valid {
+        name: "run-256",
+        description: "One pixel too long for a run, so it's split into 255 and 1.",
+        width: 257,
+        height: 1,
+        pixels: || run_then_pixel(256),
+    },
+    Valid {
+        name: "run-510",
+        description: "Two full runs of 255 back to back, followed by another color.",
+        width: 511,
+        height: 1,
+        pixels: || run_then_pixel(510),
+    },
+    Valid {
+        name: "fill",
+        description: "The fill runs marker, then one run of length 0 covering the whole image.",
+        width: 640,
+        height: 480,
+        pixels: || vec![[200, 30, 90]; 640 * 480],
+    },
+    Valid {
+        name: "fill-after-runs",
+        description: "The fill runs marker, runs of length 1, then one of length 0 for the rest.",
+        width: 300,
+        height: 300,
+        pixels: || {
+            let mut pixels = vec![[0, 0, 0], [255, 255, 255]];
+            pixels.resize(300 * 300, [40, 80, 160]);
+            pixels
+        },
+    },
+    Valid {
+        name: "run-255-whole-image",
+        description: "A run of 255 that covers the whole image, so it keeps its length.",
+        width: 15,
+        height: 17,
+        pixels: || vec![[1, 2, 3]; 255],
+    },
+    Valid {
+        name: "run-like-marker",
+        description: "Its first run, 70 of #494c01, reads as the fill runs marker but isn't one.",
+        width: 16,
+        height: 15,
+        pixels: || {
+            let mut pixels = vec![[0x49, 0x4c, 0x01]; 70];
+            pixels.resize(240, [90, 90, 90]);
+            pixels
+        },
+    },
+    Valid {
+        name: "mode-gray",
+        description: "8-bit grayscale encoded as equal channels.",
+        width: 16,
+        height: 16,
+        pixels: || from_layout(PixelLayout::Gray),
+    },
+    Valid {
+        name: "mode-gray-alpha",
+        description: "Grayscale with alpha, composited over black.",
+        width: 16,
+        height: 16,
+        pixels: || from_layout(PixelLayout::GrayAlpha),
+    },
+    Valid {
+        name: "mode-rgb",
+        description: "8-bit RGB encoded as it is.",
+        width: 16,
+        height: 16,
+        pixels: || from_layout(PixelLayout::Rgb),
+    },
+    Valid {
+        name: "mode-rgba",
+        description: "RGBA composited over black: transparent pixels become black.",
+        width: 16,
+        height: 16,
+        pixels: || from_layout(PixelLayout::Rgba),
+    },
+];
+
+const INVALID: &[Invalid] = &[
+    Invalid {
+        name: "corrupt-empty-file",
+        description: "A file with no bytes at all.",
+        contents: &[],
+        error: DecodeError::Empty,
+    },
+    Invalid {
+        name: "corrupt-short-header",
+        description: "Only the width and half the height.",
+        contents: &[4, 0, 0, 0, 4, 0],
+        error: DecodeError::MissingHeader,
+    },
+    Invalid {
+        name: "corrupt-partial-run",
+        description: "A 4×1 image whose file ends 3 bytes into its second run.",
+        contents: &[4, 0, 0, 0, 1, 0, 0, 0, 2, 255, 0, 0, 2, 0, 0],
+        error: DecodeError::PartialRun { bytes: 3 },
+    },
+    Invalid {
+        name: "corrupt-header-only",
+        description: "A 4×4 header with no runs.",
+        contents: &[4, 0, 0, 0, 4, 0, 0, 0],
+        error: DecodeError::Truncated {
+            covered: 0,
+            expected: 16,
+        },
+    },
+    Invalid {
+        name: "corrupt-truncated",
+        description: "A 4×4 image whose runs stop after 10 pixels.",
+        contents: &[4, 0, 0, 0, 4, 0, 0, 0, 10, 255, 0, 0],
+        error: DecodeError::Truncated {
+            covered: 10,
+            expected: 16,
+        },
+    },
+    Invalid {
+        name: "corrupt-too-many-pixels",
+        description: "A 4×4 image whose second run goes 4 pixels past the last one.",
+        contents: &[4, 0, 0, 0, 4, 0, 0, 0, 10, 255, 0, 0, 10, 0, 255, 0],
+        error: DecodeError::TooManyPixels {
+            covered: 20,
+            expected: 16,
+        },
+    },
+    Invalid {
+        name: "corrupt-extra-runs",
+        description: "A 4×4 image with a run after a run of length 0 has covered it.",
+        contents: &[
+            4, 0, 0, 0, 4, 0, 0, 0, b'F', b'I', b'L', 1, 0, 255, 0, 0, 1, 0, 255, 0,
+        ],
+        error: DecodeError::ExtraRuns { bytes: 4 },
+    },
+    Invalid {
+        name: "corrupt-unmarked-fill",
+        description: "A 4×4 image with a run of length 0 but no fill runs marker, so the run \
+                      covers nothing.",
+        contents: &[4, 0, 0, 0, 4, 0, 0, 0, 0, 255, 0, 0],
+        error: DecodeError::Truncated {
+            covered: 0,
+            expected: 16,
+        },
+    },
+];
+
+/// Writes the reference .bruh files to `folder`, with the pixels each valid one decodes
+/// to and a `manifest.json` describing them all, and returns how many files there are.
+///
+/// Headers are written little-endian whatever the platform, since that's what every
+/// build on common hardware reads and writes. Each file is decoded with this crate before
+/// it's written, so the manifest can't disagree with it.
+pub fn generate(folder: &Path) -> Result<usize, Box<dyn Error>> {
+    fs::create_dir_all(folder)?;
+    let mut manifest = Vec::new();
+
+    for vector in VALID {
+        let pixels = (vector.pixels)();
+        let encoded = encode_bruh(vector.width, vector.height, pixels.iter().copied());
+        let contents = [
+            &header(vector.width, vector.height)[..],
+            &encoded[HEADER_SIZE..],
+        ]
+        .concat();
+
+        let decoded = decode_bruh(&native(&contents))
+            .map_err(|e| format!("{} doesn't decode: {}", vector.name, e))?;
+        let rgb: Vec<u8> = pixels.concat();
+        let round_trip: Vec<u8> = decoded
+            .pixels
+            .chunks_exact(4)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+            .collect();
+        if round_trip != rgb {
+            return Err(format!("{} decodes to different pixels.", vector.name).into());
+        }
+
+        let file = format!("{}.bruh", vector.name);
+        let expected = format!("{}.rgb", vector.name);
+        write(&folder.join(&file), &contents)?;
+        write(&folder.join(&expected), &rgb)?;
+        manifest.push(serde_json::json!({
+            "file": file,
+            "description": vector.description,
+            "valid": true,
+            "width": vector.width,
+            "height": vector.height,
+            "runs": Runs::of(&contents).bytes.len() / 4,
+            "pixels": expected,
+        }));
+    }
+
+    for vector in INVALID {
+        let expected = &vector.error;
+        match decode_bruh(&native(vector.contents)) {
+            Err(error) if error == *expected => {}
+            Err(error) => {
+                return Err(format!("{} fails differently: {}", vector.name, error).into())
+            }
+            Ok(_) => return Err(format!("{} decodes, but shouldn't.", vector.name).into()),
+        }
+
+        let file = format!("{}.bruh", vector.name);
+        write(&folder.join(&file), vector.contents)?;
+        manifest.push(serde_json::json!({
+            "file": file,
+            "description": vector.description,
+            "valid": false,
+            "error": error_name(expected),
+            "message": expected.to_string(),
+        }));
+    }
+
+    let manifest = serde_json::json!({
+        "format": "bruh",
+        "header": "width and height as unsigned 32-bit little-endian integers",
+        "marker": "FIL\\x01 after the header of a file whose runs of length 0 fill the image; \
+                   the encoder writes it for images of more than 255 pixels, and it's a run \
+                   instead when only then do the runs cover the image",
+        "runs": "4 bytes each: a length, then red, green and blue; a length of 0 covers \
+                 every pixel left after the marker, and no pixels without it",
+        "pixels": "each valid file's .rgb file holds its pixels as packed 8-bit RGB, row by row",
+        "vectors": manifest,
+    });
+    let count = VALID.len() + INVALID.len();
+    write(
+        &folder.join("manifest.json"),
+        serde_json::to_string_pretty(&manifest)?.as_bytes(),
+    )?;
+    Ok(count)
+}
+
+fn write(path: &Path, contents: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut file = Output::create(path)?;
+    file.write_all(contents)?;
+    file.commit()?;
+    Ok(())
+}
+
+fn header(width: u32, height: u32) -> [u8; HEADER_SIZE] {
+    let mut header = [0; HEADER_SIZE];
+    header[..4].copy_from_slice(&width.to_le_bytes());
+    header[4..].copy_from_slice(&height.to_le_bytes());
+    header
+}
+
+/// `contents` with its header in this platform's byte order, the way `decode_bruh` reads it.
+fn native(contents: &[u8]) -> Vec<u8> {
+    let mut native = contents.to_vec();
+    if cfg!(target_endian = "big") && native.len() >= HEADER_SIZE {
+        native[..4].reverse();
+        native[4..HEADER_SIZE].reverse();
+    }
+    native
+}
+
+/// The variant's name, for decoders in other languages to match their own errors to.
+fn error_name(error: &DecodeError) -> &'static str {
+    match error {
+        DecodeError::Empty => "Empty",
+        DecodeError::MissingHeader => "MissingHeader",
+        DecodeError::PartialRun { .. } => "PartialRun",
+        DecodeError::ExtraRuns { .. } => "ExtraRuns",
+        DecodeError::TooManyPixels { .. } => "TooManyPixels",
+        DecodeError::Truncated { .. } => "Truncated",
+        DecodeError::TooLarge { .. } => "TooLarge",
+        DecodeError::OutsideImage { .. } => "OutsideImage",
+        DecodeError::Cancelled => "Cancelled",
+        DecodeError::Animated => "Animated",
+        DecodeError::FrameSize { .. } => "FrameSize",
+    }
+}
+
+/// `length` pixels of one color, then a pixel of another.
+fn run_then_pixel(length: usize) -> Vec<[u8; 3]> {
+    let mut pixels = vec![[10, 200, 30]; length];
+    pixels.push([250, 250, 250]);
+    pixels
+}
+
+/// A 16×16 gradient in `layout`, converted the way `compile` converts it.
+fn from_layout(layout: PixelLayout) -> Vec<[u8; 3]> {
+    let channels = layout.channels();
+    let source: Vec<u8> = (0..256usize)
+        .flat_map(|i| {
+            let (x, y) = ((i % 16 * 17) as u8, (i / 16 * 17) as u8);
+            let pixel = [x, y, x ^ y, y];
+            pixel.into_iter().take(channels).collect::<Vec<_>>()
+        })
+        .collect();
+    let mut buffer = Vec::new();
+    pixels_to_rgb(&source, layout, &mut buffer)
+        .chunks_exact(3)
+        .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_files_match_the_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(generate(dir.path()).unwrap(), VALID.len() + INVALID.len());
+
+        let manifest = fs::read(dir.path().join("manifest.json")).unwrap();
+        let manifest: serde_json::Value = serde_json::from_slice(&manifest).unwrap();
+        let entries = manifest["vectors"].as_array().unwrap();
+        assert_eq!(entries.len(), VALID.len() + INVALID.len());
+        for entry in entries {
+            let file = entry["file"].as_str().unwrap();
+            let contents = native(&fs::read(dir.path().join(file)).unwrap());
+            match entry["pixels"].as_str() {
+                Some(pixels) => {
+                    let expected = fs::read(dir.path().join(pixels)).unwrap();
+                    let decoded = decode_bruh(&contents).unwrap();
+                    let rgb: Vec<u8> = decoded
+                        .pixels
+                        .chunks_exact(4)
+                        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+                        .collect();
+                    assert_eq!(rgb, expected, "{}", file);
+                }
+                None => {
+                    let error = decode_bruh(&contents).err().expect(file);
+                    assert_eq!(entry["error"], error_name(&error), "{}", file);
+                }
+            }
+        }
+    }
+}
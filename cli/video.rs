@@ -0,0 +1,128 @@
+use std::{
+    error::Error,
+    io::{self, BufReader, Read},
+    path::Path,
+    process::{Child, Command, Stdio},
+};
+
+use crate::output::is_stdio;
+
+/// The frames of a video as RGB8 pixels, decoded by ffmpeg, or read as raw `rgb24` video
+/// from standard input.
+pub struct VideoFrames {
+    pub width: u32,
+    pub height: u32,
+    reader: Box<dyn Read>,
+    ffmpeg: Option<Child>,
+}
+
+impl VideoFrames {
+    /// Starts decoding the video at `input` at `fps` frames a second, scaled to `size` if
+    /// it's given. A missing width or height keeps the video's aspect ratio.
+    ///
+    /// With `-` as the input, frames are read from standard input instead, as raw `rgb24`
+    /// video such as `ffmpeg -f rawvideo -pix_fmt rgb24 -` writes. They're taken to be
+    /// `fps` a second already, and `size` has to give their size.
+    pub fn open(
+        input: &Path,
+        fps: f64,
+        size: (Option<u32>, Option<u32>),
+    ) -> Result<VideoFrames, Box<dyn Error>> {
+        if is_stdio(input) {
+            let (Some(width), Some(height)) = size else {
+                return Err("raw video on standard input needs --width and --height.".into());
+            };
+            return Ok(VideoFrames {
+                width,
+                height,
+                reader: Box::new(BufReader::new(io::stdin())),
+                ffmpeg: None,
+            });
+        }
+
+        let (width, height) = match (size, probe_size(input)?) {
+            ((Some(width), Some(height)), _) => (width, height),
+            ((Some(width), None), (w, h)) => (width, scale(h, width, w)),
+            ((None, Some(height)), (w, h)) => (scale(w, height, h), height),
+            ((None, None), size) => size,
+        };
+        let mut ffmpeg = Command::new("ffmpeg")
+            .args(["-v", "error", "-i"])
+            .arg(input)
+            .args(["-vf", &format!("fps={},scale={}:{}", fps, width, height)])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("couldn't run ffmpeg, which --from-video needs: {}", e))?;
+        let stdout = ffmpeg.stdout.take().expect("stdout is piped");
+        Ok(VideoFrames {
+            width,
+            height,
+            reader: Box::new(BufReader::new(stdout)),
+            ffmpeg: Some(ffmpeg),
+        })
+    }
+
+    /// Reads the next frame into `rgb`, returning `false` at the end of the video.
+    pub fn next_frame(&mut self, rgb: &mut Vec<u8>) -> Result<bool, Box<dyn Error>> {
+        let len = self.width as usize * self.height as usize * 3;
+        rgb.clear();
+        (&mut self.reader).take(len as u64).read_to_end(rgb)?;
+        match rgb.len() {
+            0 => Ok(false),
+            read if read < len => {
+                Err(format!("the video ends partway through a frame ({} of {} bytes).", read, len)
+                    .into())
+            }
+            _ => Ok(true),
+        }
+    }
+
+    /// Waits for ffmpeg to exit, failing if it did.
+    pub fn finish(mut self) -> Result<(), Box<dyn Error>> {
+        let Some(mut ffmpeg) = self.ffmpeg.take() else {
+            return Ok(());
+        };
+        let status = ffmpeg.wait()?;
+        if !status.success() {
+            return Err(format!("ffmpeg failed ({}).", status).into());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for VideoFrames {
+    /// Stops ffmpeg if the frames weren't all read, e.g. because writing one failed.
+    fn drop(&mut self) {
+        if let Some(ffmpeg) = &mut self.ffmpeg {
+            let _ = ffmpeg.kill();
+            let _ = ffmpeg.wait();
+        }
+    }
+}
+
+/// `length` scaled by `to / from`, rounded and at least 1.
+fn scale(length: u32, to: u32, from: u32) -> u32 {
+    ((length as f64 * to as f64 / from.max(1) as f64).round() as u32).max(1)
+}
+
+/// The size of the first video stream of `input`, asked of ffprobe.
+fn probe_size(input: &Path) -> Result<(u32, u32), Box<dyn Error>> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0"])
+        .args(["-show_entries", "stream=width,height", "-of", "csv=s=x:p=0"])
+        .arg(input)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("couldn't run ffprobe, which --from-video needs: {}", e))?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        let reason = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("ffprobe couldn't read {}: {}", input.display(), reason.trim()).into());
+    }
+    let size = text.trim().split_once('x').and_then(|(width, height)| {
+        Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+    });
+    size.ok_or_else(|| format!("{} has no video.", input.display()).into())
+}
@@ -0,0 +1,276 @@
+//! Just enough of WebSocket (RFC 6455) to carry the frames of `stream`, so a sender can
+//! reach a receiver through proxies that only pass HTTP, or be a page in a browser.
+//!
+//! Each .bruh frame is one binary message. Text messages are refused, pings are answered
+//! and a close ends the stream. There's no TLS, so `wss://` isn't supported.
+
+use std::{
+    error::Error,
+    io::{self, Read, Write},
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use sha1::{Digest, Sha1};
+
+/// Appended to the client's key before hashing it, as the RFC says.
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const CONTINUATION: u8 = 0x0;
+const TEXT: u8 = 0x1;
+pub const BINARY: u8 = 0x2;
+pub const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xa;
+
+/// Longest header line taken, which is far more than any real handshake needs.
+const MAX_LINE: usize = 8192;
+const MAX_HEADERS: usize = 100;
+/// Longest payload of a ping, pong or close, as the RFC says.
+const MAX_CONTROL: u64 = 125;
+
+/// What the server answers to a handshake with the key `key`.
+fn accept_key(key: &str) -> String {
+    BASE64.encode(Sha1::digest(format!("{}{}", key, GUID)))
+}
+
+/// Reads the header lines of an HTTP request or response up to the blank line that ends
+/// them, a byte at a time so nothing after them is taken from `reader`.
+fn read_headers(reader: &mut impl Read) -> io::Result<Vec<String>> {
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+    let mut byte = [0];
+    loop {
+        reader.read_exact(&mut byte)?;
+        if byte[0] != b'\n' {
+            if line.len() == MAX_LINE {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "a header is too long"));
+            }
+            line.push(byte[0]);
+            continue;
+        }
+        let text = String::from_utf8_lossy(&line).trim_end().to_owned();
+        if text.is_empty() {
+            return Ok(lines);
+        }
+        if lines.len() == MAX_HEADERS {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "too many headers"));
+        }
+        lines.push(text);
+        line.clear();
+    }
+}
+
+/// The value of the header `name` in `lines`, ignoring the case of the name.
+fn header<'a>(lines: &'a [String], name: &str) -> Option<&'a str> {
+    lines.iter().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+/// Answers the opening handshake of a client whose request has been read up to somewhere
+/// in its first line, by reading the rest of it from `reader` and accepting it on `writer`.
+pub fn accept(reader: &mut impl Read, writer: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let headers = read_headers(reader)?;
+    let upgrade = header(&headers, "Upgrade");
+    let upgrade = upgrade.is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket"));
+    let (true, Some(key)) = (upgrade, header(&headers, "Sec-WebSocket-Key")) else {
+        writer.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")?;
+        return Err("an HTTP request that isn't a WebSocket handshake.".into());
+    };
+    write!(
+        writer,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    )?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Opens a WebSocket on `stream`, already connected to `host`, for the resource `path`.
+pub fn connect(
+    stream: &mut (impl Read + Write),
+    host: &str,
+    path: &str,
+) -> Result<(), Box<dyn Error>> {
+    let key = BASE64.encode(rand::random::<[u8; 16]>());
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+        path, host, key
+    )?;
+    stream.flush()?;
+
+    let headers = read_headers(stream)?;
+    let status = headers.first().map_or("", String::as_str);
+    if status.split_whitespace().nth(1) != Some("101") {
+        return Err(format!("the server refused the WebSocket: {}", status).into());
+    }
+    if header(&headers, "Sec-WebSocket-Accept") != Some(&accept_key(&key)) {
+        return Err("the server answered the WebSocket handshake wrongly.".into());
+    }
+    Ok(())
+}
+
+/// Writes `payload` as a single message. Clients have to mask what they send, servers
+/// mustn't.
+pub fn write_message(
+    out: &mut impl Write,
+    opcode: u8,
+    payload: &[u8],
+    masked: bool,
+) -> io::Result<()> {
+    let mask_bit = if masked { 0x80 } else { 0 };
+    out.write_all(&[0x80 | opcode])?;
+    match payload.len() {
+        len @ 0..=125 => out.write_all(&[mask_bit | len as u8])?,
+        len @ 126..=0xffff => {
+            out.write_all(&[mask_bit | 126])?;
+            out.write_all(&(len as u16).to_be_bytes())?;
+        }
+        len => {
+            out.write_all(&[mask_bit | 127])?;
+            out.write_all(&(len as u64).to_be_bytes())?;
+        }
+    }
+    if !masked {
+        return out.write_all(payload);
+    }
+
+    let mask: [u8; 4] = rand::random();
+    out.write_all(&mask)?;
+    let mut buffer = [0; 8192];
+    // The buffer is a multiple of 4 bytes long, so the mask starts over with each chunk.
+    for chunk in payload.chunks(buffer.len()) {
+        for (i, (masked, byte)) in buffer.iter_mut().zip(chunk).enumerate() {
+            *masked = byte ^ mask[i % 4];
+        }
+        out.write_all(&buffer[..chunk.len()])?;
+    }
+    Ok(())
+}
+
+/// Reads the next binary message into `message`, answering any pings on the way with
+/// `reply`. Returns `false` once the other end has closed the connection, or dropped it
+/// between two messages.
+///
+/// A message longer than `limit` bytes, all its parts together, is refused before it's
+/// read.
+pub fn read_message(
+    reader: &mut impl Read,
+    message: &mut Vec<u8>,
+    limit: usize,
+    mut reply: impl FnMut(u8, &[u8]) -> io::Result<()>,
+) -> Result<bool, Box<dyn Error>> {
+    message.clear();
+    let mut started = false;
+    loop {
+        let mut head = [0; 2];
+        match reader.read_exact(&mut head) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && !started => return Ok(false),
+            Err(e) => return Err(e.into()),
+        }
+        let (fin, opcode) = (head[0] & 0x80 != 0, head[0] & 0x0f);
+        let len = match head[1] & 0x7f {
+            126 => {
+                let mut len = [0; 2];
+                reader.read_exact(&mut len)?;
+                u16::from_be_bytes(len) as u64
+            }
+            127 => {
+                let mut len = [0; 8];
+                reader.read_exact(&mut len)?;
+                u64::from_be_bytes(len)
+            }
+            len => len as u64,
+        };
+        let mut mask = [0; 4];
+        if head[1] & 0x80 != 0 {
+            reader.read_exact(&mut mask)?;
+        }
+
+        let control = opcode & 0x8 != 0;
+        if control && len > MAX_CONTROL {
+            return Err(format!("received a control message of {} bytes.", len).into());
+        }
+        if !control && len > (limit - message.len()) as u64 {
+            return Err(format!("received a message of more than {} bytes.", limit).into());
+        }
+
+        // Even within the limit, only what's actually sent takes up memory.
+        let mut payload = Vec::new();
+        (&mut *reader).take(len).read_to_end(&mut payload)?;
+        if (payload.len() as u64) < len {
+            return Err("the connection closed in the middle of a message.".into());
+        }
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+
+        match opcode {
+            // Pings and pongs can come between the parts of a message.
+            PING => {
+                reply(PONG, &payload)?;
+                continue;
+            }
+            PONG => continue,
+            CLOSE => {
+                // Answering is only polite, and the other end may not have waited for it.
+                let _ = reply(CLOSE, &payload);
+                return Ok(false);
+            }
+            TEXT => return Err("received text instead of a frame.".into()),
+            BINARY if !started => {
+                started = true;
+                message.extend_from_slice(&payload);
+            }
+            CONTINUATION if started => message.extend_from_slice(&payload),
+            _ => return Err(format!("received a message of unknown kind {:#x}.", opcode).into()),
+        }
+        if fin {
+            return Ok(true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(bytes: &[u8], limit: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut message = Vec::new();
+        read_message(&mut &bytes[..], &mut message, limit, |_, _| Ok(()))?;
+        Ok(message)
+    }
+
+    #[test]
+    fn reads_fragmented_messages() {
+        let mut bytes = vec![BINARY, 2, 1, 2, 0x80 | PING, 0, 0x80 | CONTINUATION, 1, 3];
+        assert_eq!(read(&bytes, 3).unwrap(), [1, 2, 3]);
+        // The parts together are over the limit, though each is under it.
+        assert!(read(&bytes, 2).is_err());
+
+        bytes.clear();
+        write_message(&mut bytes, BINARY, &[9; 300], true).unwrap();
+        assert_eq!(read(&bytes, 300).unwrap(), [9; 300]);
+    }
+
+    #[test]
+    fn refuses_huge_lengths_before_reading() {
+        let mut bytes = vec![0x80 | BINARY, 127];
+        bytes.extend_from_slice(&u64::MAX.to_be_bytes());
+        assert!(read(&bytes, 1 << 20).is_err());
+        assert!(read(&[0x80 | PING, 126, 1, 0], 1 << 20).is_err());
+    }
+
+    #[test]
+    fn refuses_long_header_lines() {
+        let handshake = "GET / HTTP/1.1\r\nHost: x\r\n\r\n";
+        assert_eq!(read_headers(&mut handshake.as_bytes()).unwrap().len(), 2);
+        let long = format!("GET / HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(MAX_LINE));
+        assert!(read_headers(&mut long.as_bytes()).is_err());
+    }
+}
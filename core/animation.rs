@@ -0,0 +1,124 @@
+//! Animated .bruh files: the header of a still image giving the size of every frame, then
+//! [`ANIMATION_MAGIC`](crate::ANIMATION_MAGIC), then the frames one after another. Each
+//! frame is its delay in milliseconds as a `u32`, its length in bytes as a `u64`, both in
+//! native byte order like the header, and that many bytes of a whole .bruh file the size
+//! of the animation.
+//!
+//! Nothing before a frame refers to it, so frames are added by appending them, without
+//! going back to change anything already written. A writer that's interrupted partway
+//! through a frame leaves a last frame shorter than its length, which readers drop.
+
+use std::io::{self, Write};
+
+use crate::{check_bruh, decode_header, DecodeError, ANIMATION_MAGIC, HEADER_SIZE};
+
+/// Size of the delay and length that come before each frame.
+pub const FRAME_HEADER_SIZE: usize = 12;
+
+/// A frame of an [`Animation`].
+#[derive(Clone, Copy, Debug)]
+pub struct Frame<'a> {
+    /// How long the frame is shown before the next one, in milliseconds.
+    pub delay_ms: u32,
+    /// Where the frame's contents start in the animation's file.
+    pub offset: usize,
+    /// The frame as a .bruh file of its own.
+    pub contents: &'a [u8],
+}
+
+/// The frames of an animated .bruh file, read without decoding them.
+#[derive(Clone, Debug)]
+pub struct Animation<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub frames: Vec<Frame<'a>>,
+    /// Bytes after the last whole frame, left by a writer that was interrupted.
+    pub partial: usize,
+}
+
+impl<'a> Animation<'a> {
+    /// The frames of `contents`, or `None` if it isn't an animation. A frame that isn't a
+    /// .bruh file of the animation's size is an error, but whether its runs are whole is
+    /// only found out when it's decoded.
+    pub fn of(contents: &'a [u8]) -> Result<Option<Self>, DecodeError> {
+        match check_bruh(contents) {
+            Err(DecodeError::Animated) => {}
+            _ => return Ok(None),
+        }
+        let header = contents[..HEADER_SIZE].try_into().expect("checked by check_bruh");
+        let (width, height) = decode_header(header);
+
+        let mut frames = Vec::new();
+        let mut rest = &contents[HEADER_SIZE + ANIMATION_MAGIC.len()..];
+        while let Some((frame_header, after)) = rest.split_first_chunk::<FRAME_HEADER_SIZE>() {
+            let delay_ms = u32::from_ne_bytes(frame_header[..4].try_into().expect("4 bytes"));
+            let len = u64::from_ne_bytes(frame_header[4..].try_into().expect("8 bytes"));
+            let Some(len) = usize::try_from(len).ok().filter(|&len| len <= after.len()) else {
+                break;
+            };
+            let (frame, after) = after.split_at(len);
+
+            let size = frame.first_chunk::<HEADER_SIZE>().map(decode_header);
+            if size != Some((width, height)) {
+                let (frame_width, frame_height) = size.unwrap_or((0, 0));
+                return Err(DecodeError::FrameSize {
+                    frame: frames.len(),
+                    width: frame_width,
+                    height: frame_height,
+                });
+            }
+            frames.push(Frame {
+                delay_ms,
+                offset: contents.len() - after.len() - len,
+                contents: frame,
+            });
+            rest = after;
+        }
+
+        Ok(Some(Animation {
+            width,
+            height,
+            frames,
+            partial: rest.len(),
+        }))
+    }
+}
+
+/// Writes an animated .bruh file a frame at a time.
+pub struct AnimationWriter<W: Write> {
+    out: W,
+    width: u32,
+    height: u32,
+}
+
+impl<W: Write> AnimationWriter<W> {
+    /// Starts an animation of `width` × `height` frames by writing its header to `out`.
+    pub fn new(mut out: W, width: u32, height: u32) -> io::Result<Self> {
+        out.write_all(&width.to_ne_bytes())?;
+        out.write_all(&height.to_ne_bytes())?;
+        out.write_all(ANIMATION_MAGIC)?;
+        Ok(Self { out, width, height })
+    }
+
+    /// Appends `frame`, a whole .bruh file the size of the animation, to be shown for
+    /// `delay_ms` milliseconds. It's flushed, so a reader sees every frame pushed so far.
+    pub fn push_frame(&mut self, frame: &[u8], delay_ms: u32) -> io::Result<()> {
+        let size = (self.width, self.height);
+        if frame.first_chunk::<HEADER_SIZE>().map(decode_header) != Some(size) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("A frame of a {}×{} animation has to be that size.", size.0, size.1),
+            ));
+        }
+        self.out.write_all(&delay_ms.to_ne_bytes())?;
+        self.out.write_all(&(frame.len() as u64).to_ne_bytes())?;
+        self.out.write_all(frame)?;
+        self.out.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
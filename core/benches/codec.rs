@@ -0,0 +1,110 @@
+//! Encode and decode throughput over synthetic patterns, plus any PNGs found in the
+//! directory named by `BRUH_BENCH_IMAGES`.
+
+use std::{env, fs, path::PathBuf};
+
+use bruh::{decode_bruh, encode_bruh, BruhEncoder};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+const SIZE: u32 = 1024;
+
+/// An image to benchmark, as packed RGB8 pixels.
+struct Input {
+    name: String,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+fn synthetic(name: &str, pixel: impl Fn(u32, u32) -> [u8; 3]) -> Input {
+    let mut pixels = Vec::with_capacity((SIZE * SIZE * 3) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            pixels.extend_from_slice(&pixel(x, y));
+        }
+    }
+
+    Input {
+        name: name.to_owned(),
+        width: SIZE,
+        height: SIZE,
+        pixels,
+    }
+}
+
+fn inputs() -> Vec<Input> {
+    let mut noise = vec![0; (SIZE * SIZE * 3) as usize];
+    StdRng::seed_from_u64(0).fill_bytes(&mut noise);
+
+    let mut inputs = vec![
+        synthetic("solid", |_, _| [32, 64, 128]),
+        synthetic("gradient", |x, _| [(x / 4) as u8, 0, 255 - (x / 4) as u8]),
+        synthetic("stripes", |_, y| [(y % 2 * 255) as u8; 3]),
+        Input {
+            name: "noise".to_owned(),
+            width: SIZE,
+            height: SIZE,
+            pixels: noise,
+        },
+    ];
+
+    let dir = env::var_os("BRUH_BENCH_IMAGES").map(PathBuf::from);
+    for path in dir.iter().flat_map(fs::read_dir).flatten().flatten() {
+        let path = path.path();
+        let Ok(image) = image::open(&path) else {
+            continue;
+        };
+        let image = image.to_rgb8();
+        inputs.push(Input {
+            name: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            width: image.width(),
+            height: image.height(),
+            pixels: image.into_raw(),
+        });
+    }
+
+    inputs
+}
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+    for input in inputs() {
+        group.throughput(Throughput::Bytes(input.pixels.len() as u64));
+
+        // Pixel-by-pixel comparison, as used for pixels coming from an iterator.
+        group.bench_with_input(BenchmarkId::new("per_pixel", &input.name), &input, |b, input| {
+            b.iter(|| {
+                let pixels = input.pixels.chunks_exact(3).map(|p| [p[0], p[1], p[2]]);
+                encode_bruh(input.width, input.height, pixels)
+            })
+        });
+
+        // Block-wise run detection, as used when compiling PNG rows.
+        group.bench_with_input(BenchmarkId::new("blocks", &input.name), &input, |b, input| {
+            b.iter(|| {
+                let mut encoder = BruhEncoder::new(Vec::new(), input.width, input.height).unwrap();
+                encoder.push_pixels(&input.pixels).unwrap();
+                encoder.finish().unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    for input in inputs() {
+        let pixels = input.pixels.chunks_exact(3).map(|p| [p[0], p[1], p[2]]);
+        let encoded = encode_bruh(input.width, input.height, pixels);
+
+        group.throughput(Throughput::Bytes(input.pixels.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(&input.name), &encoded, |b, encoded| {
+            b.iter(|| decode_bruh(encoded).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, encode, decode);
+criterion_main!(benches);
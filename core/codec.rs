@@ -0,0 +1,1324 @@
+//! The BRUH image format: a `u32` width and height in native byte order, followed by
+//! runs of up to 255 identical pixels, each stored as a length byte and an RGB color.
+//!
+//! Files whose runs start with [`FILL_RUNS_MAGIC`] are version 2 of the format, where a
+//! run with a length of 0 fills the rest of the image with its color, so a solid color
+//! image, or one ending in a large flat area, doesn't need a run for every 255 pixels of
+//! it. Decoders that predate it read a length of 0 as no pixels, so the marker keeps them
+//! from quietly decoding such a file wrong. In files without it, a length of 0 still
+//! covers nothing. See [`Runs`].
+//!
+//! A signed file has a chunk of [`SIGNATURE_MAGIC`] and an Ed25519 signature right after
+//! the header, before any other marker. It signs the file without that chunk.
+//!
+//! A file can carry other data along with the image in a chunk of [`DATA_MAGIC`], after
+//! the signature if there is one and before the fill runs marker. Decoders skip it.
+//!
+//! None of these markers can be told from a run by itself, and a version 1 file's first
+//! run can happen to read as one. Such a file is read as plain runs when that's the only
+//! way its runs cover the size in its header. See [`Runs::of`].
+//!
+//! An animated file has [`ANIMATION_MAGIC`] after the header, followed by its frames, each
+//! a .bruh file of its own. See [`animation`].
+//!
+//! Either dimension may be 0, in which case the file is just the header.
+//!
+//! There's no alpha channel: sources with one are composited over black before encoding.
+//!
+//! Nothing in the format is an offset or a byte count, so files over 4 GiB are valid
+//! as they are. The limits are the `u32` dimensions and, when decoding into memory,
+//! [`MAX_DECODED_PIXELS`] (see [`buffer_len`]).
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    io::{self, Write},
+    sync::{
+        atomic::{self, AtomicBool, AtomicU64},
+        Arc,
+    },
+};
+
+pub mod animation;
+pub mod file;
+pub mod palette;
+#[cfg(feature = "sidecar")]
+pub mod sidecar;
+
+/// Pixels compared at once when looking for the end of a run.
+const RUN_SCAN_BLOCK: usize = 16;
+
+/// Runs decoded between reports to a [`Progress`], so checking it costs next to nothing.
+const RUNS_PER_REPORT: usize = 4096;
+
+/// Size of the width and height that start every .bruh file.
+pub const HEADER_SIZE: usize = 8;
+
+/// Follows the header of a .bruh file encrypted with `bruh compile --encrypt`, in place of
+/// the runs. Such files can't be decoded without decrypting them first.
+pub const ENCRYPTED_MAGIC: &[u8; 4] = b"ENC\x01";
+
+/// Follows the header of a .bruh file whose runs may include fill runs, which makes it
+/// version 2 of the format. Read as a run, it's 70 pixels, so older decoders that check
+/// the runs against the header reject the file instead of misreading it.
+///
+/// The encoder writes it for every image of more than 255 pixels, the only ones a fill
+/// run can make smaller, since it doesn't know yet whether the image will end in one.
+pub const FILL_RUNS_MAGIC: &[u8; 4] = b"FIL\x01";
+
+/// Starts the chunk `bruh sign` puts right after the header: this, then [`SIGNATURE_LEN`]
+/// bytes of Ed25519 signature over the rest of the file, header included. Read as a run
+/// it's 83 pixels, followed by the signature's random bytes, so older decoders all but
+/// certainly reject the file instead of misreading it.
+pub const SIGNATURE_MAGIC: &[u8; 4] = b"SIG\x01";
+
+/// Starts the chunk `bruh embed` puts after the header and any signature: this, the length
+/// of the data as a `u32` in native byte order, then the data, which decoders skip. Read
+/// as a run it's 68 pixels, followed by the length and the data as more runs, so older
+/// decoders all but certainly reject the file instead of misreading it.
+pub const DATA_MAGIC: &[u8; 4] = b"DAT\x01";
+
+/// Follows the header of an animated .bruh file, in place of the runs. Read as a run it's
+/// 65 pixels, followed by the first frame's delay and length, so decoders of still images
+/// reject the file instead of misreading it. See [`animation`].
+pub const ANIMATION_MAGIC: &[u8; 4] = b"ANI\x01";
+
+/// Length of the signature after [`SIGNATURE_MAGIC`].
+pub const SIGNATURE_LEN: usize = 64;
+
+/// Most pixels an image decoded into memory may have: 2^28, such as 16384×16384, which is
+/// 1 GiB as RGBA8. A fill run lets a 16-byte file claim any size, so this keeps a broken
+/// or hostile file from asking for more memory than any real image needs. Larger files
+/// are still valid, and `bruh decompile` converts them a row at a time.
+pub const MAX_DECODED_PIXELS: u64 = 1 << 28;
+
+fn vec_to_u32_ne(bytes: &[u8]) -> u32 {
+    let mut result = [0u8; 4];
+    result.copy_from_slice(bytes);
+    u32::from_ne_bytes(result)
+}
+
+/// Reads the width and height from the start of a .bruh file.
+pub fn decode_header(header: &[u8; HEADER_SIZE]) -> (u32, u32) {
+    (vec_to_u32_ne(&header[0..4]), vec_to_u32_ne(&header[4..8]))
+}
+
+/// The runs of a .bruh file, and how to read their lengths, which depends on whether the
+/// file starts with [`FILL_RUNS_MAGIC`].
+#[derive(Clone, Copy, Debug)]
+pub struct Runs<'a> {
+    /// Where the first run starts in the file.
+    pub offset: usize,
+    /// The runs, up to the end of the file. A file that ends partway through one has a
+    /// few bytes left over.
+    pub bytes: &'a [u8],
+    /// Whether a run with a length of 0 fills the rest of the image.
+    pub fills: bool,
+    /// The signature in the file's [`SIGNATURE_MAGIC`] chunk, if it's signed.
+    pub signature: Option<&'a [u8; SIGNATURE_LEN]>,
+    /// What the file's [`DATA_MAGIC`] chunk holds, if it has one.
+    pub data: Option<&'a [u8]>,
+}
+
+impl<'a> Runs<'a> {
+    /// The runs of `contents`, which has to be at least [`HEADER_SIZE`] bytes long, after
+    /// its signature, data chunk and fill runs marker.
+    ///
+    /// If the runs after them don't cover the size in the header, but the runs read from
+    /// right after the header do, the file is version 1 and its first run only looks like
+    /// one of them, so there are none.
+    pub fn of(contents: &'a [u8]) -> Self {
+        let runs = Runs::after_chunks(contents);
+        if runs.offset == HEADER_SIZE {
+            return runs;
+        }
+        let plain = Runs {
+            offset: HEADER_SIZE,
+            bytes: &contents[HEADER_SIZE..],
+            fills: false,
+            signature: None,
+            data: None,
+        };
+        let header = contents[..HEADER_SIZE].try_into().expect("HEADER_SIZE bytes");
+        let (width, height) = decode_header(header);
+        let expected = width as u64 * height as u64;
+        match check_runs(runs, expected) {
+            Err(_) if check_runs(plain, expected).is_ok() => plain,
+            _ => runs,
+        }
+    }
+
+    /// The runs of `contents` after whatever reads as its chunks and marker.
+    fn after_chunks(contents: &'a [u8]) -> Self {
+        let mut rest = &contents[HEADER_SIZE..];
+        let signature = rest
+            .strip_prefix(SIGNATURE_MAGIC)
+            .and_then(|signed| signed.first_chunk::<SIGNATURE_LEN>());
+        if signature.is_some() {
+            rest = &rest[SIGNATURE_MAGIC.len() + SIGNATURE_LEN..];
+        }
+        let data = rest.strip_prefix(DATA_MAGIC).and_then(|chunk| {
+            let len = u32::from_ne_bytes(*chunk.first_chunk::<4>()?) as usize;
+            chunk[4..].get(..len)
+        });
+        if let Some(data) = data {
+            rest = &rest[DATA_MAGIC.len() + 4 + data.len()..];
+        }
+        let fills = rest.starts_with(FILL_RUNS_MAGIC);
+        if fills {
+            rest = &rest[FILL_RUNS_MAGIC.len()..];
+        }
+
+        Runs {
+            offset: contents.len() - rest.len(),
+            bytes: rest,
+            fills,
+            signature,
+            data,
+        }
+    }
+
+    /// How many pixels a run with the length byte `length` covers, when `remaining`
+    /// pixels of the image are left.
+    pub fn length(&self, length: u8, remaining: u64) -> u64 {
+        match length {
+            0 if self.fills => remaining,
+            length => length as u64,
+        }
+    }
+}
+
+/// How the channels of an 8-bit source pixel are laid out.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PixelLayout {
+    Gray,
+    GrayAlpha,
+    Rgb,
+    Rgba,
+}
+
+impl PixelLayout {
+    pub fn channels(self) -> usize {
+        match self {
+            PixelLayout::Gray => 1,
+            PixelLayout::GrayAlpha => 2,
+            PixelLayout::Rgb => 3,
+            PixelLayout::Rgba => 4,
+        }
+    }
+}
+
+/// Composites a straight-alpha RGBA8 pixel over black.
+pub fn flatten_alpha([r, g, b, a]: [u8; 4]) -> [u8; 3] {
+    let scale = |channel: u8| ((channel as u16 * a as u16 + 127) / 255) as u8;
+    [scale(r), scale(g), scale(b)]
+}
+
+/// Packs 8-bit `pixels` laid out as `layout` as RGB8, using `buffer` unless they
+/// already are. Grey is copied into all three channels and alpha is flattened with
+/// [`flatten_alpha`].
+pub fn pixels_to_rgb<'a>(
+    pixels: &'a [u8],
+    layout: PixelLayout,
+    buffer: &'a mut Vec<u8>,
+) -> &'a [u8] {
+    if layout == PixelLayout::Rgb {
+        return pixels;
+    }
+
+    buffer.clear();
+    for pixel in pixels.chunks_exact(layout.channels()) {
+        let rgb = match *pixel {
+            [l] => [l, l, l],
+            [l, a] => flatten_alpha([l, l, l, a]),
+            [r, g, b, a] => flatten_alpha([r, g, b, a]),
+            _ => unreachable!("chunks are as long as the layout has channels"),
+        };
+        buffer.extend_from_slice(&rgb);
+    }
+    buffer
+}
+
+/// Run-length encodes pixels as they arrive, writing each run out as soon as it ends.
+pub struct BruhEncoder<W: Write> {
+    out: W,
+    /// Color and length of the run not written yet, `None` before the first pixel.
+    /// The length is split into runs of 255 as it's written.
+    run: Option<([u8; 3], usize)>,
+    /// Pixels not pushed yet, to tell whether the pending run reaches the end of the image.
+    pixels_left: u64,
+    /// Whether [`FILL_RUNS_MAGIC`] was written, so the last run can be a fill run.
+    fills: bool,
+}
+
+impl<W: Write> BruhEncoder<W> {
+    pub fn new(mut out: W, width: u32, height: u32) -> io::Result<Self> {
+        out.write_all(&width.to_ne_bytes())?;
+        out.write_all(&height.to_ne_bytes())?;
+        let pixel_count = width as u64 * height as u64;
+        let fills = pixel_count > 255;
+        if fills {
+            out.write_all(FILL_RUNS_MAGIC)?;
+        }
+
+        Ok(Self {
+            out,
+            run: None,
+            pixels_left: pixel_count,
+            fills,
+        })
+    }
+
+    pub fn push(&mut self, current_color: [u8; 3]) -> io::Result<()> {
+        self.push_run(current_color, 1)
+    }
+
+    /// Encodes packed RGB8 pixels, measuring each run in blocks instead of pixel by pixel.
+    pub fn push_pixels(&mut self, pixels: &[u8]) -> io::Result<()> {
+        let mut rest = pixels;
+        while let [r, g, b, ..] = *rest {
+            let color = [r, g, b];
+            let count = run_prefix(rest, color);
+            self.push_run(color, count)?;
+            rest = &rest[count * 3..];
+        }
+        Ok(())
+    }
+
+    /// Same as pushing `color` `count` times.
+    pub fn push_run(&mut self, color: [u8; 3], count: usize) -> io::Result<()> {
+        if count == 0 {
+            return Ok(());
+        }
+
+        match &mut self.run {
+            Some((run_color, length)) if *run_color == color => *length += count,
+            _ => {
+                self.write_run()?;
+                self.run = Some((color, count));
+            }
+        }
+        self.pixels_left = self.pixels_left.saturating_sub(count as u64);
+        Ok(())
+    }
+
+    /// Writes the pending run and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.run {
+            Some((color, length)) if self.fills && self.pixels_left == 0 && length > 255 => {
+                self.out.write_all(&[0])?;
+                self.out.write_all(&color)?;
+            }
+            _ => self.write_run()?,
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn write_run(&mut self) -> io::Result<()> {
+        let Some((color, mut remaining)) = self.run.take() else {
+            return Ok(());
+        };
+        while remaining > 0 {
+            let length = remaining.min(255);
+            self.out.write_all(&[length as u8])?;
+            self.out.write_all(&color)?;
+            remaining -= length;
+        }
+        Ok(())
+    }
+}
+
+/// Changes an image row by row on its way into a .bruh file, e.g. to correct its colors.
+/// Filters are chained with [`FilteredEncoder::add_filter`].
+pub trait PixelFilter {
+    /// Called with the size of the image before its first row.
+    fn begin(&mut self, _width: u32, _height: u32) {}
+
+    /// Changes row `y` of the image in place. The row is RGBA8 and starts out opaque;
+    /// alpha left below 255 by the last filter is composited over black.
+    fn filter_row(&mut self, y: u32, row: &mut [u8]);
+}
+
+/// A [`BruhEncoder`] that passes each row through a chain of [`PixelFilter`]s first.
+///
+/// Pixels can be pushed in pieces of any size. Rows are held back until they're complete,
+/// so no more than one row is buffered.
+pub struct FilteredEncoder<W: Write> {
+    encoder: BruhEncoder<W>,
+    filters: Vec<Box<dyn PixelFilter>>,
+    width: u32,
+    height: u32,
+    /// The row being assembled, as RGBA.
+    row: Vec<u8>,
+    /// Index of the row being assembled.
+    y: u32,
+    rgb: Vec<u8>,
+    progress: Option<Progress>,
+}
+
+impl<W: Write> FilteredEncoder<W> {
+    pub fn new(out: W, width: u32, height: u32) -> io::Result<Self> {
+        Self::with_filters(out, width, height, Vec::new())
+    }
+
+    /// Like [`FilteredEncoder::new`], with the chain of `filters` already in place.
+    pub fn with_filters(
+        out: W,
+        width: u32,
+        height: u32,
+        filters: Vec<Box<dyn PixelFilter>>,
+    ) -> io::Result<Self> {
+        Ok(Self {
+            encoder: BruhEncoder::new(out, width, height)?,
+            filters,
+            width,
+            height,
+            row: Vec::new(),
+            y: 0,
+            rgb: Vec::new(),
+            progress: None,
+        })
+    }
+
+    /// Reports every finished row to `progress`, and fails the next push once it's
+    /// cancelled, with an error whose source is [`Cancelled`]. Has to be set before the
+    /// first pixel is pushed.
+    pub fn set_progress(&mut self, progress: Progress) {
+        self.progress = Some(progress);
+    }
+
+    /// Adds a filter to the end of the chain. All filters have to be added before the
+    /// first pixel is pushed.
+    pub fn add_filter(&mut self, filter: Box<dyn PixelFilter>) {
+        self.filters.push(filter);
+    }
+
+    pub fn push(&mut self, color: [u8; 3]) -> io::Result<()> {
+        self.push_pixels(&color)
+    }
+
+    /// Filters and encodes packed RGB8 pixels.
+    pub fn push_pixels(&mut self, pixels: &[u8]) -> io::Result<()> {
+        if self.filters.is_empty() && self.progress.is_none() {
+            return self.encoder.push_pixels(pixels);
+        }
+
+        let row_len = self.width as usize * 4;
+        for pixel in pixels.chunks_exact(3) {
+            // The first pixel of the image.
+            if self.row.is_empty() && self.y == 0 {
+                for filter in &mut self.filters {
+                    filter.begin(self.width, self.height);
+                }
+                if let Some(progress) = &self.progress {
+                    progress.start(self.height as u64);
+                }
+            }
+            self.row.extend_from_slice(&[pixel[0], pixel[1], pixel[2], 255]);
+            if self.row.len() == row_len {
+                for filter in &mut self.filters {
+                    filter.filter_row(self.y, &mut self.row);
+                }
+                let rgb = pixels_to_rgb(&self.row, PixelLayout::Rgba, &mut self.rgb);
+                self.encoder.push_pixels(rgb)?;
+                self.row.clear();
+                self.y += 1;
+                if let Some(progress) = &self.progress {
+                    progress.set_done(self.y as u64);
+                    if progress.is_cancelled() {
+                        return Err(io::Error::other(Cancelled));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the pending run and returns the underlying writer.
+    pub fn finish(self) -> io::Result<W> {
+        self.finish_with_filters().map(|(out, _)| out)
+    }
+
+    /// Like [`FilteredEncoder::finish`], also returning the filters, e.g. to encode the next
+    /// frame of an animation with them.
+    pub fn finish_with_filters(mut self) -> io::Result<(W, Vec<Box<dyn PixelFilter>>)> {
+        // An incomplete last row can't be filtered, and the image is short of pixels anyway.
+        let row = std::mem::take(&mut self.row);
+        let rgb = pixels_to_rgb(&row, PixelLayout::Rgba, &mut self.rgb);
+        self.encoder.push_pixels(rgb)?;
+        Ok((self.encoder.finish()?, self.filters))
+    }
+}
+
+/// How far a long encode or decode has got, which another thread can watch and use to
+/// stop it. Clones share their state, so one can go to the work and another be kept.
+///
+/// Work reports in whatever units suit it, such as rows or pixels, so only the
+/// [`fraction`](Progress::fraction) done means anything from outside.
+#[derive(Clone, Debug, Default)]
+pub struct Progress {
+    state: Arc<ProgressState>,
+}
+
+#[derive(Debug, Default)]
+struct ProgressState {
+    done: AtomicU64,
+    total: AtomicU64,
+    cancelled: AtomicBool,
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How much of the work is done, from 0 to 1. It's 0 until the work has started.
+    pub fn fraction(&self) -> f32 {
+        let total = self.state.total.load(atomic::Ordering::Relaxed);
+        let done = self.state.done.load(atomic::Ordering::Relaxed);
+        if total == 0 {
+            0.0
+        } else {
+            (done as f64 / total as f64).min(1.0) as f32
+        }
+    }
+
+    /// Asks the work to stop. It does at its next report, with a [`Cancelled`] error.
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(atomic::Ordering::Relaxed)
+    }
+
+    fn start(&self, total: u64) {
+        self.state.done.store(0, atomic::Ordering::Relaxed);
+        self.state.total.store(total, atomic::Ordering::Relaxed);
+    }
+
+    fn set_done(&self, done: u64) {
+        self.state.done.store(done, atomic::Ordering::Relaxed);
+    }
+}
+
+/// Why work given a [`Progress`] stopped early: it was cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cancelled.")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Color corrections to make while encoding, as a [`PixelFilter`]. Each works like the CSS
+/// filter function of the same name, and they're applied in the order of the fields.
+#[derive(Clone, Copy)]
+pub struct ColorAdjustments {
+    /// Multiplies every channel, so 1 leaves the image alone and 0 makes it black.
+    pub brightness: f32,
+    /// Scales every channel's distance from mid-grey, so 0 makes the image grey.
+    pub contrast: f32,
+    /// Scales how colorful the image is, so 0 leaves only its luminance.
+    pub saturation: f32,
+    pub grayscale: bool,
+}
+
+impl Default for ColorAdjustments {
+    fn default() -> Self {
+        ColorAdjustments {
+            brightness: 1.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            grayscale: false,
+        }
+    }
+}
+
+impl ColorAdjustments {
+    /// Whether the adjustments leave every color as it is, so they can be skipped.
+    pub fn is_identity(&self) -> bool {
+        self.brightness == 1.0 && self.contrast == 1.0 && self.saturation == 1.0 && !self.grayscale
+    }
+}
+
+impl PixelFilter for ColorAdjustments {
+    fn filter_row(&mut self, _y: u32, row: &mut [u8]) {
+        let saturation = if self.grayscale { 0.0 } else { self.saturation };
+        // The saturate() matrix from the CSS Filter Effects spec, with Rec. 709 weights.
+        let s = saturation;
+        let matrix = [
+            [0.213 + 0.787 * s, 0.715 - 0.715 * s, 0.072 - 0.072 * s],
+            [0.213 - 0.213 * s, 0.715 + 0.285 * s, 0.072 - 0.072 * s],
+            [0.213 - 0.213 * s, 0.715 - 0.715 * s, 0.072 + 0.928 * s],
+        ];
+
+        for pixel in row.chunks_exact_mut(4) {
+            let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(|channel| {
+                let value = channel as f32 * self.brightness;
+                (value - 127.5) * self.contrast + 127.5
+            });
+            for (channel, row) in pixel.iter_mut().zip(&matrix) {
+                let value = row[0] * r + row[1] * g + row[2] * b;
+                *channel = value.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+}
+
+/// Number of pixels at the start of `pixels` (packed RGB8) that are `color`.
+///
+/// Whole blocks are compared against a repeated pattern first, which the compiler turns
+/// into wide vector compares, and only the block containing the end of the run is
+/// checked pixel by pixel.
+fn run_prefix(pixels: &[u8], color: [u8; 3]) -> usize {
+    let mut pattern = [0; RUN_SCAN_BLOCK * 3];
+    for pixel in pattern.chunks_exact_mut(3) {
+        pixel.copy_from_slice(&color);
+    }
+
+    let mut count = 0;
+    for block in pixels.chunks_exact(pattern.len()) {
+        if block != pattern {
+            break;
+        }
+        count += RUN_SCAN_BLOCK;
+    }
+
+    count
+        + pixels[count * 3..]
+            .chunks_exact(3)
+            .take_while(|pixel| *pixel == color)
+            .count()
+}
+
+pub fn encode_bruh(width: u32, height: u32, pixels: impl IntoIterator<Item = [u8; 3]>) -> Vec<u8> {
+    let mut encoder =
+        BruhEncoder::new(Vec::new(), width, height).expect("writing to a Vec can't fail");
+    for pixel in pixels {
+        encoder.push(pixel).expect("writing to a Vec can't fail");
+    }
+    encoder.finish().expect("writing to a Vec can't fail")
+}
+
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    /// Opaque RGBA8 pixels, row by row.
+    pub pixels: Vec<u8>,
+}
+
+impl DecodedImage {
+    /// Whether the image has no pixels, which most image formats and viewers can't handle.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+}
+
+/// Why a .bruh file couldn't be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    Empty,
+    MissingHeader,
+    /// The file ends partway through a run.
+    PartialRun { bytes: usize },
+    /// Runs continue after every pixel is covered.
+    ExtraRuns { bytes: usize },
+    TooManyPixels { covered: u64, expected: u64 },
+    Truncated { covered: u64, expected: u64 },
+    /// The image has more than [`MAX_DECODED_PIXELS`], or its pixel buffer can't be
+    /// addressed on this platform.
+    TooLarge { width: u32, height: u32 },
+    /// A region asked of [`decode_bruh_region`] reaches past the edges of the image.
+    OutsideImage { width: u32, height: u32 },
+    /// The [`Progress`] given to [`decode_bruh_with_progress`] was cancelled.
+    Cancelled,
+    /// The file is an animation, whose frames are read with [`animation::Animation`].
+    Animated,
+    /// A frame of an animation isn't a .bruh file of the animation's size.
+    FrameSize { frame: usize, width: u32, height: u32 },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Empty => write!(f, "File is empty."),
+            DecodeError::MissingHeader => write!(f, "File is too small to contain a BRUH header."),
+            DecodeError::PartialRun { bytes } => write!(
+                f,
+                "Unexpected end of file while reading pixel data: the last run is {} of 4 bytes.",
+                bytes
+            ),
+            DecodeError::ExtraRuns { bytes } => write!(
+                f,
+                "Pixel data doesn't fit the image dimensions: {} bytes of runs follow the last pixel.",
+                bytes
+            ),
+            DecodeError::TooManyPixels { covered, expected } => write!(
+                f,
+                "Pixel data doesn't fit the image dimensions: runs cover {} pixels, but the header says {}.",
+                covered, expected
+            ),
+            DecodeError::Truncated { covered, expected } => write!(
+                f,
+                "File is truncated: runs cover {} of the {} pixels in the header.",
+                covered, expected
+            ),
+            DecodeError::TooLarge { width, height } => write!(
+                f,
+                "A {}×{} image is too large to decode into memory.",
+                width, height
+            ),
+            DecodeError::OutsideImage { width, height } => write!(
+                f,
+                "The region reaches past the edges of the {}×{} image.",
+                width, height
+            ),
+            DecodeError::Cancelled => write!(f, "{}", Cancelled),
+            DecodeError::Animated => write!(f, "The file is an animation, not a single image."),
+            DecodeError::FrameSize {
+                frame,
+                width,
+                height,
+            } => write!(
+                f,
+                "Frame {} of the animation is {}×{}, not the size in the header.",
+                frame + 1,
+                width,
+                height
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Length in bytes of a buffer holding `width` × `height` pixels of `bytes_per_pixel`,
+/// or an error if that's more than [`MAX_DECODED_PIXELS`] or doesn't fit in a `usize`.
+pub fn buffer_len(width: u32, height: u32, bytes_per_pixel: usize) -> Result<usize, DecodeError> {
+    let too_large = DecodeError::TooLarge { width, height };
+    if width as u64 * height as u64 > MAX_DECODED_PIXELS {
+        return Err(too_large);
+    }
+    usize::try_from(width)
+        .ok()
+        .and_then(|width| width.checked_mul(usize::try_from(height).ok()?))
+        .and_then(|pixels| pixels.checked_mul(bytes_per_pixel))
+        .ok_or(too_large)
+}
+
+/// Empties `buffer` and fills it with `width` × `height` pixels of RGBA8 zeroes, returning
+/// how many there are. If there's no memory for them the error is
+/// [`DecodeError::TooLarge`], where a plain allocation would abort the process.
+fn zeroed_pixels(buffer: &mut Vec<u8>, width: u32, height: u32) -> Result<usize, DecodeError> {
+    let len = buffer_len(width, height, 4)?;
+    buffer.clear();
+    buffer
+        .try_reserve_exact(len)
+        .map_err(|_| DecodeError::TooLarge { width, height })?;
+    buffer.resize(len, 0);
+    Ok(len / 4)
+}
+
+pub fn decode_bruh(contents: &[u8]) -> Result<DecodedImage, DecodeError> {
+    let mut pixels = Vec::new();
+    let (width, height) = decode_bruh_into(contents, &mut pixels)?;
+
+    Ok(DecodedImage {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Decodes a file whose runs don't match its header as well as possible, returning the
+/// image with what was wrong with it, or `None` if nothing was.
+///
+/// Runs are kept up to the last pixel of the image and anything after that is dropped,
+/// as the `repair` command does. Pixels past the end of a truncated file are left black.
+/// Files without a header are still refused, and so are headers over
+/// [`MAX_DECODED_PIXELS`] or too large for the memory there is, such as another format's
+/// signature read as a width and height. Those are checked before anything is allocated.
+pub fn decode_bruh_lenient(
+    contents: &[u8],
+) -> Result<(DecodedImage, Option<DecodeError>), DecodeError> {
+    let damage = match decode_bruh(contents) {
+        Ok(decoded) => return Ok((decoded, None)),
+        Err(
+            damage @ (DecodeError::PartialRun { .. }
+            | DecodeError::ExtraRuns { .. }
+            | DecodeError::TooManyPixels { .. }
+            | DecodeError::Truncated { .. }),
+        ) => damage,
+        Err(e) => return Err(e),
+    };
+
+    let header = contents[..HEADER_SIZE].try_into().expect("checked by decode_bruh");
+    let (width, height) = decode_header(header);
+    let mut pixels = Vec::new();
+    let pixel_count = zeroed_pixels(&mut pixels, width, height)?;
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel[3] = u8::MAX;
+    }
+    let runs = Runs::of(contents);
+    let mut pos = 0;
+    for run in runs.bytes.chunks_exact(4) {
+        if pos == pixel_count {
+            break;
+        }
+        let remaining = pixel_count - pos;
+        let run_length = (runs.length(run[0], remaining as u64) as usize).min(remaining);
+        for pixel in pixels[pos * 4..(pos + run_length) * 4].chunks_exact_mut(4) {
+            pixel[..3].copy_from_slice(&run[1..]);
+        }
+        pos += run_length;
+    }
+
+    let decoded = DecodedImage {
+        width,
+        height,
+        pixels,
+    };
+    Ok((decoded, Some(damage)))
+}
+
+/// Decodes into `decoded_data` as opaque RGBA8, reusing its allocation, and returns the
+/// image's width and height.
+///
+/// The runs are checked against the header before anything is allocated, so a file
+/// that's truncated or claims a size its runs don't cover is rejected up front.
+pub fn decode_bruh_into(
+    contents: &[u8],
+    decoded_data: &mut Vec<u8>,
+) -> Result<(u32, u32), DecodeError> {
+    decode_runs(contents, decoded_data, None)
+}
+
+/// Like [`decode_bruh_into`], reporting the pixels decoded so far to `progress`. Stops
+/// with [`DecodeError::Cancelled`] soon after `progress` is cancelled.
+pub fn decode_bruh_with_progress(
+    contents: &[u8],
+    decoded_data: &mut Vec<u8>,
+    progress: &Progress,
+) -> Result<(u32, u32), DecodeError> {
+    decode_runs(contents, decoded_data, Some(progress))
+}
+
+fn decode_runs(
+    contents: &[u8],
+    decoded_data: &mut Vec<u8>,
+    progress: Option<&Progress>,
+) -> Result<(u32, u32), DecodeError> {
+    let (width, height) = check_bruh(contents)?;
+    let runs = Runs::of(contents);
+
+    let pixel_count = zeroed_pixels(decoded_data, width, height)?;
+    let mut pos = 0;
+    if let Some(progress) = progress {
+        progress.start(pixel_count as u64);
+    }
+
+    for (index, run) in runs.bytes.chunks_exact(4).enumerate() {
+        let color = [run[1], run[2], run[3], u8::MAX];
+        let run_length = runs.length(run[0], (pixel_count - pos) as u64) as usize;
+
+        for pixel in decoded_data[pos * 4..(pos + run_length) * 4].chunks_exact_mut(4) {
+            pixel.copy_from_slice(&color);
+        }
+        pos += run_length;
+
+        if let Some(progress) = progress.filter(|_| index % RUNS_PER_REPORT == 0) {
+            progress.set_done(pos as u64);
+            if progress.is_cancelled() {
+                return Err(DecodeError::Cancelled);
+            }
+        }
+    }
+    if let Some(progress) = progress {
+        progress.set_done(pixel_count as u64);
+    }
+
+    Ok((width, height))
+}
+
+/// A rectangle of pixels in an image, from its top-left corner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Decodes only `region` of a .bruh file, as opaque RGBA8.
+///
+/// Runs before the region are skipped without being expanded and decoding stops after
+/// its last row, so only the region's pixels are held in memory. The whole file is still
+/// checked like [`decode_bruh`] does.
+pub fn decode_bruh_region(contents: &[u8], region: Region) -> Result<DecodedImage, DecodeError> {
+    let (width, height) = check_bruh(contents)?;
+    let fits =
+        |start: u32, len: u32, size: u32| start.checked_add(len).is_some_and(|end| end <= size);
+    if !fits(region.x, region.width, width) || !fits(region.y, region.height, height) {
+        return Err(DecodeError::OutsideImage { width, height });
+    }
+
+    let mut pixels = Vec::new();
+    zeroed_pixels(&mut pixels, region.width, region.height)?;
+    let image_width = width as u64;
+    let (left, right) = (region.x as u64, region.x as u64 + region.width as u64);
+    let start = region.y as u64 * image_width;
+    let end = (region.y as u64 + region.height as u64) * image_width;
+    let pixel_count = image_width * height as u64;
+
+    let runs = Runs::of(contents);
+    let mut pos = 0;
+    for run in runs.bytes.chunks_exact(4) {
+        if pos >= end {
+            break;
+        }
+        let run_end = pos + runs.length(run[0], pixel_count - pos);
+        let color = [run[1], run[2], run[3], u8::MAX];
+
+        // Copy the part of each row the run covers that falls inside the region.
+        let mut at = pos.max(start);
+        while at < run_end.min(end) {
+            let (row, column) = (at / image_width, at % image_width);
+            let row_end = run_end.min(end).min((row + 1) * image_width);
+            let (from, to) = (column.max(left), (column + row_end - at).min(right));
+            if from < to {
+                let offset = ((row - region.y as u64) * region.width as u64 + from - left) as usize;
+                let len = (to - from) as usize;
+                for pixel in pixels[offset * 4..(offset + len) * 4].chunks_exact_mut(4) {
+                    pixel.copy_from_slice(&color);
+                }
+            }
+            at = row_end;
+        }
+        pos = run_end;
+    }
+
+    Ok(DecodedImage {
+        width: region.width,
+        height: region.height,
+        pixels,
+    })
+}
+
+/// Checks that `contents` is a well-formed .bruh file without decoding it, returning its
+/// width and height.
+///
+/// The format has no signature, so this is also how a .bruh file is recognized: any
+/// other file is very unlikely to have runs that exactly cover the size in its header.
+/// A file whose runs don't, but which starts with [`ANIMATION_MAGIC`], is
+/// [`DecodeError::Animated`].
+pub fn check_bruh(contents: &[u8]) -> Result<(u32, u32), DecodeError> {
+    if contents.is_empty() {
+        return Err(DecodeError::Empty);
+    }
+    if contents.len() < HEADER_SIZE {
+        return Err(DecodeError::MissingHeader);
+    }
+
+    let header = contents[..HEADER_SIZE].try_into().expect("checked to be long enough");
+    let (width, height) = decode_header(header);
+    check_runs(Runs::of(contents), width as u64 * height as u64).map_err(|e| {
+        if contents[HEADER_SIZE..].starts_with(ANIMATION_MAGIC) {
+            DecodeError::Animated
+        } else {
+            e
+        }
+    })?;
+    Ok((width, height))
+}
+
+/// Checks that `runs` are whole and cover exactly `expected` pixels.
+fn check_runs(runs: Runs, expected: u64) -> Result<(), DecodeError> {
+    let bytes = runs.bytes;
+    if !bytes.len().is_multiple_of(4) {
+        return Err(DecodeError::PartialRun {
+            bytes: bytes.len() % 4,
+        });
+    }
+
+    let mut covered = 0u64;
+    for (index, run) in bytes.chunks_exact(4).enumerate() {
+        if covered >= expected {
+            return Err(DecodeError::ExtraRuns {
+                bytes: bytes.len() - index * 4,
+            });
+        }
+        covered += runs.length(run[0], expected - covered);
+    }
+
+    match covered.cmp(&expected) {
+        Ordering::Greater => Err(DecodeError::TooManyPixels { covered, expected }),
+        Ordering::Less => Err(DecodeError::Truncated { covered, expected }),
+        Ordering::Equal => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A header for hand-written files, in the byte order `decode_header` reads.
+    fn header(width: u32, height: u32) -> Vec<u8> {
+        [width.to_ne_bytes(), height.to_ne_bytes()].concat()
+    }
+
+    /// Pixels that change color every few pixels, so runs wrap from row to row.
+    fn stripes(width: u32, height: u32) -> Vec<[u8; 3]> {
+        (0..width * height)
+            .map(|i| [(i / 7 % 256) as u8, (i / 3 % 5) as u8 * 50, 99])
+            .collect()
+    }
+
+    fn opaque(pixels: &[[u8; 3]]) -> Vec<u8> {
+        pixels.iter().flat_map(|&[r, g, b]| [r, g, b, u8::MAX]).collect()
+    }
+
+    fn assert_round_trip(width: u32, height: u32, pixels: &[[u8; 3]]) -> Vec<u8> {
+        let encoded = encode_bruh(width, height, pixels.iter().copied());
+        let decoded = decode_bruh(&encoded).unwrap();
+        assert_eq!((decoded.width, decoded.height), (width, height));
+        assert_eq!(decoded.pixels, opaque(pixels));
+        encoded
+    }
+
+    #[test]
+    fn one_pixel_round_trips_whatever_its_color() {
+        for color in [[0, 0, 0], [255, 255, 255], [12, 34, 56]] {
+            let encoded = assert_round_trip(1, 1, &[color]);
+            assert_eq!(encoded[HEADER_SIZE..], [1, color[0], color[1], color[2]]);
+        }
+    }
+
+    #[test]
+    fn black_first_pixel_starts_its_own_run() {
+        let pixels = [[0, 0, 0], [0, 0, 0], [9, 9, 9]];
+        let encoded = assert_round_trip(3, 1, &pixels);
+        assert_eq!(encoded[HEADER_SIZE..], [2, 0, 0, 0, 1, 9, 9, 9]);
+    }
+
+    #[test]
+    fn runs_split_at_255() {
+        let mut pixels = vec![[255, 0, 0]; 256];
+        pixels.extend([[0, 0, 255]; 256]);
+        let encoded = assert_round_trip(256, 2, &pixels);
+        let runs = Runs::of(&encoded);
+        assert!(runs.fills);
+        assert_eq!(runs.bytes, [255, 255, 0, 0, 1, 255, 0, 0, 0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn run_of_255_covering_the_image_keeps_its_length() {
+        let encoded = assert_round_trip(15, 17, &[[1, 2, 3]; 255]);
+        assert_eq!(encoded[HEADER_SIZE..], [255, 1, 2, 3]);
+    }
+
+    #[test]
+    fn images_with_no_pixels_are_just_the_header() {
+        for (width, height) in [(0, 0), (0, 7), (7, 0)] {
+            let encoded = assert_round_trip(width, height, &[]);
+            assert_eq!(encoded, header(width, height));
+            assert!(decode_bruh(&encoded).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn wrapping_runs_round_trip() {
+        assert_round_trip(37, 23, &stripes(37, 23));
+    }
+
+    #[test]
+    fn fill_run_covers_the_rest_of_a_marked_file() {
+        let contents = [header(4, 4), FILL_RUNS_MAGIC.to_vec(), vec![2, 9, 9, 9, 0, 1, 2, 3]];
+        let decoded = decode_bruh(&contents.concat()).unwrap();
+        let mut expected = opaque(&[[9, 9, 9]; 2]);
+        expected.extend(opaque(&[[1, 2, 3]; 14]));
+        assert_eq!(decoded.pixels, expected);
+    }
+
+    #[test]
+    fn zero_length_run_covers_nothing_without_the_marker() {
+        let contents = [header(4, 4), vec![0, 1, 2, 3]].concat();
+        let error = DecodeError::Truncated {
+            covered: 0,
+            expected: 16,
+        };
+        assert_eq!(decode_bruh(&contents).err(), Some(error));
+    }
+
+    #[test]
+    fn signature_chunk_is_skipped() {
+        let pixels = stripes(37, 23);
+        let encoded = encode_bruh(37, 23, pixels.iter().copied());
+        let signature = [7; SIGNATURE_LEN];
+        let (header, rest) = encoded.split_at(HEADER_SIZE);
+        let signed = [header, SIGNATURE_MAGIC, &signature, rest].concat();
+
+        let runs = Runs::of(&signed);
+        assert_eq!(runs.signature, Some(&signature));
+        assert_eq!(runs.bytes, Runs::of(&encoded).bytes);
+        assert_eq!(decode_bruh(&signed).unwrap().pixels, opaque(&pixels));
+    }
+
+    #[test]
+    fn data_chunk_is_skipped() {
+        let pixels = stripes(37, 23);
+        let encoded = encode_bruh(37, 23, pixels.iter().copied());
+        let data = b"anything at all";
+        let (header, rest) = encoded.split_at(HEADER_SIZE);
+        let len = (data.len() as u32).to_ne_bytes();
+        let with_data = [header, DATA_MAGIC, &len, data, rest].concat();
+
+        let runs = Runs::of(&with_data);
+        assert_eq!(runs.data, Some(&data[..]));
+        assert_eq!(runs.bytes, Runs::of(&encoded).bytes);
+        assert_eq!(decode_bruh(&with_data).unwrap().pixels, opaque(&pixels));
+    }
+
+    #[test]
+    fn version_1_runs_that_read_as_markers_stay_runs() {
+        for magic in [FILL_RUNS_MAGIC, SIGNATURE_MAGIC, DATA_MAGIC] {
+            // Runs of one black pixel, which read as a chunk's length or contents too.
+            let first = magic[0] as usize;
+            let mut contents = header(16, 16);
+            contents.extend_from_slice(magic);
+            contents.extend([1, 0, 0, 0].repeat(256 - first));
+
+            let runs = Runs::of(&contents);
+            assert_eq!((runs.offset, runs.fills), (HEADER_SIZE, false));
+            assert_eq!((runs.signature, runs.data), (None, None));
+            let decoded = decode_bruh(&contents).unwrap();
+            let mut expected = opaque(&vec![[magic[1], magic[2], magic[3]]; first]);
+            expected.extend(opaque(&vec![[0, 0, 0]; 256 - first]));
+            assert_eq!(decoded.pixels, expected);
+        }
+
+        // A file that's damaged either way is still read with its marker.
+        let contents = [header(16, 16), FILL_RUNS_MAGIC.to_vec(), vec![9, 1, 2, 3]].concat();
+        assert!(Runs::of(&contents).fills);
+    }
+
+    #[test]
+    fn region_matches_the_full_decode() {
+        let (width, height) = (37, 23);
+        let encoded = encode_bruh(width, height, stripes(width, height));
+        let full = decode_bruh(&encoded).unwrap();
+        let regions = [
+            (0, 0, width, height),
+            (0, 0, 1, 1),
+            (36, 22, 1, 1),
+            (5, 3, 10, 4),
+            (0, 10, width, 2),
+            (20, 0, 17, 23),
+            (4, 4, 0, 0),
+        ];
+        for (x, y, region_width, region_height) in regions {
+            let region = Region {
+                x,
+                y,
+                width: region_width,
+                height: region_height,
+            };
+            let decoded = decode_bruh_region(&encoded, region).unwrap();
+            let expected: Vec<u8> = (y..y + region_height)
+                .flat_map(|row| {
+                    let start = (row * width + x) as usize * 4;
+                    full.pixels[start..start + region_width as usize * 4].to_vec()
+                })
+                .collect();
+            assert_eq!(decoded.pixels, expected, "region {:?}", region);
+        }
+    }
+
+    #[test]
+    fn region_past_the_edge_is_refused() {
+        let encoded = encode_bruh(4, 4, [[0, 0, 0]; 16]);
+        let region = Region {
+            x: 2,
+            y: 0,
+            width: 3,
+            height: 1,
+        };
+        let error = DecodeError::OutsideImage {
+            width: 4,
+            height: 4,
+        };
+        assert_eq!(decode_bruh_region(&encoded, region).err(), Some(error));
+    }
+
+    #[test]
+    fn truncated_file_is_an_error_and_decodes_leniently_as_damaged() {
+        let pixels = stripes(37, 23);
+        let encoded = encode_bruh(37, 23, pixels.iter().copied());
+        for cut in [1, 4, 6, encoded.len() - HEADER_SIZE - FILL_RUNS_MAGIC.len()] {
+            let truncated = &encoded[..encoded.len() - cut];
+            let error = decode_bruh(truncated).err().expect("truncated files are errors");
+            let (decoded, damage) = decode_bruh_lenient(truncated).unwrap();
+            assert_eq!(damage, Some(error));
+            assert_eq!(decoded.pixels.len(), pixels.len() * 4);
+        }
+    }
+
+    #[test]
+    fn short_files_are_errors_even_leniently() {
+        for (contents, error) in [
+            (&[][..], DecodeError::Empty),
+            (&[4, 0, 0, 0, 4][..], DecodeError::MissingHeader),
+        ] {
+            assert_eq!(decode_bruh(contents).err(), Some(error.clone()));
+            assert_eq!(decode_bruh_lenient(contents).err(), Some(error));
+        }
+    }
+
+    #[test]
+    fn other_formats_are_errors_even_leniently() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x10\0\0\0\x10\x08\x02\0\0\0";
+        assert!(decode_bruh(png).is_err());
+        assert!(matches!(
+            decode_bruh_lenient(png),
+            Err(DecodeError::TooLarge { .. })
+        ));
+    }
+
+    /// Converts `image` the way `bruh compile` does for formats other than PNG.
+    fn compile_rgb(image: image::DynamicImage) -> Vec<u8> {
+        let mut rgb = Vec::new();
+        pixels_to_rgb(image.into_rgba8().as_raw(), PixelLayout::Rgba, &mut rgb).to_vec()
+    }
+
+    #[test]
+    fn every_image_color_type_round_trips() {
+        use image::{ColorType, DynamicImage, RgbaImage};
+
+        let colors = [[0, 0, 0, 255], [255, 255, 255, 255], [12, 34, 56, 255], [200, 100, 50, 255]];
+        let greys = [[0, 0, 0, 255], [255, 255, 255, 255], [77, 77, 77, 255], [128, 128, 128, 255]];
+        let see_through = [[80, 80, 80, 0], [200, 200, 200, 128]];
+        // Each type gets the pixels it can hold exactly: grey types get grey ones, and
+        // types without alpha opaque ones.
+        type Convert = fn(&DynamicImage) -> DynamicImage;
+        let cases: [(ColorType, Convert); 10] = [
+            (ColorType::L8, |image| image.to_luma8().into()),
+            (ColorType::La8, |image| image.to_luma_alpha8().into()),
+            (ColorType::Rgb8, |image| image.to_rgb8().into()),
+            (ColorType::Rgba8, |image| image.to_rgba8().into()),
+            (ColorType::L16, |image| image.to_luma16().into()),
+            (ColorType::La16, |image| image.to_luma_alpha16().into()),
+            (ColorType::Rgb16, |image| image.to_rgb16().into()),
+            (ColorType::Rgba16, |image| image.to_rgba16().into()),
+            (ColorType::Rgb32F, |image| image.to_rgb32f().into()),
+            (ColorType::Rgba32F, |image| image.to_rgba32f().into()),
+        ];
+
+        for (color_type, convert) in cases {
+            let mut source = match color_type.has_color() {
+                true => colors.to_vec(),
+                false => greys.to_vec(),
+            };
+            if color_type.has_alpha() {
+                source.extend(see_through);
+            }
+            let width = source.len() as u32;
+            let rgba = RgbaImage::from_raw(width, 1, source.concat()).unwrap();
+            let image = convert(&DynamicImage::ImageRgba8(rgba));
+            assert_eq!(image.color(), color_type);
+
+            let layout = match color_type {
+                ColorType::L8 => Some(PixelLayout::Gray),
+                ColorType::La8 => Some(PixelLayout::GrayAlpha),
+                ColorType::Rgb8 => Some(PixelLayout::Rgb),
+                ColorType::Rgba8 => Some(PixelLayout::Rgba),
+                _ => None,
+            };
+            let rgb = match layout {
+                // 8-bit PNGs are converted row by row without `image`, which has to agree.
+                Some(layout) => {
+                    let mut buffer = Vec::new();
+                    let rgb = pixels_to_rgb(image.as_bytes(), layout, &mut buffer).to_vec();
+                    assert_eq!(rgb, compile_rgb(image), "{:?}", color_type);
+                    rgb
+                }
+                None => compile_rgb(image),
+            };
+
+            let encoded = encode_bruh(width, 1, rgb.chunks_exact(3).map(|p| [p[0], p[1], p[2]]));
+            let expected: Vec<[u8; 3]> = source.into_iter().map(flatten_alpha).collect();
+            let decoded = decode_bruh(&encoded).unwrap();
+            assert_eq!(decoded.pixels, opaque(&expected), "{:?}", color_type);
+        }
+    }
+
+    #[test]
+    fn garbage_is_an_error() {
+        let garbage: Vec<u8> = (0..1000u32).map(|i| (i * 7919 % 251) as u8).collect();
+        assert!(decode_bruh(&garbage).is_err());
+        assert!(check_bruh(&garbage).is_err());
+        assert!(decode_bruh_lenient(&garbage).is_err());
+    }
+
+    fn animation(frames: &[(&[u8], u32)]) -> Vec<u8> {
+        let mut writer = animation::AnimationWriter::new(Vec::new(), 4, 3).unwrap();
+        for &(frame, delay_ms) in frames {
+            writer.push_frame(frame, delay_ms).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn animation_frames_read_back_with_their_delays() {
+        let first = encode_bruh(4, 3, [[1, 2, 3]; 12]);
+        let second = encode_bruh(4, 3, stripes(4, 3));
+        let contents = animation(&[(&first, 100), (&second, 40)]);
+
+        let read = animation::Animation::of(&contents).unwrap().unwrap();
+        assert_eq!((read.width, read.height, read.partial), (4, 3, 0));
+        let frames: Vec<_> = read.frames.iter().map(|f| (f.contents, f.delay_ms)).collect();
+        assert_eq!(frames, [(&first[..], 100), (&second[..], 40)]);
+        assert_eq!(decode_bruh(read.frames[1].contents).unwrap().pixels, opaque(&stripes(4, 3)));
+    }
+
+    #[test]
+    fn animations_are_not_still_images() {
+        let frame = encode_bruh(4, 3, stripes(4, 3));
+        let contents = animation(&[(&frame, 100)]);
+        assert_eq!(check_bruh(&contents), Err(DecodeError::Animated));
+        assert_eq!(decode_bruh_lenient(&contents).err(), Some(DecodeError::Animated));
+        assert!(animation::Animation::of(&frame).unwrap().is_none());
+    }
+
+    #[test]
+    fn interrupted_last_frame_is_dropped() {
+        let frame = encode_bruh(4, 3, stripes(4, 3));
+        let contents = animation(&[(&frame, 100), (&frame, 100)]);
+        let cut = contents.len() - 5;
+
+        let read = animation::Animation::of(&contents[..cut]).unwrap().unwrap();
+        assert_eq!(read.frames.len(), 1);
+        assert_eq!(read.partial, animation::FRAME_HEADER_SIZE + frame.len() - 5);
+    }
+
+    #[test]
+    fn frames_of_another_size_are_refused() {
+        let small = encode_bruh(2, 2, [[0, 0, 0]; 4]);
+        let mut writer = animation::AnimationWriter::new(Vec::new(), 4, 3).unwrap();
+        assert!(writer.push_frame(&small, 100).is_err());
+
+        let mut contents = animation(&[]);
+        contents.extend_from_slice(&100u32.to_ne_bytes());
+        contents.extend_from_slice(&(small.len() as u64).to_ne_bytes());
+        contents.extend_from_slice(&small);
+        let error = DecodeError::FrameSize {
+            frame: 0,
+            width: 2,
+            height: 2,
+        };
+        assert_eq!(animation::Animation::of(&contents).err(), Some(error));
+    }
+}
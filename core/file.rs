@@ -0,0 +1,194 @@
+//! Reading and writing the files the bruh tools work on, shared by the command line and
+//! the viewer.
+
+use std::{
+    env,
+    error::Error,
+    ffi::OsString,
+    fmt,
+    fs::{self, File},
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Why an input file couldn't be read, worded for whoever named the file.
+#[derive(Debug)]
+pub enum InputError {
+    NotFound(PathBuf),
+    IsDirectory(PathBuf),
+    /// The file is there but isn't an image this program understands.
+    Unsupported {
+        path: PathBuf,
+        reason: String,
+    },
+    /// Too big to load into memory on this platform, e.g. over 4 GiB on a 32-bit target.
+    TooLarge {
+        path: PathBuf,
+        size: u64,
+    },
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::NotFound(path) => write!(f, "{} doesn't exist.", path.display()),
+            InputError::IsDirectory(path) => {
+                write!(f, "{} is a folder, not an image.", path.display())
+            }
+            InputError::Unsupported { path, reason } => {
+                write!(f, "{} isn't a supported image: {}", path.display(), reason)
+            }
+            InputError::TooLarge { path, size } => write!(
+                f,
+                "{} is {} bytes, too large to load on this platform.",
+                path.display(),
+                size
+            ),
+            InputError::Io { path, error } => {
+                write!(f, "Couldn't read {}: {}", path.display(), error)
+            }
+        }
+    }
+}
+
+impl Error for InputError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InputError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Opens `path` for reading, telling a missing file and a folder apart from other failures.
+pub fn open_input(path: &Path) -> Result<File, InputError> {
+    let io_error = |error: io::Error| match error.kind() {
+        io::ErrorKind::NotFound => InputError::NotFound(path.to_path_buf()),
+        _ => InputError::Io {
+            path: path.to_path_buf(),
+            error,
+        },
+    };
+
+    if fs::metadata(path).map_err(io_error)?.is_dir() {
+        return Err(InputError::IsDirectory(path.to_path_buf()));
+    }
+    File::open(path).map_err(io_error)
+}
+
+/// Reads all of `path` into `contents`, replacing what was there.
+///
+/// The size is checked before reading, so a file that can't fit in memory is reported
+/// instead of running out of address space partway through.
+pub fn read_input(path: &Path, contents: &mut Vec<u8>) -> Result<(), InputError> {
+    let mut file = open_input(path)?;
+    let io_error = |error| InputError::Io {
+        path: path.to_path_buf(),
+        error,
+    };
+
+    let size = file.metadata().map_err(io_error)?.len();
+    let capacity = usize::try_from(size)
+        .ok()
+        .filter(|&size| isize::try_from(size).is_ok())
+        .ok_or_else(|| InputError::TooLarge {
+            path: path.to_path_buf(),
+            size,
+        })?;
+
+    contents.clear();
+    contents.reserve(capacity);
+    file.read_to_end(contents).map_err(io_error)?;
+    Ok(())
+}
+
+/// An output file that's written under a temporary name next to its destination and
+/// only renamed into place by [`AtomicFile::commit`], so a conversion that fails or is
+/// interrupted never leaves half a file behind.
+pub struct AtomicFile {
+    /// `None` once committed.
+    file: Option<BufWriter<File>>,
+    temp_path: PathBuf,
+    path: PathBuf,
+}
+
+impl AtomicFile {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let temp_path = temp_path(path);
+        Ok(Self {
+            file: Some(BufWriter::new(File::create(&temp_path)?)),
+            temp_path,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Makes sure everything is on disk and moves the file to its destination.
+    pub fn commit(mut self) -> io::Result<()> {
+        let file = self.file.take().expect("only taken by commit and drop");
+        let result = file
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)
+            .and_then(|file| file.sync_all())
+            .and_then(|()| fs::rename(&self.temp_path, &self.path));
+
+        if result.is_err() {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+        result
+    }
+
+    fn writer(&mut self) -> &mut BufWriter<File> {
+        self.file.as_mut().expect("only taken by commit and drop")
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer().flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if let Some(file) = self.file.take() {
+            // Closed first, as Windows won't remove a file that's still open.
+            drop(file);
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+/// A hidden file in the same directory as `path`, so renaming it stays on one file system.
+///
+/// Its name is unique to this process and this call, so two writes to the same file at
+/// once, from different threads or programs, don't share a temporary file.
+fn temp_path(path: &Path) -> PathBuf {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let count = NEXT.fetch_add(1, Ordering::Relaxed);
+
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or(path.as_os_str()));
+    name.push(format!(".{}.{}.tmp", process::id(), count));
+    path.with_file_name(name)
+}
+
+/// The folder for per-user settings, if this system has one.
+pub fn config_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    }
+}
@@ -0,0 +1,101 @@
+//! Reducing images to a few colors, shared by the command line and the viewer.
+
+use std::collections::HashMap;
+
+/// A color of a palette and the share of the image's pixels it stands for, from 0 to 1.
+///
+/// That's the pixels in the color's median-cut box, whose average the color is. A few of
+/// them may be closer to another box's color, so it's close to, but not always exactly,
+/// the share [`closest`] would map to it.
+pub struct PaletteColor {
+    pub color: [u8; 3],
+    pub share: f64,
+}
+
+/// Finds the `colors` most representative colors of packed RGB8 pixels by median cut,
+/// most common first. An image with fewer distinct colors gets all of them. There has to be
+/// at least one pixel.
+pub fn median_cut(rgb: &[u8], colors: usize) -> Vec<PaletteColor> {
+    let mut histogram: HashMap<[u8; 3], u64> = HashMap::new();
+    for pixel in rgb.chunks_exact(3) {
+        *histogram.entry([pixel[0], pixel[1], pixel[2]]).or_default() += 1;
+    }
+    let mut boxes = vec![histogram.into_iter().collect::<Vec<_>>()];
+
+    while boxes.len() < colors {
+        // Split the box spanning the widest range of any channel, along that channel.
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(i, colors)| (i, widest_channel(colors)))
+            .max_by_key(|&(_, (_, range))| range);
+        let Some((i, (channel, _))) = widest else {
+            break;
+        };
+
+        let mut colors = boxes.swap_remove(i);
+        colors.sort_unstable_by_key(|(color, _)| color[channel]);
+        // Cut where half the pixels are on each side, keeping at least one color in each.
+        let total: u64 = colors.iter().map(|(_, count)| count).sum();
+        let mut below = 0;
+        let median = colors
+            .iter()
+            .position(|(_, count)| {
+                below += count;
+                below * 2 >= total
+            })
+            .unwrap_or(0);
+        let upper = colors.split_off((median + 1).min(colors.len() - 1));
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    let total = (rgb.len() / 3) as f64;
+    let mut palette: Vec<PaletteColor> = boxes
+        .iter()
+        .map(|colors| {
+            let count: u64 = colors.iter().map(|(_, count)| count).sum();
+            let mut sums = [0u64; 3];
+            for (color, n) in colors {
+                for (sum, &channel) in sums.iter_mut().zip(color) {
+                    *sum += channel as u64 * n;
+                }
+            }
+            PaletteColor {
+                color: sums.map(|sum| ((sum + count / 2) / count) as u8),
+                share: count as f64 / total,
+            }
+        })
+        .collect();
+    palette.sort_by(|a, b| b.share.total_cmp(&a.share));
+    palette
+}
+
+/// The entry of `palette` nearest to `color`, by squared distance in RGB. The palette
+/// can't be empty.
+pub fn closest(palette: &[[u8; 3]], color: [u8; 3]) -> [u8; 3] {
+    let distance = |entry: &[u8; 3]| -> u32 {
+        entry
+            .iter()
+            .zip(&color)
+            .map(|(&a, &b)| (a as i32 - b as i32).pow(2) as u32)
+            .sum()
+    };
+    *palette
+        .iter()
+        .min_by_key(|entry| distance(entry))
+        .expect("a palette of a non-empty image has colors")
+}
+
+/// The channel whose values in `colors` are furthest apart, and how far.
+fn widest_channel(colors: &[([u8; 3], u64)]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let values = colors.iter().map(|(color, _)| color[channel]);
+            let range = values.clone().max().unwrap_or(0) - values.min().unwrap_or(0);
+            (channel, range)
+        })
+        .max_by_key(|&(_, range)| range)
+        .expect("three channels")
+}
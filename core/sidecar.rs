@@ -0,0 +1,127 @@
+//! Per-image encode options from `.bruhopts` files, shared by `compile` and the viewer's
+//! batch conversion. Needs the `sidecar` feature.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use image::{
+    imageops::{self, FilterType},
+    RgbImage,
+};
+use serde::Deserialize;
+
+use crate::palette::{closest, median_cut};
+
+/// Extension of the file next to an image that holds its encode options, added to the
+/// image's full name: `hero.png` is tuned by `hero.png.bruhopts`.
+const EXTENSION: &str = "bruhopts";
+
+/// How one image is encoded, read from the TOML sidecar beside it so artists can tune
+/// individual assets of a batch:
+///
+/// ```toml
+/// width = 64     # resize, keeping the aspect ratio unless height is given too
+/// colors = 16    # reduce to a palette of at most this many colors
+/// lossy = 8      # merge pixels into the run before them when no channel is further off
+/// ```
+///
+/// Every key is optional. They're applied in that order, before the batch's other
+/// adjustments.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncodeOptions {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub colors: Option<usize>,
+    pub lossy: Option<u8>,
+}
+
+impl EncodeOptions {
+    /// The options in the sidecar of `image`, or `None` if it has none.
+    pub fn load(image: &Path) -> Result<Option<Self>, String> {
+        let path = sidecar_path(image);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Couldn't read {}: {}", path.display(), e)),
+        };
+        let options: Self =
+            toml::from_str(&text).map_err(|e| format!("{} is invalid: {}", path.display(), e))?;
+        match options {
+            Self { width: Some(0), .. } | Self { height: Some(0), .. } => {
+                Err(format!("{} resizes to nothing.", path.display()))
+            }
+            Self {
+                colors: Some(colors),
+                ..
+            } if !(1..=256).contains(&colors) => Err(format!(
+                "{} asks for {} colors, expected 1 to 256.",
+                path.display(),
+                colors
+            )),
+            _ => Ok(Some(options)),
+        }
+    }
+
+    /// Applies the options to an image of packed RGB8 pixels, returning its new size.
+    pub fn apply(&self, width: u32, height: u32, rgb: &mut Vec<u8>) -> (u32, u32) {
+        let (width, height) = self.resize(width, height, rgb);
+        if rgb.is_empty() {
+            return (width, height);
+        }
+
+        if let Some(colors) = self.colors {
+            let palette: Vec<[u8; 3]> = median_cut(rgb, colors)
+                .into_iter()
+                .map(|entry| entry.color)
+                .collect();
+            for pixel in rgb.chunks_exact_mut(3) {
+                let color = closest(&palette, [pixel[0], pixel[1], pixel[2]]);
+                pixel.copy_from_slice(&color);
+            }
+        }
+
+        if let Some(tolerance) = self.lossy.filter(|&tolerance| tolerance > 0) {
+            // Runs carry on across rows, so the merging does too.
+            let mut run = [rgb[0], rgb[1], rgb[2]];
+            for pixel in rgb.chunks_exact_mut(3) {
+                if run.iter().zip(&*pixel).all(|(&a, &b)| a.abs_diff(b) <= tolerance) {
+                    pixel.copy_from_slice(&run);
+                } else {
+                    run = [pixel[0], pixel[1], pixel[2]];
+                }
+            }
+        }
+        (width, height)
+    }
+
+    fn resize(&self, width: u32, height: u32, rgb: &mut Vec<u8>) -> (u32, u32) {
+        let scaled = |by: u32, of: u32, to: u32| {
+            ((to as u64 * by as u64 + of as u64 / 2) / of.max(1) as u64).max(1) as u32
+        };
+        let (new_width, new_height) = match (self.width, self.height) {
+            (None, None) => return (width, height),
+            (Some(new_width), Some(new_height)) => (new_width, new_height),
+            (Some(new_width), None) => (new_width, scaled(height, width, new_width)),
+            (None, Some(new_height)) => (scaled(width, height, new_height), new_height),
+        };
+        if (new_width, new_height) == (width, height) || rgb.is_empty() {
+            return (width, height);
+        }
+
+        let image = RgbImage::from_raw(width, height, std::mem::take(rgb))
+            .expect("the pixels fill the image");
+        let resized = imageops::resize(&image, new_width, new_height, FilterType::Lanczos3);
+        *rgb = resized.into_raw();
+        (new_width, new_height)
+    }
+}
+
+fn sidecar_path(image: &Path) -> PathBuf {
+    let mut name = image.as_os_str().to_owned();
+    name.push(".");
+    name.push(EXTENSION);
+    PathBuf::from(name)
+}
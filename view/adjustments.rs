@@ -0,0 +1,84 @@
+use eframe::egui;
+use image::RgbaImage;
+
+use super::{color_vision::ColorVision, locale::Localizer};
+
+/// Non-destructive tone adjustments and color vision simulation, applied to the
+/// image only as it's displayed.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Adjustments {
+    /// Added to every channel, from -1 (black) to 1 (white).
+    pub brightness: f32,
+    /// Scales the distance from mid-grey.
+    pub contrast: f32,
+    pub gamma: f32,
+    pub color_vision: ColorVision,
+}
+
+impl Default for Adjustments {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            gamma: 1.0,
+            color_vision: ColorVision::Normal,
+        }
+    }
+}
+
+impl Adjustments {
+    /// Whether brightness, contrast and gamma are at their defaults.
+    fn is_neutral_tone(&self) -> bool {
+        self.brightness == 0.0 && self.contrast == 1.0 && self.gamma == 1.0
+    }
+
+    /// Applies the adjustments to the color channels of `image`, leaving alpha alone.
+    pub fn apply(&self, image: &mut RgbaImage) {
+        if !self.is_neutral_tone() {
+            let lut = self.lut();
+            for pixel in image.pixels_mut() {
+                for channel in &mut pixel.0[..3] {
+                    *channel = lut[*channel as usize];
+                }
+            }
+        }
+        self.color_vision.apply(image);
+    }
+
+    fn lut(&self) -> [u8; 256] {
+        let mut lut = [0; 256];
+        for (value, entry) in lut.iter_mut().enumerate() {
+            let value = (value as f32 / 255.0).powf(1.0 / self.gamma);
+            let value = (value - 0.5) * self.contrast + 0.5 + self.brightness;
+            *entry = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        lut
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, locale: &Localizer) {
+        egui::Grid::new("adjustments").num_columns(2).show(ui, |ui| {
+            ui.label(locale.text("brightness"));
+            ui.add(egui::Slider::new(&mut self.brightness, -1.0..=1.0));
+            ui.end_row();
+
+            ui.label(locale.text("contrast"));
+            ui.add(egui::Slider::new(&mut self.contrast, 0.0..=4.0));
+            ui.end_row();
+
+            ui.label(locale.text("gamma"));
+            ui.add(egui::Slider::new(&mut self.gamma, 0.1..=5.0).logarithmic(true));
+            ui.end_row();
+        });
+
+        ui.separator();
+        if ui
+            .add_enabled(!self.is_neutral_tone(), egui::Button::new(locale.text("reset")))
+            .clicked()
+        {
+            *self = Self {
+                color_vision: self.color_vision,
+                ..Self::default()
+            };
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use ab_glyph::{Font, FontRef, GlyphId, ScaleFont};
+use eframe::egui::{self, emath::Rot2};
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    f32::consts::TAU,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Default stroke width as a fraction of the image's longer side.
+const STROKE_SCALE: f32 = 1.0 / 400.0;
+/// Text height relative to the stroke width.
+const TEXT_SCALE: f32 = 8.0;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Shape {
+    Rectangle { from: [f32; 2], to: [f32; 2] },
+    Arrow { from: [f32; 2], to: [f32; 2] },
+    Text { at: [f32; 2], text: String },
+}
+
+/// A shape drawn on top of the image, in image pixel coordinates.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    pub shape: Shape,
+    pub color: [u8; 4],
+    /// Stroke width, or the height of text, in image pixels.
+    pub size: f32,
+}
+
+impl Annotation {
+    /// Line segments making up a rectangle or arrow, empty for text.
+    fn segments(&self) -> Vec<[egui::Pos2; 2]> {
+        match self.shape {
+            Shape::Rectangle { from, to } => {
+                let rect = egui::Rect::from_two_pos(from.into(), to.into());
+                let [a, b, c, d] = [
+                    rect.left_top(),
+                    rect.right_top(),
+                    rect.right_bottom(),
+                    rect.left_bottom(),
+                ];
+                vec![[a, b], [b, c], [c, d], [d, a]]
+            }
+            Shape::Arrow { from, to } => {
+                let (from, to) = (egui::Pos2::from(from), egui::Pos2::from(to));
+                let direction = (to - from).normalized();
+                let tip_length = ((to - from).length() / 4.0).min(self.size * 6.0);
+                let rotation = Rot2::from_angle(TAU / 12.0);
+                vec![
+                    [from, to],
+                    [to, to - tip_length * (rotation * direction)],
+                    [to, to - tip_length * (rotation.inverse() * direction)],
+                ]
+            }
+            Shape::Text { .. } => Vec::new(),
+        }
+    }
+
+    fn color32(&self) -> egui::Color32 {
+        let [r, g, b, a] = self.color;
+        egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+    }
+}
+
+/// Markup for one image, kept in a JSON sidecar next to it so the source file
+/// is never modified.
+#[derive(Default)]
+pub struct Annotations {
+    pub items: Vec<Annotation>,
+    /// The rectangle or arrow currently being dragged out.
+    draft: Option<Annotation>,
+}
+
+impl Annotations {
+    /// Loads the sidecar of `image`, if there is one.
+    pub fn load(image: &Path) -> Self {
+        let items = fs::read_to_string(sidecar_path(image))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        Self { items, draft: None }
+    }
+
+    /// Writes the sidecar of `image`, removing it once there's nothing left in it.
+    pub fn save(&self, image: &Path) -> io::Result<()> {
+        let path = sidecar_path(image);
+        if self.items.is_empty() {
+            return match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+
+        fs::write(path, serde_json::to_string_pretty(&self.items)?)
+    }
+
+    pub fn start_drag(&mut self, shape: Shape, color: egui::Color32, image_size: egui::Vec2) {
+        self.draft = Some(Annotation {
+            shape,
+            color: color.to_srgba_unmultiplied(),
+            size: default_stroke(image_size),
+        });
+    }
+
+    pub fn drag_to(&mut self, point: [f32; 2]) {
+        if let Some(Annotation {
+            shape: Shape::Rectangle { to, .. } | Shape::Arrow { to, .. },
+            ..
+        }) = &mut self.draft
+        {
+            *to = point;
+        }
+    }
+
+    /// Keeps the shape being dragged, unless it was only a click. Returns whether one was added.
+    pub fn finish_drag(&mut self) -> bool {
+        let Some(draft) = self.draft.take() else {
+            return false;
+        };
+        if let Shape::Rectangle { from, to } | Shape::Arrow { from, to } = draft.shape {
+            if egui::Pos2::from(from).distance(to.into()) < draft.size {
+                return false;
+            }
+        }
+
+        self.items.push(draft);
+        true
+    }
+
+    pub fn add_text(
+        &mut self,
+        at: [f32; 2],
+        text: String,
+        color: egui::Color32,
+        image_size: egui::Vec2,
+    ) {
+        self.items.push(Annotation {
+            shape: Shape::Text { at, text },
+            color: color.to_srgba_unmultiplied(),
+            size: default_stroke(image_size) * TEXT_SCALE,
+        });
+    }
+
+    /// Draws the annotations over an image drawn into `image_rect` at `zoom`.
+    pub fn paint(&self, painter: &egui::Painter, image_rect: egui::Rect, zoom: f32) {
+        let to_screen = |point: egui::Pos2| image_rect.min + point.to_vec2() * zoom;
+
+        for annotation in self.items.iter().chain(&self.draft) {
+            let color = annotation.color32();
+            let stroke = egui::Stroke::new((annotation.size * zoom).max(1.0), color);
+            for [from, to] in annotation.segments() {
+                painter.line_segment([to_screen(from), to_screen(to)], stroke);
+            }
+
+            if let Shape::Text { at, text } = &annotation.shape {
+                painter.text(
+                    to_screen(at.into()),
+                    egui::Align2::LEFT_TOP,
+                    text,
+                    egui::FontId::proportional(annotation.size * zoom),
+                    color,
+                );
+            }
+        }
+    }
+
+    /// A copy of `image` with the annotations drawn into it at full resolution.
+    ///
+    /// Text is rasterized from egui's own fonts, so it matches what the viewer shows.
+    pub fn render(&self, ctx: &egui::Context, image: &RgbaImage) -> RgbaImage {
+        let mut image = image.clone();
+        for annotation in &self.items {
+            for segment in annotation.segments() {
+                draw_line(&mut image, segment, annotation.size, annotation.color);
+            }
+
+            if let Shape::Text { at, text } = &annotation.shape {
+                draw_text(ctx, &mut image, at.into(), text, annotation);
+            }
+        }
+        image
+    }
+}
+
+fn sidecar_path(image: &Path) -> PathBuf {
+    let mut name = image.as_os_str().to_owned();
+    name.push(".annotations.json");
+    PathBuf::from(name)
+}
+
+fn default_stroke(image_size: egui::Vec2) -> f32 {
+    (image_size.max_elem() * STROKE_SCALE).max(1.0)
+}
+
+/// Draws an anti-aliased line with round caps.
+fn draw_line(image: &mut RgbaImage, [from, to]: [egui::Pos2; 2], width: f32, color: [u8; 4]) {
+    let radius = width / 2.0;
+    let bounds = egui::Rect::from_two_pos(from, to).expand(radius + 1.0);
+    let along = to - from;
+
+    for_each_pixel_in(image, bounds, |pixel, center| {
+        let t = (center - from).dot(along) / along.length_sq().max(f32::EPSILON);
+        let distance = center.distance(from + along * t.clamp(0.0, 1.0));
+        blend(pixel, color, (radius + 0.5 - distance).clamp(0.0, 1.0));
+    });
+}
+
+fn draw_text(
+    ctx: &egui::Context,
+    image: &mut RgbaImage,
+    at: egui::Pos2,
+    text: &str,
+    annotation: &Annotation,
+) {
+    // The same fonts egui lays the text out with, rasterized straight into the image since
+    // the atlas only holds glyphs at the size they're shown on screen.
+    let fonts: Vec<_> = ctx.fonts(|fonts| {
+        let fonts = fonts.lock();
+        let definitions = fonts.fonts.definitions();
+        definitions
+            .families
+            .get(&egui::FontFamily::Proportional)
+            .into_iter()
+            .flatten()
+            .filter_map(|name| definitions.font_data.get(name).cloned())
+            .collect()
+    });
+    let fonts: Vec<_> = fonts
+        .iter()
+        .filter_map(|data| {
+            let font = FontRef::try_from_slice_and_index(&data.font, data.index).ok()?;
+            Some(font.into_scaled(annotation.size * data.tweak.scale))
+        })
+        .collect();
+    let Some(first) = fonts.first() else {
+        return;
+    };
+
+    let mut caret = ab_glyph::point(at.x, at.y + first.ascent());
+    let mut previous: Option<(usize, GlyphId)> = None;
+    for c in text.chars() {
+        if c == '\n' {
+            caret = ab_glyph::point(at.x, caret.y + first.height() + first.line_gap());
+            previous = None;
+            continue;
+        }
+        // Like egui, characters the first font lacks come from the fallbacks after it.
+        let (index, font) = fonts
+            .iter()
+            .enumerate()
+            .find(|(_, font)| font.glyph_id(c).0 != 0)
+            .unwrap_or((0, first));
+        let id = font.glyph_id(c);
+        if let Some((previous_index, previous_id)) = previous {
+            if previous_index == index {
+                caret.x += font.kern(previous_id, id);
+            }
+        }
+        let glyph = id.with_scale_and_position(font.scale(), caret);
+        caret.x += font.h_advance(id);
+        previous = Some((index, id));
+
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|x, y, coverage| {
+            let x = bounds.min.x as i64 + x as i64;
+            let y = bounds.min.y as i64 + y as i64;
+            if (0..image.width() as i64).contains(&x) && (0..image.height() as i64).contains(&y) {
+                blend(image.get_pixel_mut(x as u32, y as u32), annotation.color, coverage);
+            }
+        });
+    }
+}
+
+/// Calls `f` with each pixel of `image` inside `bounds` and the position of its center.
+fn for_each_pixel_in(
+    image: &mut RgbaImage,
+    bounds: egui::Rect,
+    mut f: impl FnMut(&mut Rgba<u8>, egui::Pos2),
+) {
+    let (width, height) = image.dimensions();
+    let x_range = bounds.min.x.max(0.0) as u32..(bounds.max.x.ceil().max(0.0) as u32).min(width);
+    let y_range = bounds.min.y.max(0.0) as u32..(bounds.max.y.ceil().max(0.0) as u32).min(height);
+
+    for y in y_range {
+        for x in x_range.clone() {
+            let center = egui::pos2(x as f32 + 0.5, y as f32 + 0.5);
+            f(image.get_pixel_mut(x, y), center);
+        }
+    }
+}
+
+fn blend(pixel: &mut Rgba<u8>, [r, g, b, a]: [u8; 4], coverage: f32) {
+    let alpha = coverage * a as f32 / 255.0;
+    if alpha <= 0.0 {
+        return;
+    }
+
+    for (channel, value) in pixel.0.iter_mut().zip([r, g, b]) {
+        *channel = egui::lerp(*channel as f32..=value as f32, alpha).round() as u8;
+    }
+    pixel.0[3] = pixel.0[3].max((alpha * 255.0).round() as u8);
+}
@@ -0,0 +1,687 @@
+use bruh::{
+    decode_bruh_with_progress, decode_header,
+    file::{read_input, AtomicFile},
+    pixels_to_rgb,
+    sidecar::EncodeOptions,
+    ColorAdjustments, FilteredEncoder, PixelFilter, PixelLayout, Progress, HEADER_SIZE,
+};
+use eframe::egui;
+
+use std::{
+    collections::BTreeSet,
+    error::Error,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::{
+    format_size,
+    locale::Localizer,
+    script::{BatchScript, Decision},
+};
+
+const MAX_WORKERS: usize = 4;
+/// Buffers that grew past this for one huge image are freed rather than kept around.
+const MAX_RETAINED_CAPACITY: usize = 64 * 1024 * 1024;
+/// How often the window is redrawn while converting, to move the progress bars along.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    PngToBruh,
+    BruhToPng,
+}
+
+impl Direction {
+    fn extensions(self) -> (&'static str, &'static str) {
+        match self {
+            Direction::PngToBruh => ("png", "bruh"),
+            Direction::BruhToPng => ("bruh", "png"),
+        }
+    }
+
+    /// How the direction is named to batch scripts.
+    fn script_name(self) -> &'static str {
+        match self {
+            Direction::PngToBruh => "png-to-bruh",
+            Direction::BruhToPng => "bruh-to-png",
+        }
+    }
+}
+
+enum Status {
+    Pending,
+    /// Being converted, this far along.
+    Running(Progress),
+    Done,
+    Skipped(String),
+    Failed(String),
+    Cancelled,
+}
+
+struct Job {
+    source: PathBuf,
+    output: PathBuf,
+    status: Status,
+}
+
+type Update = (usize, Status);
+/// A job's index in the list, source, output, the adjustments its script asked for and the
+/// options in the source's sidecar, if it has one.
+type QueuedJob = (usize, PathBuf, PathBuf, ColorAdjustments, Option<EncodeOptions>);
+
+/// Buffers a worker reuses from file to file, so converting thousands of small
+/// sprites doesn't allocate for each of them.
+#[derive(Default)]
+struct Scratch {
+    input: Vec<u8>,
+    pixels: Vec<u8>,
+    row: Vec<u8>,
+    output: Vec<u8>,
+}
+
+impl Scratch {
+    fn trim(&mut self) {
+        let buffers = [&mut self.input, &mut self.pixels, &mut self.row, &mut self.output];
+        for buffer in buffers {
+            if buffer.capacity() > MAX_RETAINED_CAPACITY {
+                *buffer = Vec::new();
+            }
+        }
+    }
+}
+
+/// Window for converting every PNG or BRUH file in a folder at once.
+///
+/// Files are converted on a few worker threads, each reporting back as it starts
+/// and finishes a file. While a file is converting, the codec reports how far it's got
+/// to the file's [`Progress`], which the list shows and Cancel uses to stop it partway.
+pub struct BatchConverter {
+    pub open: bool,
+    folder: String,
+    /// The folder last dropped onto the window, which `folder` only shows lossily if
+    /// its path isn't valid Unicode.
+    dropped_folder: Option<PathBuf>,
+    direction: Direction,
+    recursive: bool,
+    overwrite: bool,
+    /// Path of a script run for each file, or empty for none; see [`BatchScript`].
+    script: String,
+    jobs: Vec<Job>,
+    /// Why the last attempt to start didn't, found before converting anything.
+    preflight_problems: Vec<String>,
+    updates: Option<mpsc::Receiver<Update>>,
+    cancel: Arc<AtomicBool>,
+    /// Scratch buffers of finished workers, handed to the workers of the next batch.
+    scratch_pool: Arc<Mutex<Vec<Scratch>>>,
+}
+
+impl BatchConverter {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            folder: String::new(),
+            dropped_folder: None,
+            direction: Direction::PngToBruh,
+            recursive: false,
+            overwrite: false,
+            script: String::new(),
+            jobs: Vec::new(),
+            preflight_problems: Vec::new(),
+            updates: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            scratch_pool: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.updates.is_some()
+    }
+
+    /// Picks `folder` as the source, e.g. when it's dropped onto the window.
+    pub fn set_folder(&mut self, folder: &Path) {
+        if !self.is_running() {
+            self.folder = folder.display().to_string();
+            self.dropped_folder = Some(folder.to_path_buf());
+            self.scan();
+        }
+    }
+
+    /// The folder to convert, as dropped unless its path has been edited since.
+    fn folder_path(&self) -> PathBuf {
+        match &self.dropped_folder {
+            Some(folder) if folder.display().to_string() == self.folder => folder.clone(),
+            _ => PathBuf::from(self.folder.trim()),
+        }
+    }
+
+    /// Lists the files in the folder that the current options would convert.
+    fn scan(&mut self) {
+        let (from, to) = self.direction.extensions();
+        let mut sources = Vec::new();
+        collect_files(&self.folder_path(), from, self.recursive, &mut sources);
+        sources.sort();
+
+        self.jobs = sources
+            .into_iter()
+            .map(|source| Job {
+                output: source.with_extension(to),
+                source,
+                status: Status::Pending,
+            })
+            .collect();
+    }
+
+    fn start(&mut self, ctx: &egui::Context, locale: &Localizer) {
+        let script = match self.script.trim() {
+            "" => None,
+            path => match BatchScript::load(Path::new(path)) {
+                Ok(script) => Some(script),
+                Err(e) => {
+                    self.preflight_problems = vec![e];
+                    return;
+                }
+            },
+        };
+
+        let (_, to) = self.direction.extensions();
+        let mut queue = Vec::new();
+        for (index, job) in self.jobs.iter_mut().enumerate() {
+            // A script may have renamed the output on an earlier run.
+            job.output = job.source.with_extension(to);
+            let decision = match &script {
+                Some(script) => script.decide(
+                    &job.source,
+                    &job.output,
+                    self.direction.script_name(),
+                    image_size(self.direction, &job.source).ok(),
+                ),
+                None => Ok(Decision::Convert {
+                    output: job.output.clone(),
+                    adjustments: ColorAdjustments::default(),
+                }),
+            };
+
+            job.status = match decision {
+                Err(e) => Status::Failed(e),
+                Ok(Decision::Skip(reason)) => Status::Skipped(
+                    reason.unwrap_or_else(|| locale.text("batch-skipped-by-script")),
+                ),
+                Ok(Decision::Convert {
+                    output,
+                    adjustments,
+                }) => {
+                    job.output = output;
+                    // Sidecars tune how images are encoded, so they're only read for BRUH.
+                    let options = match self.direction {
+                        Direction::PngToBruh => EncodeOptions::load(&job.source),
+                        Direction::BruhToPng => Ok(None),
+                    };
+                    match options {
+                        Err(e) => Status::Failed(e),
+                        Ok(_) if !self.overwrite && job.output.exists() => {
+                            Status::Skipped(locale.text("batch-output-exists"))
+                        }
+                        Ok(options) => {
+                            let (source, output) = (job.source.clone(), job.output.clone());
+                            queue.push((index, source, output, adjustments, options));
+                            Status::Pending
+                        }
+                    }
+                }
+            };
+        }
+
+        self.preflight_problems =
+            preflight(self.direction, &self.folder_path(), &queue, locale);
+        if !self.preflight_problems.is_empty() {
+            return;
+        }
+
+        let (sender, updates) = mpsc::channel();
+        let queue = Arc::new(queue);
+        let next = Arc::new(AtomicUsize::new(0));
+        self.cancel = Arc::new(AtomicBool::new(false));
+
+        let workers = thread::available_parallelism().map_or(1, |n| n.get());
+        for _ in 0..workers.min(MAX_WORKERS) {
+            let (queue, next) = (Arc::clone(&queue), Arc::clone(&next));
+            let (cancel, pool) = (Arc::clone(&self.cancel), Arc::clone(&self.scratch_pool));
+            let (sender, ctx, direction) = (sender.clone(), ctx.clone(), self.direction);
+            thread::spawn(move || {
+                let pooled = pool.lock().ok().and_then(|mut pool| pool.pop());
+                let mut scratch = pooled.unwrap_or_default();
+
+                while !cancel.load(Ordering::Relaxed) {
+                    let job = queue.get(next.fetch_add(1, Ordering::Relaxed));
+                    let Some((index, source, output, adjustments, options)) = job else {
+                        break;
+                    };
+
+                    let progress = Progress::new();
+                    let _ = sender.send((*index, Status::Running(progress.clone())));
+                    let converted = convert(
+                        direction,
+                        source,
+                        output,
+                        *adjustments,
+                        *options,
+                        &progress,
+                        &mut scratch,
+                    );
+                    let status = match converted {
+                        Ok(()) => Status::Done,
+                        // Nothing was written, since the output is only put in place at the end.
+                        Err(_) if progress.is_cancelled() => Status::Cancelled,
+                        Err(e) => Status::Failed(e.to_string()),
+                    };
+                    scratch.trim();
+                    if sender.send((*index, status)).is_err() {
+                        break;
+                    }
+                    ctx.request_repaint();
+                }
+
+                if let Ok(mut pool) = pool.lock() {
+                    pool.push(scratch);
+                }
+            });
+        }
+
+        self.updates = Some(updates);
+    }
+
+    fn receive_updates(&mut self) {
+        let Some(updates) = &self.updates else {
+            return;
+        };
+
+        loop {
+            match updates.try_recv() {
+                Ok((index, status)) => {
+                    // A file started just as Cancel was clicked is stopped too.
+                    if let Status::Running(progress) = &status {
+                        if self.cancel.load(Ordering::Relaxed) {
+                            progress.cancel();
+                        }
+                    }
+                    self.jobs[index].status = status;
+                }
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => break,
+            }
+        }
+
+        // Every worker has stopped; whatever they didn't get to was cancelled.
+        self.updates = None;
+        for job in &mut self.jobs {
+            if let Status::Pending | Status::Running(_) = job.status {
+                job.status = Status::Cancelled;
+            }
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, locale: &Localizer) {
+        self.receive_updates();
+        if self.is_running() {
+            ctx.request_repaint_after(PROGRESS_INTERVAL);
+        }
+
+        let mut open = self.open;
+        egui::Window::new(locale.text("batch-convert"))
+            .open(&mut open)
+            .collapsible(false)
+            .default_size(egui::vec2(420.0, 360.0))
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(!self.is_running(), |ui| self.options(ui, locale));
+                ui.separator();
+
+                if !self.preflight_problems.is_empty() {
+                    for problem in &self.preflight_problems {
+                        ui.colored_label(ui.visuals().error_fg_color, problem);
+                    }
+                    ui.separator();
+                }
+
+                let finished = self
+                    .jobs
+                    .iter()
+                    .filter(|job| !matches!(job.status, Status::Pending | Status::Running(_)))
+                    .count();
+                ui.horizontal(|ui| {
+                    if self.is_running() {
+                        if ui.button(locale.text("batch-cancel")).clicked() {
+                            self.cancel();
+                        }
+                    } else {
+                        let convert = egui::Button::new(locale.text("batch-start"));
+                        if ui.add_enabled(!self.jobs.is_empty(), convert).clicked() {
+                            self.start(ui.ctx(), locale);
+                        }
+                    }
+
+                    let progress = finished as f32 / self.jobs.len().max(1) as f32;
+                    ui.add(
+                        egui::ProgressBar::new(progress)
+                            .text(format!("{} / {}", finished, self.jobs.len())),
+                    );
+                });
+                ui.separator();
+
+                self.job_list(ui, locale);
+            });
+        self.open = open;
+    }
+
+    /// Stops the workers taking new files and the files being converted where they are.
+    fn cancel(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+        for job in &self.jobs {
+            if let Status::Running(progress) = &job.status {
+                progress.cancel();
+            }
+        }
+    }
+
+    fn options(&mut self, ui: &mut egui::Ui, locale: &Localizer) {
+        let mut rescan = false;
+        ui.horizontal(|ui| {
+            ui.label(locale.text("batch-folder"));
+            let field = ui.add(
+                egui::TextEdit::singleline(&mut self.folder)
+                    .hint_text(locale.text("batch-folder-hint"))
+                    .desired_width(ui.available_width() - 64.0),
+            );
+            rescan |= field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            rescan |= ui.button(locale.text("batch-scan")).clicked();
+        });
+
+        ui.horizontal(|ui| {
+            rescan |= ui
+                .radio_value(&mut self.direction, Direction::PngToBruh, "PNG → BRUH")
+                .changed();
+            rescan |= ui
+                .radio_value(&mut self.direction, Direction::BruhToPng, "BRUH → PNG")
+                .changed();
+        });
+        let include_subfolders = locale.text("batch-include-subfolders");
+        rescan |= ui.checkbox(&mut self.recursive, include_subfolders).changed();
+        ui.checkbox(&mut self.overwrite, locale.text("batch-overwrite"));
+        ui.horizontal(|ui| {
+            ui.label(locale.text("batch-script"));
+            ui.add(
+                egui::TextEdit::singleline(&mut self.script)
+                    .hint_text(locale.text("batch-script-hint"))
+                    .desired_width(ui.available_width()),
+            );
+        });
+
+        if rescan {
+            self.scan();
+            self.preflight_problems.clear();
+        }
+    }
+
+    fn job_list(&self, ui: &mut egui::Ui, locale: &Localizer) {
+        if self.jobs.is_empty() {
+            let (from, _) = self.direction.extensions();
+            ui.weak(locale.format("batch-no-files", &[("extension", from.into())]));
+            return;
+        }
+
+        let folder = self.folder_path();
+        let row_height = ui.text_style_height(&egui::TextStyle::Body);
+        egui::ScrollArea::vertical().auto_shrink([false; 2]).show_rows(
+            ui,
+            row_height,
+            self.jobs.len(),
+            |ui, rows| {
+                for job in &self.jobs[rows] {
+                    ui.horizontal(|ui| {
+                        let name = job.source.strip_prefix(&folder).unwrap_or(&job.source);
+                        ui.label(name.display().to_string());
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            status_label(ui, &job.status, locale)
+                        });
+                    });
+                }
+            },
+        );
+    }
+}
+
+fn status_label(ui: &mut egui::Ui, status: &Status, locale: &Localizer) {
+    if let Status::Running(progress) = status {
+        let bar = egui::ProgressBar::new(progress.fraction())
+            .desired_width(96.0)
+            .show_percentage();
+        ui.add(bar).on_hover_text(locale.text("batch-converting"));
+        return;
+    }
+    let visuals = ui.visuals();
+    let (text, color) = match status {
+        Status::Pending => ("batch-waiting", visuals.weak_text_color()),
+        Status::Running(_) => unreachable!("running jobs show a progress bar"),
+        Status::Done => ("batch-done", egui::Color32::from_rgb(80, 180, 80)),
+        Status::Skipped(_) => ("batch-skipped", visuals.weak_text_color()),
+        Status::Failed(_) => ("batch-failed", visuals.error_fg_color),
+        Status::Cancelled => ("batch-cancelled", visuals.warn_fg_color),
+    };
+
+    let label = ui.colored_label(color, locale.text(text));
+    if let Status::Skipped(reason) | Status::Failed(reason) = status {
+        label.on_hover_text(reason);
+    }
+}
+
+fn collect_files(dir: &Path, extension: &str, recursive: bool, files: &mut Vec<PathBuf>) {
+    for path in fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+    {
+        if path.is_dir() {
+            if recursive {
+                collect_files(&path, extension, recursive, files);
+            }
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// Checks that the outputs of `queue` can be written and that there's room for them,
+/// returning what's wrong, so a batch doesn't fail halfway through with raw I/O errors.
+fn preflight(
+    direction: Direction,
+    folder: &Path,
+    queue: &[QueuedJob],
+    locale: &Localizer,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let directories: BTreeSet<&Path> = queue
+        .iter()
+        .filter_map(|(_, _, output, _, _)| output.parent())
+        .collect();
+    for directory in directories {
+        if let Err(e) = check_writable(directory) {
+            problems.push(locale.format(
+                "batch-cant-write",
+                &[
+                    ("folder", directory.display().to_string().into()),
+                    ("error", e.to_string().into()),
+                ],
+            ));
+        }
+    }
+
+    // Files whose size can't be read are left for the conversion itself to report.
+    let needed: u64 = queue
+        .iter()
+        .filter_map(|(_, source, _, _, _)| estimated_output_size(direction, source).ok())
+        .sum();
+    match fs2::available_space(folder) {
+        Ok(available) if available < needed => problems.push(locale.format(
+            "batch-not-enough-space",
+            &[
+                ("needed", format_size(needed).into()),
+                ("available", format_size(available).into()),
+            ],
+        )),
+        Ok(_) => {}
+        Err(e) => problems.push(locale.format(
+            "batch-free-space-failed",
+            &[("error", e.to_string().into())],
+        )),
+    }
+
+    problems
+}
+
+fn check_writable(directory: &Path) -> io::Result<()> {
+    let probe = directory.join(format!(".bruh-write-test-{}", process::id()));
+    File::create(&probe)?;
+    fs::remove_file(probe)
+}
+
+/// The dimensions in the source's header, without decoding the image.
+fn image_size(direction: Direction, source: &Path) -> Result<(u32, u32), Box<dyn Error>> {
+    Ok(match direction {
+        Direction::PngToBruh => {
+            let reader = png::Decoder::new(File::open(source)?).read_info()?;
+            (reader.info().width, reader.info().height)
+        }
+        Direction::BruhToPng => {
+            let mut header = [0; HEADER_SIZE];
+            File::open(source)?.read_exact(&mut header)?;
+            decode_header(&header)
+        }
+    })
+}
+
+/// The most a converted file can take, from the dimensions in the source's header.
+fn estimated_output_size(direction: Direction, source: &Path) -> Result<u64, Box<dyn Error>> {
+    let (width, height) = image_size(direction, source)?;
+
+    // Four bytes a pixel is a run per pixel for BRUH, and about uncompressed RGBA for PNG.
+    Ok(HEADER_SIZE as u64 + width as u64 * height as u64 * 4)
+}
+
+fn convert(
+    direction: Direction,
+    source: &Path,
+    output: &Path,
+    mut adjustments: ColorAdjustments,
+    options: Option<EncodeOptions>,
+    progress: &Progress,
+    scratch: &mut Scratch,
+) -> Result<(), Box<dyn Error>> {
+    scratch.output.clear();
+
+    match direction {
+        Direction::PngToBruh => {
+            let mut decoder = png::Decoder::new(File::open(source)?);
+            decoder.set_transformations(
+                png::Transformations::EXPAND | png::Transformations::STRIP_16,
+            );
+            let mut reader = decoder.read_info()?;
+            scratch.pixels.resize(reader.output_buffer_size(), 0);
+            let frame = reader.next_frame(&mut scratch.pixels)?;
+
+            let mut filters: Vec<Box<dyn PixelFilter>> = Vec::new();
+            if !adjustments.is_identity() {
+                filters.push(Box::new(adjustments));
+            }
+            let rows = scratch.pixels[..frame.buffer_size()].chunks_exact(frame.line_size);
+            match options {
+                // Resizing and finding a palette need the whole image, so it's gathered first.
+                Some(options) => {
+                    let mut rgb = Vec::with_capacity(frame.width as usize * 3);
+                    for row in rows {
+                        rgb.extend_from_slice(png_row_to_rgb(
+                            row,
+                            frame.color_type,
+                            &mut scratch.row,
+                        ));
+                    }
+                    let (width, height) = options.apply(frame.width, frame.height, &mut rgb);
+                    let mut encoder =
+                        FilteredEncoder::with_filters(&mut scratch.output, width, height, filters)?;
+                    encoder.set_progress(progress.clone());
+                    encoder.push_pixels(&rgb)?;
+                    encoder.finish()?;
+                }
+                None => {
+                    let mut encoder = FilteredEncoder::with_filters(
+                        &mut scratch.output,
+                        frame.width,
+                        frame.height,
+                        filters,
+                    )?;
+                    encoder.set_progress(progress.clone());
+                    for row in rows {
+                        let rgb = png_row_to_rgb(row, frame.color_type, &mut scratch.row);
+                        encoder.push_pixels(rgb)?;
+                    }
+                    encoder.finish()?;
+                }
+            }
+        }
+        Direction::BruhToPng => {
+            read_input(source, &mut scratch.input)?;
+            let (width, height) =
+                decode_bruh_with_progress(&scratch.input, &mut scratch.pixels, progress)?;
+            if width == 0 || height == 0 {
+                return Err(
+                    format!("PNG can't store an empty image ({}×{} pixels).", width, height).into(),
+                );
+            }
+            if !adjustments.is_identity() {
+                let rows = scratch.pixels.chunks_exact_mut(width as usize * 4);
+                for (y, row) in (0..).zip(rows) {
+                    adjustments.filter_row(y, row);
+                }
+            }
+
+            let mut encoder = png::Encoder::new(&mut scratch.output, width, height);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&scratch.pixels)?;
+            writer.finish()?;
+        }
+    }
+
+    let mut file = AtomicFile::create(output)?;
+    file.write_all(&scratch.output)?;
+    file.commit()?;
+    Ok(())
+}
+
+/// Packs one row of 8-bit PNG samples as RGB, using `buffer` unless it already is. Expects
+/// the `EXPAND` and `STRIP_16` transformations.
+fn png_row_to_rgb<'a>(
+    row: &'a [u8],
+    color_type: png::ColorType,
+    buffer: &'a mut Vec<u8>,
+) -> &'a [u8] {
+    let layout = match color_type {
+        png::ColorType::Grayscale => PixelLayout::Gray,
+        png::ColorType::GrayscaleAlpha => PixelLayout::GrayAlpha,
+        png::ColorType::Rgb | png::ColorType::Indexed => PixelLayout::Rgb,
+        png::ColorType::Rgba => PixelLayout::Rgba,
+    };
+    pixels_to_rgb(row, layout, buffer)
+}
@@ -0,0 +1,384 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    adjustments::Adjustments, annotations::Shape, locale::Localizer, selection::Selection,
+    LoadedImage,
+};
+
+const MIN_ZOOM: f32 = 0.01;
+const MAX_ZOOM: f32 = 128.0;
+const SCROLL_ZOOM_SPEED: f32 = 0.004;
+
+/// Zoom from which the pixel grid is drawn.
+const GRID_MIN_ZOOM: f32 = 8.0;
+
+/// Length of the minimap's longer side.
+const MINIMAP_SIZE: f32 = 160.0;
+const MINIMAP_MARGIN: f32 = 8.0;
+/// How far the zoomed image has to overflow the panel before the minimap appears.
+const MINIMAP_MIN_OVERFLOW: f32 = 1.5;
+
+/// How the image is sampled when drawn larger than its native size.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Filtering {
+    /// Nearest-neighbor from 100% zoom upwards, so pixel art stays crisp.
+    Auto,
+    Nearest,
+    Linear,
+}
+
+impl Filtering {
+    pub const ALL: [Filtering; 3] = [Filtering::Auto, Filtering::Nearest, Filtering::Linear];
+
+    pub fn label(self, locale: &Localizer) -> String {
+        locale.text(match self {
+            Filtering::Auto => "filtering-auto",
+            Filtering::Nearest => "filtering-nearest",
+            Filtering::Linear => "filtering-linear",
+        })
+    }
+
+    fn texture_filter(self, zoom: f32) -> egui::TextureFilter {
+        match self {
+            Filtering::Auto if zoom >= 1.0 => egui::TextureFilter::Nearest,
+            Filtering::Auto | Filtering::Linear => egui::TextureFilter::Linear,
+            Filtering::Nearest => egui::TextureFilter::Nearest,
+        }
+    }
+}
+
+/// What a click or drag on the image does.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Select,
+    Eyedropper,
+    Rectangle,
+    Arrow,
+    /// Clicking reports the pixel so the viewer can ask for the text to place there.
+    Text,
+}
+
+/// Settings that change how the image is drawn and interacted with.
+#[derive(Clone, Copy)]
+pub struct CanvasOptions {
+    pub filtering: Filtering,
+    pub tool: Tool,
+    pub pixel_grid: bool,
+    pub minimap: bool,
+    pub run_heatmap: bool,
+    /// Whether animations show the frames before and after the current one faintly over it.
+    pub onion_skin: bool,
+    pub adjustments: Adjustments,
+    pub annotations: bool,
+    pub annotation_color: egui::Color32,
+}
+
+impl Default for CanvasOptions {
+    fn default() -> Self {
+        Self {
+            filtering: Filtering::Auto,
+            tool: Tool::Select,
+            pixel_grid: true,
+            minimap: true,
+            run_heatmap: false,
+            onion_skin: false,
+            adjustments: Adjustments::default(),
+            annotations: true,
+            annotation_color: egui::Color32::from_rgb(255, 64, 64),
+        }
+    }
+}
+
+/// How a newly opened image is first shown.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ZoomMode {
+    Fit,
+    ActualSize,
+}
+
+/// Zoom and pan of the image. The image is fitted to the panel until zoomed by hand.
+#[derive(Clone, Copy, Default)]
+pub struct Camera {
+    zoom: Option<f32>,
+    pan: egui::Vec2,
+}
+
+impl Camera {
+    pub fn new(mode: ZoomMode) -> Self {
+        match mode {
+            ZoomMode::Fit => Self::default(),
+            ZoomMode::ActualSize => Self::with_zoom(1.0),
+        }
+    }
+
+    /// Centered at a fixed zoom, where 1.0 is one screen pixel per image pixel.
+    pub fn with_zoom(zoom: f32) -> Self {
+        Self {
+            zoom: Some(zoom.clamp(MIN_ZOOM, MAX_ZOOM)),
+            pan: egui::Vec2::ZERO,
+        }
+    }
+
+    pub fn fit(&mut self) {
+        *self = Self::default();
+    }
+
+    /// The zoom set by hand, or `None` while the image is fitted to the panel.
+    pub fn fixed_zoom(&self) -> Option<f32> {
+        self.zoom
+    }
+
+    /// Zooms from `current` to `zoom`, keeping the point `offset` away from the
+    /// image's center in the same place on screen.
+    pub fn zoom_about(&mut self, current: f32, zoom: f32, offset: egui::Vec2) {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.pan += offset * (1.0 - zoom / current);
+        self.zoom = Some(zoom);
+    }
+
+    /// Zooms about the center of the panel.
+    pub fn zoom_centered(&mut self, current: f32, zoom: f32) {
+        self.zoom_about(current, zoom, -self.pan);
+    }
+
+    /// Pans so that `point`, in pixels from the image's top left corner, is centered.
+    fn center_on(&mut self, zoom: f32, image_size: egui::Vec2, point: egui::Vec2) {
+        self.pan = (image_size / 2.0 - point) * zoom;
+        self.zoom = Some(zoom);
+    }
+}
+
+/// What the image panel displayed during the last frame.
+#[derive(Clone, Copy, Default)]
+pub struct ViewState {
+    pub zoom: f32,
+    pub hovered_pixel: Option<(u32, u32)>,
+    pub clicked_pixel: Option<(u32, u32)>,
+    /// A rectangle or arrow was finished this frame.
+    pub annotation_added: bool,
+}
+
+/// Draws the image into the remaining space of `ui`, handling zoom, pan and the active tool.
+///
+/// Scrolling zooms about the pointer and dragging with the middle or right button pans.
+/// Once the image is much larger than the panel, a minimap in the corner shows where
+/// the view is and can be clicked or dragged to move it.
+pub fn show(
+    ui: &mut egui::Ui,
+    loaded: &mut LoadedImage,
+    options: CanvasOptions,
+    camera: &mut Camera,
+    selection: &mut Option<Selection>,
+) -> ViewState {
+    let available = ui.available_rect_before_wrap();
+    let image_size = egui::vec2(loaded.width as f32, loaded.height as f32);
+    let fit = (available.width() / image_size.x).min(available.height() / image_size.y);
+    let mut zoom = camera.zoom.unwrap_or(fit);
+
+    // The minimap has to claim the pointer before the canvas underneath it does.
+    let overflow = (image_size * zoom / available.size()).max_elem();
+    let minimap = (options.minimap && overflow >= MINIMAP_MIN_OVERFLOW).then(|| {
+        let size = image_size * (MINIMAP_SIZE / image_size.max_elem());
+        let rect = egui::Rect::from_min_size(
+            available.max - size - egui::Vec2::splat(MINIMAP_MARGIN),
+            size,
+        );
+        let response = ui.interact(rect, ui.id().with("minimap"), egui::Sense::click_and_drag());
+        (rect, response)
+    });
+    let response = ui.allocate_rect(available, egui::Sense::click_and_drag());
+
+    if let Some((rect, minimap)) = &minimap {
+        if let Some(pos) = minimap.interact_pointer_pos() {
+            let point = (pos - rect.min) * image_size.x / rect.width();
+            camera.center_on(zoom, image_size, point);
+        }
+    }
+
+    if let Some(pointer) = response.hover_pos() {
+        let (scroll, pinch) = ui.input(|i| (i.scroll_delta.y, i.zoom_delta()));
+        let factor = pinch * (scroll * SCROLL_ZOOM_SPEED).exp();
+        if factor != 1.0 {
+            let center = available.center() + camera.pan;
+            camera.zoom_about(zoom, zoom * factor, pointer - center);
+            zoom = camera.zoom.unwrap_or(fit);
+        }
+    }
+
+    if response.dragged_by(egui::PointerButton::Middle)
+        || response.dragged_by(egui::PointerButton::Secondary)
+    {
+        camera.pan += response.drag_delta();
+        camera.zoom = Some(zoom);
+        ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+    }
+
+    let rect = egui::Rect::from_center_size(available.center() + camera.pan, image_size * zoom);
+    let painter = ui.painter_at(available);
+    let filter = options.filtering.texture_filter(zoom);
+    match &mut loaded.heatmap {
+        Some(heatmap) if options.run_heatmap => heatmap.tiles.paint(
+            ui.ctx(),
+            &painter,
+            &heatmap.pixels,
+            rect,
+            filter,
+            Adjustments::default(),
+        ),
+        _ => loaded.tiles.paint(
+            ui.ctx(),
+            &painter,
+            &loaded.pixels,
+            rect,
+            filter,
+            options.adjustments,
+        ),
+    }
+
+    if options.pixel_grid && zoom >= GRID_MIN_ZOOM {
+        paint_pixel_grid(&painter, rect, zoom, [loaded.width, loaded.height]);
+    }
+    if options.annotations {
+        loaded.annotations.paint(&painter, rect, zoom);
+    }
+    if let Some((minimap, _)) = minimap {
+        let overview = loaded.tiles.overview(ui.ctx(), &loaded.pixels);
+        paint_minimap(ui, &painter, overview, minimap, rect);
+    }
+
+    let clamped_pixel = |pos: egui::Pos2| {
+        let pixel = (pos - rect.min) / zoom;
+        let x = (pixel.x.max(0.0) as u32).min(loaded.width - 1);
+        let y = (pixel.y.max(0.0) as u32).min(loaded.height - 1);
+        [x, y]
+    };
+    let pixel_at = |pos: egui::Pos2| rect.contains(pos).then(|| clamped_pixel(pos));
+    let image_point = |pos: egui::Pos2| -> [f32; 2] { ((pos - rect.min) / zoom).into() };
+
+    let mut clicked_pixel = None;
+    let mut annotation_added = false;
+    match (options.tool, response.interact_pointer_pos()) {
+        (Tool::Select, Some(pos)) if response.drag_started_by(egui::PointerButton::Primary) => {
+            *selection = pixel_at(pos).map(Selection::new);
+        }
+        (Tool::Select, Some(pos)) if response.dragged_by(egui::PointerButton::Primary) => {
+            if let Some(selection) = selection {
+                selection.drag_to(clamped_pixel(pos));
+            }
+        }
+        (Tool::Select, _) if response.clicked() => *selection = None,
+        (Tool::Eyedropper | Tool::Text, Some(pos)) if response.clicked() => {
+            clicked_pixel = pixel_at(pos).map(|[x, y]| (x, y));
+        }
+        (Tool::Rectangle | Tool::Arrow, Some(pos))
+            if response.drag_started_by(egui::PointerButton::Primary) =>
+        {
+            let (from, to) = (image_point(pos), image_point(pos));
+            let shape = match options.tool {
+                Tool::Arrow => Shape::Arrow { from, to },
+                _ => Shape::Rectangle { from, to },
+            };
+            loaded
+                .annotations
+                .start_drag(shape, options.annotation_color, image_size);
+        }
+        (Tool::Rectangle | Tool::Arrow, Some(pos))
+            if response.dragged_by(egui::PointerButton::Primary) =>
+        {
+            loaded.annotations.drag_to(image_point(pos));
+        }
+        _ => {}
+    }
+    if response.drag_released() {
+        annotation_added = loaded.annotations.finish_drag();
+    }
+    if options.tool == Tool::Eyedropper && response.hovered() {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::Crosshair);
+    } else if options.tool == Tool::Text && response.hovered() {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::Text);
+    }
+
+    if let Some(selection) = selection {
+        let selected = selection.screen_rect(rect, zoom);
+        let stroke = ui.visuals().selection.stroke;
+        painter.rect_filled(selected, 0.0, stroke.color.gamma_multiply(0.15));
+        painter.rect_stroke(selected, 0.0, stroke);
+
+        let [width, height] = selection.size();
+        painter.text(
+            selected.left_top() + egui::vec2(4.0, 4.0),
+            egui::Align2::LEFT_TOP,
+            format!("{}x{}", width, height),
+            egui::FontId::monospace(12.0),
+            stroke.color,
+        );
+    }
+
+    ViewState {
+        zoom,
+        hovered_pixel: response.hover_pos().and_then(pixel_at).map(|[x, y]| (x, y)),
+        clicked_pixel,
+        annotation_added,
+    }
+}
+
+/// Draws the whole image into `minimap` with an outline of the part visible in the panel.
+fn paint_minimap(
+    ui: &egui::Ui,
+    painter: &egui::Painter,
+    texture: egui::TextureId,
+    minimap: egui::Rect,
+    image_rect: egui::Rect,
+) {
+    let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+    let frame = minimap.expand(2.0);
+    painter.rect_filled(frame, 2.0, ui.visuals().extreme_bg_color);
+    painter.image(texture, minimap, uv, egui::Color32::WHITE);
+    painter.rect_stroke(frame, 2.0, ui.visuals().window_stroke());
+
+    let scale = minimap.width() / image_rect.width();
+    let visible = painter.clip_rect().intersect(image_rect);
+    let viewport = egui::Rect::from_min_max(
+        minimap.min + (visible.min - image_rect.min) * scale,
+        minimap.min + (visible.max - image_rect.min) * scale,
+    );
+    painter.rect_stroke(viewport, 0.0, ui.visuals().selection.stroke);
+}
+
+/// Draws lines between pixels, limited to the part of the image that's on screen.
+fn paint_pixel_grid(painter: &egui::Painter, image_rect: egui::Rect, zoom: f32, size: [u32; 2]) {
+    let visible = image_rect.intersect(painter.clip_rect());
+    if !visible.is_positive() {
+        return;
+    }
+
+    let stroke = egui::Stroke::new(
+        1.0,
+        egui::Color32::from_rgba_unmultiplied(128, 128, 128, 96),
+    );
+    let offset = visible.min - image_rect.min;
+    let first = [
+        (offset.x / zoom).ceil() as u32,
+        (offset.y / zoom).ceil() as u32,
+    ];
+    let last = [
+        (((offset.x + visible.width()) / zoom) as u32).min(size[0]),
+        (((offset.y + visible.height()) / zoom) as u32).min(size[1]),
+    ];
+
+    for x in first[0]..=last[0] {
+        let x = image_rect.min.x + x as f32 * zoom;
+        painter.line_segment(
+            [egui::pos2(x, visible.min.y), egui::pos2(x, visible.max.y)],
+            stroke,
+        );
+    }
+    for y in first[1]..=last[1] {
+        let y = image_rect.min.y + y as f32 * zoom;
+        painter.line_segment(
+            [egui::pos2(visible.min.x, y), egui::pos2(visible.max.x, y)],
+            stroke,
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use image::RgbaImage;
+
+use std::error::Error;
+
+/// Places `image` on the system clipboard.
+///
+/// Windows gets a 32-bit DIB directly; elsewhere a PNG is handed to the platform's
+/// clipboard tool, since the clipboard crate egui uses can only carry text here.
+pub fn copy_image(image: &RgbaImage) -> Result<(), Box<dyn Error>> {
+    platform::copy_image(image)
+}
+
+#[cfg(windows)]
+mod platform {
+    use image::RgbaImage;
+    use winapi::um::{
+        winbase::{GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock, GMEM_MOVEABLE},
+        wingdi::{BITMAPINFOHEADER, BI_RGB},
+        winuser::{CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData, CF_DIB},
+    };
+
+    use std::{error::Error, mem, ptr};
+
+    pub fn copy_image(image: &RgbaImage) -> Result<(), Box<dyn Error>> {
+        unsafe {
+            if OpenClipboard(ptr::null_mut()) == 0 {
+                return Err("Couldn't open the clipboard.".into());
+            }
+            let result = set_dib(image);
+            CloseClipboard();
+            result
+        }
+    }
+
+    unsafe fn set_dib(image: &RgbaImage) -> Result<(), Box<dyn Error>> {
+        let (width, height) = image.dimensions();
+        let header_size = mem::size_of::<BITMAPINFOHEADER>();
+        let data_size = width as usize * height as usize * 4;
+
+        let handle = GlobalAlloc(GMEM_MOVEABLE, header_size + data_size);
+        if handle.is_null() {
+            return Err("Couldn't allocate clipboard memory.".into());
+        }
+
+        let buffer = GlobalLock(handle) as *mut u8;
+        if buffer.is_null() {
+            GlobalFree(handle);
+            return Err("Couldn't allocate clipboard memory.".into());
+        }
+
+        let header = BITMAPINFOHEADER {
+            biSize: header_size as u32,
+            biWidth: width as i32,
+            biHeight: height as i32,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB,
+            biSizeImage: data_size as u32,
+            biXPelsPerMeter: 0,
+            biYPelsPerMeter: 0,
+            biClrUsed: 0,
+            biClrImportant: 0,
+        };
+        ptr::write_unaligned(buffer as *mut BITMAPINFOHEADER, header);
+
+        // DIBs are stored bottom-up in BGRA order.
+        let pixels = std::slice::from_raw_parts_mut(buffer.add(header_size), data_size);
+        let stride = width as usize * 4;
+        for (y, row) in image.rows().enumerate() {
+            let start = (height as usize - 1 - y) * stride;
+            for (dst, src) in pixels[start..start + stride].chunks_exact_mut(4).zip(row) {
+                dst.copy_from_slice(&[src[2], src[1], src[0], src[3]]);
+            }
+        }
+        GlobalUnlock(handle);
+
+        EmptyClipboard();
+        if SetClipboardData(CF_DIB, handle).is_null() {
+            GlobalFree(handle);
+            return Err("Couldn't place the image on the clipboard.".into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use image::{ImageOutputFormat, RgbaImage};
+
+    use std::{
+        env,
+        error::Error,
+        fs,
+        io::{Cursor, Write},
+        process::{Command, Stdio},
+    };
+
+    pub fn copy_image(image: &RgbaImage) -> Result<(), Box<dyn Error>> {
+        let mut png = Cursor::new(Vec::new());
+        image.write_to(&mut png, ImageOutputFormat::Png)?;
+        let png = png.into_inner();
+
+        if cfg!(target_os = "macos") {
+            return copy_png_macos(&png);
+        }
+
+        let mut command = if env::var_os("WAYLAND_DISPLAY").is_some() {
+            let mut command = Command::new("wl-copy");
+            command.args(["--type", "image/png"]);
+            command
+        } else {
+            let mut command = Command::new("xclip");
+            command.args(["-selection", "clipboard", "-target", "image/png", "-in"]);
+            command
+        };
+
+        let program = command.get_program().to_string_lossy().into_owned();
+        let mut child = command
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Couldn't run `{}`: {}", program, e))?;
+        child
+            .stdin
+            .take()
+            .ok_or("Couldn't write to the clipboard tool.")?
+            .write_all(&png)?;
+
+        if !child.wait()?.success() {
+            return Err(format!("`{}` failed to copy the image.", program).into());
+        }
+        Ok(())
+    }
+
+    fn copy_png_macos(png: &[u8]) -> Result<(), Box<dyn Error>> {
+        let path = env::temp_dir().join(format!("bruh-clipboard-{}.png", std::process::id()));
+        fs::write(&path, png)?;
+
+        let script = format!(
+            "set the clipboard to (read (POSIX file \"{}\") as «class PNGf»)",
+            path.display()
+        );
+        let status = Command::new("osascript").args(["-e", &script]).status();
+        let _ = fs::remove_file(&path);
+
+        if !status?.success() {
+            return Err("`osascript` failed to copy the image.".into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use eframe::egui;
+
+const MAX_SWATCHES: usize = 8;
+
+/// Formats a color as `#RRGGBB`, or `#RRGGBBAA` when it isn't fully opaque.
+pub fn hex(color: [u8; 4]) -> String {
+    let [r, g, b, a] = color;
+    if a == u8::MAX {
+        format!("#{:02X}{:02X}{:02X}", r, g, b)
+    } else {
+        format!("#{:02X}{:02X}{:02X}{:02X}", r, g, b, a)
+    }
+}
+
+/// Recently picked colors, newest first.
+#[derive(Default)]
+pub struct Swatches {
+    colors: Vec<[u8; 4]>,
+}
+
+impl Swatches {
+    pub fn push(&mut self, color: [u8; 4]) {
+        self.colors.retain(|picked| *picked != color);
+        self.colors.insert(0, color);
+        self.colors.truncate(MAX_SWATCHES);
+    }
+
+    /// Draws the swatches and returns the one that was clicked, if any.
+    pub fn show(&self, ui: &mut egui::Ui) -> Option<[u8; 4]> {
+        let mut clicked = None;
+        for &color in &self.colors {
+            let size = egui::Vec2::splat(ui.spacing().interact_size.y * 0.8);
+            let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+
+            let [r, g, b, a] = color;
+            let fill = egui::Color32::from_rgba_unmultiplied(r, g, b, a);
+            ui.painter().rect_filled(rect, 2.0, fill);
+            ui.painter()
+                .rect_stroke(rect, 2.0, ui.visuals().widgets.noninteractive.bg_stroke);
+
+            if response.on_hover_text(hex(color)).clicked() {
+                clicked = Some(color);
+            }
+        }
+        clicked
+    }
+}
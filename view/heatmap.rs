@@ -0,0 +1,76 @@
+use bruh::{buffer_len, check_bruh, file::read_input, DecodeError, Runs, HEADER_SIZE};
+use eframe::egui;
+use image::{Rgba, RgbaImage};
+
+use std::{error::Error, path::Path};
+
+use super::{pixels::ImagePixels, tiles::TileCache};
+
+/// Colors from single-pixel runs (red) to runs of the maximum length (blue).
+const GRADIENT: [[u8; 3]; 4] = [[220, 40, 40], [240, 200, 40], [60, 190, 80], [40, 90, 220]];
+
+/// The image recolored by how long the run each pixel was encoded in is.
+///
+/// Built from the run-length data in the file itself rather than the decoded pixels,
+/// so it shows what the encoder actually wrote.
+pub struct RunHeatmap {
+    pub tiles: TileCache,
+    pub pixels: ImagePixels,
+    lengths: Vec<u8>,
+}
+
+impl RunHeatmap {
+    pub fn load(path: &Path, width: u32, height: u32) -> Result<Self, Box<dyn Error>> {
+        let mut contents = Vec::new();
+        read_input(path, &mut contents)?;
+        if contents.len() < HEADER_SIZE {
+            return Err("File is too small to contain a BRUH header.".into());
+        }
+        // Each frame of an animation has runs of its own.
+        if check_bruh(&contents) == Err(DecodeError::Animated) {
+            return Err(DecodeError::Animated.into());
+        }
+        let runs = Runs::of(&contents);
+
+        // Pixels past the last run (a file that changed since it was opened) stay at 0.
+        let pixel_count = buffer_len(width, height, 1)?;
+        let mut lengths = Vec::with_capacity(pixel_count);
+        for run in runs.bytes.chunks_exact(4) {
+            if run[0] == 0 && runs.fills {
+                // A fill run covers the rest of the image, at least as long as any other.
+                lengths.resize(pixel_count, u8::MAX);
+                break;
+            }
+            lengths.extend(std::iter::repeat_n(run[0], run[0] as usize));
+        }
+        lengths.resize(pixel_count, 0);
+
+        let pixels = ImagePixels::Decoded(RgbaImage::from_fn(width, height, |x, y| {
+            color(lengths[y as usize * width as usize + x as usize])
+        }));
+
+        Ok(Self {
+            tiles: TileCache::new(format!("{} (run heatmap)", path.display())),
+            pixels,
+            lengths,
+        })
+    }
+
+    /// Length of the run that `(x, y)` belongs to.
+    pub fn run_length(&self, (x, y): (u32, u32)) -> u8 {
+        self.lengths[y as usize * self.pixels.dimensions().0 as usize + x as usize]
+    }
+}
+
+/// Maps a run length onto [`GRADIENT`] on a log scale, since most runs are short.
+fn color(length: u8) -> Rgba<u8> {
+    if length == 0 {
+        return Rgba([0, 0, 0, u8::MAX]);
+    }
+
+    let t = (length as f32).ln() / (u8::MAX as f32).ln() * (GRADIENT.len() - 1) as f32;
+    let index = (t as usize).min(GRADIENT.len() - 2);
+    let (from, to, t) = (GRADIENT[index], GRADIENT[index + 1], t - index as f32);
+    let channel = |i: usize| egui::lerp(from[i] as f32..=to[i] as f32, t).round() as u8;
+    Rgba([channel(0), channel(1), channel(2), u8::MAX])
+}
@@ -0,0 +1,118 @@
+use fluent::{FluentArgs, FluentBundle, FluentResource, FluentValue};
+use serde::{Deserialize, Serialize};
+use unic_langid::LanguageIdentifier;
+
+/// A language the viewer's interface is translated into.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    /// The language's name in that language, so it can be found whichever one is shown.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+
+    fn tag(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+        }
+    }
+
+    fn messages(self) -> &'static str {
+        match self {
+            Language::English => include_str!("locales/en.ftl"),
+            Language::German => include_str!("locales/de.ftl"),
+        }
+    }
+
+    /// The language of the system's locale if the viewer is translated into it, or else
+    /// English.
+    pub fn system() -> Language {
+        let locale = sys_locale::get_locale().unwrap_or_default();
+        let primary = locale.split(['-', '_']).next().unwrap_or_default();
+        Language::ALL
+            .into_iter()
+            .find(|language| language.tag().eq_ignore_ascii_case(primary))
+            .unwrap_or(Language::English)
+    }
+}
+
+/// Looks up the interface's text in one language, falling back to English for messages
+/// that haven't been translated yet.
+pub struct Localizer {
+    language: Language,
+    bundle: FluentBundle<FluentResource>,
+    fallback: Option<FluentBundle<FluentResource>>,
+}
+
+impl Localizer {
+    pub fn new(language: Language) -> Self {
+        Self {
+            language,
+            bundle: bundle(language),
+            fallback: (language != Language::English).then(|| bundle(Language::English)),
+        }
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// The message `id`, or its attribute if `id` is written `message.attribute`.
+    pub fn text(&self, id: &str) -> String {
+        self.format(id, &[])
+    }
+
+    /// Like [`Localizer::text`], filling in the message's variables from `args`.
+    pub fn format(&self, id: &str, args: &[(&str, FluentValue)]) -> String {
+        let (message, attribute) = match id.split_once('.') {
+            Some((message, attribute)) => (message, Some(attribute)),
+            None => (id, None),
+        };
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, value.clone());
+        }
+
+        for bundle in std::iter::once(&self.bundle).chain(&self.fallback) {
+            let Some(found) = bundle.get_message(message) else {
+                continue;
+            };
+            let pattern = match attribute {
+                Some(attribute) => found.get_attribute(attribute).map(|a| a.value()),
+                None => found.value(),
+            };
+            if let Some(pattern) = pattern {
+                let mut errors = Vec::new();
+                return bundle
+                    .format_pattern(pattern, Some(&fluent_args), &mut errors)
+                    .into_owned();
+            }
+        }
+        // A typo in an id shouldn't hide the control it labels.
+        id.to_owned()
+    }
+}
+
+fn bundle(language: Language) -> FluentBundle<FluentResource> {
+    let tag: LanguageIdentifier = language.tag().parse().expect("a valid language tag");
+    let resource = FluentResource::try_new(language.messages().to_owned())
+        .map_err(|(_, errors)| errors)
+        .expect("the translations parse");
+    let mut bundle = FluentBundle::new(vec![tag]);
+    // Fluent wraps variables in Unicode isolation marks, which egui draws as boxes.
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .expect("each message is defined once");
+    bundle
+}
@@ -0,0 +1,165 @@
+# Deutsche Übersetzung der Oberfläche des Viewers; siehe en.ftl.
+
+untitled = Unbenannt
+open = Öffnen
+save = Speichern
+add = Hinzufügen
+path-hint = pfad/zum/bild.bruh
+dismiss = Ausblenden
+
+## Fensterinhalt, wenn kein Bild angezeigt wird
+
+couldnt-open = { $path } konnte nicht geöffnet werden
+no-image = Kein Bild geöffnet
+drop-hint = Ziehe eine .bruh-Datei hierher oder gib unten ihren Pfad ein.
+
+## Warnung über einem Bild aus einer beschädigten Datei
+
+damaged-file = Diese Datei ist beschädigt, einige Pixel können falsch oder schwarz sein: { $error }
+
+## Menüs
+
+menu-file = Datei
+menu-open = Öffnen…
+menu-open-recent = Zuletzt geöffnet
+menu-clear-recent = Liste leeren
+menu-batch-convert = Stapelkonvertierung…
+menu-preferences = Einstellungen…
+menu-close-tab = Tab schließen
+menu-quit = Beenden
+menu-edit = Bearbeiten
+menu-copy-image = Bild kopieren
+menu-save-selection = Auswahl speichern unter…
+menu-deselect = Auswahl aufheben
+menu-undo-annotation = Anmerkung rückgängig machen
+menu-clear-annotations = Anmerkungen löschen
+menu-export-annotated = Bild mit Anmerkungen exportieren…
+menu-view = Ansicht
+menu-fullscreen = Vollbild
+menu-thumbnails = Miniaturansichten
+menu-zoom-in = Vergrößern
+menu-zoom-out = Verkleinern
+menu-pixel-grid = Pixelraster
+    .hint = Ab 800 % Zoom sichtbar (G)
+menu-minimap = Übersichtskarte
+    .hint = Sichtbar, wenn das Bild viel größer als das Fenster ist
+menu-adjustments = Korrekturen…
+menu-annotations = Anmerkungen
+menu-run-heatmap = Lauflängen-Heatmap
+    .hint = Färbt Pixel nach Lauflänge, rot für 1 bis blau für 255 (H)
+menu-onion-skin = Zwiebelschalen
+    .hint = Zeigt das vorige und nächste Bild einer Animation durchscheinend über dem aktuellen (O)
+menu-color-vision = Farbsehschwäche simulieren
+menu-reload = Bei Änderung neu laden
+menu-slideshow = Diashow
+
+zoom-fit = An Fenster anpassen
+zoom-actual-size = Originalgröße
+
+## Werkzeuge
+
+tool-select = Auswählen
+    .hint = Ziehen, um einen Bereich auszuwählen (M)
+tool-pick-color = Farbe aufnehmen
+    .hint = Klicken, um die Farbe eines Pixels zu kopieren (I)
+tool-rectangle = Rechteck
+    .hint = Ziehen, um ein Rechteck zu zeichnen (R)
+tool-arrow = Pfeil
+    .hint = Ziehen, um einen Pfeil zu zeichnen (L)
+tool-text = Text
+    .hint = Klicken, um Text zu platzieren (T)
+annotation-color = Farbe der Anmerkungen
+
+## Diashow-Menü
+
+slideshow-running = Läuft
+slideshow-interval = Intervall
+slideshow-shuffle = Zufällige Reihenfolge
+slideshow-loop = Wiederholen
+
+## Statusleiste
+
+status-run = Lauf von { $length }
+status-selection = Auswahl { $width }x{ $height } bei { $x }, { $y }
+status-frame = Bild { $frame } von { $frames }
+status-frame-paused = Bild { $frame } von { $frames }, angehalten (P zum Abspielen)
+status-ratio-hint = Kompressionsrate gegenüber unkomprimierten RGB-Pixeln
+
+notice-heatmap-failed = Lauflängen-Heatmap konnte nicht erstellt werden: { $error }
+notice-copied-image = Bild in die Zwischenablage kopiert
+notice-copy-failed = Bild konnte nicht kopiert werden: { $error }
+notice-copied-color = { $color } kopiert
+notice-saved-selection = Auswahl unter { $path } gespeichert
+notice-save-selection-failed = Auswahl konnte nicht gespeichert werden: { $error }
+notice-saved-annotated = Bild mit Anmerkungen unter { $path } gespeichert
+notice-save-annotated-failed = Bild mit Anmerkungen konnte nicht gespeichert werden: { $error }
+notice-annotations-failed = Anmerkungen konnten nicht gespeichert werden: { $error }
+
+## Fenster
+
+adjustments = Korrekturen
+preferences = Einstellungen
+add-text = Text hinzufügen
+save-selection = Auswahl speichern
+export-annotated = Bild mit Anmerkungen exportieren
+save-hint = Speichert je nach Dateiendung als PNG oder BRUH.
+
+## Korrekturen
+
+brightness = Helligkeit
+contrast = Kontrast
+gamma = Gamma
+reset = Zurücksetzen
+
+color-vision-normal = Normales Farbsehen
+color-vision-protanopia = Protanopie (kein Rot)
+color-vision-deuteranopia = Deuteranopie (kein Grün)
+color-vision-tritanopia = Tritanopie (kein Blau)
+
+## Einstellungen
+
+settings-language = Sprache
+settings-language-system = System ({ $language })
+settings-theme = Design
+settings-theme-dark = Dunkel
+settings-theme-light = Hell
+settings-background = Hintergrund
+settings-background-custom = Eigene Farbe
+settings-open-at = Bilder öffnen in
+settings-recent-files = Zuletzt geöffnete Dateien
+settings-window = Fenster
+settings-remember-window = Größe und Position merken
+    .hint = Sonst nimmt das Fenster die Größe jedes Bildes an. Wirkt nach einem Neustart.
+settings-restore-defaults = Standardwerte wiederherstellen
+
+filtering = Filterung
+filtering-auto = Automatisch
+filtering-nearest = Nächster Nachbar
+filtering-linear = Linear
+
+## Stapelkonvertierung
+
+batch-convert = Stapelkonvertierung
+batch-folder = Ordner
+batch-folder-hint = Ziehe einen Ordner hierher oder gib seinen Pfad ein
+batch-scan = Durchsuchen
+batch-include-subfolders = Unterordner einbeziehen
+batch-overwrite = Vorhandene Dateien überschreiben
+batch-script = Skript
+batch-script-hint = Optionale .rhai-Datei, die für jede Datei ausgeführt wird
+batch-start = Konvertieren
+batch-cancel = Abbrechen
+batch-no-files = Keine .{ $extension }-Dateien zum Konvertieren.
+
+batch-waiting = Wartet
+batch-converting = Wird konvertiert…
+batch-done = Fertig
+batch-skipped = Übersprungen
+batch-failed = Fehlgeschlagen
+batch-cancelled = Abgebrochen
+batch-output-exists = Ausgabedatei existiert bereits
+batch-skipped-by-script = Vom Skript übersprungen
+
+batch-cant-write = In { $folder } kann nicht geschrieben werden: { $error }
+batch-not-enough-space = Die konvertierten Dateien können bis zu { $needed } belegen, aber nur { $available } sind frei.
+batch-free-space-failed = Freier Speicherplatz konnte nicht geprüft werden: { $error }
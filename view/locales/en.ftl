@@ -0,0 +1,166 @@
+# Strings of the viewer's interface, in Project Fluent syntax (https://projectfluent.org).
+# Every other language's file has the same messages; any it's missing are shown in English.
+
+untitled = Untitled
+open = Open
+save = Save
+add = Add
+path-hint = path/to/image.bruh
+dismiss = Dismiss
+
+## Window contents when no image is shown
+
+couldnt-open = Couldn't open { $path }
+no-image = No image open
+drop-hint = Drop a .bruh file here or enter its path below.
+
+## Warning above an image from a damaged file
+
+damaged-file = This file is damaged, so some pixels may be wrong or black: { $error }
+
+## Menus
+
+menu-file = File
+menu-open = Open…
+menu-open-recent = Open Recent
+menu-clear-recent = Clear Recent
+menu-batch-convert = Batch Convert…
+menu-preferences = Preferences…
+menu-close-tab = Close Tab
+menu-quit = Quit
+menu-edit = Edit
+menu-copy-image = Copy Image
+menu-save-selection = Save Selection As…
+menu-deselect = Deselect
+menu-undo-annotation = Undo Annotation
+menu-clear-annotations = Clear Annotations
+menu-export-annotated = Export Annotated Image…
+menu-view = View
+menu-fullscreen = Fullscreen
+menu-thumbnails = Thumbnails
+menu-zoom-in = Zoom In
+menu-zoom-out = Zoom Out
+menu-pixel-grid = Pixel Grid
+    .hint = Shown from 800% zoom (G)
+menu-minimap = Minimap
+    .hint = Shown when the image is much larger than the window
+menu-adjustments = Adjustments…
+menu-annotations = Annotations
+menu-run-heatmap = Run Heatmap
+    .hint = Colors pixels by run length, red for 1 to blue for 255 (H)
+menu-onion-skin = Onion Skin
+    .hint = Shows the frames before and after the current one of an animation faintly over it (O)
+menu-color-vision = Simulate Color Vision
+menu-reload = Reload on Change
+menu-slideshow = Slideshow
+
+zoom-fit = Fit to Window
+zoom-actual-size = Actual Size
+
+## Tools
+
+tool-select = Select
+    .hint = Drag to select a region (M)
+tool-pick-color = Pick Color
+    .hint = Click to copy a pixel's color (I)
+tool-rectangle = Rectangle
+    .hint = Drag to draw a rectangle (R)
+tool-arrow = Arrow
+    .hint = Drag to draw an arrow (L)
+tool-text = Text
+    .hint = Click to place text (T)
+annotation-color = Annotation color
+
+## Slideshow menu
+
+slideshow-running = Running
+slideshow-interval = Interval
+slideshow-shuffle = Shuffle
+slideshow-loop = Loop
+
+## Status bar
+
+status-run = Run of { $length }
+status-selection = Selection { $width }x{ $height } at { $x }, { $y }
+status-frame = Frame { $frame } of { $frames }
+status-frame-paused = Frame { $frame } of { $frames }, paused (P to play)
+status-ratio-hint = Compression ratio against raw RGB pixels
+
+notice-heatmap-failed = Couldn't build run heatmap: { $error }
+notice-copied-image = Copied image to clipboard
+notice-copy-failed = Couldn't copy image: { $error }
+notice-copied-color = Copied { $color }
+notice-saved-selection = Saved selection to { $path }
+notice-save-selection-failed = Couldn't save selection: { $error }
+notice-saved-annotated = Saved annotated image to { $path }
+notice-save-annotated-failed = Couldn't save annotated image: { $error }
+notice-annotations-failed = Couldn't save annotations: { $error }
+
+## Windows
+
+adjustments = Adjustments
+preferences = Preferences
+add-text = Add Text
+save-selection = Save Selection
+export-annotated = Export Annotated Image
+save-hint = Saves as PNG or BRUH depending on the extension.
+
+## Adjustments window
+
+brightness = Brightness
+contrast = Contrast
+gamma = Gamma
+reset = Reset
+
+color-vision-normal = Normal Vision
+color-vision-protanopia = Protanopia (no red)
+color-vision-deuteranopia = Deuteranopia (no green)
+color-vision-tritanopia = Tritanopia (no blue)
+
+## Preferences window
+
+settings-language = Language
+settings-language-system = System ({ $language })
+settings-theme = Theme
+settings-theme-dark = Dark
+settings-theme-light = Light
+settings-background = Background
+settings-background-custom = Custom
+settings-open-at = Open images at
+settings-recent-files = Recent files
+settings-window = Window
+settings-remember-window = Remember size and position
+    .hint = Otherwise the window takes the size of each image. Takes effect on restart.
+settings-restore-defaults = Restore Defaults
+
+filtering = Filtering
+filtering-auto = Auto
+filtering-nearest = Nearest Neighbor
+filtering-linear = Linear
+
+## Batch Convert window
+
+batch-convert = Batch Convert
+batch-folder = Folder
+batch-folder-hint = Drop a folder here or enter its path
+batch-scan = Scan
+batch-include-subfolders = Include subfolders
+batch-overwrite = Overwrite existing files
+batch-script = Script
+batch-script-hint = Optional .rhai file run for each file
+batch-start = Convert
+batch-cancel = Cancel
+batch-no-files = No .{ $extension } files to convert.
+
+batch-waiting = Waiting
+batch-converting = Converting…
+batch-done = Done
+batch-skipped = Skipped
+batch-failed = Failed
+batch-cancelled = Cancelled
+batch-output-exists = Output already exists
+batch-skipped-by-script = Skipped by the script
+
+batch-cant-write = Can't write to { $folder }: { $error }
+batch-not-enough-space = The converted files can take up to { $needed }, but only { $available } is free.
+batch-free-space-failed = Couldn't check free space: { $error }
@@ -0,0 +1,117 @@
+use bruh::{decode_bruh, decode_bruh_region, DecodeError, Region, Runs};
+use image::{imageops, Rgba, RgbaImage};
+
+use std::borrow::Cow;
+
+/// Images with more pixels than this are decoded a region at a time, as they're looked at,
+/// instead of all at once before they're shown. 64 megapixels take 256 MiB decoded.
+pub const LAZY_DECODE_PIXELS: u64 = 1 << 26;
+
+/// The pixels of an open image: decoded up front, or kept as the runs of a .bruh file and
+/// decoded only where they're needed.
+pub enum ImagePixels {
+    Decoded(RgbaImage),
+    /// A file that's been checked against its header, so any region of it decodes.
+    Lazy {
+        contents: Vec<u8>,
+        width: u32,
+        height: u32,
+    },
+}
+
+impl ImagePixels {
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            ImagePixels::Decoded(image) => image.dimensions(),
+            ImagePixels::Lazy { width, height, .. } => (*width, *height),
+        }
+    }
+
+    /// The pixels of `region`, which has to be inside the image.
+    pub fn region(&self, region: Region) -> Result<RgbaImage, DecodeError> {
+        let Region {
+            x,
+            y,
+            width,
+            height,
+        } = region;
+        match self {
+            ImagePixels::Decoded(image) => {
+                Ok(imageops::crop_imm(image, x, y, width, height).to_image())
+            }
+            ImagePixels::Lazy { contents, .. } => {
+                let decoded = decode_bruh_region(contents, region)?;
+                Ok(RgbaImage::from_raw(width, height, decoded.pixels).expect("the region's size"))
+            }
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Result<[u8; 4], DecodeError> {
+        match self {
+            ImagePixels::Decoded(image) => Ok(image.get_pixel(x, y).0),
+            ImagePixels::Lazy { .. } => {
+                let region = Region {
+                    x,
+                    y,
+                    width: 1,
+                    height: 1,
+                };
+                Ok(self.region(region)?.get_pixel(0, 0).0)
+            }
+        }
+    }
+
+    /// The whole image, decoding it if it's lazy.
+    pub fn to_image(&self) -> Result<Cow<'_, RgbaImage>, DecodeError> {
+        match self {
+            ImagePixels::Decoded(image) => Ok(Cow::Borrowed(image)),
+            ImagePixels::Lazy {
+                contents,
+                width,
+                height,
+            } => {
+                let decoded = decode_bruh(contents)?;
+                let image = RgbaImage::from_raw(*width, *height, decoded.pixels);
+                Ok(Cow::Owned(image.expect("the header's size")))
+            }
+        }
+    }
+
+    /// A copy scaled down to `width` × `height`.
+    ///
+    /// A lazy image is sampled at the nearest pixel while its runs are read, rather than
+    /// averaged, so making it takes no more memory than the copy.
+    pub fn thumbnail(&self, width: u32, height: u32) -> RgbaImage {
+        let (contents, full_width, full_height) = match self {
+            ImagePixels::Decoded(image) => return imageops::thumbnail(image, width, height),
+            ImagePixels::Lazy {
+                contents,
+                width,
+                height,
+            } => (contents, *width as u64, *height as u64),
+        };
+
+        let mut samples = (0..height as u64).flat_map(move |y| {
+            let row = y * full_height / height as u64 * full_width;
+            (0..width as u64).map(move |x| row + x * full_width / width as u64)
+        });
+        let mut thumbnail = RgbaImage::new(width, height);
+        let mut pixels = thumbnail.pixels_mut();
+        let mut next = samples.next();
+
+        let runs = Runs::of(contents);
+        let pixel_count = full_width * full_height;
+        let mut pos = 0;
+        for run in runs.bytes.chunks_exact(4) {
+            let end = pos + runs.length(run[0], pixel_count - pos);
+            while next.is_some_and(|sample| sample < end) {
+                if let Some(pixel) = pixels.next() {
+                    *pixel = Rgba([run[1], run[2], run[3], u8::MAX]);
+                }
+                next = samples.next();
+            }
+            pos = end;
+        }
+        thumbnail
+    }
+}
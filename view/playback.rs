@@ -0,0 +1,253 @@
+use bruh::{animation::Animation, decode_bruh_lenient, DecodeError};
+use eframe::egui;
+use image::RgbaImage;
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+/// Shortest time a frame is shown, as browsers do for GIFs with a delay of 0.
+const MIN_DELAY: Duration = Duration::from_millis(20);
+/// Frames after the current one that are decoded ahead of time.
+const PREFETCH: usize = 4;
+const MAX_WORKERS: usize = 4;
+/// How strongly the frames before and after the current one show through it with onion
+/// skinning.
+const ONION_SKIN_ALPHA: f32 = 0.3;
+
+type Job = (usize, egui::Context);
+type JobResult = (usize, Result<RgbaImage, DecodeError>);
+
+/// Plays an animated file in a loop.
+///
+/// Frames are decoded on a few worker threads a little ahead of the one shown, so the UI
+/// never waits for one, and only those near the current frame are kept, so a long
+/// recording takes no more memory than its file and a handful of frames. If a frame isn't
+/// ready when it's due, the current one stays up until it is.
+pub struct Playback {
+    /// Where each frame is in the file, and how long it's shown.
+    frames: Arc<Vec<(Range<usize>, Duration)>>,
+    current: usize,
+    /// When the current frame was first shown, in egui's time.
+    shown_at: Option<f64>,
+    pub paused: bool,
+    /// Whether the frames before and after the current one are drawn faintly over it.
+    onion_skin: bool,
+    /// Whether what [`Playback::frame`] gives has changed without the frame changing.
+    changed: bool,
+    /// Decoded frames near the current one. The current frame is always here.
+    decoded: HashMap<usize, Result<RgbaImage, DecodeError>>,
+    /// Frames sent to the workers that haven't come back yet.
+    requested: HashSet<usize>,
+    jobs: mpsc::Sender<Job>,
+    results: mpsc::Receiver<JobResult>,
+}
+
+impl Playback {
+    /// Takes the contents of an animated file, returning `None` if it isn't one. The first
+    /// frame is decoded straight away.
+    pub fn new(contents: Vec<u8>) -> Result<Option<Self>, DecodeError> {
+        let Some(animation) = Animation::of(&contents)? else {
+            return Ok(None);
+        };
+        let frames: Vec<_> = animation
+            .frames
+            .iter()
+            .map(|frame| {
+                let delay = Duration::from_millis(frame.delay_ms as u64).max(MIN_DELAY);
+                (frame.offset..frame.offset + frame.contents.len(), delay)
+            })
+            .collect();
+        let frames = Arc::new(frames);
+        let contents = Arc::new(contents);
+
+        let mut decoded = HashMap::new();
+        if !frames.is_empty() {
+            decoded.insert(0, decode_frame(&contents, &frames, 0));
+        }
+
+        let (jobs, job_queue) = mpsc::channel::<Job>();
+        let (result_sender, results) = mpsc::channel();
+        let job_queue = Arc::new(Mutex::new(job_queue));
+        let workers = thread::available_parallelism().map_or(1, |n| n.get());
+        for _ in 0..workers.min(MAX_WORKERS).min(frames.len().saturating_sub(1)) {
+            let job_queue = Arc::clone(&job_queue);
+            let result_sender = result_sender.clone();
+            let contents = Arc::clone(&contents);
+            let frames = Arc::clone(&frames);
+            // The workers stop once the playback is dropped and the job queue with it.
+            thread::spawn(move || loop {
+                let job = match job_queue.lock() {
+                    Ok(queue) => queue.recv(),
+                    Err(_) => break,
+                };
+                let Ok((index, ctx)) = job else { break };
+
+                let frame = decode_frame(&contents, &frames, index);
+                if result_sender.send((index, frame)).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            });
+        }
+
+        Ok(Some(Self {
+            frames,
+            current: 0,
+            shown_at: None,
+            paused: false,
+            onion_skin: false,
+            changed: false,
+            decoded,
+            requested: HashSet::new(),
+            jobs,
+            results,
+        }))
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Index of the frame being shown.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// The pixels of the frame being shown, as far as they go if it's damaged. With onion
+    /// skinning, the frames before and after it are drawn over it at reduced opacity, once
+    /// they're decoded.
+    pub fn frame(&self) -> Result<RgbaImage, DecodeError> {
+        let mut frame = self
+            .decoded
+            .get(&self.current)
+            .cloned()
+            .unwrap_or(Err(DecodeError::Empty))?;
+        if self.onion_skin {
+            for index in self.neighbours() {
+                if let Some(Ok(neighbour)) = self.decoded.get(&index) {
+                    blend(&mut frame, neighbour, ONION_SKIN_ALPHA);
+                }
+            }
+        }
+        Ok(frame)
+    }
+
+    /// Turns onion skinning on or off.
+    pub fn set_onion_skin(&mut self, onion_skin: bool) {
+        self.changed |= self.onion_skin != onion_skin;
+        self.onion_skin = onion_skin;
+    }
+
+    /// The frames before and after the current one, which onion skinning shows. They're
+    /// the same frame in an animation of two.
+    fn neighbours(&self) -> Vec<usize> {
+        let len = self.frames.len();
+        let mut neighbours = Vec::new();
+        for index in [(self.current + len - 1) % len, (self.current + 1) % len] {
+            if index != self.current && !neighbours.contains(&index) {
+                neighbours.push(index);
+            }
+        }
+        neighbours
+    }
+
+    /// Moves on to the next frame once the current one has been shown for its delay and
+    /// the next one is decoded. Returns whether [`Playback::frame`] changed, which it also
+    /// does when onion skinning is turned on or off or a frame it shows arrives. Asks for a
+    /// repaint when the next frame is due.
+    pub fn advance(&mut self, ctx: &egui::Context) -> bool {
+        self.receive();
+        self.request(ctx);
+        let changed = std::mem::take(&mut self.changed);
+        if self.paused || self.frames.len() < 2 {
+            self.shown_at = None;
+            return changed;
+        }
+
+        let now = ctx.input(|i| i.time);
+        let shown_at = *self.shown_at.get_or_insert(now);
+        let delay = self.frames[self.current].1.as_secs_f64();
+        let next = (self.current + 1) % self.frames.len();
+        let due = now - shown_at >= delay;
+        if due && !self.decoded.contains_key(&next) {
+            // A worker asks for a repaint once it's done.
+            return changed;
+        }
+        if due {
+            self.current = next;
+            self.shown_at = Some(now);
+            self.request(ctx);
+        }
+        let shown_at = self.shown_at.unwrap_or(now);
+        let remaining = self.frames[self.current].1.as_secs_f64() - (now - shown_at);
+        ctx.request_repaint_after(Duration::from_secs_f64(remaining.max(0.0)));
+        changed || due
+    }
+
+    /// The frames to keep decoded: the one before the current one, the current one and a
+    /// few after it.
+    fn wanted(&self) -> Vec<usize> {
+        let len = self.frames.len();
+        let mut wanted = vec![self.current];
+        for offset in (1..=PREFETCH).chain([len - 1]) {
+            let index = (self.current + offset) % len;
+            if !wanted.contains(&index) {
+                wanted.push(index);
+            }
+        }
+        wanted
+    }
+
+    /// Takes the frames the workers have finished, keeping the ones still wanted.
+    fn receive(&mut self) {
+        let wanted = self.wanted();
+        let neighbours = self.neighbours();
+        while let Ok((index, frame)) = self.results.try_recv() {
+            self.requested.remove(&index);
+            if wanted.contains(&index) {
+                self.changed |= self.onion_skin && neighbours.contains(&index);
+                self.decoded.insert(index, frame);
+            }
+        }
+    }
+
+    /// Drops the frames that aren't wanted any more and asks the workers for the missing
+    /// ones, in the order they'll be shown.
+    fn request(&mut self, ctx: &egui::Context) {
+        let wanted = self.wanted();
+        self.decoded.retain(|index, _| wanted.contains(index));
+        for index in wanted {
+            if self.decoded.contains_key(&index) || self.requested.contains(&index) {
+                continue;
+            }
+            if self.jobs.send((index, ctx.clone())).is_ok() {
+                self.requested.insert(index);
+            }
+        }
+    }
+}
+
+/// Draws `over` on `frame` with the opacity `alpha`.
+fn blend(frame: &mut RgbaImage, over: &RgbaImage, alpha: f32) {
+    for (pixel, over) in frame.pixels_mut().zip(over.pixels()) {
+        for (channel, over) in pixel.0.iter_mut().zip(over.0).take(3) {
+            *channel = (*channel as f32 + (over as f32 - *channel as f32) * alpha).round() as u8;
+        }
+    }
+}
+
+fn decode_frame(
+    contents: &[u8],
+    frames: &[(Range<usize>, Duration)],
+    index: usize,
+) -> Result<RgbaImage, DecodeError> {
+    let (range, _) = &frames[index];
+    let (decoded, _) = decode_bruh_lenient(&contents[range.clone()])?;
+    Ok(RgbaImage::from_raw(decoded.width, decoded.height, decoded.pixels)
+        .expect("the frame's size"))
+}
@@ -0,0 +1,118 @@
+use std::path::{Path, PathBuf};
+
+use bruh::ColorAdjustments;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+
+/// What a batch script decided for one file.
+pub enum Decision {
+    /// Don't convert the file, for the reason given, if any.
+    Skip(Option<String>),
+    Convert {
+        output: PathBuf,
+        adjustments: ColorAdjustments,
+    },
+}
+
+/// A Rhai script that a batch runs for every file before converting it.
+///
+/// The script defines `process(file)`, where `file` is a map of `path`, `name`, `stem`,
+/// `folder`, `output` and `direction`, plus `width` and `height` if the header could be
+/// read. It returns one of:
+///
+/// - nothing or `true`, to convert the file as usual;
+/// - `false`, to skip it;
+/// - a string, to write the output there instead, relative to the file's folder;
+/// - a map with any of `skip` (a bool, or a string saying why), `output`, and the
+///   `brightness`, `contrast`, `saturate` and `grayscale` options of `compile`.
+pub struct BatchScript {
+    engine: Engine,
+    ast: AST,
+}
+
+impl BatchScript {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let engine = Engine::new();
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|e| format!("Couldn't load the script {}: {}", path.display(), e))?;
+        if !ast.iter_functions().any(|f| f.name == "process" && f.params.len() == 1) {
+            return Err(format!("{} doesn't define process(file).", path.display()));
+        }
+        Ok(Self { engine, ast })
+    }
+
+    /// Runs the script for `source`, which would be converted to `output` by default.
+    pub fn decide(
+        &self,
+        source: &Path,
+        output: &Path,
+        direction: &str,
+        size: Option<(u32, u32)>,
+    ) -> Result<Decision, String> {
+        let text = |path: &Path| Dynamic::from(path.to_string_lossy().into_owned());
+        let folder = source.parent().unwrap_or(Path::new(""));
+        let mut file = Map::new();
+        file.insert("path".into(), text(source));
+        file.insert("name".into(), text(Path::new(source.file_name().unwrap_or_default())));
+        file.insert("stem".into(), text(Path::new(source.file_stem().unwrap_or_default())));
+        file.insert("folder".into(), text(folder));
+        file.insert("output".into(), text(output));
+        file.insert("direction".into(), Dynamic::from(direction.to_owned()));
+        if let Some((width, height)) = size {
+            file.insert("width".into(), Dynamic::from(width as i64));
+            file.insert("height".into(), Dynamic::from(height as i64));
+        }
+
+        let result: Dynamic = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, "process", (file,))
+            .map_err(|e| format!("The script failed: {}", e))?;
+
+        let mut output = output.to_path_buf();
+        let mut adjustments = ColorAdjustments::default();
+        if result.is_unit() {
+            // Convert as usual.
+        } else if let Ok(convert) = result.as_bool() {
+            if !convert {
+                return Ok(Decision::Skip(None));
+            }
+        } else if result.is_string() {
+            output = folder.join(result.into_string()?);
+        } else if result.is::<Map>() {
+            for (key, value) in result.cast::<Map>() {
+                let number = |value: &Dynamic| {
+                    value
+                        .as_float()
+                        .or_else(|_| value.as_int().map(|n| n as f64))
+                        .ok()
+                        .filter(|n| n.is_finite() && *n >= 0.0)
+                        .map(|n| n as f32)
+                        .ok_or_else(|| format!("The script's {} isn't a factor of 0 or more.", key))
+                };
+                match key.as_str() {
+                    "skip" => {
+                        if value.is_string() {
+                            return Ok(Decision::Skip(Some(value.into_string()?)));
+                        }
+                        if value.as_bool() == Ok(true) {
+                            return Ok(Decision::Skip(None));
+                        }
+                    }
+                    "output" => output = folder.join(value.into_string()?),
+                    "brightness" => adjustments.brightness = number(&value)?,
+                    "contrast" => adjustments.contrast = number(&value)?,
+                    "saturate" => adjustments.saturation = number(&value)?,
+                    "grayscale" => adjustments.grayscale = value.as_bool()?,
+                    _ => return Err(format!("The script returned an unknown option '{}'.", key)),
+                }
+            }
+        } else {
+            return Err(format!("The script returned a {}.", result.type_name()));
+        }
+
+        Ok(Decision::Convert {
+            output,
+            adjustments,
+        })
+    }
+}
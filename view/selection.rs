@@ -0,0 +1,80 @@
+use bruh::{encode_bruh, flatten_alpha, DecodeError, Region};
+use eframe::egui;
+use image::{ImageFormat, RgbaImage};
+
+use std::{error::Error, fs, path::Path};
+
+use super::pixels::ImagePixels;
+
+/// A rectangle of image pixels, picked by dragging across the image.
+#[derive(Clone, Copy)]
+pub struct Selection {
+    anchor: [u32; 2],
+    cursor: [u32; 2],
+}
+
+impl Selection {
+    pub fn new(pixel: [u32; 2]) -> Self {
+        Self {
+            anchor: pixel,
+            cursor: pixel,
+        }
+    }
+
+    pub fn drag_to(&mut self, pixel: [u32; 2]) {
+        self.cursor = pixel;
+    }
+
+    /// Top-left pixel of the selection.
+    pub fn min(&self) -> [u32; 2] {
+        [
+            self.anchor[0].min(self.cursor[0]),
+            self.anchor[1].min(self.cursor[1]),
+        ]
+    }
+
+    /// Width and height in pixels; both ends of the drag are included.
+    pub fn size(&self) -> [u32; 2] {
+        [
+            self.anchor[0].abs_diff(self.cursor[0]) + 1,
+            self.anchor[1].abs_diff(self.cursor[1]) + 1,
+        ]
+    }
+
+    /// The selection in screen space, for an image drawn into `image_rect` at `zoom`.
+    pub fn screen_rect(&self, image_rect: egui::Rect, zoom: f32) -> egui::Rect {
+        let [x, y] = self.min();
+        let [width, height] = self.size();
+        let min = image_rect.min + egui::vec2(x as f32, y as f32) * zoom;
+        egui::Rect::from_min_size(min, egui::vec2(width as f32, height as f32) * zoom)
+    }
+
+    pub fn crop(&self, image: &ImagePixels) -> Result<RgbaImage, DecodeError> {
+        let ([x, y], [width, height]) = (self.min(), self.size());
+        image.region(Region {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+}
+
+/// Writes `image` as PNG or BRUH depending on the extension of `path`.
+pub fn save(image: &RgbaImage, path: &Path) -> Result<(), Box<dyn Error>> {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "png" => image.save_with_format(path, ImageFormat::Png)?,
+        "bruh" => {
+            let pixels = image.pixels().map(|pixel| flatten_alpha(pixel.0));
+            fs::write(path, encode_bruh(image.width(), image.height(), pixels))?;
+        }
+        _ => return Err("Unsupported file type, use .png or .bruh.".into()),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use eframe::{egui, Storage};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    canvas::{Filtering, ZoomMode},
+    locale::{Language, Localizer},
+};
+
+const SETTINGS_KEY: &str = "settings";
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub fn visuals(self) -> egui::Visuals {
+        match self {
+            Theme::Dark => egui::Visuals::dark(),
+            Theme::Light => egui::Visuals::light(),
+        }
+    }
+}
+
+/// Preferences edited in the Preferences window and kept between runs.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Language of the interface, or the system's when `None`.
+    pub language: Option<Language>,
+    /// Fill behind the image, or the theme's panel color when `None`.
+    pub background: Option<[u8; 3]>,
+    pub zoom_mode: ZoomMode,
+    /// Filtering the viewer starts with; the View menu only changes it until restart.
+    pub filtering: Filtering,
+    pub theme: Theme,
+    pub max_recent_files: usize,
+    /// Whether the window is resizable and reopens where it was, rather than taking the
+    /// size of each image.
+    pub remember_window: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            language: None,
+            background: None,
+            zoom_mode: ZoomMode::Fit,
+            filtering: Filtering::Auto,
+            theme: Theme::Dark,
+            max_recent_files: 10,
+            remember_window: true,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(storage: &dyn Storage) -> Self {
+        storage
+            .get_string(SETTINGS_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) {
+        if let Ok(json) = serde_json::to_string(self) {
+            storage.set_string(SETTINGS_KEY, json);
+            storage.flush();
+        }
+    }
+
+    pub fn background_color(&self) -> Option<egui::Color32> {
+        self.background.map(|[r, g, b]| egui::Color32::from_rgb(r, g, b))
+    }
+
+    /// The language the interface is shown in.
+    pub fn language(&self) -> Language {
+        self.language.unwrap_or_else(Language::system)
+    }
+
+    /// Draws the preferences and returns whether anything changed.
+    pub fn show(&mut self, ui: &mut egui::Ui, locale: &Localizer) -> bool {
+        let before = self.clone();
+
+        egui::Grid::new("settings").num_columns(2).show(ui, |ui| {
+            ui.label(locale.text("settings-language"));
+            let system = locale.format(
+                "settings-language-system",
+                &[("language", Language::system().name().into())],
+            );
+            let selected = self.language.map_or_else(|| system.clone(), |l| l.name().to_owned());
+            egui::ComboBox::from_id_source("language")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.language, None, system.as_str());
+                    for language in Language::ALL {
+                        ui.selectable_value(&mut self.language, Some(language), language.name());
+                    }
+                });
+            ui.end_row();
+
+            ui.label(locale.text("settings-theme"));
+            ui.horizontal(|ui| {
+                let (dark, light) = (Theme::Dark, Theme::Light);
+                ui.radio_value(&mut self.theme, dark, locale.text("settings-theme-dark"));
+                ui.radio_value(&mut self.theme, light, locale.text("settings-theme-light"));
+            });
+            ui.end_row();
+
+            ui.label(locale.text("settings-background"));
+            ui.horizontal(|ui| {
+                let mut custom = self.background.is_some();
+                let label = locale.text("settings-background-custom");
+                if ui.checkbox(&mut custom, label).changed() {
+                    self.background = custom.then_some([0, 0, 0]);
+                }
+                if let Some(color) = &mut self.background {
+                    ui.color_edit_button_srgb(color);
+                }
+            });
+            ui.end_row();
+
+            ui.label(locale.text("settings-open-at"));
+            ui.horizontal(|ui| {
+                let (fit, actual_size) = (ZoomMode::Fit, ZoomMode::ActualSize);
+                ui.radio_value(&mut self.zoom_mode, fit, locale.text("zoom-fit"));
+                ui.radio_value(&mut self.zoom_mode, actual_size, locale.text("zoom-actual-size"));
+            });
+            ui.end_row();
+
+            ui.label(locale.text("filtering"));
+            egui::ComboBox::from_id_source("filtering")
+                .selected_text(self.filtering.label(locale))
+                .show_ui(ui, |ui| {
+                    for filtering in Filtering::ALL {
+                        let label = filtering.label(locale);
+                        ui.selectable_value(&mut self.filtering, filtering, label);
+                    }
+                });
+            ui.end_row();
+
+            ui.label(locale.text("settings-recent-files"));
+            ui.add(egui::DragValue::new(&mut self.max_recent_files).clamp_range(0..=50));
+            ui.end_row();
+
+            ui.label(locale.text("settings-window"));
+            ui.checkbox(&mut self.remember_window, locale.text("settings-remember-window"))
+                .on_hover_text(locale.text("settings-remember-window.hint"));
+            ui.end_row();
+        });
+
+        ui.separator();
+        if ui.button(locale.text("settings-restore-defaults")).clicked() {
+            *self = Self::default();
+        }
+
+        *self != before
+    }
+}
@@ -0,0 +1,108 @@
+use eframe::egui;
+use rand::seq::SliceRandom;
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Cycles through the files of the open image's directory at a fixed interval.
+pub struct Slideshow {
+    pub interval_secs: f32,
+    pub shuffle: bool,
+    pub looping: bool,
+    running: bool,
+    last_advance: f64,
+    /// Files left in the current shuffled pass, `None` until the first pass starts.
+    shuffled: Option<Vec<PathBuf>>,
+}
+
+impl Default for Slideshow {
+    fn default() -> Self {
+        Self {
+            interval_secs: 5.0,
+            shuffle: false,
+            looping: true,
+            running: false,
+            last_advance: 0.0,
+            shuffled: None,
+        }
+    }
+}
+
+impl Slideshow {
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn set_running(&mut self, ctx: &egui::Context, running: bool) {
+        self.running = running;
+        self.last_advance = ctx.input(|i| i.time);
+        self.shuffled = None;
+    }
+
+    /// Whether the interval has elapsed and the next file should be shown.
+    pub fn is_due(&self, ctx: &egui::Context) -> bool {
+        if !self.running {
+            return false;
+        }
+
+        let elapsed = ctx.input(|i| i.time) - self.last_advance;
+        let remaining = self.interval_secs as f64 - elapsed;
+        if remaining > 0.0 {
+            ctx.request_repaint_after(Duration::from_secs_f64(remaining));
+        }
+        remaining <= 0.0
+    }
+
+    /// Picks the next file among `paths`, stopping at the end unless looping.
+    pub fn advance(
+        &mut self,
+        ctx: &egui::Context,
+        paths: &[PathBuf],
+        current: Option<&Path>,
+    ) -> Option<PathBuf> {
+        self.last_advance = ctx.input(|i| i.time);
+
+        let next = if self.shuffle {
+            self.next_shuffled(paths, current)
+        } else {
+            self.next_in_order(paths, current)
+        };
+        if next.is_none() {
+            self.running = false;
+        }
+        next
+    }
+
+    fn next_in_order(&self, paths: &[PathBuf], current: Option<&Path>) -> Option<PathBuf> {
+        let index = current.and_then(|current| paths.iter().position(|path| path == current));
+        match index {
+            None => paths.first().cloned(),
+            Some(index) if index + 1 < paths.len() => Some(paths[index + 1].clone()),
+            Some(_) if self.looping => paths.first().cloned(),
+            Some(_) => None,
+        }
+    }
+
+    /// Visits every file once in random order before repeating any of them.
+    fn next_shuffled(&mut self, paths: &[PathBuf], current: Option<&Path>) -> Option<PathBuf> {
+        let pass_finished = self.shuffled.as_ref().is_some_and(Vec::is_empty);
+        if pass_finished && !self.looping {
+            return None;
+        }
+
+        let remaining = self.shuffled.get_or_insert_with(Vec::new);
+        remaining.retain(|path| paths.contains(path));
+        if remaining.is_empty() {
+            remaining.extend(
+                paths
+                    .iter()
+                    .filter(|path| Some(path.as_path()) != current)
+                    .cloned(),
+            );
+            remaining.shuffle(&mut rand::thread_rng());
+        }
+        remaining.pop()
+    }
+}
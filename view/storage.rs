@@ -0,0 +1,63 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use bruh::file::config_dir;
+
+/// [`eframe::Storage`] backed by a JSON file in the user's config directory.
+///
+/// eframe only ships its own storage with the `persistence` feature, which pulls in
+/// `ron` and `directories-next`; a flat string map is all the viewer needs.
+pub struct FileStorage {
+    path: Option<PathBuf>,
+    values: BTreeMap<String, String>,
+    dirty: bool,
+}
+
+impl FileStorage {
+    pub fn load(app_name: &str) -> Self {
+        let path = config_dir().map(|dir| dir.join(app_name).join("viewer.json"));
+        let values = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            values,
+            dirty: false,
+        }
+    }
+}
+
+impl eframe::Storage for FileStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        if self.values.get(key) != Some(&value) {
+            self.values.insert(key.to_owned(), value);
+            self.dirty = true;
+        }
+    }
+
+    fn flush(&mut self) {
+        let Some(path) = &self.path else { return };
+        if !self.dirty {
+            return;
+        }
+
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| {
+                let json = serde_json::to_string_pretty(&self.values)?;
+                fs::write(path, json)
+            });
+
+        match result {
+            Ok(()) => self.dirty = false,
+            Err(e) => eprintln!("Failed to save settings to {}: {}", path.display(), e),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use bruh::{animation::Animation, decode_bruh, file::read_input};
+use image::{ColorType, ImageFormat};
+
+use std::{
+    env,
+    error::Error,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+/// Shows the .bruh files at `paths` in the platform's default image viewer, for when the
+/// viewer can't open a window, e.g. without a display or a working GPU driver.
+///
+/// Each image is converted to a PNG in the temporary folder first. The PNGs are left
+/// there, since the other viewer reads them after this process has exited.
+pub fn open(paths: &[PathBuf]) -> Result<(), Box<dyn Error>> {
+    for path in paths {
+        let png = export_png(path)
+            .map_err(|e| format!("Couldn't convert {}: {}", path.display(), e))?;
+        launch(&png).map_err(|e| format!("Couldn't open {}: {}", png.display(), e))?;
+    }
+    Ok(())
+}
+
+fn export_png(path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let mut contents = Vec::new();
+    read_input(path, &mut contents)?;
+    // An animation stands for itself with its first frame.
+    let animation = Animation::of(&contents)?;
+    let first_frame = animation.and_then(|animation| Some(animation.frames.first()?.contents));
+    let decoded = decode_bruh(first_frame.unwrap_or(&contents))?;
+    if decoded.is_empty() {
+        return Err(format!(
+            "PNG can't store an empty image ({}×{} pixels).",
+            decoded.width, decoded.height
+        )
+        .into());
+    }
+
+    // The process id keeps two viewers started at once from overwriting each other's files.
+    let stem = path.file_stem().unwrap_or(path.as_os_str()).to_string_lossy();
+    let png = env::temp_dir().join(format!("bruh-{}-{}.png", process::id(), stem));
+    image::save_buffer_with_format(
+        &png,
+        &decoded.pixels,
+        decoded.width,
+        decoded.height,
+        ColorType::Rgba8,
+        ImageFormat::Png,
+    )?;
+    Ok(png)
+}
+
+/// Opens `path` with whatever the desktop associates with its file type.
+fn launch(path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut command = if cfg!(windows) {
+        // `start` is built into cmd; its first quoted argument is the window title.
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(path).spawn()?;
+    Ok(())
+}
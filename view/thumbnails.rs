@@ -0,0 +1,245 @@
+use bruh::{animation::Animation, decode_bruh, file::read_input};
+use eframe::egui;
+use image::{imageops, RgbaImage};
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+const THUMBNAIL_SIZE: u32 = 96;
+const THUMBNAIL_PADDING: f32 = 4.0;
+const MAX_WORKERS: usize = 4;
+
+/// Height of the thumbnail strip panel, including its scroll bar.
+pub const STRIP_HEIGHT: f32 = THUMBNAIL_SIZE as f32 + 2.0 * THUMBNAIL_PADDING + 16.0;
+
+type Job = (PathBuf, egui::Context);
+type JobResult = (PathBuf, Result<egui::ColorImage, String>);
+
+enum State {
+    NotRequested,
+    Requested,
+    Ready(egui::TextureHandle),
+    Failed(String),
+}
+
+struct Entry {
+    path: PathBuf,
+    state: State,
+}
+
+/// Horizontal strip of every .bruh file in the open image's directory.
+///
+/// Thumbnails are only decoded once they scroll into view, on a small pool of
+/// worker threads so large folders don't stall the UI.
+pub struct ThumbnailStrip {
+    dir: Option<PathBuf>,
+    entries: Vec<Entry>,
+    jobs: mpsc::Sender<Job>,
+    results: mpsc::Receiver<JobResult>,
+}
+
+impl ThumbnailStrip {
+    pub fn new() -> Self {
+        let (jobs, job_queue) = mpsc::channel::<Job>();
+        let (result_sender, results) = mpsc::channel();
+        let job_queue = Arc::new(Mutex::new(job_queue));
+
+        let workers = thread::available_parallelism().map_or(1, |n| n.get());
+        for _ in 0..workers.min(MAX_WORKERS) {
+            let job_queue = Arc::clone(&job_queue);
+            let result_sender = result_sender.clone();
+            thread::spawn(move || loop {
+                let job = match job_queue.lock() {
+                    Ok(queue) => queue.recv(),
+                    Err(_) => break,
+                };
+                let Ok((path, ctx)) = job else { break };
+
+                let thumbnail = decode_thumbnail(&path).map_err(|e| e.to_string());
+                if result_sender.send((path, thumbnail)).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            });
+        }
+
+        Self {
+            dir: None,
+            entries: Vec::new(),
+            jobs,
+            results,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.entries
+            .iter()
+            .map(|entry| entry.path.clone())
+            .collect()
+    }
+
+    /// Lists the .bruh files in `dir`, keeping existing thumbnails if it's already shown.
+    pub fn set_directory(&mut self, dir: &Path) {
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        if self.dir.as_deref() == Some(dir) {
+            return;
+        }
+
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("bruh"))
+            })
+            .collect();
+        paths.sort();
+
+        self.dir = Some(dir.to_path_buf());
+        self.entries = paths
+            .into_iter()
+            .map(|path| Entry {
+                path,
+                state: State::NotRequested,
+            })
+            .collect();
+    }
+
+    /// Drops the thumbnail of `path` so it gets decoded again.
+    pub fn invalidate(&mut self, path: &Path) {
+        for entry in self.entries.iter_mut().filter(|entry| entry.path == path) {
+            entry.state = State::NotRequested;
+        }
+    }
+
+    /// Draws the strip and returns the file that was clicked, if any.
+    pub fn show(&mut self, ui: &mut egui::Ui, current: Option<&Path>) -> Option<PathBuf> {
+        self.receive_results(ui.ctx());
+
+        let mut selected = None;
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for entry in &mut self.entries {
+                    let size = egui::Vec2::splat(THUMBNAIL_SIZE as f32 + 2.0 * THUMBNAIL_PADDING);
+                    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+
+                    if !ui.is_rect_visible(rect) {
+                        continue;
+                    }
+                    if let State::NotRequested = entry.state {
+                        let _ = self.jobs.send((entry.path.clone(), ui.ctx().clone()));
+                        entry.state = State::Requested;
+                    }
+
+                    paint_entry(ui, rect, entry, &response, current == Some(&*entry.path));
+
+                    if response.clicked() {
+                        selected = Some(entry.path.clone());
+                    }
+                    let name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+                    match &entry.state {
+                        State::Failed(message) => {
+                            response.on_hover_text(format!("{}\n{}", name, message))
+                        }
+                        _ => response.on_hover_text(name),
+                    };
+                }
+            });
+        });
+
+        selected
+    }
+
+    fn receive_results(&mut self, ctx: &egui::Context) {
+        for (path, result) in self.results.try_iter() {
+            let Some(entry) = self.entries.iter_mut().find(|entry| entry.path == path) else {
+                continue;
+            };
+
+            entry.state = match result {
+                Ok(image) => {
+                    let name = path.to_string_lossy();
+                    State::Ready(ctx.load_texture(name, image, egui::TextureOptions::LINEAR))
+                }
+                Err(message) => State::Failed(message),
+            };
+        }
+    }
+}
+
+fn paint_entry(
+    ui: &egui::Ui,
+    rect: egui::Rect,
+    entry: &Entry,
+    response: &egui::Response,
+    is_current: bool,
+) {
+    let visuals = ui.visuals();
+    let painter = ui.painter();
+
+    if is_current {
+        painter.rect_filled(rect, 2.0, visuals.selection.bg_fill);
+    } else if response.hovered() {
+        painter.rect_filled(rect, 2.0, visuals.widgets.hovered.bg_fill);
+    }
+
+    let inner = rect.shrink(THUMBNAIL_PADDING);
+    match &entry.state {
+        State::Ready(texture) => {
+            let size = texture.size_vec2();
+            let image_rect = egui::Rect::from_center_size(inner.center(), size);
+            let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+            painter.image(texture.id(), image_rect, uv, egui::Color32::WHITE);
+        }
+        State::Failed(_) => {
+            painter.text(
+                inner.center(),
+                egui::Align2::CENTER_CENTER,
+                "⚠",
+                egui::FontId::proportional(24.0),
+                visuals.error_fg_color,
+            );
+        }
+        State::NotRequested | State::Requested => {
+            painter.rect_stroke(inner, 2.0, visuals.widgets.noninteractive.bg_stroke);
+        }
+    }
+}
+
+fn decode_thumbnail(path: &Path) -> Result<egui::ColorImage, Box<dyn std::error::Error>> {
+    let mut contents = Vec::new();
+    read_input(path, &mut contents)?;
+    // An animation stands for itself with its first frame.
+    let animation = Animation::of(&contents)?;
+    let first_frame = animation.and_then(|animation| Some(animation.frames.first()?.contents));
+    let decoded = decode_bruh(first_frame.unwrap_or(&contents))?;
+    if decoded.is_empty() {
+        return Err("The image is empty.".into());
+    }
+
+    let image = RgbaImage::from_raw(decoded.width, decoded.height, decoded.pixels)
+        .ok_or("Pixel data doesn't fit the image dimensions.")?;
+
+    let scale = THUMBNAIL_SIZE as f32 / decoded.width.max(decoded.height) as f32;
+    let width = ((decoded.width as f32 * scale) as u32).max(1);
+    let height = ((decoded.height as f32 * scale) as u32).max(1);
+    let thumbnail = imageops::thumbnail(&image, width, height);
+
+    Ok(egui::ColorImage::from_rgba_unmultiplied(
+        [width as usize, height as usize],
+        thumbnail.as_raw(),
+    ))
+}
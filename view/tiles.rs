@@ -0,0 +1,174 @@
+use bruh::Region;
+use eframe::egui;
+use image::RgbaImage;
+
+use std::collections::HashMap;
+
+use super::{adjustments::Adjustments, pixels::ImagePixels};
+
+/// Preferred tile size; smaller if the GPU's texture limit is below it.
+const TILE_SIZE: u32 = 2048;
+/// Uploads are spread over several frames so scrolling into a new area doesn't stall.
+const MAX_UPLOADS_PER_FRAME: usize = 4;
+/// Size of the downscaled copy that stands in for tiles that haven't been uploaded yet.
+const OVERVIEW_SIZE: u32 = 512;
+
+/// Streams an image to the GPU as a grid of textures.
+///
+/// Only the tiles that intersect the visible area are uploaded, and tiles more than one
+/// tile out of view are dropped again, so images beyond the texture size limit
+/// stay viewable without keeping the whole image in video memory. A lazily decoded
+/// image is decoded a tile at a time as the tiles are uploaded.
+pub struct TileCache {
+    name: String,
+    filter: egui::TextureFilter,
+    adjustments: Adjustments,
+    tiles: HashMap<[u32; 2], egui::TextureHandle>,
+    overview: Option<egui::TextureHandle>,
+}
+
+impl TileCache {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            filter: egui::TextureFilter::Linear,
+            adjustments: Adjustments::default(),
+            tiles: HashMap::new(),
+            overview: None,
+        }
+    }
+
+    /// A small texture of the whole image, for the minimap and as a placeholder.
+    pub fn overview(&mut self, ctx: &egui::Context, pixels: &ImagePixels) -> egui::TextureId {
+        let (name, adjustments) = (&self.name, self.adjustments);
+        self.overview
+            .get_or_insert_with(|| {
+                let (width, height) = pixels.dimensions();
+                let scale = (OVERVIEW_SIZE as f32 / width.max(height) as f32).min(1.0);
+                let mut small = pixels.thumbnail(
+                    ((width as f32 * scale) as u32).max(1),
+                    ((height as f32 * scale) as u32).max(1),
+                );
+                adjustments.apply(&mut small);
+                let options = egui::TextureOptions::LINEAR;
+                ctx.load_texture(
+                    format!("{} (overview)", name),
+                    to_color_image(&small),
+                    options,
+                )
+            })
+            .id()
+    }
+
+    /// Draws `pixels` into `image_rect`, uploading the visible tiles that are missing.
+    ///
+    /// Changing `adjustments` re-uploads the tiles with the new tone curve applied.
+    pub fn paint(
+        &mut self,
+        ctx: &egui::Context,
+        painter: &egui::Painter,
+        pixels: &ImagePixels,
+        image_rect: egui::Rect,
+        filter: egui::TextureFilter,
+        adjustments: Adjustments,
+    ) {
+        if filter != self.filter {
+            self.filter = filter;
+            self.tiles.clear();
+        }
+        if adjustments != self.adjustments {
+            self.adjustments = adjustments;
+            self.tiles.clear();
+            self.overview = None;
+        }
+
+        let tile_size = TILE_SIZE.min(ctx.input(|i| i.max_texture_side) as u32);
+        let (width, height) = pixels.dimensions();
+        let scale = image_rect.width() / width as f32;
+
+        let full_uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+        let overview = self.overview(ctx, pixels);
+
+        // Zoomed out far enough that the overview has all the detail that fits on screen.
+        let visible = image_rect.intersect(painter.clip_rect());
+        let overview_is_enough = image_rect.width() <= OVERVIEW_SIZE as f32
+            && image_rect.height() <= OVERVIEW_SIZE as f32
+            && (width > tile_size || height > tile_size);
+        if !visible.is_positive() || overview_is_enough {
+            self.tiles.clear();
+            painter.image(overview, image_rect, full_uv, egui::Color32::WHITE);
+            return;
+        }
+        let first = [
+            ((visible.min.x - image_rect.min.x) / scale) as u32 / tile_size,
+            ((visible.min.y - image_rect.min.y) / scale) as u32 / tile_size,
+        ];
+        let last = [
+            (((visible.max.x - image_rect.min.x) / scale) as u32).min(width - 1) / tile_size,
+            (((visible.max.y - image_rect.min.y) / scale) as u32).min(height - 1) / tile_size,
+        ];
+        // Tiles just out of view are kept, so panning back and forth doesn't decode and
+        // upload them again.
+        let is_near = |[x, y]: [u32; 2]| {
+            (first[0].saturating_sub(1)..=last[0] + 1).contains(&x)
+                && (first[1].saturating_sub(1)..=last[1] + 1).contains(&y)
+        };
+        self.tiles.retain(|&tile, _| is_near(tile));
+
+        let mut uploads = 0;
+        for ty in first[1]..=last[1] {
+            for tx in first[0]..=last[0] {
+                let min = [tx * tile_size, ty * tile_size];
+                let size = [
+                    tile_size.min(width - min[0]),
+                    tile_size.min(height - min[1]),
+                ];
+                let rect = egui::Rect::from_min_size(
+                    image_rect.min + egui::vec2(min[0] as f32, min[1] as f32) * scale,
+                    egui::vec2(size[0] as f32, size[1] as f32) * scale,
+                );
+
+                if !self.tiles.contains_key(&[tx, ty]) && uploads < MAX_UPLOADS_PER_FRAME {
+                    let region = Region {
+                        x: min[0],
+                        y: min[1],
+                        width: size[0],
+                        height: size[1],
+                    };
+                    let mut tile = pixels.region(region).expect("a tile of a checked file decodes");
+                    adjustments.apply(&mut tile);
+                    let options = egui::TextureOptions {
+                        magnification: filter,
+                        minification: egui::TextureFilter::Linear,
+                    };
+                    let name = format!("{} ({}, {})", self.name, tx, ty);
+                    let texture = ctx.load_texture(name, to_color_image(&tile), options);
+                    self.tiles.insert([tx, ty], texture);
+                    uploads += 1;
+                }
+
+                match self.tiles.get(&[tx, ty]) {
+                    Some(texture) => {
+                        painter.image(texture.id(), rect, full_uv, egui::Color32::WHITE)
+                    }
+                    None => {
+                        let uv = egui::Rect::from_min_max(
+                            egui::pos2(min[0] as f32 / width as f32, min[1] as f32 / height as f32),
+                            egui::pos2(
+                                (min[0] + size[0]) as f32 / width as f32,
+                                (min[1] + size[1]) as f32 / height as f32,
+                            ),
+                        );
+                        painter.image(overview, rect, uv, egui::Color32::WHITE);
+                        ctx.request_repaint();
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn to_color_image(pixels: &RgbaImage) -> egui::ColorImage {
+    let size = [pixels.width() as usize, pixels.height() as usize];
+    egui::ColorImage::from_rgba_unmultiplied(size, pixels.as_raw())
+}
@@ -0,0 +1,1506 @@
+//! The .bruh image viewer, opened by `bruh view` and by the standalone `bruh-view`.
+
+use bruh::{
+    check_bruh, decode_bruh_lenient, file::read_input, DecodeError, ENCRYPTED_MAGIC, HEADER_SIZE,
+};
+use css_color_parser::Color as CssColor;
+use eframe::{egui, Storage};
+use image::RgbaImage;
+
+use std::{
+    env,
+    ffi::OsString,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use annotations::Annotations;
+use batch::BatchConverter;
+use canvas::{Camera, CanvasOptions, Filtering, Tool, ViewState, ZoomMode};
+use color_vision::ColorVision;
+use eyedropper::Swatches;
+use heatmap::RunHeatmap;
+use locale::Localizer;
+use pixels::{ImagePixels, LAZY_DECODE_PIXELS};
+use playback::Playback;
+use selection::Selection;
+use settings::Settings;
+use slideshow::Slideshow;
+use storage::FileStorage;
+use thumbnails::ThumbnailStrip;
+use tiles::TileCache;
+use watcher::FileWatcher;
+use window_state::WindowState;
+
+mod adjustments;
+mod annotations;
+mod batch;
+mod canvas;
+mod clipboard;
+mod color_vision;
+mod eyedropper;
+mod heatmap;
+mod locale;
+mod pixels;
+mod playback;
+mod script;
+mod selection;
+mod settings;
+mod slideshow;
+mod storage;
+mod system_viewer;
+mod thumbnails;
+mod tiles;
+mod watcher;
+mod window_state;
+
+const MESSAGE_WINDOW_SIZE: egui::Vec2 = egui::vec2(480.0, 200.0);
+
+const ZOOM_STEP: f32 = 1.25;
+const TAB_BAR_HEIGHT: f32 = 24.0;
+
+const RECENT_FILES_KEY: &str = "recent_files";
+
+/// How often a live file is checked for new frames.
+const LIVE_INTERVAL: Duration = Duration::from_millis(15);
+
+/// Initial presentation of the viewer window, set from the command line.
+pub struct Options {
+    pub fullscreen: bool,
+    /// Window title bar and borders.
+    pub decorated: bool,
+    /// Zoom of the files opened at startup, in percent. They're fitted to the window if unset.
+    pub zoom: Option<f32>,
+    /// Fill behind the image for this session, overriding the preference.
+    pub background: Option<[u8; 3]>,
+    /// Signals that the first file was replaced, e.g. by `bruh stream`, so it's reloaded
+    /// right away rather than once the file watcher sees it settle.
+    pub live: Option<mpsc::Receiver<()>>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            fullscreen: false,
+            decorated: true,
+            zoom: None,
+            background: None,
+            live: None,
+        }
+    }
+}
+
+/// Splits the viewer's command line into the files to open and the [`Options`].
+pub fn parse_args<'a>(
+    mut args: impl Iterator<Item = &'a OsString>,
+) -> Result<(Vec<PathBuf>, Options), String> {
+    let mut options = Options::default();
+    let mut file_paths = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--fullscreen") => options.fullscreen = true,
+            Some("--no-decorations") => options.decorated = false,
+            Some("--zoom") => options.zoom = Some(parse_zoom(args.next())?),
+            Some("--bg") => options.background = Some(parse_background(args.next())?),
+            _ => file_paths.push(arg.into()),
+        }
+    }
+    Ok((file_paths, options))
+}
+
+/// Parses a zoom percentage such as `200` or `50%`.
+fn parse_zoom(value: Option<&OsString>) -> Result<f32, String> {
+    let value = value
+        .ok_or("--zoom needs a percentage, e.g. `--zoom 200`")?
+        .to_string_lossy();
+    match value.trim_end_matches('%').parse::<f32>() {
+        Ok(zoom) if zoom.is_finite() && zoom > 0.0 => Ok(zoom),
+        _ => Err(format!("Invalid zoom '{}', expected a percentage above 0.", value)),
+    }
+}
+
+/// Parses a CSS color such as `#202020` or `black` for `--bg`, ignoring its alpha.
+fn parse_background(value: Option<&OsString>) -> Result<[u8; 3], String> {
+    let value = value
+        .ok_or("--bg needs a color, e.g. `--bg #202020`")?
+        .to_string_lossy();
+    let color: CssColor = value
+        .parse()
+        .map_err(|_| format!("Invalid color '{}' for --bg.", value))?;
+    Ok([color.r, color.g, color.b])
+}
+
+/// Opens the viewer on `paths`. If it can't make a window, the images are shown in the
+/// system's default viewer instead, and an error is only returned if that fails too.
+pub fn run(paths: Vec<PathBuf>, options: Options) -> Result<(), Box<dyn std::error::Error>> {
+    let storage = FileStorage::load("bruh");
+    let mut preview = ImagePreview::new(storage);
+    preview.fullscreen = options.fullscreen;
+    preview.background = options
+        .background
+        .map(|[r, g, b]| egui::Color32::from_rgb(r, g, b));
+    preview.live = options.live;
+    for path in &paths {
+        preview.open(path.clone());
+    }
+    let remember = preview.settings.remember_window;
+    let window = preview.window.clone();
+    let zoom = options.zoom.map(|zoom| zoom / 100.0);
+    if let Some(zoom) = zoom.or(window.zoom.filter(|_| remember)) {
+        for tab in &mut preview.tabs {
+            tab.camera = Camera::with_zoom(zoom);
+        }
+    }
+    preview.activate(0);
+    // With nothing to show, start from the folder the last image was in.
+    if let Some(dir) = window.last_directory.as_deref() {
+        if matches!(preview.tab().content, Content::Empty) {
+            preview.thumbnails.set_directory(dir);
+            preview.path_input = format!("{}{}", dir.display(), std::path::MAIN_SEPARATOR);
+        }
+    }
+
+    let saved_size = window.size.filter(|_| remember).map(egui::Vec2::from);
+    let options = eframe::NativeOptions {
+        resizable: remember,
+        decorated: options.decorated,
+        fullscreen: options.fullscreen,
+        initial_window_size: Some(saved_size.unwrap_or_else(|| preview.window_size())),
+        initial_window_pos: window.position.filter(|_| remember).map(egui::Pos2::from),
+        ..Default::default()
+    };
+
+    // winit panics rather than returning an error when there's no display to connect to.
+    let started = panic::catch_unwind(AssertUnwindSafe(|| {
+        eframe::run_native(
+            "bruh",
+            options,
+            Box::new(move |cc| {
+                cc.egui_ctx.set_visuals(preview.settings.theme.visuals());
+                Box::new(preview)
+            }),
+        )
+    }));
+    let error = match started {
+        Ok(Ok(())) => return Ok(()),
+        Ok(Err(e)) => e.to_string(),
+        Err(_) => "the windowing system couldn't be initialized".to_owned(),
+    };
+    if paths.is_empty() {
+        return Err(error.into());
+    }
+
+    eprintln!("Couldn't open a window ({}), using the system's image viewer.", error);
+    system_viewer::open(&paths).map_err(|e| format!("{}. {}", error, e).into())
+}
+
+struct LoadedImage {
+    tiles: TileCache,
+    pixels: ImagePixels,
+    /// Built the first time the run heatmap is shown for this image.
+    heatmap: Option<RunHeatmap>,
+    /// The frames of an animation, of which `pixels` is the one being shown.
+    playback: Option<Playback>,
+    annotations: Annotations,
+    /// What's wrong with a damaged file that was shown anyway, until the warning about
+    /// it is dismissed.
+    damage: Option<String>,
+    path: PathBuf,
+    file_size: u64,
+    width: u32,
+    height: u32,
+}
+
+impl LoadedImage {
+    /// Ratio of the raw RGB8 pixel data to the size of the file on disk.
+    fn compression_ratio(&self) -> f64 {
+        let raw_size = self.width as f64 * self.height as f64 * 3.0;
+        raw_size / self.file_size.max(1) as f64
+    }
+}
+
+fn load_image(path: &Path) -> Result<LoadedImage, Box<dyn std::error::Error>> {
+    let mut contents = Vec::new();
+    read_input(path, &mut contents)?;
+    let file_size = contents.len() as u64;
+    let (pixels, damage, playback) = match check_bruh(&contents) {
+        Err(DecodeError::Animated) => {
+            let playback = Playback::new(contents)?.expect("checked to be an animation");
+            if playback.len() == 0 {
+                return Err("The animation has no frames.".into());
+            }
+            (ImagePixels::Decoded(playback.frame()?), None, Some(playback))
+        }
+        // A large file that matches its header is decoded a region at a time as it's
+        // looked at, so it opens without waiting for all of it to decode.
+        Ok((width, height)) if width as u64 * height as u64 > LAZY_DECODE_PIXELS => {
+            let pixels = ImagePixels::Lazy {
+                contents,
+                width,
+                height,
+            };
+            (pixels, None, None)
+        }
+        _ => {
+            let (pixels, damage) = decode_pixels(&contents)?;
+            (pixels, damage, None)
+        }
+    };
+    let (width, height) = pixels.dimensions();
+
+    Ok(LoadedImage {
+        tiles: TileCache::new(path.display().to_string()),
+        pixels,
+        heatmap: None,
+        playback,
+        annotations: Annotations::load(path),
+        damage: damage.map(|e| e.to_string()),
+        path: path.to_path_buf(),
+        file_size,
+        width,
+        height,
+    })
+}
+
+/// Decodes all of `contents`, returning what's wrong with it if it's damaged.
+fn decode_pixels(
+    contents: &[u8],
+) -> Result<(ImagePixels, Option<DecodeError>), Box<dyn std::error::Error>> {
+    // A damaged file is shown as far as it goes, with a warning, rather than not at all.
+    // Encrypted files don't decode either, but showing their noise would help no one.
+    let (decoded, damage) = decode_bruh_lenient(contents)?;
+    let magic = contents.get(HEADER_SIZE..HEADER_SIZE + ENCRYPTED_MAGIC.len());
+    if damage.is_some() && magic == Some(&ENCRYPTED_MAGIC[..]) {
+        return Err("The image is encrypted. Use `decompile --decrypt` to convert it.".into());
+    }
+    let (width, height) = (decoded.width, decoded.height);
+    if decoded.is_empty() {
+        return Err(format!("The image is empty ({}×{} pixels).", width, height).into());
+    }
+    let pixels = RgbaImage::from_raw(width, height, decoded.pixels)
+        .ok_or("Pixel data doesn't fit the image dimensions.")?;
+    Ok((ImagePixels::Decoded(pixels), damage))
+}
+
+enum Content {
+    Empty,
+    Image(Box<LoadedImage>),
+    Error { path: PathBuf, message: String },
+}
+
+impl Content {
+    fn load(path: PathBuf) -> Self {
+        match load_image(&path) {
+            Ok(image) => Content::Image(Box::new(image)),
+            Err(e) => Content::Error {
+                message: e.to_string(),
+                path,
+            },
+        }
+    }
+
+    /// The file being shown, including one that failed to load.
+    fn file(&self) -> Option<&Path> {
+        match self {
+            Content::Image(loaded) => Some(&loaded.path),
+            Content::Error { path, .. } => Some(path),
+            Content::Empty => None,
+        }
+    }
+
+    fn file_name(&self) -> Option<String> {
+        let path = self.file()?;
+        let name = path.file_name().unwrap_or(path.as_os_str());
+        Some(name.to_string_lossy().into_owned())
+    }
+}
+
+/// An open file together with how it's being looked at.
+struct Tab {
+    content: Content,
+    camera: Camera,
+    selection: Option<Selection>,
+    view: ViewState,
+    watcher: Option<FileWatcher>,
+}
+
+impl Tab {
+    fn new(content: Content, zoom_mode: ZoomMode) -> Self {
+        Self {
+            content,
+            camera: Camera::new(zoom_mode),
+            selection: None,
+            view: ViewState::default(),
+            watcher: None,
+        }
+    }
+
+    /// Reloads the file from disk, keeping zoom and pan.
+    fn reload(&mut self) {
+        if let Some(path) = self.content.file().map(Path::to_path_buf) {
+            self.content = Content::load(path);
+            self.selection = None;
+        }
+    }
+}
+
+struct ImagePreview {
+    tabs: Vec<Tab>,
+    active: usize,
+    path_input: String,
+    pending_resize: bool,
+    show_open_prompt: bool,
+    show_adjustments: bool,
+    show_settings: bool,
+    fullscreen: bool,
+    /// Background given on the command line, which takes precedence over the preference.
+    background: Option<egui::Color32>,
+    canvas: CanvasOptions,
+    save_prompt: Option<(SaveTarget, String)>,
+    /// Where text is being placed and what's been typed so far.
+    text_prompt: Option<([f32; 2], String)>,
+    swatches: Swatches,
+    batch: BatchConverter,
+    title: String,
+    thumbnails: ThumbnailStrip,
+    show_thumbnails: bool,
+    slideshow: Slideshow,
+    auto_reload: bool,
+    live: Option<mpsc::Receiver<()>>,
+    notice: Option<(String, f64)>,
+    recent_files: Vec<PathBuf>,
+    settings: Settings,
+    locale: Localizer,
+    window: WindowState,
+    storage: FileStorage,
+}
+
+impl ImagePreview {
+    fn new(storage: FileStorage) -> Self {
+        let recent_files = storage
+            .get_string(RECENT_FILES_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        let settings = Settings::load(&storage);
+
+        Self {
+            tabs: vec![Tab::new(Content::Empty, settings.zoom_mode)],
+            active: 0,
+            path_input: String::new(),
+            pending_resize: false,
+            show_open_prompt: false,
+            show_adjustments: false,
+            show_settings: false,
+            fullscreen: false,
+            background: None,
+            canvas: CanvasOptions {
+                filtering: settings.filtering,
+                ..CanvasOptions::default()
+            },
+            save_prompt: None,
+            text_prompt: None,
+            swatches: Swatches::default(),
+            batch: BatchConverter::new(),
+            title: String::new(),
+            thumbnails: ThumbnailStrip::new(),
+            show_thumbnails: true,
+            slideshow: Slideshow::default(),
+            auto_reload: true,
+            live: None,
+            notice: None,
+            recent_files,
+            locale: Localizer::new(settings.language()),
+            settings,
+            window: WindowState::load(&storage),
+            storage,
+        }
+    }
+
+    fn tab(&self) -> &Tab {
+        &self.tabs[self.active]
+    }
+
+    fn tab_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.active]
+    }
+
+    /// Replaces the file shown in the current tab.
+    fn load(&mut self, path: PathBuf) {
+        *self.tab_mut() = Tab::new(Content::load(path), self.settings.zoom_mode);
+        self.activate(self.active);
+    }
+
+    /// Opens `path` in a new tab, or switches to the tab already showing it.
+    fn open(&mut self, path: PathBuf) {
+        if let Some(index) = self
+            .tabs
+            .iter()
+            .position(|tab| tab.content.file() == Some(&path))
+        {
+            self.activate(index);
+        } else if matches!(self.tab().content, Content::Empty) {
+            self.load(path);
+        } else {
+            let tab = Tab::new(Content::load(path), self.settings.zoom_mode);
+            self.tabs.push(tab);
+            self.activate(self.tabs.len() - 1);
+        }
+    }
+
+    /// Makes `index` the current tab and points the directory-wide views at its file.
+    fn activate(&mut self, index: usize) {
+        self.active = index.min(self.tabs.len() - 1);
+        if let Content::Image(loaded) = &self.tab().content {
+            let path = absolute(&loaded.path);
+            self.window.last_directory = path.parent().map(Path::to_path_buf);
+            self.remember_recent(&path);
+            if let Some(dir) = path.parent() {
+                self.thumbnails.set_directory(dir);
+            }
+        }
+        self.pending_resize = true;
+    }
+
+    fn close_tab(&mut self, index: usize) {
+        self.tabs.remove(index);
+        if self.tabs.is_empty() {
+            self.tabs.push(Tab::new(Content::Empty, self.settings.zoom_mode));
+        }
+        if index < self.active || self.active == self.tabs.len() {
+            self.active = self.active.saturating_sub(1);
+        }
+        self.activate(self.active);
+    }
+
+    /// Keeps a watcher on every open file and reloads tabs whose file changes.
+    fn watch_files(&mut self, ctx: &egui::Context) {
+        for tab in &mut self.tabs {
+            if !self.auto_reload {
+                tab.watcher = None;
+                continue;
+            }
+
+            let file = tab.content.file();
+            if tab.watcher.as_ref().map(FileWatcher::path) != file {
+                tab.watcher = file.map(|path| FileWatcher::new(ctx, path));
+            }
+
+            if tab.watcher.as_ref().is_some_and(FileWatcher::changed) {
+                if let Some(path) = tab.content.file() {
+                    self.thumbnails.invalidate(path);
+                }
+                tab.reload();
+            }
+        }
+    }
+
+    /// Reloads the first tab whenever a new frame of a live file has arrived.
+    fn follow_live(&mut self, ctx: &egui::Context) {
+        let Some(live) = &self.live else {
+            return;
+        };
+        if live.try_iter().count() > 0 {
+            if let Some(tab) = self.tabs.first_mut() {
+                tab.reload();
+            }
+        }
+        ctx.request_repaint_after(LIVE_INTERVAL);
+    }
+
+    /// Shows the next frame of the current image once its delay is up, if it's animated.
+    fn play_animation(&mut self, ctx: &egui::Context) {
+        let onion_skin = self.canvas.onion_skin;
+        let Content::Image(loaded) = &mut self.tab_mut().content else {
+            return;
+        };
+        let Some(playback) = &mut loaded.playback else {
+            return;
+        };
+        playback.set_onion_skin(onion_skin);
+        if playback.advance(ctx) {
+            // Every frame of a checked animation has the animation's size.
+            if let Ok(frame) = playback.frame() {
+                loaded.pixels = ImagePixels::Decoded(frame);
+                loaded.tiles = TileCache::new(loaded.path.display().to_string());
+            }
+        }
+    }
+
+    /// Builds the run heatmap of the current image if it hasn't been yet.
+    fn load_heatmap(&mut self, ctx: &egui::Context) {
+        let Content::Image(loaded) = &mut self.tab_mut().content else {
+            return;
+        };
+        if loaded.heatmap.is_some() {
+            return;
+        }
+
+        match RunHeatmap::load(&loaded.path, loaded.width, loaded.height) {
+            Ok(heatmap) => loaded.heatmap = Some(heatmap),
+            Err(e) => {
+                self.canvas.run_heatmap = false;
+                let args = [("error", e.to_string().into())];
+                let message = self.locale.format("notice-heatmap-failed", &args);
+                self.show_notice(ctx, message);
+            }
+        }
+    }
+
+    fn copy_image(&mut self, ctx: &egui::Context) {
+        let Content::Image(loaded) = &self.tab().content else {
+            return;
+        };
+
+        let copied = loaded.pixels.to_image().map_err(Into::into);
+        let message = match copied.and_then(|image| clipboard::copy_image(&image)) {
+            Ok(()) => self.locale.text("notice-copied-image"),
+            Err(e) => {
+                let args = [("error", e.to_string().into())];
+                self.locale.format("notice-copy-failed", &args)
+            }
+        };
+        self.show_notice(ctx, message);
+    }
+
+    fn pick_color(&mut self, ctx: &egui::Context, (x, y): (u32, u32)) {
+        let Content::Image(loaded) = &self.tab().content else {
+            return;
+        };
+
+        match loaded.pixels.pixel(x, y) {
+            Ok(color) => {
+                self.swatches.push(color);
+                self.copy_color(ctx, color);
+            }
+            Err(e) => self.show_notice(ctx, e.to_string()),
+        }
+    }
+
+    fn copy_color(&mut self, ctx: &egui::Context, color: [u8; 4]) {
+        let hex = eyedropper::hex(color);
+        ctx.output_mut(|o| o.copied_text = hex.clone());
+        let message = self.locale.format("notice-copied-color", &[("color", hex.into())]);
+        self.show_notice(ctx, message);
+    }
+
+    fn save_image(&mut self, ctx: &egui::Context, target: SaveTarget, path: &Path) {
+        let Content::Image(loaded) = &self.tab().content else {
+            return;
+        };
+        let image = match target {
+            SaveTarget::Selection => match self.tab().selection {
+                Some(selection) => selection.crop(&loaded.pixels),
+                None => return,
+            },
+            SaveTarget::Annotated => loaded
+                .pixels
+                .to_image()
+                .map(|image| loaded.annotations.render(ctx, &image)),
+        };
+
+        let (saved, failed) = target.notices();
+        let saved_image = image.map_err(Into::into);
+        let message = match saved_image.and_then(|image| selection::save(&image, path)) {
+            Ok(()) => {
+                let args = [("path", path.display().to_string().into())];
+                self.locale.format(saved, &args)
+            }
+            Err(e) => self.locale.format(failed, &[("error", e.to_string().into())]),
+        };
+        self.show_notice(ctx, message);
+    }
+
+    /// Suggests `<name>-<suffix>.png` next to the open file.
+    fn default_save_path(&self, target: SaveTarget) -> String {
+        let Some(path) = self.current_path() else {
+            return String::new();
+        };
+
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        path.with_file_name(format!("{}-{}.png", stem, target.suffix()))
+            .display()
+            .to_string()
+    }
+
+    /// Applies `change` to the current image's annotations and writes their sidecar.
+    fn edit_annotations(&mut self, ctx: &egui::Context, change: impl FnOnce(&mut Annotations)) {
+        let Content::Image(loaded) = &mut self.tab_mut().content else {
+            return;
+        };
+
+        change(&mut loaded.annotations);
+        if let Err(e) = loaded.annotations.save(&loaded.path) {
+            let args = [("error", e.to_string().into())];
+            let message = self.locale.format("notice-annotations-failed", &args);
+            self.show_notice(ctx, message);
+        }
+    }
+
+    /// Shows `message` in the status bar for a few seconds.
+    fn show_notice(&mut self, ctx: &egui::Context, message: String) {
+        const NOTICE_DURATION: f64 = 3.0;
+
+        self.notice = Some((message, ctx.input(|i| i.time) + NOTICE_DURATION));
+        ctx.request_repaint_after(std::time::Duration::from_secs_f64(NOTICE_DURATION));
+    }
+
+    fn remember_recent(&mut self, path: &Path) {
+        let path = absolute(path);
+        self.recent_files.retain(|recent| *recent != path);
+        self.recent_files.insert(0, path);
+        self.recent_files.truncate(self.settings.max_recent_files);
+        self.save_recent_files();
+    }
+
+    fn save_recent_files(&mut self) {
+        // JSON can only hold Unicode paths; any others are remembered until the viewer closes.
+        let storable: Vec<&Path> = self
+            .recent_files
+            .iter()
+            .map(PathBuf::as_path)
+            .filter(|path| path.to_str().is_some())
+            .collect();
+        if let Ok(json) = serde_json::to_string(&storable) {
+            self.storage.set_string(RECENT_FILES_KEY, json);
+            self.storage.flush();
+        }
+    }
+
+    fn window_size(&self) -> egui::Vec2 {
+        match &self.tab().content {
+            Content::Image(loaded) => {
+                let mut size = egui::vec2(loaded.width as f32, loaded.height as f32);
+                if self.thumbnails_visible() {
+                    size.y += thumbnails::STRIP_HEIGHT;
+                }
+                if self.tabs_visible() {
+                    size.y += TAB_BAR_HEIGHT;
+                }
+                size
+            }
+            _ => MESSAGE_WINDOW_SIZE,
+        }
+    }
+
+    fn thumbnails_visible(&self) -> bool {
+        self.show_thumbnails && !self.fullscreen && !self.thumbnails.is_empty()
+    }
+
+    /// Warns that the current image came from a damaged file, until it's dismissed.
+    fn damage_banner(&mut self, ctx: &egui::Context) {
+        let Content::Image(loaded) = &mut self.tabs[self.active].content else {
+            return;
+        };
+        let Some(damage) = &loaded.damage else {
+            return;
+        };
+        let message = self.locale.format("damaged-file", &[("error", damage.clone().into())]);
+        let dismiss = self.locale.text("dismiss");
+        let visuals = ctx.style().visuals.clone();
+        let frame = egui::Frame::side_top_panel(&ctx.style())
+            .fill(visuals.warn_fg_color.linear_multiply(0.15));
+        egui::TopBottomPanel::top("damage_banner").frame(frame).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.colored_label(visuals.warn_fg_color, "⚠");
+                ui.label(message);
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button(dismiss).clicked() {
+                        loaded.damage = None;
+                    }
+                });
+            });
+        });
+    }
+
+    fn message_panel(&mut self, ui: &mut egui::Ui) {
+        ui.vertical_centered(|ui| {
+            ui.add_space(24.0);
+            match &self.tab().content {
+                Content::Error { path, message } => {
+                    let args = [("path", path.display().to_string().into())];
+                    ui.heading(self.locale.format("couldnt-open", &args));
+                    ui.colored_label(ui.visuals().error_fg_color, message);
+                }
+                _ => {
+                    ui.heading(self.locale.text("no-image"));
+                    ui.label(self.locale.text("drop-hint"));
+                }
+            }
+            ui.add_space(12.0);
+
+            if let Some(path) = path_prompt(ui, &mut self.path_input, &self.locale, "open") {
+                self.load(path);
+            }
+        });
+    }
+
+    fn menu_bar(&mut self, ui: &mut egui::Ui, frame: &mut eframe::Frame) {
+        egui::menu::bar(ui, |ui| {
+            ui.menu_button(self.locale.text("menu-file"), |ui| {
+                if ui.button(self.locale.text("menu-open")).clicked() {
+                    self.show_open_prompt = true;
+                    ui.close_menu();
+                }
+
+                ui.add_enabled_ui(!self.recent_files.is_empty(), |ui| {
+                    ui.menu_button(self.locale.text("menu-open-recent"), |ui| {
+                        let mut selected = None;
+                        for path in &self.recent_files {
+                            let name = path.file_name().unwrap_or(path.as_os_str());
+                            let entry = ui.button(name.to_string_lossy());
+                            if entry.on_hover_text(path.display().to_string()).clicked() {
+                                selected = Some(path.clone());
+                            }
+                        }
+
+                        ui.separator();
+                        if ui.button(self.locale.text("menu-clear-recent")).clicked() {
+                            self.recent_files.clear();
+                            self.save_recent_files();
+                            ui.close_menu();
+                        }
+
+                        if let Some(path) = selected {
+                            self.open(path);
+                            ui.close_menu();
+                        }
+                    });
+                });
+
+                if ui.button(self.locale.text("menu-batch-convert")).clicked() {
+                    self.batch.open = true;
+                    ui.close_menu();
+                }
+                if ui.button(self.locale.text("menu-preferences")).clicked() {
+                    self.show_settings = true;
+                    ui.close_menu();
+                }
+
+                let close =
+                    egui::Button::new(self.locale.text("menu-close-tab")).shortcut_text("Ctrl+W");
+                if ui.add(close).clicked() {
+                    self.close_tab(self.active);
+                    ui.close_menu();
+                }
+
+                ui.separator();
+                if ui.button(self.locale.text("menu-quit")).clicked() {
+                    frame.close();
+                }
+            });
+
+            ui.menu_button(self.locale.text("menu-edit"), |ui| {
+                let has_image = matches!(self.tab().content, Content::Image(_));
+                let copy =
+                    egui::Button::new(self.locale.text("menu-copy-image")).shortcut_text("Ctrl+C");
+                if ui.add_enabled(has_image, copy).clicked() {
+                    self.copy_image(ui.ctx());
+                    ui.close_menu();
+                }
+
+                ui.separator();
+                let has_selection = self.tab().selection.is_some();
+                let save_selection = egui::Button::new(self.locale.text("menu-save-selection"));
+                if ui.add_enabled(has_selection, save_selection).clicked() {
+                    let path = self.default_save_path(SaveTarget::Selection);
+                    self.save_prompt = Some((SaveTarget::Selection, path));
+                    ui.close_menu();
+                }
+                let deselect =
+                    egui::Button::new(self.locale.text("menu-deselect")).shortcut_text("Esc");
+                if ui.add_enabled(has_selection, deselect).clicked() {
+                    self.tab_mut().selection = None;
+                    ui.close_menu();
+                }
+
+                ui.separator();
+                let has_annotations = match &self.tab().content {
+                    Content::Image(loaded) => !loaded.annotations.items.is_empty(),
+                    _ => false,
+                };
+                let undo = egui::Button::new(self.locale.text("menu-undo-annotation"))
+                    .shortcut_text("Ctrl+Z");
+                if ui.add_enabled(has_annotations, undo).clicked() {
+                    self.edit_annotations(ui.ctx(), |annotations| {
+                        annotations.items.pop();
+                    });
+                }
+                let clear = egui::Button::new(self.locale.text("menu-clear-annotations"));
+                if ui.add_enabled(has_annotations, clear).clicked() {
+                    self.edit_annotations(ui.ctx(), |annotations| annotations.items.clear());
+                    ui.close_menu();
+                }
+                let export = egui::Button::new(self.locale.text("menu-export-annotated"));
+                if ui.add_enabled(has_annotations, export).clicked() {
+                    let path = self.default_save_path(SaveTarget::Annotated);
+                    self.save_prompt = Some((SaveTarget::Annotated, path));
+                    ui.close_menu();
+                }
+            });
+
+            ui.menu_button(self.locale.text("menu-view"), |ui| {
+                let fullscreen =
+                    egui::Button::new(self.locale.text("menu-fullscreen")).shortcut_text("F11");
+                if ui.add(fullscreen).clicked() {
+                    self.set_fullscreen(frame, true);
+                    ui.close_menu();
+                }
+
+                let thumbnails = self.locale.text("menu-thumbnails");
+                if ui.checkbox(&mut self.show_thumbnails, thumbnails).changed() {
+                    self.pending_resize = true;
+                }
+
+                ui.separator();
+                let has_image = matches!(self.tab().content, Content::Image(_));
+                let zoom_in =
+                    egui::Button::new(self.locale.text("menu-zoom-in")).shortcut_text("+");
+                if ui.add_enabled(has_image, zoom_in).clicked() {
+                    self.zoom_by(ZOOM_STEP);
+                }
+                let zoom_out =
+                    egui::Button::new(self.locale.text("menu-zoom-out")).shortcut_text("-");
+                if ui.add_enabled(has_image, zoom_out).clicked() {
+                    self.zoom_by(1.0 / ZOOM_STEP);
+                }
+                let fit = egui::Button::new(self.locale.text("zoom-fit")).shortcut_text("0");
+                if ui.add_enabled(has_image, fit).clicked() {
+                    self.tab_mut().camera.fit();
+                }
+                let actual_size =
+                    egui::Button::new(self.locale.text("zoom-actual-size")).shortcut_text("1");
+                if ui.add_enabled(has_image, actual_size).clicked() {
+                    self.zoom_by(1.0 / self.tab().view.zoom);
+                }
+                ui.checkbox(&mut self.canvas.pixel_grid, self.locale.text("menu-pixel-grid"))
+                    .on_hover_text(self.locale.text("menu-pixel-grid.hint"));
+                ui.checkbox(&mut self.canvas.minimap, self.locale.text("menu-minimap"))
+                    .on_hover_text(self.locale.text("menu-minimap.hint"));
+                let adjust =
+                    egui::Button::new(self.locale.text("menu-adjustments")).shortcut_text("A");
+                if ui.add(adjust).clicked() {
+                    self.show_adjustments = true;
+                    ui.close_menu();
+                }
+                ui.checkbox(&mut self.canvas.annotations, self.locale.text("menu-annotations"));
+                ui.checkbox(&mut self.canvas.run_heatmap, self.locale.text("menu-run-heatmap"))
+                    .on_hover_text(self.locale.text("menu-run-heatmap.hint"));
+                ui.checkbox(&mut self.canvas.onion_skin, self.locale.text("menu-onion-skin"))
+                    .on_hover_text(self.locale.text("menu-onion-skin.hint"));
+                ui.separator();
+
+                ui.menu_button(self.locale.text("menu-color-vision"), |ui| {
+                    for vision in ColorVision::ALL {
+                        let current = &mut self.canvas.adjustments.color_vision;
+                        let label = vision.label(&self.locale);
+                        if ui.radio_value(current, vision, label).clicked() {
+                            ui.close_menu();
+                        }
+                    }
+                });
+
+                ui.menu_button(self.locale.text("filtering"), |ui| {
+                    for filtering in Filtering::ALL {
+                        let label = filtering.label(&self.locale);
+                        ui.radio_value(&mut self.canvas.filtering, filtering, label);
+                    }
+                });
+
+                ui.checkbox(&mut self.auto_reload, self.locale.text("menu-reload"));
+                ui.menu_button(self.locale.text("menu-slideshow"), |ui| self.slideshow_menu(ui));
+            });
+
+            ui.separator();
+            let tools = [
+                (Tool::Select, "tool-select"),
+                (Tool::Eyedropper, "tool-pick-color"),
+                (Tool::Rectangle, "tool-rectangle"),
+                (Tool::Arrow, "tool-arrow"),
+                (Tool::Text, "tool-text"),
+            ];
+            for (tool, id) in tools {
+                // The annotation tools are set apart from the selecting ones.
+                if tool == Tool::Rectangle {
+                    ui.separator();
+                }
+                ui.selectable_value(&mut self.canvas.tool, tool, self.locale.text(id))
+                    .on_hover_text(self.locale.text(&format!("{}.hint", id)));
+            }
+            ui.color_edit_button_srgba(&mut self.canvas.annotation_color)
+                .on_hover_text(self.locale.text("annotation-color"));
+        });
+    }
+
+    /// Zooms about the center of the panel, relative to the current zoom.
+    fn zoom_by(&mut self, factor: f32) {
+        if matches!(self.tab().content, Content::Image(_)) && self.tab().view.zoom > 0.0 {
+            let zoom = self.tab().view.zoom;
+            self.tab_mut().camera.zoom_centered(zoom, zoom * factor);
+        }
+    }
+
+    fn slideshow_menu(&mut self, ui: &mut egui::Ui) {
+        let mut running = self.slideshow.is_running();
+        let toggle = egui::Checkbox::new(&mut running, self.locale.text("slideshow-running"));
+        if ui
+            .add_enabled(!self.thumbnails.is_empty(), toggle)
+            .changed()
+        {
+            self.slideshow.set_running(ui.ctx(), running);
+        }
+
+        ui.add(
+            egui::Slider::new(&mut self.slideshow.interval_secs, 1.0..=60.0)
+                .suffix(" s")
+                .text(self.locale.text("slideshow-interval")),
+        );
+        ui.checkbox(&mut self.slideshow.shuffle, self.locale.text("slideshow-shuffle"));
+        ui.checkbox(&mut self.slideshow.looping, self.locale.text("slideshow-loop"));
+    }
+
+    fn current_path(&self) -> Option<&Path> {
+        match &self.tab().content {
+            Content::Image(loaded) => Some(&loaded.path),
+            _ => None,
+        }
+    }
+
+    fn set_fullscreen(&mut self, frame: &mut eframe::Frame, fullscreen: bool) {
+        self.fullscreen = fullscreen;
+        frame.set_fullscreen(fullscreen);
+        if !fullscreen {
+            self.pending_resize = true;
+        }
+    }
+
+    fn window_title(&self) -> String {
+        let tab = self.tab();
+        let Some(file_name) = tab.content.file_name() else {
+            return "bruh".to_owned();
+        };
+
+        match &tab.content {
+            Content::Image(loaded) => format!(
+                "{} — {}x{} — {:.0}% — bruh",
+                file_name,
+                loaded.width,
+                loaded.height,
+                tab.view.zoom * 100.0
+            ),
+            _ => format!("{} — bruh", file_name),
+        }
+    }
+
+    fn tabs_visible(&self) -> bool {
+        self.tabs.len() > 1 && !self.fullscreen
+    }
+
+    fn tab_bar(&mut self, ui: &mut egui::Ui) {
+        let mut selected = None;
+        let mut closed = None;
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            ui.horizontal_centered(|ui| {
+                for (index, tab) in self.tabs.iter().enumerate() {
+                    let name = tab
+                        .content
+                        .file_name()
+                        .unwrap_or_else(|| self.locale.text("untitled"));
+                    let label = ui.selectable_label(index == self.active, name);
+                    if let Some(path) = tab.content.file() {
+                        label.clone().on_hover_text(path.display().to_string());
+                    }
+                    if label.clicked() {
+                        selected = Some(index);
+                    }
+                    if label.middle_clicked() || ui.small_button("×").clicked() {
+                        closed = Some(index);
+                    }
+                    ui.separator();
+                }
+            });
+        });
+
+        if let Some(index) = closed {
+            self.close_tab(index);
+        } else if let Some(index) = selected {
+            self.activate(index);
+        }
+    }
+
+    fn status_bar(&mut self, ui: &mut egui::Ui) {
+        let Content::Image(loaded) = &self.tab().content else {
+            return;
+        };
+        let (file_size, compression_ratio) = (loaded.file_size, loaded.compression_ratio());
+        let hovered_run = loaded
+            .heatmap
+            .as_ref()
+            .filter(|_| self.canvas.run_heatmap)
+            .zip(self.tab().view.hovered_pixel)
+            .map(|(heatmap, pixel)| heatmap.run_length(pixel));
+        let frame = loaded.playback.as_ref().map(|playback| {
+            let args = [
+                ("frame", (playback.current() + 1).into()),
+                ("frames", playback.len().into()),
+            ];
+            let id = if playback.paused { "status-frame-paused" } else { "status-frame" };
+            self.locale.format(id, &args)
+        });
+
+        ui.horizontal(|ui| {
+            match self.tab().view.hovered_pixel {
+                Some((x, y)) => ui.label(format!("{}, {}", x, y)),
+                None => ui.label("–"),
+            };
+            if let Some(length) = hovered_run {
+                ui.separator();
+                ui.label(self.locale.format("status-run", &[("length", length.into())]));
+            }
+
+            if let Some(frame) = frame {
+                ui.separator();
+                ui.label(frame);
+            }
+
+            if let Some(selection) = self.tab().selection {
+                let [x, y] = selection.min();
+                let [width, height] = selection.size();
+                ui.separator();
+                let args = [
+                    ("width", width.into()),
+                    ("height", height.into()),
+                    ("x", x.into()),
+                    ("y", y.into()),
+                ];
+                ui.label(self.locale.format("status-selection", &args));
+            }
+
+            let now = ui.input(|i| i.time);
+            if let Some((message, _)) = self.notice.as_ref().filter(|(_, until)| *until > now) {
+                ui.separator();
+                ui.label(message);
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.label(format!("{:.1}:1", compression_ratio))
+                    .on_hover_text(self.locale.text("status-ratio-hint"));
+                ui.separator();
+                ui.label(format_size(file_size));
+                ui.separator();
+
+                if let Some(color) = self.swatches.show(ui) {
+                    self.copy_color(ui.ctx(), color);
+                }
+            });
+        });
+    }
+
+    fn open_prompt(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_open_prompt;
+        let mut selected = None;
+        egui::Window::new(self.locale.text("open"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                selected = path_prompt(ui, &mut self.path_input, &self.locale, "open")
+            });
+
+        self.show_open_prompt = open && selected.is_none();
+        if let Some(path) = selected {
+            self.open(path);
+        }
+    }
+
+    fn adjustments_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new(self.locale.text("adjustments"))
+            .open(&mut self.show_adjustments)
+            .collapsible(false)
+            .resizable(false)
+            .default_pos(ctx.available_rect().right_top() + egui::vec2(-16.0, 16.0))
+            .pivot(egui::Align2::RIGHT_TOP)
+            .show(ctx, |ui| self.canvas.adjustments.show(ui, &self.locale));
+    }
+
+    fn settings_window(&mut self, ctx: &egui::Context) {
+        let previous = self.settings.clone();
+        let mut changed = false;
+        egui::Window::new(self.locale.text("preferences"))
+            .open(&mut self.show_settings)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| changed = self.settings.show(ui, &self.locale));
+
+        if !changed {
+            return;
+        }
+        if self.settings.language() != self.locale.language() {
+            self.locale = Localizer::new(self.settings.language());
+        }
+        if self.settings.theme != previous.theme {
+            ctx.set_visuals(self.settings.theme.visuals());
+        }
+        if self.settings.filtering != previous.filtering {
+            self.canvas.filtering = self.settings.filtering;
+        }
+        if self.recent_files.len() > self.settings.max_recent_files {
+            self.recent_files.truncate(self.settings.max_recent_files);
+            self.save_recent_files();
+        }
+        self.settings.save(&mut self.storage);
+    }
+
+    fn save_prompt(&mut self, ctx: &egui::Context) {
+        let Some((target, input)) = &mut self.save_prompt else {
+            return;
+        };
+        let target = *target;
+
+        let mut open = true;
+        let mut selected = None;
+        egui::Window::new(self.locale.text(target.title()))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(self.locale.text("save-hint"));
+                selected = path_prompt(ui, input, &self.locale, "save");
+            });
+
+        if !open || selected.is_some() {
+            self.save_prompt = None;
+        }
+        if let Some(path) = selected {
+            self.save_image(ctx, target, &path);
+        }
+    }
+
+    fn text_prompt(&mut self, ctx: &egui::Context) {
+        let Some((at, input)) = &mut self.text_prompt else {
+            return;
+        };
+        let at = *at;
+
+        let mut open = true;
+        let mut done = false;
+        egui::Window::new(self.locale.text("add-text"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let field = ui.text_edit_singleline(input);
+                    field.request_focus();
+                    done |= field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    done |= ui.button(self.locale.text("add")).clicked();
+                });
+            });
+
+        if !open || done {
+            let text = self.text_prompt.take().map(|(_, text)| text);
+            let text = text.filter(|text| done && !text.trim().is_empty());
+            if let (Some(text), Content::Image(loaded)) = (text, &self.tab().content) {
+                let color = self.canvas.annotation_color;
+                let size = egui::vec2(loaded.width as f32, loaded.height as f32);
+                self.edit_annotations(ctx, |annotations| {
+                    annotations.add_text(at, text, color, size)
+                });
+            }
+        }
+    }
+}
+
+/// What the save prompt writes out.
+#[derive(Clone, Copy)]
+enum SaveTarget {
+    Selection,
+    Annotated,
+}
+
+impl SaveTarget {
+    /// Id of the prompt's title.
+    fn title(self) -> &'static str {
+        match self {
+            SaveTarget::Selection => "save-selection",
+            SaveTarget::Annotated => "export-annotated",
+        }
+    }
+
+    /// Ids of the notices shown when saving succeeds and when it fails.
+    fn notices(self) -> (&'static str, &'static str) {
+        match self {
+            SaveTarget::Selection => ("notice-saved-selection", "notice-save-selection-failed"),
+            SaveTarget::Annotated => ("notice-saved-annotated", "notice-save-annotated-failed"),
+        }
+    }
+
+    fn suffix(self) -> &'static str {
+        match self {
+            SaveTarget::Selection => "crop",
+            SaveTarget::Annotated => "annotated",
+        }
+    }
+}
+
+/// A path field with a button labelled with the message `action`.
+fn path_prompt(
+    ui: &mut egui::Ui,
+    input: &mut String,
+    locale: &Localizer,
+    action: &str,
+) -> Option<PathBuf> {
+    let mut open = false;
+    ui.horizontal(|ui| {
+        let field = ui.add(
+            egui::TextEdit::singleline(input)
+                .hint_text(locale.text("path-hint"))
+                .desired_width(ui.available_width().min(360.0) - 64.0),
+        );
+        open |= field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        open |= ui.button(locale.text(action)).clicked();
+    });
+
+    let path = input.trim();
+    (open && !path.is_empty()).then(|| PathBuf::from(path))
+}
+
+impl eframe::App for ImagePreview {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let dropped: Vec<_> = ctx.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|f| f.path.clone())
+                .collect()
+        });
+        for path in dropped {
+            if path.is_dir() {
+                self.batch.set_folder(&path);
+                self.batch.open = true;
+            } else {
+                self.open(path);
+            }
+        }
+
+        self.watch_files(ctx);
+        self.follow_live(ctx);
+        self.play_animation(ctx);
+
+        let (close_tab, switch_tab) = ctx.input_mut(|i| {
+            let close = i.consume_key(egui::Modifiers::COMMAND, egui::Key::W);
+            let next = i.consume_key(egui::Modifiers::CTRL, egui::Key::Tab);
+            let previous = i.consume_key(
+                egui::Modifiers::CTRL | egui::Modifiers::SHIFT,
+                egui::Key::Tab,
+            );
+            (close, next as isize - previous as isize)
+        });
+        if close_tab {
+            self.close_tab(self.active);
+        } else if switch_tab != 0 {
+            let count = self.tabs.len() as isize;
+            self.activate((self.active as isize + switch_tab).rem_euclid(count) as usize);
+        }
+
+        let undo = ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z));
+        if undo && !ctx.wants_keyboard_input() {
+            self.edit_annotations(ctx, |annotations| {
+                annotations.items.pop();
+            });
+        }
+
+        let copy_requested = ctx.input(|i| i.events.contains(&egui::Event::Copy));
+        if copy_requested && !ctx.wants_keyboard_input() {
+            self.copy_image(ctx);
+        }
+
+        if !ctx.wants_keyboard_input() {
+            if ctx.input(|i| i.key_pressed(egui::Key::M)) {
+                self.canvas.tool = Tool::Select;
+            } else if ctx.input(|i| i.key_pressed(egui::Key::I)) {
+                self.canvas.tool = Tool::Eyedropper;
+            } else if ctx.input(|i| i.key_pressed(egui::Key::R)) {
+                self.canvas.tool = Tool::Rectangle;
+            } else if ctx.input(|i| i.key_pressed(egui::Key::L)) {
+                self.canvas.tool = Tool::Arrow;
+            } else if ctx.input(|i| i.key_pressed(egui::Key::T)) {
+                self.canvas.tool = Tool::Text;
+            } else if ctx.input(|i| i.key_pressed(egui::Key::G)) {
+                self.canvas.pixel_grid = !self.canvas.pixel_grid;
+            } else if ctx.input(|i| i.key_pressed(egui::Key::A)) {
+                self.show_adjustments = !self.show_adjustments;
+            } else if ctx.input(|i| i.key_pressed(egui::Key::H)) {
+                self.canvas.run_heatmap = !self.canvas.run_heatmap;
+            } else if ctx.input(|i| i.key_pressed(egui::Key::O)) {
+                self.canvas.onion_skin = !self.canvas.onion_skin;
+            } else if ctx.input(|i| i.key_pressed(egui::Key::P)) {
+                if let Content::Image(loaded) = &mut self.tab_mut().content {
+                    if let Some(playback) = &mut loaded.playback {
+                        playback.paused = !playback.paused;
+                    }
+                }
+            } else if ctx.input(|i| i.key_pressed(egui::Key::PlusEquals)) {
+                self.zoom_by(ZOOM_STEP);
+            } else if ctx.input(|i| i.key_pressed(egui::Key::Minus)) {
+                self.zoom_by(1.0 / ZOOM_STEP);
+            } else if ctx.input(|i| i.key_pressed(egui::Key::Num0)) {
+                self.tab_mut().camera.fit();
+            } else if ctx.input(|i| i.key_pressed(egui::Key::Num1)) {
+                self.zoom_by(1.0 / self.tab().view.zoom);
+            }
+        }
+
+        if ctx.input(|i| i.key_pressed(egui::Key::F11)) {
+            self.set_fullscreen(frame, !self.fullscreen);
+        } else if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            if self.fullscreen {
+                self.set_fullscreen(frame, false);
+            } else {
+                self.tab_mut().selection = None;
+            }
+        }
+
+        // A window the user sizes keeps its size from one image to the next.
+        if self.pending_resize && !self.fullscreen {
+            if !self.settings.remember_window {
+                frame.set_window_size(self.window_size());
+            }
+            self.pending_resize = false;
+        }
+        self.window.track(&frame.info().window_info);
+
+        let mut central_panel = egui::CentralPanel::default();
+        if self.fullscreen {
+            let background = self.background.unwrap_or(egui::Color32::BLACK);
+            central_panel = central_panel.frame(egui::Frame::none().fill(background));
+        } else {
+            // `--bg` wins over the preference, and either only changes the panel's fill.
+            if let Some(background) = self.background.or(self.settings.background_color()) {
+                let panel_frame = egui::Frame::central_panel(&ctx.style()).fill(background);
+                central_panel = central_panel.frame(panel_frame);
+            }
+            egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| self.menu_bar(ui, frame));
+            if self.tabs_visible() {
+                egui::TopBottomPanel::top("tab_bar")
+                    .exact_height(TAB_BAR_HEIGHT)
+                    .show(ctx, |ui| self.tab_bar(ui));
+            }
+            egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| self.status_bar(ui));
+            self.damage_banner(ctx);
+        }
+
+        if !ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(egui::Key::Space)) {
+            let running = !self.slideshow.is_running() && !self.thumbnails.is_empty();
+            self.slideshow.set_running(ctx, running);
+        }
+
+        if self.slideshow.is_due(ctx) {
+            let paths = self.thumbnails.paths();
+            let current = self.current_path().map(Path::to_path_buf);
+            if let Some(path) = self.slideshow.advance(ctx, &paths, current.as_deref()) {
+                self.load(path);
+            }
+        }
+
+        if self.thumbnails_visible() {
+            let current = self.current_path().map(Path::to_path_buf);
+            let selected = egui::TopBottomPanel::bottom("thumbnails")
+                .exact_height(thumbnails::STRIP_HEIGHT)
+                .show(ctx, |ui| self.thumbnails.show(ui, current.as_deref()))
+                .inner;
+
+            if let Some(path) = selected {
+                self.load(path);
+            }
+        }
+
+        if self.show_open_prompt {
+            self.open_prompt(ctx);
+        }
+        self.save_prompt(ctx);
+        self.text_prompt(ctx);
+        if self.show_settings {
+            self.settings_window(ctx);
+        }
+        if self.batch.open || self.batch.is_running() {
+            self.batch.show(ctx, &self.locale);
+        }
+        if self.show_adjustments {
+            self.adjustments_window(ctx);
+        }
+
+        if self.canvas.run_heatmap {
+            self.load_heatmap(ctx);
+        }
+
+        let view = central_panel.show(ctx, |ui| {
+            let tab = &mut self.tabs[self.active];
+            match &mut tab.content {
+                Content::Image(loaded) => {
+                    canvas::show(ui, loaded, self.canvas, &mut tab.camera, &mut tab.selection)
+                }
+                _ => {
+                    self.message_panel(ui);
+                    ViewState::default()
+                }
+            }
+        });
+        self.tab_mut().view = view.inner;
+
+        if let Some((x, y)) = self.tab().view.clicked_pixel {
+            match self.canvas.tool {
+                Tool::Text => self.text_prompt = Some(([x as f32, y as f32], String::new())),
+                _ => self.pick_color(ctx, (x, y)),
+            }
+        }
+        if self.tab().view.annotation_added {
+            self.edit_annotations(ctx, |_| ());
+        }
+
+        let title = self.window_title();
+        if title != self.title {
+            frame.set_window_title(&title);
+            self.title = title;
+        }
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Content::Image(_) = self.tab().content {
+            self.window.zoom = self.tab().camera.fixed_zoom();
+        }
+        self.window.save(&mut self.storage);
+        self.storage.flush();
+    }
+}
+
+/// `path` resolved against the current folder, so it still points at the file after the
+/// viewer is launched from somewhere else.
+fn absolute(path: &Path) -> PathBuf {
+    if path.is_relative() {
+        env::current_dir().map_or_else(|_| path.to_path_buf(), |cwd| cwd.join(path))
+    } else {
+        path.to_path_buf()
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
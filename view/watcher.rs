@@ -0,0 +1,82 @@
+use eframe::egui;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Watches a single file for changes on a background thread.
+///
+/// The file's modification time and size are polled rather than relying on OS
+/// notifications, and a change is only reported once they stop moving, so a file
+/// that is still being written isn't picked up half-way.
+pub struct FileWatcher {
+    path: PathBuf,
+    changes: mpsc::Receiver<()>,
+    stop: Arc<AtomicBool>,
+}
+
+impl FileWatcher {
+    pub fn new(ctx: &egui::Context, path: &Path) -> Self {
+        let (sender, changes) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let ctx = ctx.clone();
+        let watched = path.to_path_buf();
+        let stopped = Arc::clone(&stop);
+        thread::spawn(move || {
+            let mut last_seen = stamp(&watched);
+            let mut pending = false;
+
+            while !stopped.load(Ordering::Relaxed) {
+                thread::sleep(POLL_INTERVAL);
+
+                let current = stamp(&watched);
+                if current != last_seen {
+                    last_seen = current;
+                    pending = true;
+                } else if pending && current.is_some() {
+                    pending = false;
+                    if sender.send(()).is_err() {
+                        break;
+                    }
+                    ctx.request_repaint();
+                }
+            }
+        });
+
+        Self {
+            path: path.to_path_buf(),
+            changes,
+            stop,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the file changed since the last call.
+    pub fn changed(&self) -> bool {
+        self.changes.try_iter().count() > 0
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
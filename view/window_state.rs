@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+use eframe::{egui, Storage};
+use serde::{Deserialize, Serialize};
+
+const WINDOW_STATE_KEY: &str = "window";
+
+/// The window's geometry and what it was showing when the viewer last closed, restored
+/// on the next launch if [`Settings::remember_window`](super::settings::Settings) is on.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowState {
+    /// Inner size in points.
+    pub size: Option<[f32; 2]>,
+    /// Position of the inner area's top left corner on the desktop.
+    pub position: Option<[f32; 2]>,
+    /// Zoom of the image last shown, if it wasn't fitted to the window.
+    pub zoom: Option<f32>,
+    /// Folder of the image last shown, offered again when the viewer opens without one.
+    pub last_directory: Option<PathBuf>,
+}
+
+impl WindowState {
+    pub fn load(storage: &dyn Storage) -> Self {
+        storage
+            .get_string(WINDOW_STATE_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) {
+        if let Ok(json) = serde_json::to_string(self) {
+            storage.set_string(WINDOW_STATE_KEY, json);
+        }
+    }
+
+    /// Notes where the window is. A fullscreen window isn't noted, so the next launch
+    /// gets the usual window back.
+    pub fn track(&mut self, window: &eframe::WindowInfo) {
+        if window.fullscreen || window.size == egui::Vec2::ZERO {
+            return;
+        }
+        self.size = Some(window.size.into());
+        self.position = window.position.map(Into::into);
+    }
+}
@@ -0,0 +1,163 @@
+//! The .bruh viewer for web pages, built with `trunk build` from index.html.
+//!
+//! It's much smaller than the desktop viewer: images come from a file picker or are
+//! dropped on the page, and can be zoomed and scrolled but not edited or saved. Nothing
+//! leaves the browser.
+
+#![cfg(target_arch = "wasm32")]
+
+use std::sync::mpsc;
+
+use bruh::decode_bruh;
+use eframe::egui;
+use wasm_bindgen::prelude::*;
+
+const ZOOM_STEP: f32 = 1.25;
+
+/// Starts the viewer on the page's `bruh_canvas` when the module loads.
+#[wasm_bindgen(start)]
+pub fn start() {
+    wasm_bindgen_futures::spawn_local(async {
+        eframe::WebRunner::new()
+            .start(
+                "bruh_canvas",
+                eframe::WebOptions::default(),
+                Box::new(|cc| Box::new(WebViewer::new(&cc.egui_ctx))),
+            )
+            .await
+            .expect("the viewer couldn't start");
+    });
+}
+
+struct Image {
+    name: String,
+    texture: egui::TextureHandle,
+}
+
+struct WebViewer {
+    image: Option<Image>,
+    error: Option<String>,
+    zoom: f32,
+    /// Files chosen in the picker, which answers asynchronously.
+    picked: mpsc::Receiver<(String, Vec<u8>)>,
+    pick: mpsc::Sender<(String, Vec<u8>)>,
+    ctx: egui::Context,
+}
+
+impl WebViewer {
+    fn new(ctx: &egui::Context) -> Self {
+        let (pick, picked) = mpsc::channel();
+        Self {
+            image: None,
+            error: None,
+            zoom: 1.0,
+            picked,
+            pick,
+            ctx: ctx.clone(),
+        }
+    }
+
+    fn open_picker(&self) {
+        let pick = self.pick.clone();
+        let ctx = self.ctx.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let file = rfd::AsyncFileDialog::new()
+                .add_filter("BRUH image", &["bruh"])
+                .pick_file()
+                .await;
+            if let Some(file) = file {
+                let _ = pick.send((file.file_name(), file.read().await));
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    fn show(&mut self, name: String, contents: &[u8]) {
+        let decoded = match decode_bruh(contents) {
+            Ok(decoded) if decoded.is_empty() => {
+                let (width, height) = (decoded.width, decoded.height);
+                self.error = Some(format!("{} is empty ({}×{} pixels).", name, width, height));
+                return;
+            }
+            Ok(decoded) => decoded,
+            Err(e) => {
+                self.error = Some(format!("Couldn't open {}: {}", name, e));
+                return;
+            }
+        };
+        let size = [decoded.width as usize, decoded.height as usize];
+        let pixels = egui::ColorImage::from_rgba_unmultiplied(size, &decoded.pixels);
+        let texture = self
+            .ctx
+            .load_texture(&name, pixels, egui::TextureOptions::NEAREST);
+        self.image = Some(Image { name, texture });
+        self.error = None;
+        self.zoom = 1.0;
+    }
+}
+
+impl eframe::App for WebViewer {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Browsers hand over the contents of dropped files, since there's no path to read.
+        let dropped: Vec<_> = ctx.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| Some((file.name.clone(), file.bytes.clone()?)))
+                .collect()
+        });
+        for (name, contents) in dropped {
+            self.show(name, &contents);
+        }
+        while let Ok((name, contents)) = self.picked.try_recv() {
+            self.show(name, &contents);
+        }
+
+        egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Open…").clicked() {
+                    self.open_picker();
+                }
+                ui.add_enabled_ui(self.image.is_some(), |ui| {
+                    if ui.button("−").clicked() {
+                        self.zoom /= ZOOM_STEP;
+                    }
+                    if ui.button("+").clicked() {
+                        self.zoom *= ZOOM_STEP;
+                    }
+                    if ui.button("100%").clicked() {
+                        self.zoom = 1.0;
+                    }
+                });
+                if let Some(image) = &self.image {
+                    let [width, height] = image.texture.size();
+                    ui.label(format!(
+                        "{}  {}×{}  {:.0}%",
+                        image.name,
+                        width,
+                        height,
+                        self.zoom * 100.0
+                    ));
+                }
+            });
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if let Some(error) = &self.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+            match &self.image {
+                Some(image) => {
+                    egui::ScrollArea::both().show(ui, |ui| {
+                        ui.image(image.texture.id(), image.texture.size_vec2() * self.zoom);
+                    });
+                }
+                None => {
+                    ui.centered_and_justified(|ui| {
+                        ui.label("Open a .bruh file, or drop one on the page.");
+                    });
+                }
+            }
+        });
+    }
+}